@@ -1,4 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
+use photo_tiler::config::TilingConfig;
 use photo_tiler::tiling::lod::generate_lod_chain;
 use photo_tiler::tiling::simplifier::simplify_mesh;
 use photo_tiler::types::{BoundingBox, IndexedMesh};
@@ -38,13 +39,14 @@ fn make_grid(n: usize) -> IndexedMesh {
 fn bench_simplify(c: &mut Criterion) {
     // ~100K triangles: 224x224 grid = 50176 quads = 100352 triangles
     let mesh = make_grid(224);
+    let config = TilingConfig::default();
 
     c.bench_function("simplify_mesh_50pct_100k", |b| {
-        b.iter(|| simplify_mesh(&mesh, 0.5, false));
+        b.iter(|| simplify_mesh(&mesh, 0.5, false, &config));
     });
 
     c.bench_function("simplify_mesh_25pct_100k", |b| {
-        b.iter(|| simplify_mesh(&mesh, 0.25, true));
+        b.iter(|| simplify_mesh(&mesh, 0.25, true, &config));
     });
 }
 
@@ -54,9 +56,10 @@ fn bench_lod_chain(c: &mut Criterion) {
         min: [0.0, 0.0, 0.0],
         max: [1.0, 1.0, 0.0],
     };
+    let config = TilingConfig::default();
 
     c.bench_function("lod_chain_4_levels_100k", |b| {
-        b.iter(|| generate_lod_chain(mesh.clone(), &bounds, 4));
+        b.iter(|| generate_lod_chain(mesh.clone(), &bounds, 4, &config));
     });
 }
 
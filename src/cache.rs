@@ -0,0 +1,605 @@
+//! On-disk cache of transform-stage results.
+//!
+//! Ingestion and transform only depend on the input files and a handful of
+//! settings, so re-running with different tiling or texture options can
+//! reuse the geometry of an earlier run. Entries are keyed by a hash of the
+//! input paths, the explicitly given sidecar files and those settings, and
+//! record a content hash of every file ingestion read (see
+//! [`IngestionResult::source_files`]); a changed setting misses, and so does
+//! an entry whose files changed since. Hashes are XXH64, stable across
+//! builds. Entries use a private binary layout in host byte order and are
+//! never shared between machines.
+//!
+//! [`IngestionResult::source_files`]: crate::ingestion::IngestionResult::source_files
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use tracing::{info, warn};
+
+use crate::config::PipelineConfig;
+use crate::hash::Xxh64;
+use crate::transform::TransformResult;
+use crate::types::{
+    AlphaMode, BoundingBox, IndexedMesh, MaterialGroup, MaterialLibrary, PBRMaterial, TextureData,
+    WrapMode,
+};
+
+/// Default cache directory, relative to the working directory.
+pub const DEFAULT_CACHE_DIR: &str = ".photo-tiler-cache";
+
+/// Bumped whenever the entry layout or what feeds the key changes.
+const CACHE_VERSION: u32 = 9;
+const MAGIC: &[u8; 4] = b"PTC\0";
+
+/// Directory of cached transform results.
+pub struct GeometryCache {
+    dir: PathBuf,
+}
+
+impl GeometryCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.bin"))
+    }
+
+    /// The cached result for `key`, or `None` when there is none, it can't
+    /// be read back or one of the files it was built from changed.
+    pub fn load(&self, key: &str) -> Option<TransformResult> {
+        let path = self.entry_path(key);
+        let data = fs::read(&path).ok()?;
+        let Some((sources, result)) = decode(&data) else {
+            warn!(path = %path.display(), "Ignoring unreadable cache entry");
+            return None;
+        };
+        if let Some((file, _)) = sources.iter().find(|(file, hash)| file_hash(file) != *hash) {
+            info!(file = %file.display(), "Cached geometry is stale");
+            return None;
+        }
+        Some(result)
+    }
+
+    /// Store `result` under `key`, replacing any previous entry, along with
+    /// the content hash of each of `source_files` (`None` for files that
+    /// don't exist).
+    pub fn store(
+        &self,
+        key: &str,
+        result: &TransformResult,
+        source_files: &[PathBuf],
+    ) -> io::Result<()> {
+        let mut seen = HashSet::new();
+        let sources: Vec<(PathBuf, Option<u64>)> = source_files
+            .iter()
+            .filter(|file| seen.insert(*file))
+            .map(|file| (file.clone(), file_hash(file)))
+            .collect();
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.entry_path(key);
+        let tmp = path.with_extension("bin.tmp");
+        fs::write(&tmp, encode(&sources, result))?;
+        fs::rename(&tmp, &path)?;
+        info!(path = %path.display(), "Cached transformed geometry");
+        Ok(())
+    }
+}
+
+/// Cache key for the ingestion and transform stages of `config`.
+///
+/// Input contents are not part of the key: the entry records the files
+/// ingestion read and [`GeometryCache::load`] checks them.
+pub fn cache_key(config: &PipelineConfig) -> io::Result<String> {
+    let mut hasher = Xxh64::new();
+    hasher.write(&CACHE_VERSION.to_le_bytes());
+    for input in config.inputs() {
+        hash_bytes(&mut hasher, input.as_os_str().as_encoded_bytes());
+    }
+
+    let explicit = [
+        &config.transform_file,
+        &config.offset_file,
+        &config.metadata_xml,
+    ];
+    for file in explicit.into_iter().flatten() {
+        hash_bytes(&mut hasher, file.as_os_str().as_encoded_bytes());
+        hash_bytes(&mut hasher, &fs::read(file)?);
+    }
+
+    // Settings read by ingestion and transform
+    let settings = format!(
+        "{:?} {:?} {:?} {} {} {} {:?} {:?} {:?} {:?} {} {} {}",
+        config.units,
+        config.input_up,
+        config.scale,
+        config.recenter,
+        config.keep_node_transforms,
+        config.gpu_instancing,
+        config.flip_uv_v,
+        config.georeference,
+        config.origin,
+        config.transform_order,
+        config.frame,
+        config.texture.enabled,
+        config.strict_textures,
+    );
+    hash_bytes(&mut hasher, settings.as_bytes());
+
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// Feed `bytes` to `hasher`, length first so consecutive values can't run
+/// into each other.
+fn hash_bytes(hasher: &mut Xxh64, bytes: &[u8]) {
+    hasher.write(&(bytes.len() as u64).to_le_bytes());
+    hasher.write(bytes);
+}
+
+/// XXH64 of the contents of `path`, or `None` when it can't be read.
+fn file_hash(path: &Path) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let mut hasher = Xxh64::new();
+    let mut buf = vec![0; 1 << 20];
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => return Some(hasher.finish()),
+            Ok(n) => hasher.write(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(_) => return None,
+        }
+    }
+}
+
+/// Serialize a transform result into a cache entry, after the files it was
+/// built from and their content hashes.
+fn encode(sources: &[(PathBuf, Option<u64>)], result: &TransformResult) -> Vec<u8> {
+    let mut w = Writer(MAGIC.to_vec());
+    w.u64(u64::from(CACHE_VERSION));
+
+    w.u64(sources.len() as u64);
+    for (file, hash) in sources {
+        w.str(&file.to_string_lossy());
+        w.u64(u64::from(hash.is_some()));
+        w.u64(hash.unwrap_or(0));
+    }
+
+    w.u64(result.meshes.len() as u64);
+    for mesh in &result.meshes {
+        w.mesh(mesh);
+    }
+
+    let materials = &result.materials;
+    w.u64(materials.materials.len() as u64);
+    for material in &materials.materials {
+        w.str(&material.name);
+        w.f32s(&material.base_color);
+        w.f32s(&[material.metallic, material.roughness]);
+        w.index(material.base_color_texture);
+        w.index(material.metallic_roughness_texture);
+        w.index(material.normal_texture);
+        w.f32s(&[material.normal_scale]);
+        match material.alpha_mode {
+            AlphaMode::Opaque => w.u64(0),
+            AlphaMode::Mask { cutoff } => {
+                w.u64(1);
+                w.f32s(&[cutoff]);
+            }
+            AlphaMode::Blend => w.u64(2),
+        }
+        w.f32s(&material.emissive);
+    }
+    w.u64(materials.textures.len() as u64);
+    for texture in &materials.textures {
+        w.bytes(&texture.data);
+        w.str(&texture.mime_type);
+        w.u64(u64::from(texture.width));
+        w.u64(u64::from(texture.height));
+        for wrap in texture.wrap {
+            w.u64(wrap as u64);
+        }
+    }
+    w.u64(materials.features.len() as u64);
+    for feature in &materials.features {
+        w.str(feature);
+    }
+
+    w.f64s(&result.root_transform);
+    w.f64s(result.ecef_origin.as_ref().map_or(&[][..], |o| &o[..]));
+    w.f64s(&result.bounds.min);
+    w.f64s(&result.bounds.max);
+    w.u64(result.tile_transforms.len() as u64);
+    for transform in &result.tile_transforms {
+        w.f64s(transform.as_ref().map_or(&[][..], |t| &t[..]));
+    }
+    w.u64(result.instances.len() as u64);
+    for instances in &result.instances {
+        w.f64s(instances.as_flattened());
+    }
+    w.0
+}
+
+/// Parse a cache entry into the files it was built from and the result;
+/// `None` if it is truncated or of another version.
+fn decode(data: &[u8]) -> Option<(Vec<(PathBuf, Option<u64>)>, TransformResult)> {
+    let mut r = Reader { data, pos: 0 };
+    if r.take(4)? != MAGIC || r.u64()? != u64::from(CACHE_VERSION) {
+        return None;
+    }
+
+    let mut sources = Vec::new();
+    for _ in 0..r.u64()? {
+        let file = PathBuf::from(r.str()?);
+        let present = r.u64()? != 0;
+        let hash = r.u64()?;
+        sources.push((file, present.then_some(hash)));
+    }
+
+    let mut meshes = Vec::new();
+    for _ in 0..r.u64()? {
+        meshes.push(r.mesh()?);
+    }
+
+    let mut materials = MaterialLibrary::default();
+    for _ in 0..r.u64()? {
+        let name = r.str()?;
+        let base_color = r.f32s()?.try_into().ok()?;
+        let [metallic, roughness] = r.f32s()?.try_into().ok()?;
+        materials.materials.push(PBRMaterial {
+            name,
+            base_color,
+            metallic,
+            roughness,
+            base_color_texture: r.index()?,
+            metallic_roughness_texture: r.index()?,
+            normal_texture: r.index()?,
+            normal_scale: {
+                let [scale] = r.f32s()?.try_into().ok()?;
+                scale
+            },
+            alpha_mode: match r.u64()? {
+                0 => AlphaMode::Opaque,
+                1 => {
+                    let [cutoff] = r.f32s()?.try_into().ok()?;
+                    AlphaMode::Mask { cutoff }
+                }
+                2 => AlphaMode::Blend,
+                _ => return None,
+            },
+            emissive: r.f32s()?.try_into().ok()?,
+        });
+    }
+    for _ in 0..r.u64()? {
+        let data = r.bytes()?;
+        let mime_type = r.str()?;
+        let width = u32::try_from(r.u64()?).ok()?;
+        let height = u32::try_from(r.u64()?).ok()?;
+        let mut wrap = [WrapMode::Repeat; 2];
+        for w in &mut wrap {
+            *w = match r.u64()? {
+                0 => WrapMode::Repeat,
+                1 => WrapMode::MirroredRepeat,
+                2 => WrapMode::ClampToEdge,
+                _ => return None,
+            };
+        }
+        materials.textures.push(TextureData {
+            data,
+            mime_type,
+            width,
+            height,
+            wrap,
+        });
+    }
+    for _ in 0..r.u64()? {
+        materials.features.push(r.str()?);
+    }
+
+    let root_transform = r.f64s()?.try_into().ok()?;
+    let ecef_origin = match r.f64s()? {
+        origin if origin.is_empty() => None,
+        origin => Some(origin.try_into().ok()?),
+    };
+    let bounds = BoundingBox {
+        min: r.f64s()?.try_into().ok()?,
+        max: r.f64s()?.try_into().ok()?,
+    };
+    let mut tile_transforms = Vec::new();
+    for _ in 0..r.u64()? {
+        let transform = r.f64s()?;
+        tile_transforms.push(match transform.len() {
+            0 => None,
+            _ => Some(transform.try_into().ok()?),
+        });
+    }
+    let mut instances = Vec::new();
+    for _ in 0..r.u64()? {
+        let flat = r.f64s()?;
+        let matrices = flat.chunks_exact(16).map(|m| m.try_into().unwrap());
+        instances.push(matrices.collect());
+    }
+
+    let result = TransformResult {
+        meshes,
+        materials,
+        root_transform,
+        ecef_origin,
+        bounds,
+        tile_transforms,
+        instances,
+    };
+    (r.pos == data.len()).then_some((sources, result))
+}
+
+/// Serialize one mesh in the cache's entry layout, for meshes parked on
+/// disk during a build.
+pub(crate) fn encode_mesh(mesh: &IndexedMesh) -> Vec<u8> {
+    let mut w = Writer(Vec::new());
+    w.mesh(mesh);
+    w.0
+}
+
+/// Parse a mesh written by [`encode_mesh`]; `None` if it is truncated.
+pub(crate) fn decode_mesh(data: &[u8]) -> Option<IndexedMesh> {
+    let mut r = Reader { data, pos: 0 };
+    let mesh = r.mesh()?;
+    (r.pos == data.len()).then_some(mesh)
+}
+
+/// Appends length-prefixed values to a cache entry.
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_ne_bytes());
+    }
+
+    fn bytes(&mut self, data: &[u8]) {
+        self.u64(data.len() as u64);
+        self.0.extend_from_slice(data);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.bytes(s.as_bytes());
+    }
+
+    fn f32s(&mut self, data: &[f32]) {
+        self.bytes(bytemuck::cast_slice(data));
+    }
+
+    fn u32s(&mut self, data: &[u32]) {
+        self.bytes(bytemuck::cast_slice(data));
+    }
+
+    fn f64s(&mut self, data: &[f64]) {
+        self.bytes(bytemuck::cast_slice(data));
+    }
+
+    /// An optional index, stored as `u64::MAX` when absent.
+    fn index(&mut self, index: Option<usize>) {
+        self.u64(index.map_or(u64::MAX, |i| i as u64));
+    }
+
+    fn mesh(&mut self, mesh: &IndexedMesh) {
+        for data in [
+            &mesh.positions,
+            &mesh.normals,
+            &mesh.uvs,
+            &mesh.uvs1,
+            &mesh.colors,
+        ] {
+            self.f32s(data);
+        }
+        self.u32s(&mesh.feature_ids);
+        self.u32s(&mesh.indices);
+        self.index(mesh.material_index);
+        self.str(mesh.name.as_deref().unwrap_or(""));
+        self.u64(u64::from(mesh.name.is_some()));
+        self.u64(mesh.material_groups.len() as u64);
+        for group in &mesh.material_groups {
+            self.u64(group.indices.start as u64);
+            self.u64(group.indices.end as u64);
+            self.index(group.material_index);
+        }
+    }
+}
+
+/// Reads back what [`Writer`] wrote; every method is `None` past the end.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Option<&[u8]> {
+        let end = self.pos.checked_add(len)?;
+        let slice = self.data.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_ne_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn bytes(&mut self) -> Option<Vec<u8>> {
+        let len = usize::try_from(self.u64()?).ok()?;
+        self.take(len).map(<[u8]>::to_vec)
+    }
+
+    fn str(&mut self) -> Option<String> {
+        String::from_utf8(self.bytes()?).ok()
+    }
+
+    fn f32s(&mut self) -> Option<Vec<f32>> {
+        Some(bytemuck::pod_collect_to_vec(&self.bytes()?))
+    }
+
+    fn u32s(&mut self) -> Option<Vec<u32>> {
+        Some(bytemuck::pod_collect_to_vec(&self.bytes()?))
+    }
+
+    fn f64s(&mut self) -> Option<Vec<f64>> {
+        let bytes = self.bytes()?;
+        (bytes.len() % 8 == 0).then(|| bytemuck::pod_collect_to_vec(&bytes))
+    }
+
+    fn index(&mut self) -> Option<Option<usize>> {
+        match self.u64()? {
+            u64::MAX => Some(None),
+            i => usize::try_from(i).ok().map(Some),
+        }
+    }
+
+    fn mesh(&mut self) -> Option<IndexedMesh> {
+        let mut mesh = IndexedMesh {
+            positions: self.f32s()?,
+            normals: self.f32s()?,
+            uvs: self.f32s()?,
+            uvs1: self.f32s()?,
+            colors: self.f32s()?,
+            feature_ids: self.u32s()?,
+            indices: self.u32s()?,
+            material_index: self.index()?,
+            ..Default::default()
+        };
+        let name = self.str()?;
+        mesh.name = (self.u64()? != 0).then_some(name);
+        for _ in 0..self.u64()? {
+            let start = self.u64()? as usize;
+            let end = self.u64()? as usize;
+            mesh.material_groups.push(MaterialGroup {
+                indices: start..end,
+                material_index: self.index()?,
+            });
+        }
+        Some(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result() -> TransformResult {
+        let mut translation = [0.0; 16];
+        translation[0] = 1.0;
+        translation[5] = 1.0;
+        translation[10] = 1.0;
+        translation[15] = 1.0;
+        translation[12] = 4.0;
+        TransformResult {
+            meshes: vec![IndexedMesh {
+                positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+                uvs: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+                indices: vec![0, 1, 2],
+                material_index: Some(0),
+                name: Some("wall".into()),
+                material_groups: vec![MaterialGroup {
+                    indices: 0..3,
+                    material_index: None,
+                }],
+                ..Default::default()
+            }],
+            materials: MaterialLibrary {
+                materials: vec![PBRMaterial {
+                    name: "brick".into(),
+                    base_color_texture: Some(0),
+                    alpha_mode: AlphaMode::Mask { cutoff: 0.25 },
+                    emissive: [2.0, 1.0, 0.0],
+                    ..Default::default()
+                }],
+                textures: vec![TextureData {
+                    data: vec![1, 2, 3],
+                    mime_type: "image/png".into(),
+                    width: 1,
+                    height: 1,
+                    wrap: [WrapMode::ClampToEdge, WrapMode::MirroredRepeat],
+                }],
+                features: vec!["wall".into()],
+                missing_textures: Vec::new(),
+                source_files: Vec::new(),
+            },
+            root_transform: translation,
+            ecef_origin: Some([1.0, 2.0, 3.0]),
+            bounds: BoundingBox {
+                min: [0.0, 0.0, 0.0],
+                max: [1.0, 1.0, 0.0],
+            },
+            tile_transforms: vec![Some(translation)],
+            instances: vec![vec![translation, translation]],
+        }
+    }
+
+    #[test]
+    fn entry_roundtrips() {
+        let original = sample_result();
+        let sources = vec![
+            (PathBuf::from("model.obj"), Some(42)),
+            (PathBuf::from("offset.xyz"), None),
+        ];
+        let (decoded_sources, decoded) =
+            decode(&encode(&sources, &original)).expect("entry should decode");
+        assert_eq!(decoded_sources, sources);
+
+        let mesh = &decoded.meshes[0];
+        assert_eq!(mesh.positions, original.meshes[0].positions);
+        assert_eq!(mesh.uvs, original.meshes[0].uvs);
+        assert!(mesh.normals.is_empty());
+        assert_eq!(mesh.name.as_deref(), Some("wall"));
+        assert_eq!(mesh.material_groups, original.meshes[0].material_groups);
+        assert_eq!(decoded.materials.materials[0].base_color_texture, Some(0));
+        assert_eq!(
+            decoded.materials.materials[0].alpha_mode,
+            AlphaMode::Mask { cutoff: 0.25 }
+        );
+        assert_eq!(decoded.materials.materials[0].emissive, [2.0, 1.0, 0.0]);
+        assert_eq!(decoded.materials.textures[0].data, vec![1, 2, 3]);
+        assert_eq!(
+            decoded.materials.textures[0].wrap,
+            [WrapMode::ClampToEdge, WrapMode::MirroredRepeat]
+        );
+        assert_eq!(decoded.materials.features, vec!["wall".to_string()]);
+        assert_eq!(decoded.root_transform, original.root_transform);
+        assert_eq!(decoded.ecef_origin, original.ecef_origin);
+        assert_eq!(decoded.bounds, original.bounds);
+        assert_eq!(decoded.tile_transforms, original.tile_transforms);
+        assert_eq!(decoded.instances, original.instances);
+    }
+
+    #[test]
+    fn truncated_entry_is_rejected() {
+        let data = encode(&[], &sample_result());
+        assert!(decode(&data[..data.len() - 1]).is_none());
+        assert!(decode(b"PTC\0").is_none());
+    }
+
+    #[test]
+    fn entry_misses_once_a_source_file_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let texture = tmp.path().join("maps").join("wall.png");
+        let sidecar = tmp.path().join("offset.xyz");
+        fs::create_dir_all(texture.parent().unwrap()).unwrap();
+        fs::write(&texture, b"first").unwrap();
+
+        let cache = GeometryCache::new(tmp.path().join("cache"));
+        let sources = [texture.clone(), sidecar.clone()];
+        cache.store("key", &sample_result(), &sources).unwrap();
+        assert!(cache.load("key").is_some());
+
+        // Same length, new contents
+        fs::write(&texture, b"other").unwrap();
+        assert!(cache.load("key").is_none());
+        cache.store("key", &sample_result(), &sources).unwrap();
+        assert!(cache.load("key").is_some());
+
+        // A file that was missing shows up
+        fs::write(&sidecar, b"1 2 3").unwrap();
+        assert!(cache.load("key").is_none());
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{ArgGroup, Parser};
 
 /// Input coordinate units.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
@@ -50,8 +50,233 @@ impl std::fmt::Display for TextureFormat {
     }
 }
 
+/// Up axis of the input coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum UpAxis {
+    /// Y-up, as in OBJ and glTF; rotated to Z-up during transform.
+    #[default]
+    #[value(name = "y")]
+    YUp,
+    /// Already Z-up, as in many CAD and Blender exports; left as is.
+    #[value(name = "z")]
+    ZUp,
+}
+
+impl std::fmt::Display for UpAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpAxis::YUp => write!(f, "y"),
+            UpAxis::ZUp => write!(f, "z"),
+        }
+    }
+}
+
+/// Whether the transform stage moves the model to the origin.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Center {
+    /// Subtract the centroid, keeping f32 vertices precise; the root
+    /// transform adds it back.
+    #[default]
+    #[value(name = "centroid")]
+    Centroid,
+    /// Keep the input coordinates.
+    #[value(name = "none")]
+    None,
+}
+
+impl std::fmt::Display for Center {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Center::Centroid => write!(f, "centroid"),
+            Center::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Coordinate frame the tileset's root transform places the model in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Frame {
+    /// Bake the placement into the root transform, in ECEF.
+    #[default]
+    #[value(name = "ecef")]
+    Ecef,
+    /// Keep the model in its local east-north-up frame with an identity
+    /// root transform; the frame's ECEF origin is recorded in the tileset's
+    /// `extras`.
+    #[value(name = "local-enu")]
+    LocalEnu,
+}
+
+impl std::fmt::Display for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Frame::Ecef => write!(f, "ecef"),
+            Frame::LocalEnu => write!(f, "local-enu"),
+        }
+    }
+}
+
+/// Element order of the 16 numbers in a `--transform-file`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MatrixOrder {
+    /// Column-major, as stored in tileset.json.
+    #[default]
+    #[value(name = "column")]
+    Column,
+    /// Row-major, as matrices are usually written out by hand.
+    #[value(name = "row")]
+    Row,
+}
+
+impl std::fmt::Display for MatrixOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatrixOrder::Column => write!(f, "column"),
+            MatrixOrder::Row => write!(f, "row"),
+        }
+    }
+}
+
+/// 3D Tiles specification version written to `asset.version`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TilesVersion {
+    /// 3D Tiles 1.0; glTF content is declared via `3DTILES_content_gltf`.
+    #[value(name = "1.0")]
+    V1_0,
+    /// 3D Tiles 1.1, where glTF content is core.
+    #[default]
+    #[value(name = "1.1")]
+    V1_1,
+}
+
+impl TilesVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TilesVersion::V1_0 => "1.0",
+            TilesVersion::V1_1 => "1.1",
+        }
+    }
+}
+
+impl std::fmt::Display for TilesVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Spatial subdivision scheme for the tile hierarchy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Scheme {
+    /// Split all three axes into 8 children.
+    #[default]
+    #[value(name = "octree")]
+    Octree,
+    /// Split only X and Y into 4 children, for mostly flat terrain.
+    #[value(name = "quadtree")]
+    Quadtree,
+}
+
+impl std::fmt::Display for Scheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scheme::Octree => write!(f, "octree"),
+            Scheme::Quadtree => write!(f, "quadtree"),
+        }
+    }
+}
+
+/// Value of `--max-depth`: a fixed depth, or `auto` to estimate it from the
+/// triangle count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxDepth {
+    Levels(u32),
+    Auto,
+}
+
+impl std::fmt::Display for MaxDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaxDepth::Levels(depth) => write!(f, "{depth}"),
+            MaxDepth::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// How triangles crossing a cell boundary are divided between children.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Split {
+    /// Clip triangles at cell boundaries (watertight, adds triangles).
+    #[default]
+    #[value(name = "clip")]
+    Clip,
+    /// Keep triangles whole and assign each to the cell of its centroid.
+    #[value(name = "centroid")]
+    Centroid,
+}
+
+impl std::fmt::Display for Split {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Split::Clip => write!(f, "clip"),
+            Split::Centroid => write!(f, "centroid"),
+        }
+    }
+}
+
+/// Order of sibling tiles in tileset.json, which is the order clients
+/// traverse and usually request them in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TileOrder {
+    /// Octant index order, i.e. a Z-order (Morton) curve over each cell.
+    #[default]
+    #[value(name = "morton")]
+    Morton,
+    /// Along a Hilbert curve over the whole tileset, so consecutive siblings
+    /// always share a face.
+    #[value(name = "hilbert")]
+    Hilbert,
+}
+
+impl std::fmt::Display for TileOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TileOrder::Morton => write!(f, "morton"),
+            TileOrder::Hilbert => write!(f, "hilbert"),
+        }
+    }
+}
+
+/// How a tile's children relate to its content (`refine` in tileset.json).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Refine {
+    /// Children replace the parent's simplified content.
+    #[default]
+    #[value(name = "replace")]
+    Replace,
+    /// Children add detail on top of the parent, which keeps a subset of the
+    /// original triangles.
+    #[value(name = "add")]
+    Add,
+}
+
+impl Refine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Refine::Replace => "REPLACE",
+            Refine::Add => "ADD",
+        }
+    }
+}
+
+impl std::fmt::Display for Refine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Georeferencing parameters.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Georeference {
     pub epsg: u32,
     pub easting: f64,
@@ -60,11 +285,107 @@ pub struct Georeference {
     pub true_north: f64,
 }
 
+/// Model origin given directly in WGS84, so no map projection is needed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CartographicOrigin {
+    /// Longitude in degrees
+    pub lon: f64,
+    /// Latitude in degrees
+    pub lat: f64,
+    /// Ellipsoidal height in metres
+    pub height: f64,
+}
+
 /// Tiling parameters.
 #[derive(Debug, Clone)]
 pub struct TilingConfig {
     pub max_triangles_per_tile: usize,
     pub max_depth: u32,
+    /// Ignore `max_depth` and pick the depth at which the input's triangles
+    /// come down to `max_triangles_per_tile` per leaf, up to a safety limit.
+    pub auto_depth: bool,
+    /// Also split tiles whose estimated size (vertex data, indices and atlas
+    /// texels) exceeds this many bytes. `None` splits on triangle count alone.
+    pub max_tile_bytes: Option<u64>,
+    /// Simplifier weight for vertex normals (0 = ignore normals).
+    pub normal_weight: f32,
+    /// Simplifier weight for texture coordinates (0 = ignore UVs).
+    pub uv_weight: f32,
+    /// Fall back to topology-ignoring sloppy simplification when the regular
+    /// simplifier stalls well above its target. Ignored where the border is
+    /// locked, which sloppy simplification cannot preserve.
+    pub allow_sloppy: bool,
+    /// Meshes with more triangles than this are simplified in spatial
+    /// chunks of at most this many, bounding the simplifier's memory at
+    /// some cost along chunk seams. 0 always simplifies whole meshes.
+    pub simplify_chunk_triangles: usize,
+    /// Compress vertex and index buffers with EXT_meshopt_compression. Viewers
+    /// must support the extension; when off, tiles store plain f32 buffers.
+    pub meshopt: bool,
+    /// Write tiles with KHR_mesh_quantization instead of EXT_meshopt_compression,
+    /// for viewers that can't decode meshopt buffers. Takes precedence over `meshopt`.
+    /// Normals are quantized per component, not octahedral-encoded.
+    pub quantize: bool,
+    /// Octahedral-encode normals of meshopt-compressed tiles with this many
+    /// bits per octahedral coordinate (4..=16). `None` keeps f32 normals.
+    pub oct_normal_bits: Option<u8>,
+    /// 3D Tiles version of the emitted tileset.json.
+    pub tiles_version: TilesVersion,
+    /// Octree (8-way) or quadtree (4-way, X/Y only) subdivision.
+    pub scheme: Scheme,
+    /// Screen-space error in pixels at which tiles should swap. `None` keeps
+    /// the depth-halving geometric error heuristic.
+    pub target_sse: Option<f64>,
+    /// Ceiling for every tile's geometric error (and the tileset's), for
+    /// viewers that mishandle very large errors. `None` leaves them as built.
+    pub max_geometric_error: Option<f64>,
+    /// Factor applied to every tile's geometric error (and the tileset's),
+    /// to tune refinement for a viewer's screen-space error convention.
+    /// Applied before `max_geometric_error`.
+    pub geometric_error_scale: f64,
+    /// Replace parent content with children, or add children on top of it.
+    pub refine: Refine,
+    /// Clip boundary triangles, or assign them whole by centroid.
+    pub split_mode: Split,
+    /// Merge sibling leaves while their combined mesh still fits a single tile.
+    /// Off by default, so every cell keeps its own tile.
+    pub merge_leaves: bool,
+    /// Weld the vertices of clipped cells. Off skips a hash lookup per
+    /// vertex: source vertices stay shared, but each vertex a clip creates
+    /// is new, so cells are no longer watertight along clipped edges.
+    pub clip_weld: bool,
+    /// Order of sibling tiles in tileset.json.
+    pub tile_order: TileOrder,
+    /// Tag each vertex with the ID of its source object and write the
+    /// object names as EXT_mesh_features / EXT_structural_metadata.
+    pub feature_ids: bool,
+    /// Write tiles as Batched 3D Models (`.b3dm`): each source object
+    /// becomes a batch, with a `_BATCHID` vertex attribute and a batch table
+    /// of object names and triangle counts. Implies feature ID tagging.
+    pub b3dm: bool,
+    /// Prefix for every content URI in tileset.json, e.g. a CDN URL. Tiles
+    /// are still written under the output directory; `None` keeps URIs
+    /// relative to tileset.json.
+    pub uri_base: Option<String>,
+    /// Write tiles at multiples of this depth, with their subtrees, as
+    /// separate tileset.json files referenced from their parent tileset.
+    /// `None` writes a single tileset.json.
+    pub external_tileset_depth: Option<u32>,
+    /// Fit an oriented box to each tile's vertices and write it where it is
+    /// tighter than the axis-aligned one: as the tile bounding volume of
+    /// leaves, and as the content bounding volume of tiles with children.
+    pub oriented_boxes: bool,
+    /// Subdivide a cube centred on the scene instead of its exact bounds,
+    /// so octants stay cubic for elongated scenes (a square footprint for
+    /// the quadtree). Tile bounding volumes are fitted back to their content.
+    pub cube_root: bool,
+    /// Give each input mesh a subtree of its own under the root instead of
+    /// merging all meshes into one tree, so separate objects refine
+    /// independently.
+    pub separate_objects: bool,
+    /// Write each tile's level and content triangle count to its `extras`,
+    /// for analytics over a tileset.
+    pub tile_extras: bool,
 }
 
 impl Default for TilingConfig {
@@ -72,6 +393,33 @@ impl Default for TilingConfig {
         Self {
             max_triangles_per_tile: 65_000,
             max_depth: 6,
+            auto_depth: false,
+            max_tile_bytes: None,
+            normal_weight: 0.5,
+            uv_weight: 1.0,
+            allow_sloppy: false,
+            simplify_chunk_triangles: 4_000_000,
+            meshopt: true,
+            quantize: false,
+            oct_normal_bits: None,
+            tiles_version: TilesVersion::V1_1,
+            scheme: Scheme::Octree,
+            target_sse: None,
+            max_geometric_error: None,
+            geometric_error_scale: 1.0,
+            refine: Refine::Replace,
+            split_mode: Split::Clip,
+            merge_leaves: false,
+            clip_weld: true,
+            tile_order: TileOrder::Morton,
+            feature_ids: false,
+            b3dm: false,
+            uri_base: None,
+            external_tileset_depth: None,
+            oriented_boxes: false,
+            cube_root: false,
+            separate_objects: false,
+            tile_extras: false,
         }
     }
 }
@@ -82,7 +430,33 @@ pub struct TextureConfig {
     pub format: TextureFormat,
     pub quality: u8,
     pub max_size: u32,
+    /// Largest atlas side any GPU must accept. A tile whose atlas would be
+    /// larger (only possible when `max_size` exceeds it) spreads its
+    /// islands over several atlases, one glTF primitive each.
+    pub hard_max_size: u32,
+    /// Source textures larger than this are downscaled to fit while they
+    /// are decoded, bounding the memory each decode needs.
+    pub max_source_size: u32,
+    /// Budget in bytes for the encoded atlases of all tiles together. As it
+    /// runs out, later tiles get atlases of a lower max size; a tile whose
+    /// smallest atlas no longer fits is written untextured.
+    pub max_memory: Option<u64>,
     pub enabled: bool,
+    /// Write byte-identical atlases once to `tiles/shared/` and reference
+    /// them from each GLB instead of embedding a copy per tile.
+    pub share_atlases: bool,
+    /// Let the atlas packer rotate islands by 90° for a tighter fit.
+    pub allow_rotation: bool,
+    /// Reduce atlas resolution of simplified (coarse LOD) tiles in
+    /// proportion to their simplification ratio.
+    pub lod_downscale: bool,
+    /// Pack only the bounding rectangle of each island's non-transparent
+    /// texels, clipping away geometry that maps to fully transparent texels.
+    /// Islands of opaque materials are kept whole.
+    pub trim_transparent: bool,
+    /// Bleed padding in pixels around every atlas island. `None` picks
+    /// 2-5 px from the island size.
+    pub bleed_padding: Option<u32>,
 }
 
 impl Default for TextureConfig {
@@ -91,7 +465,15 @@ impl Default for TextureConfig {
             format: TextureFormat::WebP,
             quality: 85,
             max_size: 2048,
+            hard_max_size: 8192,
+            max_source_size: 8192,
+            max_memory: None,
             enabled: true,
+            share_atlases: false,
+            allow_rotation: false,
+            lod_downscale: true,
+            trim_transparent: false,
+            bleed_padding: None,
         }
     }
 }
@@ -116,62 +498,173 @@ impl Default for DracoConfig {
 #[derive(Debug, Clone)]
 pub struct PipelineConfig {
     pub input: PathBuf,
+    /// Further inputs merged into the same tileset. Georeference detection
+    /// only looks next to `input`.
+    pub extra_inputs: Vec<PathBuf>,
     pub output: PathBuf,
     pub units: Option<Units>,
+    pub input_up: UpAxis,
+    /// Extra per-axis scale `[x, y, z]` in the Z-up frame, applied after
+    /// unit conversion (e.g. `[1.0, 1.0, 2.0]` doubles heights).
+    pub scale: Option<[f64; 3]>,
+    /// Move the model's centroid to the origin before casting vertices to
+    /// f32. Off keeps the input coordinates (after unit scaling, axis swap
+    /// and rotation), which lose precision far from the origin.
+    pub recenter: bool,
+    /// Keep each glTF mesh whose node has a non-identity world matrix in
+    /// that local frame and tile it as its own subtree, whose root carries
+    /// the matrix as `transform`, instead of baking it. Meshes with an
+    /// identity matrix have nothing to keep and are tiled with the rest.
+    pub keep_node_transforms: bool,
+    /// Write a glTF mesh placed by several nodes once, with the node
+    /// placements as EXT_mesh_gpu_instancing instances.
+    pub gpu_instancing: bool,
+    /// Count open and non-manifold edges and duplicate faces on load, and
+    /// warn when they predict cracks between tiles.
+    pub analyze_mesh: bool,
+    /// Fail ingestion when a referenced texture is missing or unreadable,
+    /// instead of warning and leaving its material untextured.
+    pub strict_textures: bool,
+    /// Flip texture V (`v` to `1 - v`) on load. `None` flips OBJ UVs, whose
+    /// origin is bottom-left, and keeps glTF and PLY UVs as they are.
+    pub flip_uv_v: Option<bool>,
     pub georeference: Option<Georeference>,
+    /// Place the model's origin at this WGS84 position, bypassing projection
+    /// of `georeference`.
+    pub origin: Option<CartographicOrigin>,
+    /// File with a 4x4 root transform for the model's coordinates, overriding
+    /// any georeference-derived placement.
+    pub transform_file: Option<PathBuf>,
+    /// Element order of the numbers in `transform_file`.
+    pub transform_order: MatrixOrder,
+    /// Frame of the root transform: the placement in ECEF, or identity with
+    /// the placement's origin recorded in tileset.json.
+    pub frame: Frame,
     pub offset_file: Option<PathBuf>,
     pub metadata_xml: Option<PathBuf>,
     pub tiling: TilingConfig,
     pub texture: TextureConfig,
     pub draco: DracoConfig,
     pub validate: bool,
+    /// Only validate this existing tileset (file or directory) and exit.
+    pub validate_only: Option<PathBuf>,
     pub dry_run: bool,
     pub show_georef: bool,
+    /// Print `dry_run` and `show_georef` output as JSON.
+    pub json: bool,
     pub verbose: bool,
+    /// Worker thread count for the pool the CLI runs on. Library callers
+    /// pass their own pool to [`crate::Pipeline::run_in`].
     pub threads: Option<usize>,
+    /// Write a JSON conversion report to this path after a run.
+    pub report: Option<PathBuf>,
+    /// Allow writing into a non-empty output directory, replacing the
+    /// previous `tiles/` and `tileset.json`.
+    pub force: bool,
+    /// Cache transformed geometry in this directory and reuse it when the
+    /// inputs and the settings ingestion and transform read are unchanged.
+    pub cache_dir: Option<PathBuf>,
 }
 
 impl Default for PipelineConfig {
     fn default() -> Self {
         Self {
             input: PathBuf::new(),
+            extra_inputs: Vec::new(),
             output: PathBuf::new(),
             units: None,
+            input_up: UpAxis::YUp,
+            scale: None,
+            recenter: true,
+            keep_node_transforms: false,
+            gpu_instancing: false,
+            analyze_mesh: false,
+            strict_textures: false,
+            flip_uv_v: None,
             georeference: None,
+            origin: None,
+            transform_file: None,
+            transform_order: MatrixOrder::Column,
+            frame: Frame::Ecef,
             offset_file: None,
             metadata_xml: None,
             tiling: TilingConfig::default(),
             texture: TextureConfig::default(),
             draco: DracoConfig::default(),
             validate: false,
+            validate_only: None,
             dry_run: false,
             show_georef: false,
+            json: false,
             verbose: false,
             threads: None,
+            report: None,
+            force: false,
+            cache_dir: None,
         }
     }
 }
 
+impl PipelineConfig {
+    /// All input files, `input` first.
+    pub fn inputs(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.input).chain(&self.extra_inputs)
+    }
+}
+
 /// CLI argument definition (clap derive).
 #[derive(Parser, Debug)]
 #[command(
     name = "photo-tiler",
     about = "Photogrammetry mesh to OGC 3D Tiles 1.1 converter",
-    version
+    version,
+    group(ArgGroup::new("report_only").args(["show_georef", "dry_run"]).multiple(true))
 )]
 pub struct CliArgs {
-    /// Input file (OBJ, glTF, GLB, PLY)
-    #[arg(short = 'i', long)]
-    pub input: PathBuf,
+    /// Input file(s) (OBJ, glTF, GLB, PLY); several are merged into one tileset
+    #[arg(short = 'i', long, required_unless_present = "validate_only", num_args = 1..)]
+    pub input: Vec<PathBuf>,
 
     /// Output directory
-    #[arg(short = 'o', long)]
-    pub output: PathBuf,
+    #[arg(short = 'o', long, required_unless_present = "validate_only")]
+    pub output: Option<PathBuf>,
 
     /// Input coordinate units
     #[arg(long, value_enum)]
     pub units: Option<Units>,
 
+    /// Up axis of the input: y (OBJ/glTF convention) or z (already Z-up)
+    #[arg(long, value_enum, default_value = "y")]
+    pub input_up: UpAxis,
+
+    /// Per-axis scale X,Y,Z (Z up) applied after unit conversion, e.g. 1,1,2
+    #[arg(long, value_parser = parse_scale, allow_hyphen_values = true)]
+    pub scale: Option<[f64; 3]>,
+
+    /// Move the model to the origin (centroid) or keep its coordinates (none)
+    #[arg(long, value_enum, default_value = "centroid")]
+    pub center: Center,
+
+    /// Keep non-identity glTF node transforms as per-mesh subtree transforms instead of baking them
+    #[arg(long)]
+    pub keep_node_transforms: bool,
+
+    /// Write glTF meshes repeated by several nodes once, as EXT_mesh_gpu_instancing instances
+    #[arg(long)]
+    pub gpu_instancing: bool,
+
+    /// Count open/non-manifold edges and duplicate faces, warning when tiles may crack
+    #[arg(long)]
+    pub analyze_mesh: bool,
+
+    /// Fail when a referenced texture is missing or can't be read, instead of tiling it untextured
+    #[arg(long)]
+    pub strict_textures: bool,
+
+    /// Flip texture V on load: true or false [default: true for OBJ, false for glTF/PLY]
+    #[arg(long, value_name = "BOOL")]
+    pub flip_uv_v: Option<bool>,
+
     /// EPSG code (e.g. 32636)
     #[arg(long)]
     pub epsg: Option<u32>,
@@ -192,6 +685,41 @@ pub struct CliArgs {
     #[arg(long, default_value_t = 0.0)]
     pub true_north: f64,
 
+    /// Origin latitude in degrees (WGS84); places the model without an EPSG code
+    #[arg(
+        long,
+        requires = "origin_lon",
+        conflicts_with = "epsg",
+        allow_negative_numbers = true,
+        value_parser = parse_latitude
+    )]
+    pub origin_lat: Option<f64>,
+
+    /// Origin longitude in degrees (WGS84)
+    #[arg(
+        long,
+        requires = "origin_lat",
+        allow_negative_numbers = true,
+        value_parser = parse_longitude
+    )]
+    pub origin_lon: Option<f64>,
+
+    /// Origin ellipsoidal height in metres
+    #[arg(long, requires = "origin_lat", allow_negative_numbers = true)]
+    pub origin_height: Option<f64>,
+
+    /// File with 16 numbers used as the tileset root transform, overriding georeferencing
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["epsg", "origin_lat"])]
+    pub transform_file: Option<PathBuf>,
+
+    /// Element order of --transform-file: column (as in tileset.json) or row
+    #[arg(long, value_enum, default_value = "column")]
+    pub transform_order: MatrixOrder,
+
+    /// Root transform frame: ecef, or local-enu (identity, origin in tileset extras)
+    #[arg(long, value_enum, default_value = "ecef")]
+    pub frame: Frame,
+
     /// Path to offset.xyz file
     #[arg(long)]
     pub offset_file: Option<PathBuf>,
@@ -208,13 +736,126 @@ pub struct CliArgs {
     #[arg(long)]
     pub dry_run: bool,
 
+    /// Print --dry-run and --show-georef output as JSON on stdout (logs go to stderr)
+    #[arg(long, requires = "report_only")]
+    pub json: bool,
+
     /// Max triangles per leaf tile
     #[arg(long, default_value_t = 65_000)]
     pub max_triangles: usize,
 
-    /// Max octree depth
-    #[arg(long, default_value_t = 6)]
-    pub max_depth: u32,
+    /// Max octree depth, or "auto" to estimate it from the triangle count
+    #[arg(long, value_name = "DEPTH", default_value = "6", value_parser = parse_max_depth)]
+    pub max_depth: MaxDepth,
+
+    /// Also split tiles whose estimated decoded size exceeds this many bytes
+    #[arg(long)]
+    pub max_tile_bytes: Option<u64>,
+
+    /// Simplifier weight for vertex normals (0 disables)
+    #[arg(long, default_value_t = 0.5)]
+    pub normal_weight: f32,
+
+    /// Simplifier weight for texture coordinates (0 disables)
+    #[arg(long, default_value_t = 1.0)]
+    pub uv_weight: f32,
+
+    /// Allow sloppy simplification when coarse LODs can't reach their target
+    #[arg(long)]
+    pub allow_sloppy: bool,
+
+    /// Simplify meshes above this many triangles in spatial chunks of at most this size (0 disables)
+    #[arg(long, value_name = "TRIANGLES", default_value_t = 4_000_000)]
+    pub simplify_chunk_triangles: usize,
+
+    /// Write uncompressed tiles instead of EXT_meshopt_compression, which viewers must support
+    #[arg(long)]
+    pub no_meshopt: bool,
+
+    /// Write quantized (KHR_mesh_quantization) tiles instead of meshopt-compressed ones
+    #[arg(long)]
+    pub quantize: bool,
+
+    /// Store normals of meshopt tiles octahedral-encoded in this many bits per coordinate (4-16)
+    #[arg(long, value_name = "BITS", value_parser = clap::value_parser!(u8).range(4..=16))]
+    pub oct_normals: Option<u8>,
+
+    /// 3D Tiles version to write: 1.1, or 1.0 for older viewers
+    #[arg(long, value_enum, default_value = "1.1")]
+    pub tiles_version: TilesVersion,
+
+    /// Spatial subdivision: octree, or quadtree for mostly flat terrain
+    #[arg(long, value_enum, default_value = "octree")]
+    pub scheme: Scheme,
+
+    /// Calibrate geometric error so tiles swap when their error reaches this many pixels
+    #[arg(long, value_name = "PX", value_parser = parse_target_sse)]
+    pub target_sse: Option<f64>,
+
+    /// Clamp every tile's geometric error, the root's included, to at most this value
+    #[arg(long, value_name = "ERROR", value_parser = parse_max_geometric_error)]
+    pub max_geometric_error: Option<f64>,
+
+    /// Multiply every tile's geometric error, the root's included, by this factor (applied before clamping)
+    #[arg(
+        long,
+        value_name = "FACTOR",
+        default_value = "1.0",
+        value_parser = parse_geometric_error_scale
+    )]
+    pub geometric_error_scale: f64,
+
+    /// Tile refinement: replace, or add to keep parent triangles and send only the rest to children
+    #[arg(long, value_enum, default_value = "replace")]
+    pub refine: Refine,
+
+    /// Boundary triangles: clip them, or assign each whole to its centroid's cell (fewer triangles)
+    #[arg(long, value_enum, default_value = "clip")]
+    pub split_mode: Split,
+
+    /// Merge small sibling leaves into one tile while their combined mesh still fits
+    #[arg(long)]
+    pub merge_leaves: bool,
+
+    /// Skip vertex welding when clipping cells: faster, but clipped edges no longer share vertices
+    #[arg(long)]
+    pub no_clip_weld: bool,
+
+    /// Sibling tile order in tileset.json: morton (octant order) or hilbert (better fetch locality)
+    #[arg(long, value_enum, default_value = "morton")]
+    pub tile_order: TileOrder,
+
+    /// Write per-object feature IDs and a property table of object names (EXT_mesh_features)
+    #[arg(long)]
+    pub feature_ids: bool,
+
+    /// Write tiles as b3dm with a _BATCHID per source object and a batch table (Cesium picking)
+    #[arg(long)]
+    pub b3dm: bool,
+
+    /// Prefix content URIs in tileset.json with this base (e.g. a CDN URL); files stay under --output
+    #[arg(long, value_name = "URL")]
+    pub uri_base: Option<String>,
+
+    /// Split tileset.json: tiles at every multiple of this depth get their own external tileset.json
+    #[arg(long, value_name = "DEPTH", value_parser = clap::value_parser!(u32).range(1..))]
+    pub external_tileset_depth: Option<u32>,
+
+    /// Write oriented (PCA-fitted) bounding boxes for leaf tiles and content, tighter around rotated geometry
+    #[arg(long)]
+    pub oriented_boxes: bool,
+
+    /// Subdivide a cube around the scene so octants stay cubic for elongated scenes
+    #[arg(long)]
+    pub cube_root: bool,
+
+    /// Tile each input mesh as its own subtree instead of merging them
+    #[arg(long)]
+    pub separate_objects: bool,
+
+    /// Tag every tile with its LOD level and triangle count in tileset.json `extras`
+    #[arg(long)]
+    pub tile_extras: bool,
 
     /// Disable Draco mesh compression
     #[arg(long)]
@@ -240,10 +881,68 @@ pub struct CliArgs {
     #[arg(long, default_value_t = 2048)]
     pub texture_max_size: u32,
 
+    /// Split a tile's atlas into several textures rather than exceed this size (GPU limit)
+    #[arg(
+        long,
+        value_name = "PX",
+        default_value_t = 8192,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub texture_hard_max_size: u32,
+
+    /// Downscale larger source textures to this dimension while decoding them
+    #[arg(long, value_name = "PX", default_value_t = 8192)]
+    pub texture_max_source_size: u32,
+
+    /// Cap the encoded atlas bytes of all tiles together, shrinking later atlases to fit
+    #[arg(long, value_name = "BYTES")]
+    pub max_texture_memory: Option<u64>,
+
+    /// Store identical atlas textures once as external files shared by tiles
+    #[arg(long)]
+    pub share_atlases: bool,
+
+    /// Allow rotating UV islands by 90° when packing atlases
+    #[arg(long)]
+    pub atlas_rotation: bool,
+
+    /// Keep full texture resolution on coarse LOD tiles
+    #[arg(long)]
+    pub no_lod_downscale: bool,
+
+    /// Trim fully transparent margins off atlas islands (for alpha-cut textures)
+    #[arg(long)]
+    pub trim_transparent: bool,
+
+    /// Bleed padding around atlas islands in pixels (default: 2-5 by island size)
+    #[arg(long, value_name = "PX")]
+    pub bleed_padding: Option<u32>,
+
     /// Run tileset validation after conversion
     #[arg(long)]
     pub validate: bool,
 
+    /// Validate an existing tileset.json (or the directory holding it) and exit
+    #[arg(long, value_name = "TILESET")]
+    pub validate_only: Option<PathBuf>,
+
+    /// Write a JSON conversion report (counts, sizes, stage timings) to this path
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+
+    /// Overwrite a non-empty output directory (removes previous tiles/ and tileset.json)
+    #[arg(long)]
+    pub force: bool,
+
+    /// Reuse ingested and transformed geometry from earlier runs with the same inputs (default dir: .photo-tiler-cache)
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        default_missing_value = crate::cache::DEFAULT_CACHE_DIR
+    )]
+    pub cache: Option<PathBuf>,
+
     /// Enable verbose logging
     #[arg(short = 'v', long)]
     pub verbose: bool,
@@ -253,6 +952,88 @@ pub struct CliArgs {
     pub threads: Option<usize>,
 }
 
+/// Parse `--scale X,Y,Z` into three positive factors.
+fn parse_scale(value: &str) -> Result<[f64; 3], String> {
+    let factors = value
+        .split(',')
+        .map(|part| {
+            let factor = part
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| format!("{part:?}: {e}"))?;
+            if factor.is_finite() && factor > 0.0 {
+                Ok(factor)
+            } else {
+                Err(format!("expected a positive factor, got {}", part.trim()))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    <[f64; 3]>::try_from(factors)
+        .map_err(|f| format!("expected three comma-separated factors, got {}", f.len()))
+}
+
+/// Parse `--target-sse`, which must be a positive pixel count.
+fn parse_target_sse(value: &str) -> Result<f64, String> {
+    let sse = value.trim().parse::<f64>().map_err(|e| e.to_string())?;
+    if sse.is_finite() && sse > 0.0 {
+        Ok(sse)
+    } else {
+        Err(format!("expected a positive number of pixels, got {value}"))
+    }
+}
+
+/// Parse `--max-geometric-error`, which must be a positive length.
+fn parse_max_geometric_error(value: &str) -> Result<f64, String> {
+    let error = value.trim().parse::<f64>().map_err(|e| e.to_string())?;
+    if error.is_finite() && error > 0.0 {
+        Ok(error)
+    } else {
+        Err(format!("expected a positive geometric error, got {value}"))
+    }
+}
+
+/// Parse `--geometric-error-scale`, which must be a positive factor.
+fn parse_geometric_error_scale(value: &str) -> Result<f64, String> {
+    let scale = value.trim().parse::<f64>().map_err(|e| e.to_string())?;
+    if scale.is_finite() && scale > 0.0 {
+        Ok(scale)
+    } else {
+        Err(format!("expected a positive scale, got {value}"))
+    }
+}
+
+/// Parse `--max-depth`, a depth or `auto`.
+fn parse_max_depth(value: &str) -> Result<MaxDepth, String> {
+    match value.trim() {
+        "auto" => Ok(MaxDepth::Auto),
+        depth => depth
+            .parse::<u32>()
+            .map(MaxDepth::Levels)
+            .map_err(|e| format!("expected a depth or \"auto\", got {value}: {e}")),
+    }
+}
+
+/// Parse `--origin-lat`, in degrees within [-90, 90].
+fn parse_latitude(value: &str) -> Result<f64, String> {
+    parse_degrees(value, 90.0)
+}
+
+/// Parse `--origin-lon`, in degrees within [-180, 180].
+fn parse_longitude(value: &str) -> Result<f64, String> {
+    parse_degrees(value, 180.0)
+}
+
+fn parse_degrees(value: &str, limit: f64) -> Result<f64, String> {
+    let degrees = value.trim().parse::<f64>().map_err(|e| e.to_string())?;
+    if (-limit..=limit).contains(&degrees) {
+        Ok(degrees)
+    } else {
+        Err(format!(
+            "expected degrees within [-{limit}, {limit}], got {value}"
+        ))
+    }
+}
+
 impl From<CliArgs> for PipelineConfig {
     fn from(args: CliArgs) -> Self {
         let georeference = args.epsg.map(|epsg| Georeference {
@@ -263,32 +1044,99 @@ impl From<CliArgs> for PipelineConfig {
             true_north: args.true_north,
         });
 
+        let origin = args
+            .origin_lat
+            .zip(args.origin_lon)
+            .map(|(lat, lon)| CartographicOrigin {
+                lon,
+                lat,
+                height: args.origin_height.unwrap_or(0.0),
+            });
+
+        let mut inputs = args.input.into_iter();
+
         PipelineConfig {
-            input: args.input,
-            output: args.output,
+            input: inputs.next().unwrap_or_default(),
+            extra_inputs: inputs.collect(),
+            output: args.output.unwrap_or_default(),
             units: args.units,
+            input_up: args.input_up,
+            recenter: args.center == Center::Centroid,
+            scale: args.scale,
+            keep_node_transforms: args.keep_node_transforms,
+            gpu_instancing: args.gpu_instancing,
+            analyze_mesh: args.analyze_mesh,
+            strict_textures: args.strict_textures,
+            flip_uv_v: args.flip_uv_v,
             georeference,
+            origin,
+            transform_file: args.transform_file,
+            transform_order: args.transform_order,
+            frame: args.frame,
             offset_file: args.offset_file,
             metadata_xml: args.metadata_xml,
             tiling: TilingConfig {
                 max_triangles_per_tile: args.max_triangles,
-                max_depth: args.max_depth,
+                max_depth: match args.max_depth {
+                    MaxDepth::Levels(depth) => depth,
+                    MaxDepth::Auto => TilingConfig::default().max_depth,
+                },
+                auto_depth: args.max_depth == MaxDepth::Auto,
+                max_tile_bytes: args.max_tile_bytes,
+                normal_weight: args.normal_weight,
+                uv_weight: args.uv_weight,
+                allow_sloppy: args.allow_sloppy,
+                simplify_chunk_triangles: args.simplify_chunk_triangles,
+                meshopt: !args.no_meshopt,
+                quantize: args.quantize,
+                oct_normal_bits: args.oct_normals,
+                tiles_version: args.tiles_version,
+                scheme: args.scheme,
+                target_sse: args.target_sse,
+                max_geometric_error: args.max_geometric_error,
+                geometric_error_scale: args.geometric_error_scale,
+                refine: args.refine,
+                split_mode: args.split_mode,
+                merge_leaves: args.merge_leaves,
+                clip_weld: !args.no_clip_weld,
+                tile_order: args.tile_order,
+                feature_ids: args.feature_ids,
+                b3dm: args.b3dm,
+                uri_base: args.uri_base,
+                external_tileset_depth: args.external_tileset_depth,
+                oriented_boxes: args.oriented_boxes,
+                cube_root: args.cube_root,
+                separate_objects: args.separate_objects,
+                tile_extras: args.tile_extras,
             },
             texture: TextureConfig {
                 format: args.texture_format,
                 quality: args.texture_quality,
                 max_size: args.texture_max_size,
+                hard_max_size: args.texture_hard_max_size,
+                max_source_size: args.texture_max_source_size,
+                max_memory: args.max_texture_memory,
                 enabled: !args.no_textures,
+                share_atlases: args.share_atlases,
+                allow_rotation: args.atlas_rotation,
+                lod_downscale: !args.no_lod_downscale,
+                trim_transparent: args.trim_transparent,
+                bleed_padding: args.bleed_padding,
             },
             draco: DracoConfig {
                 enabled: !args.no_draco,
                 level: args.draco_level,
             },
             validate: args.validate,
+            validate_only: args.validate_only,
             dry_run: args.dry_run,
             show_georef: args.show_georef,
+            json: args.json,
             verbose: args.verbose,
             threads: args.threads,
+            report: args.report,
+            force: args.force,
+            cache_dir: args.cache,
         }
     }
 }
@@ -302,6 +1150,28 @@ mod tests {
         let tc = TilingConfig::default();
         assert_eq!(tc.max_triangles_per_tile, 65_000);
         assert_eq!(tc.max_depth, 6);
+        assert!(!tc.auto_depth);
+        assert_eq!(tc.max_tile_bytes, None);
+        assert!((tc.normal_weight - 0.5).abs() < f32::EPSILON);
+        assert!((tc.uv_weight - 1.0).abs() < f32::EPSILON);
+        assert!(!tc.allow_sloppy);
+        assert_eq!(tc.simplify_chunk_triangles, 4_000_000);
+        assert!(tc.meshopt);
+        assert!(!tc.quantize);
+        assert_eq!(tc.tiles_version, TilesVersion::V1_1);
+        assert_eq!(tc.scheme, Scheme::Octree);
+        assert!(tc.target_sse.is_none());
+        assert!(tc.max_geometric_error.is_none());
+        assert_eq!(tc.geometric_error_scale, 1.0);
+        assert_eq!(tc.refine, Refine::Replace);
+        assert_eq!(tc.split_mode, Split::Clip);
+        assert!(!tc.merge_leaves);
+        assert!(tc.clip_weld);
+        assert_eq!(tc.tile_order, TileOrder::Morton);
+        assert!(!tc.b3dm);
+        assert!(!tc.cube_root);
+        assert!(!tc.separate_objects);
+        assert!(!tc.tile_extras);
     }
 
     #[test]
@@ -310,7 +1180,15 @@ mod tests {
         assert_eq!(tc.format, TextureFormat::WebP);
         assert_eq!(tc.quality, 85);
         assert_eq!(tc.max_size, 2048);
+        assert_eq!(tc.hard_max_size, 8192);
+        assert_eq!(tc.max_source_size, 8192);
+        assert_eq!(tc.max_memory, None);
         assert!(tc.enabled);
+        assert!(!tc.share_atlases);
+        assert!(!tc.allow_rotation);
+        assert!(tc.lod_downscale);
+        assert!(!tc.trim_transparent);
+        assert!(tc.bleed_padding.is_none());
     }
 
     #[test]
@@ -346,6 +1224,36 @@ mod tests {
             "./out",
             "--units",
             "m",
+            "--input-up",
+            "z",
+            "--center",
+            "none",
+            "--frame",
+            "local-enu",
+            "--scale",
+            "1,1,2.5",
+            "--tiles-version",
+            "1.0",
+            "--scheme",
+            "quadtree",
+            "--target-sse",
+            "8",
+            "--max-geometric-error",
+            "5000",
+            "--geometric-error-scale",
+            "0.5",
+            "--simplify-chunk-triangles",
+            "1000000",
+            "--refine",
+            "add",
+            "--split-mode",
+            "centroid",
+            "--tile-order",
+            "hilbert",
+            "--b3dm",
+            "--cube-root",
+            "--separate-objects",
+            "--tile-extras",
             "--epsg",
             "32636",
             "--easting",
@@ -359,7 +1267,11 @@ mod tests {
             "--no-draco",
             "--no-textures",
             "--validate",
+            "--report",
+            "report.json",
+            "--force",
             "--dry-run",
+            "--json",
             "-v",
             "-j",
             "8",
@@ -370,6 +1282,10 @@ mod tests {
         assert_eq!(config.input, PathBuf::from("model.obj"));
         assert_eq!(config.output, PathBuf::from("./out"));
         assert_eq!(config.units, Some(Units::Meters));
+        assert_eq!(config.input_up, UpAxis::ZUp);
+        assert!(!config.recenter);
+        assert_eq!(config.frame, Frame::LocalEnu);
+        assert_eq!(config.scale, Some([1.0, 1.0, 2.5]));
         assert!(config.georeference.is_some());
         let geo = config.georeference.unwrap();
         assert_eq!(geo.epsg, 32636);
@@ -377,22 +1293,196 @@ mod tests {
         assert!((geo.northing - 2_800_000.0).abs() < f64::EPSILON);
         assert_eq!(config.tiling.max_triangles_per_tile, 50_000);
         assert_eq!(config.tiling.max_depth, 4);
+        assert_eq!(config.tiling.tiles_version, TilesVersion::V1_0);
+        assert_eq!(config.tiling.scheme, Scheme::Quadtree);
+        assert_eq!(config.tiling.target_sse, Some(8.0));
+        assert_eq!(config.tiling.max_geometric_error, Some(5000.0));
+        assert_eq!(config.tiling.geometric_error_scale, 0.5);
+        assert_eq!(config.tiling.simplify_chunk_triangles, 1_000_000);
+        assert_eq!(config.tiling.refine, Refine::Add);
+        assert_eq!(config.tiling.split_mode, Split::Centroid);
+        assert_eq!(config.tiling.tile_order, TileOrder::Hilbert);
+        assert!(config.tiling.b3dm);
+        assert!(config.tiling.cube_root);
+        assert!(config.tiling.separate_objects);
+        assert!(config.tiling.tile_extras);
         assert!(!config.draco.enabled);
         assert!(!config.texture.enabled);
         assert!(config.validate);
+        assert_eq!(config.report, Some(PathBuf::from("report.json")));
+        assert!(config.force);
         assert!(config.dry_run);
+        assert!(config.json);
         assert!(config.verbose);
         assert_eq!(config.threads, Some(8));
     }
 
+    #[test]
+    fn cli_args_multiple_inputs() {
+        let args = CliArgs::parse_from([
+            "photo-tiler",
+            "-i",
+            "a.obj",
+            "b.ply",
+            "--input",
+            "c.glb",
+            "-o",
+            "out",
+        ]);
+        let config: PipelineConfig = args.into();
+
+        assert_eq!(config.input, PathBuf::from("a.obj"));
+        assert_eq!(
+            config.extra_inputs,
+            vec![PathBuf::from("b.ply"), PathBuf::from("c.glb")]
+        );
+        assert_eq!(config.inputs().count(), 3);
+    }
+
+    #[test]
+    fn cli_args_flip_uv_v_takes_a_bool() {
+        let parse = |extra: &[&str]| -> PipelineConfig {
+            let base = ["photo-tiler", "-i", "a.obj", "-o", "out"];
+            CliArgs::parse_from(base.iter().chain(extra)).into()
+        };
+        assert_eq!(parse(&[]).flip_uv_v, None);
+        assert_eq!(parse(&["--flip-uv-v", "false"]).flip_uv_v, Some(false));
+        assert_eq!(parse(&["--flip-uv-v", "true"]).flip_uv_v, Some(true));
+    }
+
+    #[test]
+    fn cli_args_cache_dir_is_optional() {
+        let parse = |extra: &[&str]| -> PipelineConfig {
+            let base = ["photo-tiler", "-i", "a.obj", "-o", "out"];
+            CliArgs::parse_from(base.iter().chain(extra)).into()
+        };
+        assert_eq!(parse(&[]).cache_dir, None);
+        assert_eq!(
+            parse(&["--cache"]).cache_dir,
+            Some(PathBuf::from(".photo-tiler-cache"))
+        );
+        assert_eq!(
+            parse(&["--cache", "/tmp/c"]).cache_dir,
+            Some(PathBuf::from("/tmp/c"))
+        );
+    }
+
+    #[test]
+    fn cli_args_validate_only_needs_no_input() {
+        let args = CliArgs::parse_from(["photo-tiler", "--validate-only", "other/tileset.json"]);
+        let config: PipelineConfig = args.into();
+
+        assert_eq!(
+            config.validate_only,
+            Some(PathBuf::from("other/tileset.json"))
+        );
+        assert_eq!(config.input, PathBuf::new());
+
+        assert!(CliArgs::try_parse_from(["photo-tiler", "-o", "out"]).is_err());
+    }
+
+    #[test]
+    fn cli_args_json_needs_dry_run_or_show_georef() {
+        let args = ["photo-tiler", "-i", "model.obj", "-o", "out", "--json"];
+        assert!(CliArgs::try_parse_from(args).is_err());
+        for flag in ["--dry-run", "--show-georef"] {
+            let args = CliArgs::try_parse_from(args.into_iter().chain([flag])).unwrap();
+            assert!(args.json);
+        }
+        let both = args.into_iter().chain(["--dry-run", "--show-georef"]);
+        assert!(CliArgs::try_parse_from(both).is_ok());
+    }
+
+    #[test]
+    fn cli_args_scale_needs_three_values() {
+        let parse = |scale: &str| {
+            CliArgs::try_parse_from(["photo-tiler", "-i", "a.obj", "-o", "out", "--scale", scale])
+        };
+        assert!(parse("1,1,2").is_ok());
+        assert!(parse("1,2").is_err());
+        assert!(parse("1,1,1,1").is_err());
+    }
+
+    #[test]
+    fn cli_args_scale_rejects_non_positive_factors() {
+        let parse = |scale: &str| {
+            CliArgs::try_parse_from(["photo-tiler", "-i", "a.obj", "-o", "out", "--scale", scale])
+        };
+        assert!(parse("0.5,0.5,2").is_ok());
+        assert!(parse("1,0,1").is_err());
+        assert!(parse("-1,1,1").is_err());
+        assert!(parse("1,1,inf").is_err());
+    }
+
+    #[test]
+    fn cli_args_cartographic_origin() {
+        let parse = |extra: &[&str]| {
+            let mut args = vec!["photo-tiler", "-i", "a.obj", "-o", "out"];
+            args.extend_from_slice(extra);
+            CliArgs::try_parse_from(args)
+        };
+
+        let config: PipelineConfig = parse(&["--origin-lat", "-33.86", "--origin-lon", "151.21"])
+            .unwrap()
+            .into();
+        assert_eq!(
+            config.origin,
+            Some(CartographicOrigin {
+                lon: 151.21,
+                lat: -33.86,
+                height: 0.0,
+            })
+        );
+
+        assert!(parse(&["--origin-lat", "-33.86"]).is_err());
+        assert!(parse(&["--origin-lat", "91", "--origin-lon", "0"]).is_err());
+        assert!(parse(&["--origin-lat", "0", "--origin-lon", "0", "--epsg", "4326"]).is_err());
+    }
+
+    #[test]
+    fn cli_args_max_depth_auto() {
+        let parse = |depth: &str| {
+            let args = vec!["photo-tiler", "-i", "a.obj", "-o", "out"];
+            CliArgs::try_parse_from(args.into_iter().chain(["--max-depth", depth]))
+        };
+
+        let config: PipelineConfig = parse("auto").unwrap().into();
+        assert!(config.tiling.auto_depth);
+        let config: PipelineConfig = parse("3").unwrap().into();
+        assert!(!config.tiling.auto_depth);
+        assert_eq!(config.tiling.max_depth, 3);
+        assert!(parse("deep").is_err());
+
+        assert_eq!(MaxDepth::Auto.to_string(), "auto");
+        assert_eq!(MaxDepth::Levels(3).to_string(), "3");
+    }
+
+    #[test]
+    fn cli_args_oct_normals_bits() {
+        let parse = |bits: &str| {
+            let args = vec!["photo-tiler", "-i", "a.obj", "-o", "out"];
+            CliArgs::try_parse_from(args.into_iter().chain(["--oct-normals", bits]))
+        };
+
+        let config: PipelineConfig = parse("10").unwrap().into();
+        assert_eq!(config.tiling.oct_normal_bits, Some(10));
+        assert!(parse("3").is_err());
+        assert!(parse("17").is_err());
+    }
+
     #[test]
     fn cli_args_minimal() {
         let args = CliArgs::parse_from(["photo-tiler", "-i", "test.glb", "-o", "output"]);
         let config: PipelineConfig = args.into();
 
         assert_eq!(config.input, PathBuf::from("test.glb"));
+        assert!(config.extra_inputs.is_empty());
         assert_eq!(config.output, PathBuf::from("output"));
         assert_eq!(config.units, None);
+        assert_eq!(config.input_up, UpAxis::YUp);
+        assert!(config.recenter);
+        assert_eq!(config.frame, Frame::Ecef);
+        assert_eq!(config.scale, None);
         assert!(config.georeference.is_none());
         assert!(config.draco.enabled);
         assert!(config.texture.enabled);
@@ -400,5 +1490,8 @@ mod tests {
         assert!(!config.dry_run);
         assert!(!config.verbose);
         assert_eq!(config.threads, None);
+        assert_eq!(config.report, None);
+        assert!(!config.force);
+        assert_eq!(config.validate_only, None);
     }
 }
@@ -1,5 +1,5 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use tracing::{debug, warn};
 
@@ -61,6 +61,33 @@ pub fn detect_georeference(config: &PipelineConfig) -> Result<Option<Georeferenc
     Ok(None)
 }
 
+/// Files [`detect_georeference`] and unit detection may read next to the
+/// primary input, whether they exist or not, so a cache can tell when one
+/// appears, changes or goes away.
+pub fn sidecar_files(config: &PipelineConfig) -> Vec<PathBuf> {
+    let input_dir = config.input.parent().unwrap_or_else(|| Path::new("."));
+    let mut files = vec![
+        config
+            .metadata_xml
+            .clone()
+            .unwrap_or_else(|| input_dir.join("metadata.xml")),
+        config
+            .offset_file
+            .clone()
+            .unwrap_or_else(|| input_dir.join("offset.xyz")),
+    ];
+    let mut prj: Vec<PathBuf> = fs::read_dir(input_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("prj"))
+        .collect();
+    prj.sort();
+    files.extend(prj);
+    files
+}
+
 /// Parse an `offset.xyz` file containing `easting northing elevation`.
 pub fn parse_offset_xyz(path: &Path) -> Result<(f64, f64, f64)> {
     let content = fs::read_to_string(path).map_err(|e| {
@@ -1,14 +1,88 @@
 use std::path::Path;
 
-use tracing::debug;
+use glam::{Mat3, Mat4, Vec3};
+use gltf::accessor::{DataType, Item, Iter};
+use serde::Deserialize;
+use tracing::{debug, warn};
 
 use crate::error::{PhotoTilerError, Result};
-use crate::types::{IndexedMesh, MaterialLibrary, PBRMaterial, TextureData};
+use crate::ingestion::flip_uv_v;
+use crate::types::{AlphaMode, IndexedMesh, MaterialLibrary, PBRMaterial, TextureData, WrapMode};
 
-/// Load a glTF or GLB file into our internal types.
-pub fn load_gltf(path: &Path) -> Result<(Vec<IndexedMesh>, MaterialLibrary)> {
-    let (document, buffers, images) = gltf::import(path)
-        .map_err(|e| PhotoTilerError::Input(format!("Failed to load glTF: {e}")))?;
+/// Extensions decoded by this loader on top of those the `gltf` crate
+/// validates itself.
+const SUPPORTED_EXTENSIONS: &[&str] = &[
+    "KHR_materials_emissive_strength",
+    "KHR_mesh_quantization",
+    "KHR_texture_transform",
+];
+
+/// `KHR_texture_transform` properties of a texture reference.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TextureTransform {
+    offset: [f32; 2],
+    /// Counter-clockwise rotation of the UVs in radians.
+    rotation: f32,
+    scale: [f32; 2],
+    /// Overrides the texture reference's UV set.
+    tex_coord: Option<u32>,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+            tex_coord: None,
+        }
+    }
+}
+
+impl TextureTransform {
+    /// Read the extension from a texture reference, if present and valid.
+    fn from_info(info: &gltf::texture::Info<'_>) -> Option<Self> {
+        let value = info.extensions()?.get("KHR_texture_transform")?;
+        match serde_json::from_value(value.clone()) {
+            Ok(transform) => Some(transform),
+            Err(e) => {
+                warn!("Ignoring malformed KHR_texture_transform: {e}");
+                None
+            }
+        }
+    }
+
+    /// Map a UV through offset * rotation * scale, as the extension defines.
+    fn apply(&self, uv: [f32; 2]) -> [f32; 2] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let u = uv[0] * self.scale[0];
+        let v = uv[1] * self.scale[1];
+        [
+            cos * u + sin * v + self.offset[0],
+            -sin * u + cos * v + self.offset[1],
+        ]
+    }
+}
+
+/// Load a glTF or GLB file into our internal types. `flip_v` flips texture V
+/// of both UV sets, for files authored with a bottom-left UV origin.
+pub fn load_gltf(path: &Path, flip_v: bool) -> Result<(Vec<IndexedMesh>, MaterialLibrary)> {
+    let (mut meshes, transforms, lib) = load_gltf_with_transforms(path, flip_v)?;
+    for (mesh, world) in meshes.iter_mut().zip(&transforms) {
+        bake_transform(mesh, Mat4::from_cols_array(&world.map(|v| v as f32)));
+    }
+    Ok((meshes, lib))
+}
+
+/// Like [`load_gltf`], but leave each mesh in the local space of the node
+/// that instances it and return that node's column-major world matrix,
+/// aligned with the meshes.
+pub fn load_gltf_with_transforms(
+    path: &Path,
+    flip_v: bool,
+) -> Result<(Vec<IndexedMesh>, Vec<[f64; 16]>, MaterialLibrary)> {
+    let (document, buffers, images) = import_gltf(path)?;
 
     debug!(
         meshes = document.meshes().len(),
@@ -16,22 +90,31 @@ pub fn load_gltf(path: &Path) -> Result<(Vec<IndexedMesh>, MaterialLibrary)> {
         "Loaded glTF document"
     );
 
-    let mut meshes = Vec::new();
+    let mut instances = Vec::new();
 
-    for mesh in document.meshes() {
-        for primitive in mesh.primitives() {
-            match extract_primitive(&primitive, &buffers) {
-                Ok(mut indexed) => {
-                    indexed.material_index = primitive.material().index();
-                    meshes.push(indexed);
-                }
-                Err(e) => {
-                    tracing::warn!(mesh = ?mesh.name(), "Skipping primitive: {e}");
-                }
+    // Walk the scene graph to pair each mesh with its node's world matrix.
+    // Files without any scene fall back to every mesh in its local space.
+    match document.default_scene().or_else(|| document.scenes().next()) {
+        Some(scene) => {
+            for node in scene.nodes() {
+                collect_node_meshes(&node, Mat4::IDENTITY, &buffers, &mut instances);
+            }
+        }
+        None => {
+            for mesh in document.meshes() {
+                collect_mesh_primitives(&mesh, Mat4::IDENTITY, &buffers, &mut instances);
             }
         }
     }
 
+    // After baking KHR_texture_transform, which maps glTF-space UVs
+    if flip_v {
+        for (mesh, _) in &mut instances {
+            flip_uv_v(&mut mesh.uvs);
+            flip_uv_v(&mut mesh.uvs1);
+        }
+    }
+
     let mut lib = MaterialLibrary::default();
 
     // Convert materials
@@ -44,7 +127,185 @@ pub fn load_gltf(path: &Path) -> Result<(Vec<IndexedMesh>, MaterialLibrary)> {
         lib.textures.push(convert_gltf_image(image_data));
     }
 
-    Ok((meshes, lib))
+    // External buffers and images, which `import_gltf` read next to the file
+    let base = path.parent().unwrap_or_else(|| Path::new("./"));
+    let buffer_uris = document
+        .buffers()
+        .filter_map(|buffer| match buffer.source() {
+            gltf::buffer::Source::Uri(uri) => Some(uri),
+            gltf::buffer::Source::Bin => None,
+        });
+    let image_uris = document.images().filter_map(|image| match image.source() {
+        gltf::image::Source::Uri { uri, .. } => Some(uri),
+        gltf::image::Source::View { .. } => None,
+    });
+    lib.source_files = buffer_uris
+        .chain(image_uris)
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(|uri| base.join(uri))
+        .collect();
+
+    // Carry the material texture samplers' wrap modes onto their images
+    for material in document.materials() {
+        let pbr = material.pbr_metallic_roughness();
+        let textures = [
+            pbr.base_color_texture().map(|info| info.texture()),
+            pbr.metallic_roughness_texture().map(|info| info.texture()),
+            material.normal_texture().map(|normal| normal.texture()),
+        ];
+        for texture in textures.into_iter().flatten() {
+            if let Some(tex) = lib.textures.get_mut(texture.source().index()) {
+                let sampler = texture.sampler();
+                tex.wrap = [sampler.wrap_s(), sampler.wrap_t()].map(convert_wrap_mode);
+            }
+        }
+    }
+
+    let (meshes, transforms) = instances
+        .into_iter()
+        .map(|(mesh, world)| (mesh, world.to_cols_array().map(f64::from)))
+        .unzip();
+    Ok((meshes, transforms, lib))
+}
+
+/// Import a glTF/GLB like `gltf::import`, but accept the required
+/// extensions in [`SUPPORTED_EXTENSIONS`], which this loader decodes itself.
+///
+/// Any other required extension the `gltf` crate does not know about is an
+/// error, since the file can't be read correctly without it. Unknown
+/// optional extensions are reported and ignored.
+fn import_gltf(
+    path: &Path,
+) -> Result<(
+    gltf::Document,
+    Vec<gltf::buffer::Data>,
+    Vec<gltf::image::Data>,
+)> {
+    let load_error = |e: gltf::Error| PhotoTilerError::Input(format!("Failed to load glTF: {e}"));
+    let base = path.parent().unwrap_or_else(|| Path::new("./"));
+    let bytes = std::fs::read(path).map_err(|e| load_error(gltf::Error::Io(e)))?;
+    let gltf::Gltf { document, blob } =
+        gltf::Gltf::from_slice_without_validation(&bytes).map_err(load_error)?;
+
+    let mut root = document.into_json();
+    let known = |ext: &str| {
+        SUPPORTED_EXTENSIONS.contains(&ext)
+            || gltf::json::extensions::ENABLED_EXTENSIONS.contains(&ext)
+    };
+    if let Some(ext) = root.extensions_required.iter().find(|ext| !known(ext)) {
+        return Err(PhotoTilerError::Input(format!(
+            "{}: unsupported required glTF extension {ext}",
+            path.display()
+        )));
+    }
+    for ext in root.extensions_used.iter().filter(|ext| !known(ext)) {
+        warn!(extension = %ext, "Ignoring unsupported optional glTF extension");
+    }
+    // Validation rejects required extensions the gltf crate doesn't enable
+    root.extensions_required
+        .retain(|ext| gltf::json::extensions::ENABLED_EXTENSIONS.contains(&ext.as_str()));
+
+    let document = gltf::Document::from_json(root).map_err(load_error)?;
+    let buffers = gltf::import_buffers(&document, Some(base), blob).map_err(load_error)?;
+    let images = gltf::import_images(&document, Some(base), &buffers).map_err(load_error)?;
+    Ok((document, buffers, images))
+}
+
+/// Recursively collect the meshes under `node`, accumulating its local TRS
+/// onto `parent` to form the world matrix. Meshes instanced by several nodes
+/// are emitted once per instance.
+fn collect_node_meshes(
+    node: &gltf::Node<'_>,
+    parent: Mat4,
+    buffers: &[gltf::buffer::Data],
+    meshes: &mut Vec<(IndexedMesh, Mat4)>,
+) {
+    let world = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+
+    if let Some(mesh) = node.mesh() {
+        collect_mesh_primitives(&mesh, world, buffers, meshes);
+    }
+
+    for child in node.children() {
+        collect_node_meshes(&child, world, buffers, meshes);
+    }
+}
+
+/// Extract every primitive of `mesh`, paired with the `world` matrix of the
+/// node instancing it.
+fn collect_mesh_primitives(
+    mesh: &gltf::Mesh<'_>,
+    world: Mat4,
+    buffers: &[gltf::buffer::Data],
+    meshes: &mut Vec<(IndexedMesh, Mat4)>,
+) {
+    for primitive in mesh.primitives() {
+        match extract_primitive(&primitive, buffers) {
+            Ok(mut indexed) => {
+                indexed.material_index = primitive.material().index();
+                if let Some(info) = primitive
+                    .material()
+                    .pbr_metallic_roughness()
+                    .base_color_texture()
+                {
+                    bake_texture_transform(&mut indexed, &info);
+                }
+                indexed.name = mesh.name().map(str::to_string);
+                meshes.push((indexed, world));
+            }
+            Err(e) => {
+                tracing::warn!(mesh = ?mesh.name(), "Skipping primitive: {e}");
+            }
+        }
+    }
+}
+
+/// Apply a node world matrix to positions and normals in place.
+///
+/// Normals use the inverse-transpose so non-uniform scale keeps them
+/// perpendicular; mirroring transforms flip the triangle winding.
+pub(crate) fn bake_transform(mesh: &mut IndexedMesh, world: Mat4) {
+    if world == Mat4::IDENTITY {
+        return;
+    }
+
+    for p in mesh.positions.chunks_exact_mut(3) {
+        let v = world.transform_point3(Vec3::new(p[0], p[1], p[2]));
+        p.copy_from_slice(&v.to_array());
+    }
+
+    let linear = Mat3::from_mat4(world);
+    let normal_matrix = linear.inverse().transpose();
+    for n in mesh.normals.chunks_exact_mut(3) {
+        let v = (normal_matrix * Vec3::new(n[0], n[1], n[2])).normalize_or_zero();
+        n.copy_from_slice(&v.to_array());
+    }
+
+    if linear.determinant() < 0.0 {
+        for tri in mesh.indices.chunks_exact_mut(3) {
+            tri.swap(1, 2);
+        }
+    }
+}
+
+/// Bake the base color texture's `KHR_texture_transform` into the UV set it
+/// samples, so atlas repacking sees the UVs the texture is actually read at.
+fn bake_texture_transform(mesh: &mut IndexedMesh, info: &gltf::texture::Info<'_>) {
+    let Some(transform) = TextureTransform::from_info(info) else {
+        return;
+    };
+    let uvs = match transform.tex_coord.unwrap_or(info.tex_coord()) {
+        0 => &mut mesh.uvs,
+        1 => &mut mesh.uvs1,
+        set => {
+            warn!(set, "Ignoring KHR_texture_transform on unsupported UV set");
+            return;
+        }
+    };
+    for uv in uvs.chunks_exact_mut(2) {
+        let t = transform.apply([uv[0], uv[1]]);
+        uv.copy_from_slice(&t);
+    }
 }
 
 /// Extract geometry from a single glTF primitive.
@@ -54,23 +315,31 @@ fn extract_primitive(
 ) -> Result<IndexedMesh> {
     let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
 
-    // Positions (required)
-    let positions: Vec<f32> = reader
-        .read_positions()
-        .ok_or_else(|| PhotoTilerError::Input("Primitive missing positions".into()))?
-        .flatten()
-        .collect();
+    // Positions (required). Positions, normals and UVs go through
+    // `read_attribute` so KHR_mesh_quantization integer data is dequantized;
+    // the node scale/offset that accompanies quantized positions is applied
+    // later with the node transform.
+    let positions: Vec<f32> = primitive
+        .get(&gltf::Semantic::Positions)
+        .and_then(|accessor| read_attribute::<3>(accessor, buffers))
+        .ok_or_else(|| PhotoTilerError::Input("Primitive missing positions".into()))?;
 
     // Normals (optional)
-    let normals: Vec<f32> = reader
-        .read_normals()
-        .map(|iter| iter.flatten().collect())
+    let normals: Vec<f32> = primitive
+        .get(&gltf::Semantic::Normals)
+        .and_then(|accessor| read_attribute::<3>(accessor, buffers))
         .unwrap_or_default();
 
     // UVs (optional, no V-flip needed for glTF)
-    let uvs: Vec<f32> = reader
-        .read_tex_coords(0)
-        .map(|iter| iter.into_f32().flatten().collect())
+    let uvs: Vec<f32> = primitive
+        .get(&gltf::Semantic::TexCoords(0))
+        .and_then(|accessor| read_attribute::<2>(accessor, buffers))
+        .unwrap_or_default();
+
+    // Second UV set (optional, e.g. lightmaps)
+    let uvs1: Vec<f32> = primitive
+        .get(&gltf::Semantic::TexCoords(1))
+        .and_then(|accessor| read_attribute::<2>(accessor, buffers))
         .unwrap_or_default();
 
     // Vertex colors (optional)
@@ -90,12 +359,70 @@ fn extract_primitive(
         positions,
         normals,
         uvs,
+        uvs1,
         colors,
+        feature_ids: Vec::new(),
         indices,
         material_index: None, // Set by caller
+        name: None,
+        material_groups: Vec::new(),
     })
 }
 
+/// Read a float vector attribute, dequantizing integer component types.
+///
+/// Normalized integers map to [0, 1] (unsigned) or [-1, 1] (signed) per the
+/// glTF spec; non-normalized integers are converted as-is. Returns `None`
+/// for unreadable accessors or unsupported component types.
+fn read_attribute<const N: usize>(
+    accessor: gltf::Accessor<'_>,
+    buffers: &[gltf::buffer::Data],
+) -> Option<Vec<f32>>
+where
+    [f32; N]: Item,
+    [i8; N]: Item,
+    [u8; N]: Item,
+    [i16; N]: Item,
+    [u16; N]: Item,
+{
+    let get_buffer_data =
+        |buffer: gltf::Buffer<'_>| buffers.get(buffer.index()).map(|d| d.0.as_slice());
+    let normalized = accessor.normalized();
+
+    let values = match accessor.data_type() {
+        DataType::F32 => Iter::<[f32; N]>::new(accessor, get_buffer_data)?
+            .flatten()
+            .collect(),
+        DataType::I8 => Iter::<[i8; N]>::new(accessor, get_buffer_data)?
+            .flatten()
+            .map(|c| dequantize(c as f32, i8::MAX as f32, normalized))
+            .collect(),
+        DataType::U8 => Iter::<[u8; N]>::new(accessor, get_buffer_data)?
+            .flatten()
+            .map(|c| dequantize(c as f32, u8::MAX as f32, normalized))
+            .collect(),
+        DataType::I16 => Iter::<[i16; N]>::new(accessor, get_buffer_data)?
+            .flatten()
+            .map(|c| dequantize(c as f32, i16::MAX as f32, normalized))
+            .collect(),
+        DataType::U16 => Iter::<[u16; N]>::new(accessor, get_buffer_data)?
+            .flatten()
+            .map(|c| dequantize(c as f32, u16::MAX as f32, normalized))
+            .collect(),
+        DataType::U32 => return None,
+    };
+    Some(values)
+}
+
+/// Convert one integer component to float, normalizing by `max` if needed.
+fn dequantize(value: f32, max: f32, normalized: bool) -> f32 {
+    if normalized {
+        (value / max).max(-1.0)
+    } else {
+        value
+    }
+}
+
 /// Convert a glTF material to our PBR material type.
 fn convert_gltf_material(material: &gltf::Material<'_>) -> PBRMaterial {
     let pbr = material.pbr_metallic_roughness();
@@ -104,6 +431,11 @@ fn convert_gltf_material(material: &gltf::Material<'_>) -> PBRMaterial {
     let base_color_texture = pbr
         .base_color_texture()
         .map(|info| info.texture().source().index());
+    let metallic_roughness_texture = pbr
+        .metallic_roughness_texture()
+        .map(|info| info.texture().source().index());
+    let normal = material.normal_texture();
+    let strength = emissive_strength(material);
 
     PBRMaterial {
         name: material.name().unwrap_or("").to_string(),
@@ -111,6 +443,43 @@ fn convert_gltf_material(material: &gltf::Material<'_>) -> PBRMaterial {
         metallic: pbr.metallic_factor(),
         roughness: pbr.roughness_factor(),
         base_color_texture,
+        metallic_roughness_texture,
+        normal_texture: normal.as_ref().map(|n| n.texture().source().index()),
+        normal_scale: normal.map_or(1.0, |n| n.scale()),
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask {
+                cutoff: material.alpha_cutoff().unwrap_or(0.5),
+            },
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        emissive: material.emissive_factor().map(|c| c * strength),
+    }
+}
+
+/// The material's `KHR_materials_emissive_strength`, or 1 when absent.
+fn emissive_strength(material: &gltf::Material<'_>) -> f32 {
+    let Some(value) = material
+        .extensions()
+        .and_then(|ext| ext.get("KHR_materials_emissive_strength"))
+    else {
+        return 1.0;
+    };
+    match value.get("emissiveStrength").map(serde_json::Value::as_f64) {
+        None => 1.0,
+        Some(Some(strength)) if strength >= 0.0 => strength as f32,
+        Some(_) => {
+            warn!("Ignoring malformed KHR_materials_emissive_strength");
+            1.0
+        }
+    }
+}
+
+fn convert_wrap_mode(mode: gltf::texture::WrappingMode) -> WrapMode {
+    match mode {
+        gltf::texture::WrappingMode::Repeat => WrapMode::Repeat,
+        gltf::texture::WrappingMode::MirroredRepeat => WrapMode::MirroredRepeat,
+        gltf::texture::WrappingMode::ClampToEdge => WrapMode::ClampToEdge,
     }
 }
 
@@ -131,6 +500,7 @@ fn convert_gltf_image(image_data: &gltf::image::Data) -> TextureData {
         mime_type: mime_type.to_string(),
         width: image_data.width,
         height: image_data.height,
+        wrap: [WrapMode::Repeat; 2],
     }
 }
 
@@ -149,6 +519,358 @@ mod tests {
         assert_eq!(mat.base_color_texture, None);
     }
 
+    /// Write a one-triangle glTF (with external .bin) using the given
+    /// `nodes`/`scenes` JSON and return its path.
+    fn write_triangle_gltf(dir: &Path, nodes: &str) -> std::path::PathBuf {
+        let mut bin = Vec::new();
+        for v in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [0.0f32, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0] {
+            bin.extend_from_slice(&v.to_le_bytes());
+        }
+        for i in [0u16, 1, 2, 0] {
+            bin.extend_from_slice(&i.to_le_bytes());
+        }
+        std::fs::write(dir.join("tri.bin"), &bin).unwrap();
+
+        let json = format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "buffers": [{{"uri": "tri.bin", "byteLength": {len}}}],
+  "bufferViews": [
+    {{"buffer": 0, "byteOffset": 0, "byteLength": 72}},
+    {{"buffer": 0, "byteOffset": 72, "byteLength": 6}}
+  ],
+  "accessors": [
+    {{"bufferView": 0, "byteOffset": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+      "min": [0, 0, 0], "max": [1, 1, 0]}},
+    {{"bufferView": 0, "byteOffset": 36, "componentType": 5126, "count": 3, "type": "VEC3"}},
+    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+  ],
+  "meshes": [{{"name": "tri", "primitives": [{{"attributes": {{"POSITION": 0, "NORMAL": 1}}, "indices": 2}}]}}],
+  {nodes}
+}}"#,
+            len = bin.len(),
+        );
+        let path = dir.join("tri.gltf");
+        std::fs::write(&path, json).unwrap();
+        path
+    }
+
+    #[test]
+    fn node_translation_is_baked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_triangle_gltf(
+            dir.path(),
+            r#""nodes": [{"mesh": 0, "translation": [10, 20, 30]}],
+  "scenes": [{"nodes": [0]}], "scene": 0"#,
+        );
+
+        let (meshes, _) = load_gltf(&path, false).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].positions[..3], [10.0, 20.0, 30.0]);
+        assert_eq!(meshes[0].positions[3..6], [11.0, 20.0, 30.0]);
+        assert_eq!(meshes[0].name.as_deref(), Some("tri"));
+    }
+
+    #[test]
+    fn node_transform_can_be_kept_unbaked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_triangle_gltf(
+            dir.path(),
+            r#""nodes": [{"mesh": 0, "translation": [10, 20, 30]}],
+  "scenes": [{"nodes": [0]}], "scene": 0"#,
+        );
+
+        let (meshes, transforms, _) = load_gltf_with_transforms(&path, false).unwrap();
+        assert_eq!(meshes[0].positions[3..6], [1.0, 0.0, 0.0]);
+        assert_eq!(transforms.len(), 1);
+        assert_eq!(transforms[0][12..], [10.0, 20.0, 30.0, 1.0]);
+    }
+
+    #[test]
+    fn nested_nodes_and_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        // Parent scales by 2 and translates; child rotates 90 degrees about
+        // Z. The mesh is also instanced directly at the scene root.
+        let path = write_triangle_gltf(
+            dir.path(),
+            r#""nodes": [
+    {"translation": [5, 0, 0], "scale": [2, 2, 2], "children": [1]},
+    {"mesh": 0, "rotation": [0, 0, 0.70710677, 0.70710677]},
+    {"mesh": 0}
+  ],
+  "scenes": [{"nodes": [0, 2]}]"#,
+        );
+
+        let (meshes, _) = load_gltf(&path, false).unwrap();
+        assert_eq!(meshes.len(), 2);
+
+        // Vertex (1,0,0) -> rotate -> (0,1,0) -> scale -> (0,2,0) -> translate
+        let nested = &meshes[0];
+        assert!((nested.positions[3] - 5.0).abs() < 1e-5);
+        assert!((nested.positions[4] - 2.0).abs() < 1e-5);
+        // Normals stay unit length under uniform scale
+        assert!((nested.normals[2] - 1.0).abs() < 1e-5);
+
+        let instance = &meshes[1];
+        assert_eq!(instance.positions[3..6], [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn mirrored_transform_flips_winding() {
+        let mut mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        };
+        bake_transform(&mut mesh, Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0)));
+        assert_eq!(mesh.indices, vec![0, 2, 1]);
+        assert_eq!(mesh.positions[3], -1.0);
+    }
+
+    /// Write a GLB whose triangle uses KHR_mesh_quantization attributes:
+    /// normalized SHORT positions (dequantized by a node scale of 2),
+    /// normalized BYTE normals and normalized BYTE UVs.
+    fn write_quantized_glb(dir: &Path) -> std::path::PathBuf {
+        let mut bin = Vec::new();
+        // Positions: i16 VEC3 padded to an 8-byte stride
+        for p in [[0i16, 0, 0], [i16::MAX, 0, 0], [0, i16::MAX, 0]] {
+            for c in p {
+                bin.extend_from_slice(&c.to_le_bytes());
+            }
+            bin.extend_from_slice(&[0, 0]);
+        }
+        // Normals: i8 VEC3 padded to a 4-byte stride
+        for _ in 0..3 {
+            bin.extend_from_slice(&[0, 0, i8::MAX as u8, 0]);
+        }
+        // UVs: i8 VEC2 padded to a 4-byte stride
+        for uv in [[0u8, 0], [127, 0], [0, 127]] {
+            bin.extend_from_slice(&[uv[0], uv[1], 0, 0]);
+        }
+        for i in [0u16, 1, 2, 0] {
+            bin.extend_from_slice(&i.to_le_bytes());
+        }
+
+        let json = format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "extensionsUsed": ["KHR_mesh_quantization"],
+  "extensionsRequired": ["KHR_mesh_quantization"],
+  "buffers": [{{"byteLength": {len}}}],
+  "bufferViews": [
+    {{"buffer": 0, "byteOffset": 0, "byteLength": 24, "byteStride": 8}},
+    {{"buffer": 0, "byteOffset": 24, "byteLength": 12, "byteStride": 4}},
+    {{"buffer": 0, "byteOffset": 36, "byteLength": 12, "byteStride": 4}},
+    {{"buffer": 0, "byteOffset": 48, "byteLength": 6}}
+  ],
+  "accessors": [
+    {{"bufferView": 0, "componentType": 5122, "normalized": true, "count": 3, "type": "VEC3",
+      "min": [0, 0, 0], "max": [32767, 32767, 0]}},
+    {{"bufferView": 1, "componentType": 5120, "normalized": true, "count": 3, "type": "VEC3"}},
+    {{"bufferView": 2, "componentType": 5120, "normalized": true, "count": 3, "type": "VEC2"}},
+    {{"bufferView": 3, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+  ],
+  "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2}}, "indices": 3}}]}}],
+  "nodes": [{{"mesh": 0, "scale": [2, 2, 2]}}],
+  "scenes": [{{"nodes": [0]}}]
+}}"#,
+            len = bin.len(),
+        );
+        let mut json_bytes = json.into_bytes();
+        while json_bytes.len() % 4 != 0 {
+            json_bytes.push(b' ');
+        }
+
+        let glb = gltf::binary::Glb {
+            header: gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                length: (12 + 8 + json_bytes.len() + 8 + bin.len()) as u32,
+            },
+            json: json_bytes.into(),
+            bin: Some(bin.into()),
+        };
+        let path = dir.join("quantized.glb");
+        std::fs::write(&path, glb.to_vec().unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn quantized_attributes_are_dequantized() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_quantized_glb(dir.path());
+
+        let (meshes, _) = load_gltf(&path, false).unwrap();
+        assert_eq!(meshes.len(), 1);
+        let mesh = &meshes[0];
+
+        let reference = [0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0];
+        for (got, want) in mesh.positions.iter().zip(reference) {
+            assert!((got - want).abs() < 1e-4, "position {got} != {want}");
+        }
+        assert!((mesh.normals[2] - 1.0).abs() < 1e-4);
+        assert_eq!(mesh.uvs, vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn texture_transform_offset_shifts_atlas_sampling() {
+        use crate::config::{TextureConfig, TextureFormat};
+        use crate::tiling::atlas_repacker::repack_atlas;
+
+        let dir = tempfile::tempdir().unwrap();
+        // Left half red, right half blue
+        let source = image::RgbaImage::from_fn(8, 8, |x, _| {
+            if x < 4 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+        source.save(dir.path().join("tex.png")).unwrap();
+
+        // A triangle whose UVs cover the red half, offset by 0.5 in U
+        let mut bin = Vec::new();
+        for v in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [0.05f32, 0.05, 0.45, 0.05, 0.05, 0.95] {
+            bin.extend_from_slice(&v.to_le_bytes());
+        }
+        for i in [0u16, 1, 2, 0] {
+            bin.extend_from_slice(&i.to_le_bytes());
+        }
+        std::fs::write(dir.path().join("tri.bin"), &bin).unwrap();
+        let json = format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "extensionsUsed": ["KHR_texture_transform"],
+  "buffers": [{{"uri": "tri.bin", "byteLength": {len}}}],
+  "bufferViews": [
+    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+    {{"buffer": 0, "byteOffset": 36, "byteLength": 24}},
+    {{"buffer": 0, "byteOffset": 60, "byteLength": 6}}
+  ],
+  "accessors": [
+    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+      "min": [0, 0, 0], "max": [1, 1, 0]}},
+    {{"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2"}},
+    {{"bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+  ],
+  "images": [{{"uri": "tex.png"}}],
+  "textures": [{{"source": 0}}],
+  "materials": [{{"pbrMetallicRoughness": {{"baseColorTexture": {{"index": 0,
+    "extensions": {{"KHR_texture_transform": {{"offset": [0.5, 0]}}}}}}}}}}],
+  "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0, "TEXCOORD_0": 1}},
+    "indices": 2, "material": 0}}]}}],
+  "nodes": [{{"mesh": 0}}],
+  "scenes": [{{"nodes": [0]}}]
+}}"#,
+            len = bin.len(),
+        );
+        let path = dir.path().join("tri.gltf");
+        std::fs::write(&path, json).unwrap();
+
+        let (meshes, lib) = load_gltf(&path, false).unwrap();
+        assert!((meshes[0].uvs[0] - 0.55).abs() < 1e-6);
+        assert!((meshes[0].uvs[2] - 0.95).abs() < 1e-6);
+
+        let config = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let result = repack_atlas(&meshes[0], &lib, &config).expect("should atlas");
+        let atlas = image::load_from_memory(&result.atlas_texture.data)
+            .unwrap()
+            .to_rgba8();
+
+        // The triangle's centroid samples the shifted (blue) region
+        let uvs = &result.mesh.uvs;
+        let u = (uvs[0] + uvs[2] + uvs[4]) / 3.0;
+        let v = (uvs[1] + uvs[3] + uvs[5]) / 3.0;
+        let x = (u * atlas.width() as f32) as u32;
+        let y = (v * atlas.height() as f32) as u32;
+        assert_eq!(atlas.get_pixel(x, y), &image::Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn texture_transform_rotation_and_scale() {
+        let transform = TextureTransform {
+            rotation: std::f32::consts::FRAC_PI_2,
+            scale: [2.0, 1.0],
+            ..Default::default()
+        };
+        let [u, v] = transform.apply([0.5, 0.25]);
+        // Scale to (1, 0.25), then rotate: u' = v, v' = -u
+        assert!((u - 0.25).abs() < 1e-6);
+        assert!((v + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn unsupported_required_extension_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let nodes = r#""nodes": [{"mesh": 0}],
+  "scenes": [{"nodes": [0]}]"#;
+        let optional = write_triangle_gltf(
+            dir.path(),
+            &format!(
+                r#""extensionsUsed": ["EXT_unknown"],
+  {nodes}"#
+            ),
+        );
+        assert!(load_gltf(&optional, false).is_ok());
+
+        let required = write_triangle_gltf(
+            dir.path(),
+            &format!(
+                r#""extensionsUsed": ["EXT_unknown"],
+  "extensionsRequired": ["EXT_unknown"],
+  {nodes}"#
+            ),
+        );
+        let err = load_gltf(&required, false).unwrap_err();
+        assert!(matches!(&err, PhotoTilerError::Input(msg) if msg.contains("EXT_unknown")));
+    }
+
+    #[test]
+    fn emissive_strength_scales_emissive_factor() {
+        use crate::tiling::glb_writer::write_glb;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_triangle_gltf(
+            dir.path(),
+            r#""extensionsUsed": ["KHR_materials_emissive_strength"],
+  "materials": [{"emissiveFactor": [1, 0.5, 0],
+    "extensions": {"KHR_materials_emissive_strength": {"emissiveStrength": 5}}}],
+  "nodes": [{"mesh": 0}],
+  "scenes": [{"nodes": [0]}]"#,
+        );
+
+        let (mut meshes, lib) = load_gltf(&path, false).unwrap();
+        assert_eq!(lib.materials[0].emissive, [5.0, 2.5, 0.0]);
+
+        meshes[0].material_index = Some(0);
+        let glb = write_glb(&meshes[0], &lib, None);
+        let (doc, _, _) = gltf::import_slice(&glb).unwrap();
+        let mat = doc.materials().next().unwrap();
+        assert_eq!(mat.emissive_factor(), [1.0, 0.5, 0.0]);
+        let ext = mat.extensions().unwrap();
+        assert_eq!(
+            ext["KHR_materials_emissive_strength"]["emissiveStrength"],
+            5.0
+        );
+    }
+
+    #[test]
+    fn dequantize_normalized_ranges() {
+        assert_eq!(dequantize(255.0, u8::MAX as f32, true), 1.0);
+        assert_eq!(dequantize(-128.0, i8::MAX as f32, true), -1.0);
+        assert_eq!(dequantize(-32767.0, i16::MAX as f32, true), -1.0);
+        assert_eq!(dequantize(300.0, u16::MAX as f32, false), 300.0);
+    }
+
     #[test]
     fn gltf_image_conversion() {
         let image_data = gltf::image::Data {
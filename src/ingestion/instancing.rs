@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use glam::{DMat4, Mat4};
+use tracing::info;
+
+use crate::ingestion::gltf_loader::bake_transform;
+use crate::types::IndexedMesh;
+
+/// Collapse identical meshes placed by several glTF nodes into one mesh per
+/// group, drawn once per node through EXT_mesh_gpu_instancing.
+///
+/// `node_transforms` holds each mesh's world matrix (`None` for identity).
+/// Returns the instance matrices per remaining mesh; an empty list marks a
+/// mesh drawn once. Instanced meshes stay in their local space with their
+/// `node_transforms` entry cleared. Unless `keep_node_transforms` is set,
+/// the matrices of meshes drawn once are baked into them as usual.
+pub fn group_instances(
+    meshes: &mut Vec<IndexedMesh>,
+    node_transforms: &mut Vec<Option<[f64; 16]>>,
+    keep_node_transforms: bool,
+) -> Vec<Vec<[f64; 16]>> {
+    node_transforms.resize(meshes.len(), None);
+    let identity = DMat4::IDENTITY.to_cols_array();
+
+    // Candidates by content hash, confirmed by comparing the geometry
+    let mut by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (i, mesh) in meshes.iter().enumerate() {
+        if !is_trs(&node_transforms[i].unwrap_or(identity)) {
+            continue;
+        }
+        let candidates = by_hash.entry(mesh_hash(mesh)).or_default();
+        match candidates
+            .iter()
+            .find(|&&g| same_geometry(&meshes[groups[g][0]], mesh))
+        {
+            Some(&g) => groups[g].push(i),
+            None => {
+                candidates.push(groups.len());
+                groups.push(vec![i]);
+            }
+        }
+    }
+
+    let mut instances = vec![Vec::new(); meshes.len()];
+    let mut duplicate = vec![false; meshes.len()];
+    for group in groups.iter().filter(|g| g.len() > 1) {
+        instances[group[0]] = group
+            .iter()
+            .map(|&i| node_transforms[i].unwrap_or(identity))
+            .collect();
+        node_transforms[group[0]] = None;
+        for &i in &group[1..] {
+            duplicate[i] = true;
+        }
+    }
+
+    let instanced = instances.iter().filter(|m| !m.is_empty()).count();
+    if instanced > 0 {
+        info!(
+            meshes = instanced,
+            copies_removed = duplicate.iter().filter(|&&d| d).count(),
+            "Grouped repeated meshes into GPU instances"
+        );
+    }
+
+    if !keep_node_transforms {
+        for (i, (mesh, world)) in meshes
+            .iter_mut()
+            .zip(node_transforms.iter_mut())
+            .enumerate()
+        {
+            if let Some(matrix) = world.take().filter(|_| !duplicate[i]) {
+                bake_transform(mesh, Mat4::from_cols_array(&matrix.map(|v| v as f32)));
+            }
+        }
+    }
+
+    *meshes = drop_marked(std::mem::take(meshes), &duplicate);
+    *node_transforms = drop_marked(std::mem::take(node_transforms), &duplicate);
+    drop_marked(instances, &duplicate)
+}
+
+fn drop_marked<T>(items: Vec<T>, marked: &[bool]) -> Vec<T> {
+    items
+        .into_iter()
+        .zip(marked)
+        .filter_map(|(item, &drop)| (!drop).then_some(item))
+        .collect()
+}
+
+/// Whether a matrix decomposes exactly into translation, rotation and a
+/// positive scale, the only placement an instance can carry.
+fn is_trs(matrix: &[f64; 16]) -> bool {
+    let m = DMat4::from_cols_array(matrix);
+    if m.row(3) != glam::DVec4::W || m.determinant() <= 0.0 {
+        return false;
+    }
+    let (scale, rotation, translation) = m.to_scale_rotation_translation();
+    DMat4::from_scale_rotation_translation(scale, rotation, translation).abs_diff_eq(m, 1e-6)
+}
+
+fn mesh_hash(mesh: &IndexedMesh) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for data in [
+        &mesh.positions,
+        &mesh.normals,
+        &mesh.uvs,
+        &mesh.uvs1,
+        &mesh.colors,
+    ] {
+        data.len().hash(&mut hasher);
+        for v in data {
+            v.to_bits().hash(&mut hasher);
+        }
+    }
+    mesh.indices.hash(&mut hasher);
+    mesh.material_index.hash(&mut hasher);
+    mesh.name.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn same_geometry(a: &IndexedMesh, b: &IndexedMesh) -> bool {
+    a.positions == b.positions
+        && a.normals == b.normals
+        && a.uvs == b.uvs
+        && a.uvs1 == b.uvs1
+        && a.colors == b.colors
+        && a.indices == b.indices
+        && a.material_index == b.material_index
+        && a.material_groups == b.material_groups
+        && a.name == b.name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(x: f32) -> IndexedMesh {
+        IndexedMesh {
+            positions: vec![x, 0.0, 0.0, x + 1.0, 0.0, 0.0, x, 1.0, 0.0],
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        }
+    }
+
+    fn translation(x: f64) -> Option<[f64; 16]> {
+        Some(DMat4::from_translation(glam::DVec3::new(x, 0.0, 0.0)).to_cols_array())
+    }
+
+    #[test]
+    fn identical_meshes_become_one_instanced_mesh() {
+        let mut meshes = vec![triangle(0.0), triangle(0.0), triangle(5.0), triangle(0.0)];
+        let mut transforms = vec![None, translation(10.0), translation(1.0), translation(20.0)];
+
+        let instances = group_instances(&mut meshes, &mut transforms, false);
+
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].len(), 3);
+        assert_eq!(instances[0][0], DMat4::IDENTITY.to_cols_array());
+        assert_eq!(instances[0][2][12], 20.0);
+        assert!(transforms.iter().all(Option::is_none));
+
+        // The distinct mesh is drawn once, its node translation baked in
+        assert!(instances[1].is_empty());
+        assert_eq!(meshes[1].positions[0], 6.0);
+    }
+
+    #[test]
+    fn sheared_placements_are_not_instanced() {
+        let mut shear = DMat4::IDENTITY.to_cols_array();
+        shear[4] = 0.5; // x += 0.5 y
+        let mut meshes = vec![triangle(0.0), triangle(0.0)];
+        let mut transforms = vec![None, Some(shear)];
+
+        let instances = group_instances(&mut meshes, &mut transforms, true);
+
+        assert_eq!(meshes.len(), 2);
+        assert!(instances.iter().all(Vec::is_empty));
+        // Kept node transforms survive for meshes drawn once
+        assert_eq!(transforms[1], Some(shear));
+    }
+}
@@ -1,27 +1,49 @@
 pub mod georef;
 pub mod gltf_loader;
+pub mod instancing;
 pub mod obj_loader;
+pub mod obj_parallel;
 pub mod ply_loader;
+pub mod topology;
+pub mod units;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use tracing::{debug, info};
+use glam::Mat4;
+use tracing::{debug, info, warn};
 
-use crate::config::{Georeference, PipelineConfig};
+use crate::config::{Georeference, PipelineConfig, Units};
 use crate::error::{PhotoTilerError, Result};
+use crate::tiling::simplifier::compact_mesh;
 use crate::types::{IndexedMesh, MaterialLibrary};
+use topology::MeshTopology;
 
 /// Result of the ingestion stage.
 #[derive(Debug)]
 pub struct IngestionResult {
     pub meshes: Vec<IndexedMesh>,
+    /// Column-major glTF node world matrix per mesh, kept with
+    /// `--keep-node-transforms`. The matrix is also baked into the mesh;
+    /// `None` for identity and for other formats.
+    pub node_transforms: Vec<Option<[f64; 16]>>,
+    /// Column-major world matrices of each mesh's instances with
+    /// `--gpu-instancing`. A mesh with a non-empty list is in its local
+    /// space and drawn once per matrix; others are drawn once.
+    pub instances: Vec<Vec<[f64; 16]>>,
     pub materials: MaterialLibrary,
     pub georeference: Option<Georeference>,
+    /// Units detected from input metadata when `config.units` is unset.
+    pub units: Option<Units>,
     pub stats: IngestionStats,
+    /// Every file ingestion read or looked for: the inputs, the files their
+    /// loaders opened and the georeference sidecars. The geometry cache
+    /// checks them before reusing an entry.
+    pub source_files: Vec<PathBuf>,
 }
 
 /// Statistics about the ingested data.
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct IngestionStats {
     pub total_vertices: usize,
     pub total_triangles: usize,
@@ -32,6 +54,13 @@ pub struct IngestionStats {
     pub texture_count: usize,
     pub material_count: usize,
     pub input_format: String,
+    /// Triangles removed for non-finite vertices or zero area.
+    pub dropped_triangles: usize,
+    /// Referenced textures that could not be found or read.
+    pub missing_textures: usize,
+    /// Edge and face defects, with `--analyze-mesh`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topology: Option<MeshTopology>,
 }
 
 /// Supported input formats.
@@ -80,31 +109,75 @@ impl std::fmt::Display for InputFormat {
 }
 
 /// Run the full ingestion stage.
+///
+/// Every input in `config.inputs()` is loaded and appended into one result,
+/// so several exported chunks become a single tileset.
 pub fn ingest(config: &PipelineConfig) -> Result<IngestionResult> {
-    // 1. Validate input exists
-    if !config.input.exists() {
-        return Err(PhotoTilerError::Input(format!(
-            "Input file not found: {}",
-            config.input.display()
-        )));
+    let mut meshes = Vec::new();
+    let mut node_transforms = Vec::new();
+    let mut materials = MaterialLibrary::default();
+    let mut formats: Vec<InputFormat> = Vec::new();
+
+    // 1. Load every input into one mesh list and material library
+    for path in config.inputs() {
+        let (input_meshes, input_transforms, input_materials, format) = load_input(path, config)?;
+        node_transforms.extend(input_transforms);
+        append_input(&mut meshes, &mut materials, input_meshes, input_materials);
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    if !config.extra_inputs.is_empty() {
+        info!(inputs = config.extra_inputs.len() + 1, "Merged input files");
     }
 
-    // 2. Detect format
-    let format = InputFormat::from_path(&config.input)?;
-    info!(format = %format, path = %config.input.display(), "Detected input format");
+    // 2. Drop triangles that would poison bounds and octree splits
+    let dropped_triangles: usize = meshes.iter_mut().map(sanitize_mesh).sum();
+    if dropped_triangles > 0 {
+        warn!(
+            triangles = dropped_triangles,
+            "Dropped triangles with non-finite vertices or zero area"
+        );
+    }
 
-    // 3. Dispatch to loader
-    let (meshes, materials) = match format {
-        InputFormat::Obj => obj_loader::load_obj(&config.input, config)?,
-        InputFormat::Gltf | InputFormat::Glb => gltf_loader::load_gltf(&config.input)?,
-        InputFormat::Ply => {
-            let mesh = ply_loader::load_ply(&config.input)?;
-            (vec![mesh], MaterialLibrary::default())
-        }
+    // 3. Draw meshes repeated by several glTF nodes as GPU instances
+    let instances = if config.gpu_instancing {
+        instancing::group_instances(&mut meshes, &mut node_transforms, config.keep_node_transforms)
+    } else {
+        Vec::new()
     };
 
     // 4. Compute stats
-    let stats = compute_stats(&meshes, &materials, format);
+    let mut stats = compute_stats(&meshes, &materials, formats[0]);
+    stats.dropped_triangles = dropped_triangles;
+    if formats.len() > 1 {
+        let names: Vec<&str> = formats.iter().map(InputFormat::as_str).collect();
+        stats.input_format = names.join("+");
+    }
+    if stats.missing_textures > 0 {
+        warn!(
+            textures = stats.missing_textures,
+            "Materials left untextured for missing textures; --strict-textures makes this an error"
+        );
+    }
+    if config.analyze_mesh {
+        let topology = topology::analyze_topology(&meshes);
+        info!(
+            boundary_edges = topology.boundary_edges,
+            non_manifold_edges = topology.non_manifold_edges,
+            duplicate_faces = topology.duplicate_faces,
+            "Mesh topology"
+        );
+        if topology.likely_to_crack() {
+            warn!(
+                boundary_edges = topology.boundary_edges,
+                non_manifold_edges = topology.non_manifold_edges,
+                duplicate_faces = topology.duplicate_faces,
+                "Input mesh is open or non-manifold; tiles may show cracks along their borders"
+            );
+        }
+        stats.topology = Some(topology);
+    }
     debug!(
         vertices = stats.total_vertices,
         triangles = stats.total_triangles,
@@ -112,7 +185,7 @@ pub fn ingest(config: &PipelineConfig) -> Result<IngestionResult> {
         "Ingestion stats"
     );
 
-    // 5. Detect georeferencing
+    // 5. Detect georeferencing (next to the primary input)
     let georeference = georef::detect_georeference(config)?;
     if let Some(ref geo) = georeference {
         info!(
@@ -124,14 +197,256 @@ pub fn ingest(config: &PipelineConfig) -> Result<IngestionResult> {
         );
     }
 
+    // 6. Detect units when not given (next to the primary input)
+    let units = if config.units.is_none() {
+        units::detect_units(config)
+    } else {
+        None
+    };
+    if let Some(units) = units {
+        info!(units = %units, "Detected input units");
+    }
+
+    let mut source_files: Vec<PathBuf> = config.inputs().cloned().collect();
+    source_files.extend(materials.source_files.iter().cloned());
+    source_files.extend(georef::sidecar_files(config));
+
     Ok(IngestionResult {
         meshes,
+        node_transforms,
+        instances,
         materials,
         georeference,
+        units,
         stats,
+        source_files,
     })
 }
 
+/// Validate, detect the format of and load a single input file.
+///
+/// Also returns the node transform to keep for each mesh (see
+/// [`IngestionResult::node_transforms`]).
+fn load_input(
+    path: &Path,
+    config: &PipelineConfig,
+) -> Result<(
+    Vec<IndexedMesh>,
+    Vec<Option<[f64; 16]>>,
+    MaterialLibrary,
+    InputFormat,
+)> {
+    // 1. Validate input exists
+    if !path.exists() {
+        return Err(PhotoTilerError::Input(format!(
+            "Input file not found: {}",
+            path.display()
+        )));
+    }
+
+    // 2. Detect format
+    let format = InputFormat::from_path(path)?;
+    info!(format = %format, path = %path.display(), "Detected input format");
+
+    // 3. Dispatch to loader
+    let (meshes, transforms, materials) = match format {
+        InputFormat::Obj => {
+            let (meshes, materials) = obj_loader::load_obj(path, config)?;
+            (meshes, Vec::new(), materials)
+        }
+        InputFormat::Gltf | InputFormat::Glb
+            if config.keep_node_transforms || config.gpu_instancing =>
+        {
+            gltf_loader::load_gltf_with_transforms(path, config.flip_uv_v.unwrap_or(false))?
+        }
+        InputFormat::Gltf | InputFormat::Glb => {
+            let (meshes, materials) =
+                gltf_loader::load_gltf(path, config.flip_uv_v.unwrap_or(false))?;
+            (meshes, Vec::new(), materials)
+        }
+        InputFormat::Ply => {
+            let (mesh, materials) = ply_loader::load_ply(path, config.flip_uv_v.unwrap_or(false))?;
+            (vec![mesh], Vec::new(), materials)
+        }
+    };
+
+    // 4. Fail on textures that were skipped, when asked to
+    if config.strict_textures && !materials.missing_textures.is_empty() {
+        return Err(PhotoTilerError::Input(format!(
+            "{}: missing or unreadable textures: {}",
+            path.display(),
+            materials.missing_textures.join(", ")
+        )));
+    }
+
+    // 5. Reject meshes later stages would index out of bounds
+    for mesh in &meshes {
+        validate_mesh(mesh).map_err(|e| match e {
+            PhotoTilerError::Input(msg) => {
+                PhotoTilerError::Input(format!("{}: {msg}", path.display()))
+            }
+            other => other,
+        })?;
+    }
+
+    // Identity transforms gain nothing from a tile of their own
+    let identity = Mat4::IDENTITY.to_cols_array().map(f64::from);
+    let mut transforms: Vec<Option<[f64; 16]>> = transforms
+        .into_iter()
+        .map(|t| (t != identity).then_some(t))
+        .collect();
+    transforms.resize(meshes.len(), None);
+
+    Ok((meshes, transforms, materials, format))
+}
+
+/// Append one input's meshes and materials to those already loaded.
+///
+/// Material indices on the new meshes and texture indices on the new
+/// materials are offset past the existing library entries.
+pub fn append_input(
+    meshes: &mut Vec<IndexedMesh>,
+    materials: &mut MaterialLibrary,
+    new_meshes: Vec<IndexedMesh>,
+    new_materials: MaterialLibrary,
+) {
+    let material_offset = materials.materials.len();
+    let texture_offset = materials.textures.len();
+
+    meshes.extend(new_meshes.into_iter().map(|mut mesh| {
+        mesh.material_index = mesh.material_index.map(|i| i + material_offset);
+        mesh
+    }));
+    materials
+        .materials
+        .extend(new_materials.materials.into_iter().map(|mut material| {
+            material.base_color_texture = material.base_color_texture.map(|i| i + texture_offset);
+            material.metallic_roughness_texture = material
+                .metallic_roughness_texture
+                .map(|i| i + texture_offset);
+            material.normal_texture = material.normal_texture.map(|i| i + texture_offset);
+            material
+        }));
+    materials.textures.extend(new_materials.textures);
+    materials
+        .missing_textures
+        .extend(new_materials.missing_textures);
+    materials.source_files.extend(new_materials.source_files);
+}
+
+/// Flip texture V (`v` to `1 - v`) between bottom-left and top-left origins.
+pub(crate) fn flip_uv_v(uvs: &mut [f32]) {
+    for uv in uvs.chunks_exact_mut(2) {
+        uv[1] = 1.0 - uv[1];
+    }
+}
+
+/// Check that a loaded mesh's attribute arrays and indices agree.
+///
+/// Every attribute must be empty or hold exactly one element per vertex,
+/// indices must form whole triangles of existing vertices, and material
+/// groups must cover whole triangles within the index buffer.
+pub fn validate_mesh(mesh: &IndexedMesh) -> Result<()> {
+    let label = match &mesh.name {
+        Some(name) => format!("mesh '{name}'"),
+        None => "mesh".to_string(),
+    };
+    let invalid = |msg: String| Err(PhotoTilerError::Input(format!("Invalid {label}: {msg}")));
+
+    if mesh.positions.len() % 3 != 0 {
+        return invalid(format!(
+            "{} position components is not a multiple of 3",
+            mesh.positions.len()
+        ));
+    }
+    let vertices = mesh.vertex_count();
+    let attributes = [
+        ("normals", mesh.normals.len(), 3),
+        ("uvs", mesh.uvs.len(), 2),
+        ("uvs1", mesh.uvs1.len(), 2),
+        ("colors", mesh.colors.len(), 4),
+        ("feature ids", mesh.feature_ids.len(), 1),
+    ];
+    for (name, len, stride) in attributes {
+        if len != 0 && len != vertices * stride {
+            return invalid(format!(
+                "{len} {name} components for {vertices} vertices (expected {})",
+                vertices * stride
+            ));
+        }
+    }
+
+    if mesh.indices.len() % 3 != 0 {
+        return invalid(format!(
+            "{} indices is not a multiple of 3",
+            mesh.indices.len()
+        ));
+    }
+    if let Some((at, &index)) = mesh
+        .indices
+        .iter()
+        .enumerate()
+        .find(|&(_, &i)| i as usize >= vertices)
+    {
+        return invalid(format!(
+            "index {index} at position {at} is out of range for {vertices} vertices"
+        ));
+    }
+
+    for group in &mesh.material_groups {
+        let range = &group.indices;
+        if range.start % 3 != 0 || range.end % 3 != 0 || range.end > mesh.indices.len() {
+            return invalid(format!(
+                "material group {range:?} does not cover whole triangles of {} indices",
+                mesh.indices.len()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Remove triangles with a non-finite vertex position or zero area, along
+/// with any vertices left unreferenced. Returns the number of triangles
+/// dropped.
+pub fn sanitize_mesh(mesh: &mut IndexedMesh) -> usize {
+    let position = |i: u32| {
+        let i = i as usize * 3;
+        [
+            mesh.positions[i],
+            mesh.positions[i + 1],
+            mesh.positions[i + 2],
+        ]
+    };
+
+    let kept: Vec<u32> = mesh
+        .indices
+        .chunks_exact(3)
+        .filter(|tri| {
+            let [a, b, c] = [position(tri[0]), position(tri[1]), position(tri[2])];
+            if [a, b, c].iter().flatten().any(|v| !v.is_finite()) {
+                return false;
+            }
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let cross = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            cross.iter().any(|&v| v != 0.0)
+        })
+        .flatten()
+        .copied()
+        .collect();
+
+    let dropped = mesh.triangle_count() - kept.len() / 3;
+    // Unreferenced non-finite vertices would still poison the bounding box
+    if dropped > 0 || mesh.positions.iter().any(|v| !v.is_finite()) {
+        *mesh = compact_mesh(kept, mesh);
+    }
+    dropped
+}
+
 /// Compute summary statistics from the ingested meshes and materials.
 pub fn compute_stats(
     meshes: &[IndexedMesh],
@@ -154,6 +469,9 @@ pub fn compute_stats(
         texture_count: materials.textures.len(),
         material_count: materials.materials.len(),
         input_format: format.to_string(),
+        dropped_triangles: 0,
+        missing_textures: materials.missing_textures.len(),
+        topology: None,
     }
 }
 
@@ -218,17 +536,25 @@ mod tests {
                 positions: vec![0.0; 9],
                 normals: vec![0.0; 9],
                 uvs: vec![0.0; 6],
+                uvs1: Vec::new(),
                 colors: vec![],
+                feature_ids: Vec::new(),
                 indices: vec![0, 1, 2],
                 material_index: Some(0),
+                name: None,
+                material_groups: Vec::new(),
             },
             IndexedMesh {
                 positions: vec![0.0; 12],
                 normals: vec![],
                 uvs: vec![],
+                uvs1: Vec::new(),
                 colors: vec![0.0; 16],
+                feature_ids: Vec::new(),
                 indices: vec![0, 1, 2, 0, 2, 3],
                 material_index: None,
+                name: None,
+                material_groups: Vec::new(),
             },
         ];
 
@@ -248,6 +574,181 @@ mod tests {
         assert_eq!(stats.input_format, "OBJ");
     }
 
+    #[test]
+    fn append_input_offsets_material_and_texture_indices() {
+        let textured = |name: &str| PBRMaterial {
+            name: name.into(),
+            base_color_texture: Some(0),
+            ..Default::default()
+        };
+        let texture = || crate::types::TextureData {
+            data: vec![0],
+            mime_type: "image/png".into(),
+            width: 1,
+            height: 1,
+            wrap: Default::default(),
+        };
+        let library = |name: &str| MaterialLibrary {
+            materials: vec![PBRMaterial::default(), textured(name)],
+            textures: vec![texture()],
+            features: Vec::new(),
+            missing_textures: Vec::new(),
+            source_files: Vec::new(),
+        };
+        let mesh = |material_index| IndexedMesh {
+            positions: vec![0.0; 9],
+            indices: vec![0, 1, 2],
+            material_index,
+            ..Default::default()
+        };
+
+        let mut meshes = Vec::new();
+        let mut materials = MaterialLibrary::default();
+        append_input(&mut meshes, &mut materials, vec![mesh(Some(1))], library("a"));
+        append_input(
+            &mut meshes,
+            &mut materials,
+            vec![mesh(Some(1)), mesh(None)],
+            library("b"),
+        );
+
+        assert_eq!(meshes.len(), 3);
+        assert_eq!(meshes[0].material_index, Some(1));
+        assert_eq!(meshes[1].material_index, Some(3));
+        assert_eq!(meshes[2].material_index, None);
+        assert_eq!(materials.materials.len(), 4);
+        assert_eq!(materials.textures.len(), 2);
+        assert_eq!(materials.materials[3].name, "b");
+        assert_eq!(materials.materials[3].base_color_texture, Some(1));
+    }
+
+    #[test]
+    fn validate_rejects_short_normals() {
+        let mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            indices: vec![0, 1, 2],
+            name: Some("wall".into()),
+            ..Default::default()
+        };
+
+        let err = validate_mesh(&mesh).unwrap_err();
+
+        assert!(matches!(err, PhotoTilerError::Input(_)));
+        let msg = err.to_string();
+        assert!(msg.contains("'wall'"), "{msg}");
+        assert!(msg.contains("6 normals components for 3 vertices (expected 9)"), "{msg}");
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_index() {
+        let mut mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            indices: vec![0, 1, 2, 2, 1, 3],
+            ..Default::default()
+        };
+
+        let err = validate_mesh(&mesh).unwrap_err();
+
+        assert!(matches!(err, PhotoTilerError::Input(_)));
+        let msg = err.to_string();
+        assert!(msg.contains("index 3 at position 5 is out of range for 3 vertices"), "{msg}");
+
+        mesh.indices.truncate(3);
+        assert!(validate_mesh(&mesh).is_ok());
+    }
+
+    #[test]
+    fn sanitize_drops_nan_and_zero_area_triangles() {
+        let mut positions = vec![
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0,
+        ];
+        positions[12] = f32::NAN;
+        let mut mesh = IndexedMesh {
+            positions,
+            uvs: vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.5, 0.5],
+            // A good quad, a triangle on the NaN vertex and a collapsed one
+            indices: vec![0, 1, 2, 0, 2, 3, 1, 4, 2, 0, 0, 3],
+            ..Default::default()
+        };
+
+        let dropped = sanitize_mesh(&mut mesh);
+
+        assert_eq!(dropped, 2);
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.uvs.len(), 8);
+        assert!(mesh.positions.iter().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn sanitize_keeps_clean_mesh_untouched() {
+        let mut mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        };
+
+        assert_eq!(sanitize_mesh(&mut mesh), 0);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert_eq!(mesh.vertex_count(), 3);
+    }
+
+    #[test]
+    fn analyze_mesh_reports_open_edges() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("quad.obj");
+        // A single open quad
+        std::fs::write(&input, "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3\nf 1 3 4\n")
+            .unwrap();
+        let config = PipelineConfig {
+            input,
+            analyze_mesh: true,
+            ..Default::default()
+        };
+
+        let stats = ingest(&config).unwrap().stats;
+
+        let topology = stats.topology.expect("analysis requested");
+        assert_eq!(topology.boundary_edges, 4);
+        assert_eq!(topology.edges, 5);
+        assert!(topology.likely_to_crack());
+
+        let config = PipelineConfig {
+            analyze_mesh: false,
+            ..config
+        };
+        assert!(ingest(&config).unwrap().stats.topology.is_none());
+    }
+
+    #[test]
+    fn dangling_texture_is_counted_or_fails_strict_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("wall.obj");
+        std::fs::write(dir.path().join("wall.mtl"), "newmtl wall\nmap_Kd gone.jpg\n").unwrap();
+        std::fs::write(
+            &input,
+            "mtllib wall.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nusemtl wall\nf 1/1 2/1 3/1\n",
+        )
+        .unwrap();
+        let config = PipelineConfig {
+            input,
+            ..Default::default()
+        };
+
+        let ingestion = ingest(&config).unwrap();
+        assert_eq!(ingestion.stats.missing_textures, 1);
+        assert_eq!(ingestion.materials.materials[0].base_color_texture, None);
+
+        let config = PipelineConfig {
+            strict_textures: true,
+            ..config
+        };
+        let err = ingest(&config).unwrap_err();
+        assert!(matches!(err, PhotoTilerError::Input(_)));
+        assert!(err.to_string().contains("gone.jpg"), "{err}");
+    }
+
     #[test]
     fn ingest_missing_file() {
         let config = PipelineConfig {
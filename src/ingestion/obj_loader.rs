@@ -1,49 +1,118 @@
-use std::path::Path;
+use std::cell::RefCell;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
 
+use rayon::prelude::*;
 use tracing::{debug, warn};
 
 use crate::config::PipelineConfig;
 use crate::error::{PhotoTilerError, Result};
-use crate::types::{IndexedMesh, MaterialLibrary, PBRMaterial, TextureData};
+use crate::ingestion::flip_uv_v;
+use crate::ingestion::obj_parallel::{PARALLEL_MIN_BYTES, load_obj_parallel};
+use crate::types::{AlphaMode, IndexedMesh, MaterialLibrary, PBRMaterial, TextureData, WrapMode};
+
+/// A material from an MTL file, with the directory of that file.
+pub(crate) type ObjMaterial = (tobj::Material, PathBuf);
+
+/// A parsed OBJ file: its models, its materials and the MTL files it names.
+pub(crate) type ObjContents = (Vec<tobj::Model>, Vec<ObjMaterial>, Vec<PathBuf>);
 
 /// Load an OBJ file (+ associated MTL and textures) into our internal types.
 pub fn load_obj(path: &Path, config: &PipelineConfig) -> Result<(Vec<IndexedMesh>, MaterialLibrary)> {
-    let (models, materials_result) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)
-        .map_err(|e| PhotoTilerError::Input(format!("Failed to load OBJ: {e}")))?;
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let (mut models, tobj_materials, mtl_files) = if size >= PARALLEL_MIN_BYTES {
+        load_obj_parallel(path)?
+    } else {
+        load_obj_sequential(path)?
+    };
 
     debug!(model_count = models.len(), "Loaded OBJ models");
+    normalize_vertex_colors(&mut models);
 
     let obj_dir = path.parent().unwrap_or_else(|| Path::new("."));
 
-    let tobj_materials = match materials_result {
-        Ok(mats) => mats,
+    let mut material_lib = convert_materials(&tobj_materials, obj_dir, config)?;
+    material_lib.source_files.splice(0..0, mtl_files);
+
+    // OBJ UVs have a bottom-left origin, glTF's top-left
+    let flip_v = config.flip_uv_v.unwrap_or(true);
+    let meshes: Vec<IndexedMesh> = models
+        .into_par_iter()
+        .map(|model| convert_mesh(model, flip_v))
+        .collect();
+
+    Ok((meshes, material_lib))
+}
+
+/// Parse an OBJ file with tobj on the calling thread.
+///
+/// Each material is paired with the directory of the MTL file it came from.
+/// Also returns the path of every `mtllib`, loaded or not.
+fn load_obj_sequential(path: &Path) -> Result<ObjContents> {
+    let obj_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file = std::fs::File::open(path)?;
+    // tobj appends each MTL's materials in load order
+    let mtl_dirs = RefCell::new(Vec::new());
+    let mtl_files = RefCell::new(Vec::new());
+    let (models, materials_result) = tobj::load_obj_buf(
+        &mut BufReader::new(file),
+        &tobj::GPU_LOAD_OPTIONS,
+        |mtl_path| {
+            let mtl_path = obj_dir.join(mtl_path);
+            let result = tobj::load_mtl(&mtl_path);
+            mtl_files.borrow_mut().push(mtl_path.clone());
+            if let Ok((mats, _)) = &result {
+                let dir = mtl_path.parent().unwrap_or(obj_dir).to_path_buf();
+                mtl_dirs.borrow_mut().extend(vec![dir; mats.len()]);
+            }
+            result
+        },
+    )
+    .map_err(|e| PhotoTilerError::Input(format!("Failed to load OBJ: {e}")))?;
+
+    let materials = match materials_result {
+        Ok(mats) => mats.into_iter().zip(mtl_dirs.into_inner()).collect(),
         Err(e) => {
             warn!("Failed to load MTL: {e}");
             Vec::new()
         }
     };
+    Ok((models, materials, mtl_files.into_inner()))
+}
 
-    let material_lib = convert_materials(&tobj_materials, obj_dir, config)?;
-
-    let meshes: Vec<IndexedMesh> = models
-        .into_iter()
-        .map(|model| convert_mesh(model.mesh))
-        .collect();
-
-    Ok((meshes, material_lib))
+/// Bring `v x y z r g b` colors into `[0, 1]`.
+///
+/// Most exporters write floats, but some (MeshLab's integer mode, a few
+/// photogrammetry tools) write 0-255. Any component above 1 anywhere in the
+/// file means the whole file uses the byte range.
+fn normalize_vertex_colors(models: &mut [tobj::Model]) {
+    let bytes = models
+        .iter()
+        .flat_map(|m| &m.mesh.vertex_color)
+        .any(|&c| c > 1.0);
+    if bytes {
+        debug!("OBJ vertex colors are in 0-255; normalizing");
+    }
+    let scale = if bytes { 1.0 / 255.0 } else { 1.0 };
+    for c in models.iter_mut().flat_map(|m| &mut m.mesh.vertex_color) {
+        *c = (*c * scale).clamp(0.0, 1.0);
+    }
 }
 
-/// Convert a `tobj::Mesh` into our `IndexedMesh`.
-fn convert_mesh(mesh: tobj::Mesh) -> IndexedMesh {
+/// Convert a `tobj::Model` into our `IndexedMesh`, keeping its `o`/`g` name.
+/// `flip_v` flips texture V into glTF's top-left origin.
+fn convert_mesh(model: tobj::Model, flip_v: bool) -> IndexedMesh {
+    let mesh = model.mesh;
+    // tobj names objects without an `o`/`g` statement "unnamed_object"
+    let name = Some(model.name).filter(|n| !n.is_empty() && n != "unnamed_object");
+
     let positions = mesh.positions;
     let normals = mesh.normals;
 
-    // UV V-flip: OBJ uses bottom-left origin, glTF uses top-left
-    let uvs: Vec<f32> = mesh
-        .texcoords
-        .chunks_exact(2)
-        .flat_map(|uv| [uv[0], 1.0 - uv[1]])
-        .collect();
+    let mut uvs = mesh.texcoords;
+    if flip_v {
+        flip_uv_v(&mut uvs);
+    }
 
     // Vertex colors: expand RGB (3 components) to RGBA (4 components, alpha=1.0)
     let colors: Vec<f32> = mesh
@@ -58,52 +127,92 @@ fn convert_mesh(mesh: tobj::Mesh) -> IndexedMesh {
         positions,
         normals,
         uvs,
+        uvs1: Vec::new(),
         colors,
+        feature_ids: Vec::new(),
         indices: mesh.indices,
         material_index,
+        name,
+        material_groups: Vec::new(),
     }
 }
 
 /// Convert tobj materials into our `MaterialLibrary`.
 fn convert_materials(
-    tobj_mats: &[tobj::Material],
+    tobj_mats: &[ObjMaterial],
     obj_dir: &Path,
     config: &PipelineConfig,
 ) -> Result<MaterialLibrary> {
     let mut lib = MaterialLibrary::default();
 
-    for mat in tobj_mats {
+    for (mat, mtl_dir) in tobj_mats {
+        // d (or Tr = 1 - d) -> alpha, blended when not fully opaque
+        let alpha = mat
+            .dissolve
+            .or_else(|| {
+                let tr: f32 = mat.unknown_param.get("Tr")?.parse().ok()?;
+                Some(1.0 - tr)
+            })
+            .unwrap_or(1.0)
+            .clamp(0.0, 1.0);
+        let diffuse = mat.diffuse.unwrap_or([1.0; 3]);
+
         let mut pbr = PBRMaterial {
             name: mat.name.clone(),
-            metallic: 0.0,
-            roughness: 1.0,
+            // Kd -> base_color
+            base_color: [diffuse[0], diffuse[1], diffuse[2], alpha],
+            // Ks -> metallic
+            metallic: mat
+                .specular
+                .map_or(0.0, |specular| solve_metallic(diffuse, specular)),
+            // Ns -> roughness
+            roughness: mat.shininess.map_or(1.0, shininess_to_roughness),
+            alpha_mode: if alpha < 1.0 {
+                AlphaMode::Blend
+            } else {
+                AlphaMode::Opaque
+            },
             ..Default::default()
         };
 
-        // Kd -> base_color
-        if let Some(diffuse) = mat.diffuse {
-            pbr.base_color = [
-                diffuse[0],
-                diffuse[1],
-                diffuse[2],
-                mat.dissolve.unwrap_or(1.0),
-            ];
-        }
-
-        // Load diffuse texture (map_Kd)
         if config.texture.enabled {
-            if let Some(ref tex_name) = mat.diffuse_texture {
-                let tex_path = obj_dir.join(tex_name);
-                match load_texture(&tex_path) {
-                    Ok(tex) => {
-                        let tex_idx = lib.textures.len();
+            let mut load_map = |statement: &str| {
+                let map = MapStatement::parse(statement);
+                let path = resolve_texture_path(map.file, mtl_dir, obj_dir);
+                // A texture that shows up later should be found where the MTL points
+                let source = path.clone().unwrap_or_else(|| mtl_dir.join(map.file));
+                lib.source_files.push(source);
+                match path.map(|p| load_texture(&p)) {
+                    Some(Ok(tex)) => {
                         lib.textures.push(tex);
-                        pbr.base_color_texture = Some(tex_idx);
+                        Some((lib.textures.len() - 1, map.bump_multiplier))
+                    }
+                    Some(Err(e)) => {
+                        warn!(texture = %map.file, "Failed to load texture: {e}");
+                        lib.missing_textures.push(map.file.to_string());
+                        None
                     }
-                    Err(e) => {
-                        warn!(texture = %tex_name, "Failed to load texture: {e}");
+                    None => {
+                        warn!(texture = %map.file, "Texture not found next to the MTL or OBJ file");
+                        lib.missing_textures.push(map.file.to_string());
+                        None
                     }
                 }
+            };
+
+            // map_Kd -> base color texture
+            if let Some(ref statement) = mat.diffuse_texture {
+                pbr.base_color_texture = load_map(statement).map(|(index, _)| index);
+            }
+
+            // map_Bump / bump / norm -> normal texture, -bm as its scale
+            let normal_map = mat
+                .normal_texture
+                .as_ref()
+                .or_else(|| mat.unknown_param.get("norm"));
+            if let Some((index, bump_multiplier)) = normal_map.and_then(|s| load_map(s)) {
+                pbr.normal_texture = Some(index);
+                pbr.normal_scale = bump_multiplier.unwrap_or(1.0);
             }
         }
 
@@ -113,18 +222,127 @@ fn convert_materials(
     Ok(lib)
 }
 
-/// Load a texture file: read raw bytes and decode for width/height.
-fn load_texture(path: &Path) -> Result<TextureData> {
+/// A texture map statement's file name and the options this loader uses.
+struct MapStatement<'a> {
+    file: &'a str,
+    /// `-bm`: bump multiplier.
+    bump_multiplier: Option<f32>,
+}
+
+impl<'a> MapStatement<'a> {
+    /// Split `-option value...` prefixes off a map statement such as
+    /// `-bm 0.5 -s 2 2 normal map.png`. The file name is the rest, which may
+    /// contain spaces.
+    fn parse(statement: &'a str) -> Self {
+        let mut rest = statement.trim();
+        let mut bump_multiplier = None;
+        while let Some(option) = rest.strip_prefix('-') {
+            let (name, mut args) = split_word(option);
+            let arg_count = match name {
+                "blendu" | "blendv" | "bm" | "boost" | "cc" | "clamp" | "imfchan" | "texres" => 1,
+                "mm" => 2,
+                // Up to three numbers
+                "o" | "s" | "t" => 3,
+                _ => break,
+            };
+            for i in 0..arg_count {
+                let (arg, after) = split_word(args);
+                let is_number = arg.parse::<f32>().is_ok();
+                if i > 0 && !is_number {
+                    break;
+                }
+                if name == "bm" {
+                    bump_multiplier = arg.parse().ok();
+                }
+                args = after;
+            }
+            rest = args;
+        }
+        Self {
+            file: rest,
+            bump_multiplier,
+        }
+    }
+}
+
+/// Split the first whitespace-separated word off `s`.
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    (&s[..end], s[end..].trim_start())
+}
+
+/// Perceptual roughness for a Blinn-Phong specular exponent,
+/// `sqrt(2 / (Ns + 2))`: 1 for `Ns = 0`, falling towards 0 as highlights
+/// get tighter.
+fn shininess_to_roughness(ns: f32) -> f32 {
+    (2.0 / (ns.max(0.0) + 2.0)).sqrt()
+}
+
+/// Metalness from MTL diffuse (`Kd`) and specular (`Ks`) colors, solved as in
+/// the Khronos specular-glossiness to metallic-roughness conversion:
+/// dielectrics reflect 4%, so specular above that with little diffuse left
+/// reads as metal. Ambient (`Ka`) has no PBR counterpart and is ignored.
+fn solve_metallic(diffuse: [f32; 3], specular: [f32; 3]) -> f32 {
+    const DIELECTRIC_SPECULAR: f32 = 0.04;
+    let brightness =
+        |c: [f32; 3]| (0.299 * c[0] * c[0] + 0.587 * c[1] * c[1] + 0.114 * c[2] * c[2]).sqrt();
+    let spec = brightness(specular);
+    if spec < DIELECTRIC_SPECULAR {
+        return 0.0;
+    }
+    let one_minus_specular_strength = 1.0 - specular.into_iter().fold(0.0, f32::max);
+    let diff = brightness(diffuse);
+
+    let a = DIELECTRIC_SPECULAR;
+    let b = diff * one_minus_specular_strength / (1.0 - DIELECTRIC_SPECULAR) + spec
+        - 2.0 * DIELECTRIC_SPECULAR;
+    let c = DIELECTRIC_SPECULAR - spec;
+    let discriminant = (b * b - 4.0 * a * c).max(0.0);
+    ((-b + discriminant.sqrt()) / (2.0 * a)).clamp(0.0, 1.0)
+}
+
+/// Find the texture an MTL map statement refers to.
+///
+/// Tries the path as written (absolute paths), then relative to the MTL
+/// file, then relative to the OBJ file, and finally the file name in a
+/// `textures/` folder next to the OBJ.
+fn resolve_texture_path(tex_name: &str, mtl_dir: &Path, obj_dir: &Path) -> Option<PathBuf> {
+    let tex_path = Path::new(tex_name);
+    let mut candidates = Vec::new();
+    if tex_path.is_absolute() {
+        candidates.push(("as written", tex_path.to_path_buf()));
+    }
+    candidates.push(("relative to the MTL file", mtl_dir.join(tex_path)));
+    candidates.push(("relative to the OBJ file", obj_dir.join(tex_path)));
+    if let Some(file_name) = tex_path.file_name() {
+        candidates.push(("in textures/", obj_dir.join("textures").join(file_name)));
+    }
+
+    let (how, path) = candidates.into_iter().find(|(_, path)| path.is_file())?;
+    debug!(texture = %tex_name, path = %path.display(), "Resolved texture {how}");
+    Some(path)
+}
+
+/// Load a texture file: read raw bytes and the width/height from its header.
+///
+/// Pixels are only decoded later, per tile, so large sources are never held
+/// decoded for the whole run.
+pub(crate) fn load_texture(path: &Path) -> Result<TextureData> {
     let data = std::fs::read(path).map_err(|e| {
         PhotoTilerError::Input(format!("Failed to read texture {}: {e}", path.display()))
     })?;
 
-    let img = image::load_from_memory(&data).map_err(|e| {
-        PhotoTilerError::Input(format!(
-            "Failed to decode texture {}: {e}",
-            path.display()
-        ))
-    })?;
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(&data))
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)
+        .and_then(|reader| reader.into_dimensions())
+        .map_err(|e| {
+            PhotoTilerError::Input(format!(
+                "Failed to read texture header {}: {e}",
+                path.display()
+            ))
+        })?;
 
     let mime_type = match path.extension().and_then(|e| e.to_str()) {
         Some("jpg" | "jpeg") => "image/jpeg",
@@ -135,16 +353,18 @@ fn load_texture(path: &Path) -> Result<TextureData> {
 
     debug!(
         path = %path.display(),
-        width = img.width(),
-        height = img.height(),
+        width,
+        height,
         "Loaded texture"
     );
 
     Ok(TextureData {
         data,
         mime_type: mime_type.to_string(),
-        width: img.width(),
-        height: img.height(),
+        width,
+        height,
+        // OBJ maps repeat by default; `-clamp on` is not parsed
+        wrap: [WrapMode::Repeat; 2],
     })
 }
 
@@ -166,7 +386,7 @@ mod tests {
             material_id: Some(0),
         };
 
-        let indexed = convert_mesh(mesh);
+        let indexed = convert_mesh(tobj::Model::new(mesh, String::new()), true);
         assert_eq!(indexed.vertex_count(), 3);
         assert_eq!(indexed.triangle_count(), 1);
         assert!(indexed.has_normals());
@@ -189,7 +409,7 @@ mod tests {
             material_id: None,
         };
 
-        let indexed = convert_mesh(mesh);
+        let indexed = convert_mesh(tobj::Model::new(mesh, String::new()), true);
         // V-flip: v = 1.0 - v
         // Original UVs: (0.0,0.0), (1.0,0.3), (0.5,1.0)
         // Flipped UVs:  (0.0,1.0), (1.0,0.7), (0.5,0.0)
@@ -198,6 +418,37 @@ mod tests {
         assert!((indexed.uvs[5] - 0.0).abs() < f32::EPSILON);
     }
 
+    fn load_single_uv_triangle(flip_uv_v: Option<bool>) -> Vec<f32> {
+        let dir = tempfile::tempdir().unwrap();
+        let obj_path = dir.path().join("uv.obj");
+        std::fs::write(
+            &obj_path,
+            "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.25 0.1\nvt 0.75 0.1\nvt 0.25 0.8\nf 1/1 2/2 3/3\n",
+        )
+        .unwrap();
+        let config = PipelineConfig {
+            flip_uv_v,
+            ..Default::default()
+        };
+        let (meshes, _) = load_obj(&obj_path, &config).unwrap();
+        meshes.into_iter().next().unwrap().uvs
+    }
+
+    #[test]
+    fn load_obj_flips_uv_v_by_default() {
+        let uvs = load_single_uv_triangle(None);
+        let expected = [0.25, 0.9, 0.75, 0.9, 0.25, 0.2];
+        for (uv, e) in uvs.iter().zip(expected) {
+            assert!((uv - e).abs() < 1e-6, "{uvs:?}");
+        }
+    }
+
+    #[test]
+    fn load_obj_keeps_uv_v_when_flip_disabled() {
+        let uvs = load_single_uv_triangle(Some(false));
+        assert_eq!(uvs, vec![0.25, 0.1, 0.75, 0.1, 0.25, 0.8]);
+    }
+
     #[test]
     fn convert_mesh_vertex_color_rgb_to_rgba() {
         let mesh = tobj::Mesh {
@@ -212,7 +463,7 @@ mod tests {
             material_id: None,
         };
 
-        let indexed = convert_mesh(mesh);
+        let indexed = convert_mesh(tobj::Model::new(mesh, String::new()), true);
         assert!(indexed.has_colors());
         // 3 vertices * 4 components = 12 floats
         assert_eq!(indexed.colors.len(), 12);
@@ -227,4 +478,259 @@ mod tests {
         assert!((indexed.colors[10] - 1.0).abs() < f32::EPSILON);
         assert!((indexed.colors[11] - 1.0).abs() < f32::EPSILON);
     }
+
+    fn load_colored_triangle(rgb: [&str; 3]) -> Vec<f32> {
+        let dir = tempfile::tempdir().unwrap();
+        let obj_path = dir.path().join("colored.obj");
+        let obj = format!(
+            "v 0 0 0 {}\nv 1 0 0 {}\nv 0 1 0 {}\nf 1 2 3\n",
+            rgb[0], rgb[1], rgb[2]
+        );
+        std::fs::write(&obj_path, obj).unwrap();
+        let (meshes, _) = load_obj(&obj_path, &PipelineConfig::default()).unwrap();
+        assert_eq!(meshes[0].positions[3..6], [1.0, 0.0, 0.0]);
+        meshes.into_iter().next().unwrap().colors
+    }
+
+    #[test]
+    fn load_obj_reads_extended_vertex_colors() {
+        let colors = load_colored_triangle(["1 0 0", "0 0.5 0", "0 0 1"]);
+        let expected = [1.0, 0.0, 0.0, 1.0, 0.0, 0.5, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0];
+        assert_eq!(colors, expected);
+    }
+
+    #[test]
+    fn load_obj_normalizes_byte_vertex_colors() {
+        let colors = load_colored_triangle(["255 0 0", "0 51 0", "0 0 255"]);
+        let expected = [1.0, 0.0, 0.0, 1.0, 0.0, 0.2, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0];
+        for (c, e) in colors.iter().zip(expected) {
+            assert!((c - e).abs() < 1e-6, "{colors:?}");
+        }
+        assert_eq!(colors.len(), 12);
+    }
+
+    #[test]
+    fn convert_mesh_unnamed_object_has_no_name() {
+        let model = tobj::Model::new(tobj::Mesh::default(), "unnamed_object".into());
+        assert_eq!(convert_mesh(model, true).name, None);
+    }
+
+    #[test]
+    fn load_obj_named_objects_become_separate_meshes() {
+        let dir = tempfile::tempdir().unwrap();
+        let obj_path = dir.path().join("two.obj");
+        std::fs::write(
+            &obj_path,
+            "\
+o building
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+g terrain
+v 0 0 1
+v 1 0 1
+v 0 1 1
+f 4 5 6
+",
+        )
+        .unwrap();
+
+        let (meshes, _) = load_obj(&obj_path, &PipelineConfig::default()).unwrap();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].name.as_deref(), Some("building"));
+        assert_eq!(meshes[1].name.as_deref(), Some("terrain"));
+        assert_eq!(meshes[0].triangle_count(), 1);
+        assert_eq!(meshes[1].triangle_count(), 1);
+    }
+
+    #[test]
+    fn parallel_loader_matches_tobj_on_multi_model_obj() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("mats.mtl"),
+            "newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n",
+        )
+        .unwrap();
+        let obj_path = dir.path().join("multi.obj");
+        std::fs::write(
+            &obj_path,
+            "\
+mtllib mats.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+usemtl red
+f 1/1/1 2/2/1 3/3/1 4/4/1
+usemtl blue
+f -4/-4/-1 -2/-2/-1 -1/-1/-1
+o roof
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+v 0.5 0.5 2
+f 5 6 7 8 9
+g wall
+f 1//1 2//1 6//1
+f 1//1 6//1 5//1
+",
+        )
+        .unwrap();
+
+        let (mut sequential, seq_mats, seq_mtls) = load_obj_sequential(&obj_path).unwrap();
+        let (mut parallel, par_mats, par_mtls) = load_obj_parallel(&obj_path).unwrap();
+        assert_eq!(seq_mtls, par_mtls);
+        assert_eq!(seq_mats.len(), par_mats.len());
+        assert_eq!(seq_mats[1].1, par_mats[1].1);
+
+        // tobj also emits a trailing model when the file ends without faces
+        sequential.retain(|m| !m.mesh.indices.is_empty());
+        parallel.retain(|m| !m.mesh.indices.is_empty());
+        assert_eq!(parallel.len(), 4);
+
+        let summary = |models: Vec<tobj::Model>| -> Vec<_> {
+            models
+                .into_iter()
+                .map(|m| {
+                    let material = m.mesh.material_id;
+                    let mesh = convert_mesh(m, true);
+                    (
+                        mesh.name.clone(),
+                        material,
+                        mesh.vertex_count(),
+                        mesh.triangle_count(),
+                        mesh.uvs,
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(summary(parallel), summary(sequential));
+    }
+
+    #[test]
+    fn mtl_specular_and_dissolve_become_pbr() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("a.mtl"),
+            "\
+newmtl glass
+Kd 0.2 0.4 0.6
+Ks 0.04 0.04 0.04
+Ns 500
+d 0.4
+newmtl matte
+Kd 0.5 0.5 0.5
+Tr 0
+",
+        )
+        .unwrap();
+        let obj_path = dir.path().join("a.obj");
+        std::fs::write(
+            &obj_path,
+            "mtllib a.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl glass\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let (_, lib) = load_obj(&obj_path, &PipelineConfig::default()).unwrap();
+        let glass = &lib.materials[0];
+        assert!(glass.roughness < 0.1, "roughness {}", glass.roughness);
+        assert!(glass.metallic < 0.05, "metallic {}", glass.metallic);
+        assert_eq!(glass.base_color, [0.2, 0.4, 0.6, 0.4]);
+        assert_eq!(glass.alpha_mode, AlphaMode::Blend);
+
+        let matte = &lib.materials[1];
+        assert_eq!(matte.roughness, 1.0);
+        assert_eq!(matte.base_color[3], 1.0);
+        assert_eq!(matte.alpha_mode, AlphaMode::Opaque);
+    }
+
+    #[test]
+    fn bright_specular_over_dark_diffuse_is_metallic() {
+        assert!(solve_metallic([0.05; 3], [0.9, 0.8, 0.6]) > 0.9);
+        assert_eq!(solve_metallic([0.8; 3], [0.0; 3]), 0.0);
+        assert_eq!(shininess_to_roughness(0.0), 1.0);
+    }
+
+    #[test]
+    fn mtl_bump_map_becomes_normal_texture() {
+        let dir = tempfile::tempdir().unwrap();
+        image::RgbaImage::from_pixel(2, 2, image::Rgba([128, 128, 255, 255]))
+            .save(dir.path().join("normal.png"))
+            .unwrap();
+        std::fs::write(
+            dir.path().join("a.mtl"),
+            "newmtl plain\nmap_Bump normal.png\nnewmtl scaled\nnorm -bm 0.5 normal.png\n",
+        )
+        .unwrap();
+        let obj_path = dir.path().join("a.obj");
+        std::fs::write(
+            &obj_path,
+            "mtllib a.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl plain\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let (_, lib) = load_obj(&obj_path, &PipelineConfig::default()).unwrap();
+        let plain = &lib.materials[0];
+        assert_eq!(plain.normal_texture, Some(0));
+        assert_eq!(plain.normal_scale, 1.0);
+        let scaled = &lib.materials[1];
+        assert_eq!(scaled.normal_texture, Some(1));
+        assert_eq!(scaled.normal_scale, 0.5);
+        assert_eq!(lib.textures[1].width, 2);
+    }
+
+    #[test]
+    fn map_statement_options_are_split_from_file_name() {
+        let map = MapStatement::parse("-bm 0.3 -s 2 2 -clamp on my map.png");
+        assert_eq!(map.file, "my map.png");
+        assert_eq!(map.bump_multiplier, Some(0.3));
+        let map = MapStatement::parse("-o 0.5 tex.jpg");
+        assert_eq!(map.file, "tex.jpg");
+        assert_eq!(MapStatement::parse("plain.png").file, "plain.png");
+    }
+
+    #[test]
+    fn mtl_texture_resolves_relative_to_mtl_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_dir = dir.path().join("model");
+        std::fs::create_dir_all(model_dir.join("mtl")).unwrap();
+        std::fs::create_dir_all(model_dir.join("tex")).unwrap();
+        image::RgbaImage::new(2, 2)
+            .save(model_dir.join("tex").join("foo.png"))
+            .unwrap();
+        std::fs::write(
+            model_dir.join("mtl").join("a.mtl"),
+            "newmtl wall\nmap_Kd ../tex/foo.png\n",
+        )
+        .unwrap();
+        let obj_path = model_dir.join("a.obj");
+        std::fs::write(
+            &obj_path,
+            "mtllib mtl/a.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nusemtl wall\nf 1/1 2/1 3/1\n",
+        )
+        .unwrap();
+
+        let (_, lib) = load_obj(&obj_path, &PipelineConfig::default()).unwrap();
+        assert_eq!(lib.materials[0].base_color_texture, Some(0));
+        assert_eq!((lib.textures[0].width, lib.textures[0].height), (2, 2));
+    }
+
+    #[test]
+    fn mtl_texture_falls_back_to_textures_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let textures = dir.path().join("textures");
+        std::fs::create_dir_all(&textures).unwrap();
+        image::RgbaImage::new(2, 2)
+            .save(textures.join("bar.png"))
+            .unwrap();
+        let found = resolve_texture_path(r"C:/export/maps/bar.png", dir.path(), dir.path());
+        assert_eq!(found, Some(textures.join("bar.png")));
+    }
 }
@@ -0,0 +1,452 @@
+//! Chunked, parallel OBJ parser for very large files.
+//!
+//! The file is memory-mapped and split into newline-aligned chunks. A cheap
+//! first pass counts `v`/`vt`/`vn` statements per chunk so every chunk knows
+//! its global attribute offsets (negative face indices resolve locally). The
+//! chunks are then parsed in parallel, stitched into models in file order,
+//! and each model is de-indexed in parallel. The result mirrors what
+//! `tobj::load_obj` returns with `GPU_LOAD_OPTIONS`, so `convert_mesh` applies
+//! unchanged.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use memmap2::Mmap;
+use rayon::prelude::*;
+use tracing::{debug, info, warn};
+
+use crate::error::{PhotoTilerError, Result};
+use crate::ingestion::obj_loader::{ObjContents, ObjMaterial};
+
+/// OBJ files at least this large are parsed with [`load_obj_parallel`].
+pub const PARALLEL_MIN_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Target chunk size; chunks end on the next newline.
+const CHUNK_BYTES: usize = 8 * 1024 * 1024;
+
+/// Marks a missing `vt`/`vn` reference in a face corner.
+const MISSING: u32 = u32::MAX;
+
+/// Attribute counts (in elements, not floats).
+#[derive(Debug, Default, Clone, Copy)]
+struct Counts {
+    v: usize,
+    vt: usize,
+    vn: usize,
+}
+
+/// A statement that can split the current model.
+#[derive(Debug)]
+enum Statement {
+    /// `o` / `g` with the trimmed name.
+    Object(String),
+    /// `usemtl` with the material name.
+    UseMtl(String),
+}
+
+#[derive(Debug, Default)]
+struct Chunk {
+    positions: Vec<f32>,
+    colors: Vec<f32>,
+    texcoords: Vec<f32>,
+    normals: Vec<f32>,
+    /// Triangle corners as global `[v, vt, vn]` indices.
+    corners: Vec<[u32; 3]>,
+    /// Model-splitting statements, keyed by the corner count preceding them.
+    statements: Vec<(usize, Statement)>,
+    mtllibs: Vec<String>,
+}
+
+/// A run of triangle corners that becomes one `tobj::Model`.
+struct Segment {
+    name: String,
+    material_id: Option<usize>,
+    /// `(chunk index, corner range)` pieces in file order.
+    pieces: Vec<(usize, std::ops::Range<usize>)>,
+}
+
+/// Parse an OBJ file with all available cores.
+///
+/// Returns the models and materials `tobj::load_obj` would, each material
+/// paired with its MTL file's directory; MTL load failures are logged and
+/// yield no materials. Unlike tobj, `usemtl` can refer to a material from
+/// an `mtllib` declared later in the file.
+pub fn load_obj_parallel(path: &Path) -> Result<ObjContents> {
+    let file = File::open(path)?;
+    // SAFETY: the mapping is read-only and the file is not modified while
+    // it is being parsed.
+    let mmap = unsafe { Mmap::map(&file)? };
+    let text = std::str::from_utf8(&mmap)
+        .map_err(|e| PhotoTilerError::Input(format!("Failed to load OBJ: {e}")))?;
+
+    let ranges = chunk_ranges(text.as_bytes(), CHUNK_BYTES);
+    debug!(
+        chunks = ranges.len(),
+        bytes = text.len(),
+        "Parsing OBJ in parallel"
+    );
+
+    let counts: Vec<Counts> = ranges
+        .par_iter()
+        .map(|r| count_attributes(&text[r.clone()]))
+        .collect();
+    let mut bases = Vec::with_capacity(counts.len());
+    let mut total = Counts::default();
+    for c in &counts {
+        bases.push(total);
+        total.v += c.v;
+        total.vt += c.vt;
+        total.vn += c.vn;
+    }
+
+    let parsed = AtomicUsize::new(0);
+    let chunks: Vec<Chunk> = ranges
+        .par_iter()
+        .zip(bases.par_iter())
+        .map(|(r, base)| {
+            let chunk = parse_chunk(&text[r.clone()], *base, total)?;
+            let done = parsed.fetch_add(r.len(), Ordering::Relaxed) + r.len();
+            let before = (done - r.len()) * 10 / text.len().max(1);
+            if done * 10 / text.len().max(1) > before {
+                info!(percent = done * 100 / text.len().max(1), "Parsing OBJ");
+            }
+            Ok(chunk)
+        })
+        .collect::<Result<_>>()?;
+
+    let obj_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let (materials, mat_map, mtl_files) = load_materials(&chunks, obj_dir);
+    let segments = split_models(&chunks, &mat_map);
+
+    let positions = concat(&chunks, |c| &c.positions);
+    let colors = concat(&chunks, |c| &c.colors);
+    let texcoords = concat(&chunks, |c| &c.texcoords);
+    let normals = concat(&chunks, |c| &c.normals);
+    let attributes = Attributes {
+        positions: &positions,
+        colors: &colors,
+        texcoords: &texcoords,
+        normals: &normals,
+    };
+
+    let models = segments
+        .into_par_iter()
+        .map(|segment| export_model(segment, &chunks, &attributes))
+        .collect::<Result<Vec<_>>>()?;
+
+    info!(models = models.len(), vertices = total.v, "Parsed OBJ");
+    Ok((models, materials, mtl_files))
+}
+
+/// Split `bytes` into ranges of roughly `target` bytes ending after a newline.
+fn chunk_ranges(bytes: &[u8], target: usize) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < bytes.len() {
+        let mut end = (start + target).min(bytes.len());
+        while end < bytes.len() && bytes[end - 1] != b'\n' {
+            end += 1;
+        }
+        ranges.push(start..end);
+        start = end;
+    }
+    ranges
+}
+
+fn count_attributes(text: &str) -> Counts {
+    let mut counts = Counts::default();
+    for line in text.lines() {
+        match line.split_whitespace().next() {
+            Some("v") => counts.v += 1,
+            Some("vt") => counts.vt += 1,
+            Some("vn") => counts.vn += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+fn parse_error(what: &str, line: &str) -> PhotoTilerError {
+    PhotoTilerError::Input(format!(
+        "Failed to load OBJ: invalid {what} '{}'",
+        line.trim()
+    ))
+}
+
+/// Parse `n` floats from `words` into `out`.
+fn parse_floats<'a>(
+    words: &mut impl Iterator<Item = &'a str>,
+    out: &mut Vec<f32>,
+    n: usize,
+) -> Option<()> {
+    for _ in 0..n {
+        out.push(words.next()?.parse().ok()?);
+    }
+    Some(())
+}
+
+fn parse_chunk(text: &str, base: Counts, total: Counts) -> Result<Chunk> {
+    let mut chunk = Chunk::default();
+    let mut face = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => {
+                parse_floats(&mut words, &mut chunk.positions, 3)
+                    .ok_or_else(|| parse_error("position", line))?;
+                let rest: Vec<&str> = words.collect();
+                match rest.as_slice() {
+                    [] => {}
+                    [w] => {
+                        let w: f32 = w.parse().map_err(|_| parse_error("position", line))?;
+                        if w == 0.0 {
+                            return Err(parse_error("position", line));
+                        }
+                        let len = chunk.positions.len();
+                        chunk.positions[len - 3..].iter_mut().for_each(|c| *c /= w);
+                    }
+                    [r, g, b] => {
+                        for c in [r, g, b] {
+                            let c = c.parse().map_err(|_| parse_error("vertex color", line))?;
+                            chunk.colors.push(c);
+                        }
+                    }
+                    _ => return Err(parse_error("vertex color", line)),
+                }
+            }
+            Some("vt") => {
+                parse_floats(&mut words, &mut chunk.texcoords, 2)
+                    .ok_or_else(|| parse_error("texcoord", line))?;
+            }
+            Some("vn") => {
+                parse_floats(&mut words, &mut chunk.normals, 3)
+                    .ok_or_else(|| parse_error("normal", line))?;
+            }
+            Some("f") => {
+                // Attributes seen so far, for resolving negative indices
+                let seen = Counts {
+                    v: base.v + chunk.positions.len() / 3,
+                    vt: base.vt + chunk.texcoords.len() / 2,
+                    vn: base.vn + chunk.normals.len() / 3,
+                };
+                face.clear();
+                for word in words {
+                    face.push(
+                        parse_corner(word, seen, total).ok_or_else(|| parse_error("face", line))?,
+                    );
+                }
+                // Fan triangulation, as tobj does for quads and polygons
+                for i in 1..face.len().saturating_sub(1) {
+                    chunk.corners.extend([face[0], face[i], face[i + 1]]);
+                }
+            }
+            Some("o" | "g") => {
+                let name = line[1..].trim();
+                let name = if name.is_empty() {
+                    "unnamed_object"
+                } else {
+                    name
+                };
+                chunk
+                    .statements
+                    .push((chunk.corners.len(), Statement::Object(name.to_owned())));
+            }
+            Some("usemtl") => {
+                let name = line.split_once(' ').unwrap_or_default().1.trim();
+                if name.is_empty() {
+                    return Err(parse_error("usemtl", line));
+                }
+                chunk
+                    .statements
+                    .push((chunk.corners.len(), Statement::UseMtl(name.to_owned())));
+            }
+            Some("mtllib") => {
+                let lib = line.split_once(' ').unwrap_or_default().1.trim();
+                chunk.mtllibs.push(lib.to_owned());
+            }
+            _ => {}
+        }
+    }
+
+    Ok(chunk)
+}
+
+/// Parse a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner into global
+/// 0-based indices. `seen` resolves negative indices; `total` bounds them.
+fn parse_corner(word: &str, seen: Counts, total: Counts) -> Option<[u32; 3]> {
+    let resolve = |s: Option<&str>, seen: usize, total: usize| -> Option<u32> {
+        let idx: isize = match s {
+            None | Some("") => return Some(MISSING),
+            Some(s) => s.parse().ok()?,
+        };
+        let idx = match idx {
+            0 => return None,
+            i if i > 0 => i as usize - 1,
+            i => seen.checked_sub(i.unsigned_abs())?,
+        };
+        (idx < total).then_some(idx as u32)
+    };
+
+    let mut parts = word.split('/');
+    let v = resolve(parts.next(), seen.v, total.v)?;
+    let vt = resolve(parts.next(), seen.vt, total.vt)?;
+    let vn = resolve(parts.next(), seen.vn, total.vn)?;
+    (v != MISSING).then_some([v, vt, vn])
+}
+
+/// Load every `mtllib` in file order, merging them like tobj does. Also
+/// returns the path of each, loaded or not.
+fn load_materials(
+    chunks: &[Chunk],
+    obj_dir: &Path,
+) -> (Vec<ObjMaterial>, HashMap<String, usize>, Vec<PathBuf>) {
+    let mut materials = Vec::new();
+    let mut mat_map = HashMap::new();
+    let mut mtl_files = Vec::new();
+    for lib in chunks.iter().flat_map(|c| &c.mtllibs) {
+        let mtl_path = obj_dir.join(lib);
+        mtl_files.push(mtl_path.clone());
+        match tobj::load_mtl(&mtl_path) {
+            Ok((mats, map)) => {
+                let offset = materials.len();
+                mat_map.extend(map.into_iter().map(|(name, i)| (name, i + offset)));
+                let mtl_dir = mtl_path.parent().unwrap_or(obj_dir);
+                materials.extend(mats.into_iter().map(|m| (m, mtl_dir.to_path_buf())));
+            }
+            Err(e) => warn!("Failed to load MTL {lib}: {e}"),
+        }
+    }
+    (materials, mat_map, mtl_files)
+}
+
+/// Walk the statements in file order and cut the corners into models: a new
+/// model starts at `o`/`g`, or at `usemtl` switching material, whenever the
+/// current one already has faces.
+fn split_models(chunks: &[Chunk], mat_map: &HashMap<String, usize>) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut current = Segment {
+        name: "unnamed_object".to_owned(),
+        material_id: None,
+        pieces: Vec::new(),
+    };
+    for (ci, chunk) in chunks.iter().enumerate() {
+        let mut start = 0;
+        let boundaries = chunk
+            .statements
+            .iter()
+            .map(|(at, s)| (*at, Some(s)))
+            .chain(std::iter::once((chunk.corners.len(), None)));
+        for (at, statement) in boundaries {
+            if at > start {
+                current.pieces.push((ci, start..at));
+                start = at;
+            }
+            let (name, material_id) = match statement {
+                None => continue,
+                Some(Statement::Object(name)) => (name.clone(), current.material_id),
+                Some(Statement::UseMtl(mat)) => {
+                    let material_id = mat_map.get(mat).copied();
+                    if material_id.is_none() {
+                        warn!(material = %mat, "OBJ refers to an unknown material");
+                    }
+                    if material_id == current.material_id {
+                        continue;
+                    }
+                    (current.name.clone(), material_id)
+                }
+            };
+            if current.pieces.is_empty() {
+                current.name = name;
+                current.material_id = material_id;
+            } else {
+                let next = Segment {
+                    name,
+                    material_id,
+                    pieces: Vec::new(),
+                };
+                segments.push(std::mem::replace(&mut current, next));
+            }
+        }
+    }
+    if !current.pieces.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
+
+fn concat(chunks: &[Chunk], field: impl Fn(&Chunk) -> &Vec<f32>) -> Vec<f32> {
+    let mut out = Vec::with_capacity(chunks.iter().map(|c| field(c).len()).sum());
+    for chunk in chunks {
+        out.extend_from_slice(field(chunk));
+    }
+    out
+}
+
+/// Global attribute arrays shared by all models.
+struct Attributes<'a> {
+    positions: &'a [f32],
+    colors: &'a [f32],
+    texcoords: &'a [f32],
+    normals: &'a [f32],
+}
+
+/// De-index one model's corners into a single-index `tobj::Model`.
+fn export_model(segment: Segment, chunks: &[Chunk], attrs: &Attributes) -> Result<tobj::Model> {
+    let mut mesh = tobj::Mesh {
+        material_id: segment.material_id,
+        ..Default::default()
+    };
+    let mut index_map: HashMap<[u32; 3], u32> = HashMap::new();
+    let out_of_bounds =
+        || PhotoTilerError::Input("Failed to load OBJ: vertex color index out of bounds".into());
+
+    for (ci, range) in segment.pieces {
+        for corner in &chunks[ci].corners[range] {
+            if let Some(&i) = index_map.get(corner) {
+                mesh.indices.push(i);
+                continue;
+            }
+            let [v, vt, vn] = corner.map(|i| i as usize);
+            mesh.positions
+                .extend_from_slice(&attrs.positions[v * 3..v * 3 + 3]);
+            if !attrs.texcoords.is_empty() && corner[1] != MISSING {
+                mesh.texcoords
+                    .extend_from_slice(&attrs.texcoords[vt * 2..vt * 2 + 2]);
+            }
+            if !attrs.normals.is_empty() && corner[2] != MISSING {
+                mesh.normals
+                    .extend_from_slice(&attrs.normals[vn * 3..vn * 3 + 3]);
+            }
+            if !attrs.colors.is_empty() {
+                let rgb = attrs
+                    .colors
+                    .get(v * 3..v * 3 + 3)
+                    .ok_or_else(out_of_bounds)?;
+                mesh.vertex_color.extend_from_slice(rgb);
+            }
+            let i = (mesh.positions.len() / 3 - 1) as u32;
+            index_map.insert(*corner, i);
+            mesh.indices.push(i);
+        }
+    }
+
+    Ok(tobj::Model::new(mesh, segment.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_end_on_newlines() {
+        let text = b"v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+        let ranges = chunk_ranges(text, 3);
+        assert_eq!(ranges.len(), 3);
+        assert!(ranges.iter().all(|r| text[r.end - 1] == b'\n'));
+        assert_eq!(ranges.last().unwrap().end, text.len());
+    }
+}
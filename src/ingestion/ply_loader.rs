@@ -1,19 +1,27 @@
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufRead, BufReader, Seek};
 use std::path::Path;
 
 use ply_rs::parser::Parser;
 use ply_rs::ply::{DefaultElement, Property};
-use tracing::debug;
+use tracing::{debug, warn};
 
 use crate::error::{PhotoTilerError, Result};
-use crate::types::IndexedMesh;
+use crate::ingestion::flip_uv_v;
+use crate::ingestion::obj_loader::load_texture;
+use crate::types::{IndexedMesh, MaterialLibrary, PBRMaterial};
 
 /// Load a PLY file into an `IndexedMesh`.
-pub fn load_ply(path: &Path) -> Result<IndexedMesh> {
+///
+/// Per-vertex `s`/`t` (or `texture_u`/`texture_v`) coordinates are read as
+/// UVs, and a `comment TextureFile <name>` header line is resolved relative
+/// to the PLY and loaded as the mesh's base color texture. `flip_v` flips
+/// texture V for files whose UVs have a bottom-left origin.
+pub fn load_ply(path: &Path, flip_v: bool) -> Result<(IndexedMesh, MaterialLibrary)> {
     let file = File::open(path)
         .map_err(|e| PhotoTilerError::Input(format!("Failed to open PLY: {e}")))?;
     let mut reader = BufReader::new(file);
+    check_ply_format(&mut reader)?;
 
     let parser = Parser::<DefaultElement>::new();
     let ply = parser.read_ply(&mut reader).map_err(|e| {
@@ -29,6 +37,7 @@ pub fn load_ply(path: &Path) -> Result<IndexedMesh> {
     let mut positions = Vec::with_capacity(vertices.len() * 3);
     let mut normals = Vec::new();
     let mut colors = Vec::new();
+    let mut uvs = Vec::new();
 
     let has_normals = vertices
         .first()
@@ -37,6 +46,15 @@ pub fn load_ply(path: &Path) -> Result<IndexedMesh> {
     let has_colors = vertices.first().map(|v| {
         v.contains_key("red") || v.contains_key("r")
     }).unwrap_or(false);
+    let uv_keys = vertices.first().and_then(|v| {
+        if v.contains_key("s") && v.contains_key("t") {
+            Some(("s", "t"))
+        } else if v.contains_key("texture_u") && v.contains_key("texture_v") {
+            Some(("texture_u", "texture_v"))
+        } else {
+            None
+        }
+    });
 
     if has_normals {
         normals.reserve(vertices.len() * 3);
@@ -44,6 +62,9 @@ pub fn load_ply(path: &Path) -> Result<IndexedMesh> {
     if has_colors {
         colors.reserve(vertices.len() * 4);
     }
+    if uv_keys.is_some() {
+        uvs.reserve(vertices.len() * 2);
+    }
 
     for vertex in vertices {
         positions.push(get_float_property(vertex, "x")?);
@@ -57,20 +78,30 @@ pub fn load_ply(path: &Path) -> Result<IndexedMesh> {
         }
 
         if has_colors {
-            let r = get_color_property(vertex)?;
-            colors.push(r.0);
-            colors.push(r.1);
-            colors.push(r.2);
-            colors.push(1.0); // alpha
+            let (r, g, b, a) = get_color_property(vertex)?;
+            colors.push(r);
+            colors.push(g);
+            colors.push(b);
+            colors.push(a);
         }
+
+        if let Some((u_key, v_key)) = uv_keys {
+            uvs.push(get_float_property(vertex, u_key)?);
+            uvs.push(get_float_property(vertex, v_key)?);
+        }
+    }
+
+    if flip_v {
+        flip_uv_v(&mut uvs);
     }
 
     // Parse faces
     let mut indices = Vec::new();
     if let Some(faces) = ply.payload.get("face") {
         debug!(face_count = faces.len(), "Parsing PLY faces");
+        let mut float_indices = false;
         for face in faces {
-            let face_indices = get_index_list(face)?;
+            let face_indices = get_index_list(face, &mut float_indices)?;
             // Fan-triangulate polygons with >3 vertices
             if face_indices.len() >= 3 {
                 for i in 1..face_indices.len() - 1 {
@@ -80,18 +111,95 @@ pub fn load_ply(path: &Path) -> Result<IndexedMesh> {
                 }
             }
         }
+        if float_indices {
+            warn!("PLY face indices are stored as floats; rounded to the nearest vertex index");
+        }
     }
 
-    Ok(IndexedMesh {
+    let mut materials = MaterialLibrary::default();
+    let mut material_index = None;
+    if let Some(tex_name) = texture_file_comment(&ply.header.comments) {
+        let ply_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let tex_path = ply_dir.join(tex_name);
+        materials.source_files.push(tex_path.clone());
+        match load_texture(&tex_path) {
+            Ok(tex) => {
+                materials.textures.push(tex);
+                materials.materials.push(PBRMaterial {
+                    name: tex_name.to_string(),
+                    metallic: 0.0,
+                    roughness: 1.0,
+                    base_color_texture: Some(0),
+                    ..Default::default()
+                });
+                material_index = Some(0);
+            }
+            Err(e) => {
+                warn!(texture = %tex_name, "Failed to load texture: {e}");
+                materials.missing_textures.push(tex_name.to_string());
+            }
+        }
+    }
+
+    let mesh = IndexedMesh {
         positions,
         normals,
-        uvs: Vec::new(), // PLY typically lacks UVs
+        uvs,
+        uvs1: Vec::new(),
         colors,
+        feature_ids: Vec::new(),
         indices,
-        material_index: None,
+        material_index,
+        name: None,
+        material_groups: Vec::new(),
+    };
+
+    Ok((mesh, materials))
+}
+
+/// Find the texture file name in a `comment TextureFile <name>` header line.
+fn texture_file_comment(comments: &[String]) -> Option<&str> {
+    comments.iter().find_map(|c| {
+        c.trim()
+            .strip_prefix("TextureFile")
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
     })
 }
 
+/// Encodings `ply_rs` can read.
+const PLY_FORMATS: [&str; 3] = ["ascii", "binary_little_endian", "binary_big_endian"];
+
+/// Check the header's `format` line before parsing, so an unknown encoding
+/// fails with a clear message instead of a grammar error. Rewinds `reader`.
+fn check_ply_format(reader: &mut BufReader<File>) -> Result<()> {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        let mut words = text.split_whitespace();
+        match words.next() {
+            Some("format") => {
+                let format = words.next().unwrap_or("");
+                if !PLY_FORMATS.contains(&format) {
+                    return Err(PhotoTilerError::Input(format!(
+                        "Unsupported PLY format '{format}' (expected {})",
+                        PLY_FORMATS.join(", ")
+                    )));
+                }
+                break;
+            }
+            Some("end_header") => break,
+            _ => {}
+        }
+    }
+    reader.rewind()?;
+    Ok(())
+}
+
 /// Extract a float property, handling Float/Double/Int/Short types.
 fn get_float_property(element: &DefaultElement, key: &str) -> Result<f32> {
     let prop = element.get(key).ok_or_else(|| {
@@ -113,8 +221,10 @@ fn get_float_property(element: &DefaultElement, key: &str) -> Result<f32> {
     }
 }
 
-/// Extract RGB color from a vertex, normalizing UChar 0-255 to f32 0.0-1.0.
-fn get_color_property(element: &DefaultElement) -> Result<(f32, f32, f32)> {
+/// Extract RGBA color from a vertex, normalizing UChar 0-255 to f32 0.0-1.0.
+///
+/// Alpha is read from `alpha`/`a` when present and defaults to 1.0 otherwise.
+fn get_color_property(element: &DefaultElement) -> Result<(f32, f32, f32, f32)> {
     // Try "red"/"green"/"blue" first, then "r"/"g"/"b"
     let r_key = if element.contains_key("red") { "red" } else { "r" };
     let g_key = if element.contains_key("green") { "green" } else { "g" };
@@ -124,7 +234,15 @@ fn get_color_property(element: &DefaultElement) -> Result<(f32, f32, f32)> {
     let g = normalize_color_value(element, g_key)?;
     let b = normalize_color_value(element, b_key)?;
 
-    Ok((r, g, b))
+    let a = if element.contains_key("alpha") {
+        normalize_color_value(element, "alpha")?
+    } else if element.contains_key("a") {
+        normalize_color_value(element, "a")?
+    } else {
+        1.0
+    };
+
+    Ok((r, g, b, a))
 }
 
 /// Normalize a single color channel: UChar 0-255 -> 0.0-1.0, Float stays as-is.
@@ -137,9 +255,12 @@ fn normalize_color_value(element: &DefaultElement, key: &str) -> Result<f32> {
         Property::UChar(v) => Ok(*v as f32 / 255.0),
         Property::Float(v) => Ok(*v),
         Property::Double(v) => Ok(*v as f32),
-        Property::Short(v) => Ok(*v as f32 / 255.0),
-        Property::UShort(v) => Ok(*v as f32 / 255.0),
-        Property::Int(v) => Ok(*v as f32 / 255.0),
+        // Signed 8-bit color spans -128..=127
+        Property::Char(v) => Ok((*v as f32 + 128.0) / 255.0),
+        Property::Short(v) => Ok((*v as f32 / 255.0).max(0.0)),
+        // 16-bit color
+        Property::UShort(v) => Ok(*v as f32 / 65535.0),
+        Property::Int(v) => Ok((*v as f32 / 255.0).max(0.0)),
         Property::UInt(v) => Ok(*v as f32 / 255.0),
         _ => Err(PhotoTilerError::Input(format!(
             "PLY color property '{key}' has unsupported type"
@@ -148,7 +269,10 @@ fn normalize_color_value(element: &DefaultElement, key: &str) -> Result<f32> {
 }
 
 /// Extract the index list from a face element.
-fn get_index_list(face: &DefaultElement) -> Result<Vec<u32>> {
+///
+/// Some exporters write float lists; those are rounded to the nearest index
+/// and `float_indices` is set so the caller can warn once per file.
+fn get_index_list(face: &DefaultElement, float_indices: &mut bool) -> Result<Vec<u32>> {
     // Try "vertex_indices" first, then "vertex_index"
     let key = if face.contains_key("vertex_indices") {
         "vertex_indices"
@@ -166,12 +290,33 @@ fn get_index_list(face: &DefaultElement) -> Result<Vec<u32>> {
         Property::ListUChar(v) => Ok(v.iter().map(|&i| i as u32).collect()),
         Property::ListShort(v) => Ok(v.iter().map(|&i| i as u32).collect()),
         Property::ListUShort(v) => Ok(v.iter().map(|&i| i as u32).collect()),
+        Property::ListChar(v) => Ok(v.iter().map(|&i| i as u32).collect()),
+        Property::ListFloat(v) => {
+            *float_indices = true;
+            v.iter().map(|&i| round_index(f64::from(i))).collect()
+        }
+        Property::ListDouble(v) => {
+            *float_indices = true;
+            v.iter().map(|&i| round_index(i)).collect()
+        }
         _ => Err(PhotoTilerError::Input(
             "PLY face vertex_indices has unsupported type".into(),
         )),
     }
 }
 
+/// Round a float face index to the nearest vertex index.
+fn round_index(index: f64) -> Result<u32> {
+    let rounded = index.round();
+    if rounded >= 0.0 && rounded <= f64::from(u32::MAX) {
+        Ok(rounded as u32)
+    } else {
+        Err(PhotoTilerError::Input(format!(
+            "PLY face index {index} is not a valid vertex index"
+        )))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -203,7 +348,7 @@ end_header
 3 0 1 2
 ";
         let file = write_ascii_ply(ply_content);
-        let mesh = load_ply(file.path()).unwrap();
+        let (mesh, _) = load_ply(file.path(), false).unwrap();
 
         assert_eq!(mesh.vertex_count(), 3);
         assert_eq!(mesh.triangle_count(), 1);
@@ -233,7 +378,7 @@ end_header
 3 0 1 2
 ";
         let file = write_ascii_ply(ply_content);
-        let mesh = load_ply(file.path()).unwrap();
+        let (mesh, _) = load_ply(file.path(), false).unwrap();
 
         assert!(mesh.has_colors());
         assert_eq!(mesh.colors.len(), 12); // 3 verts * 4 (RGBA)
@@ -263,13 +408,214 @@ end_header
 4 0 1 2 3
 ";
         let file = write_ascii_ply(ply_content);
-        let mesh = load_ply(file.path()).unwrap();
+        let (mesh, _) = load_ply(file.path(), false).unwrap();
 
         // Quad -> 2 triangles
         assert_eq!(mesh.triangle_count(), 2);
         assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
     }
 
+    #[test]
+    fn float_vertex_indices_are_rounded() {
+        let ply_content = "\
+ply
+format ascii 1.0
+element vertex 4
+property float x
+property float y
+property float z
+element face 2
+property list uchar float vertex_indices
+end_header
+0.0 0.0 0.0
+1.0 0.0 0.0
+1.0 1.0 0.0
+0.0 1.0 0.0
+3 0 1 2
+3 0.0 1.9999 3.0001
+";
+        let file = write_ascii_ply(ply_content);
+        let (mesh, _) = load_ply(file.path(), false).unwrap();
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+
+        let doubles = ply_content.replace("uchar float", "uchar double");
+        let file = write_ascii_ply(&doubles);
+        let (mesh, _) = load_ply(file.path(), false).unwrap();
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+
+        let negative = ply_content.replace("3 0 1 2\n", "3 0 -1 2\n");
+        let file = write_ascii_ply(&negative);
+        let err = load_ply(file.path(), false).unwrap_err();
+        assert!(err.to_string().contains("not a valid vertex"), "{err}");
+    }
+
+    const QUAD_POSITIONS: [[f64; 3]; 4] = [
+        [0.0, 0.0, 0.0],
+        [1.5, 0.0, 0.25],
+        [1.5, 2.0, 0.5],
+        [0.0, 2.0, 0.75],
+    ];
+    const QUAD_COLORS: [[u8; 3]; 4] = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [10, 20, 30]];
+
+    /// A colored quad as a binary PLY, with `f32` or `f64` positions.
+    fn binary_quad_ply(format: &str, double: bool) -> NamedTempFile {
+        let big = format == "binary_big_endian";
+        let scalar = if double { "double" } else { "float" };
+        let mut bytes = format!(
+            "ply\nformat {format} 1.0\nelement vertex 4\n\
+             property {scalar} x\nproperty {scalar} y\nproperty {scalar} z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n"
+        )
+        .into_bytes();
+        for (p, c) in QUAD_POSITIONS.iter().zip(QUAD_COLORS) {
+            for &v in p {
+                match (double, big) {
+                    (true, true) => bytes.extend(v.to_be_bytes()),
+                    (true, false) => bytes.extend(v.to_le_bytes()),
+                    (false, true) => bytes.extend((v as f32).to_be_bytes()),
+                    (false, false) => bytes.extend((v as f32).to_le_bytes()),
+                }
+            }
+            bytes.extend(c);
+        }
+        bytes.push(4);
+        for i in 0..4i32 {
+            let mut index = i.to_le_bytes();
+            if big {
+                index.reverse();
+            }
+            bytes.extend(index);
+        }
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    #[test]
+    fn binary_ply_matches_ascii() {
+        let mut ascii = String::from(
+            "ply\nformat ascii 1.0\nelement vertex 4\n\
+             property float x\nproperty float y\nproperty float z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n",
+        );
+        for (p, c) in QUAD_POSITIONS.iter().zip(QUAD_COLORS) {
+            ascii += &format!("{} {} {} {} {} {}\n", p[0], p[1], p[2], c[0], c[1], c[2]);
+        }
+        ascii += "4 0 1 2 3\n";
+        let (expected, _) = load_ply(write_ascii_ply(&ascii).path(), false).unwrap();
+        assert_eq!(expected.triangle_count(), 2);
+        assert!((expected.colors[4 * 3] - 10.0 / 255.0).abs() < 1e-6);
+
+        for format in ["binary_little_endian", "binary_big_endian"] {
+            for double in [false, true] {
+                let file = binary_quad_ply(format, double);
+                let (mesh, _) = load_ply(file.path(), false).unwrap();
+                let case = format!("{format} double={double}");
+                assert_eq!(mesh.positions, expected.positions, "{case}");
+                assert_eq!(mesh.colors, expected.colors, "{case}");
+                assert_eq!(mesh.indices, expected.indices, "{case}");
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_ply_format_is_reported() {
+        let ply_content = "\
+ply
+format binary_middle_endian 1.0
+element vertex 0
+property float x
+property float y
+property float z
+end_header
+";
+        let file = write_ascii_ply(ply_content);
+        let err = load_ply(file.path(), false).unwrap_err().to_string();
+        assert!(err.contains("format 'binary_middle_endian'"), "{err}");
+        assert!(err.contains("binary_big_endian"), "{err}");
+    }
+
+    #[test]
+    fn load_ascii_ply_with_texture() {
+        let dir = tempfile::tempdir().unwrap();
+        image::RgbImage::from_pixel(4, 4, image::Rgb([200, 100, 50]))
+            .save(dir.path().join("model.png"))
+            .unwrap();
+
+        let ply_content = "\
+ply
+format ascii 1.0
+comment TextureFile model.png
+element vertex 3
+property float x
+property float y
+property float z
+property float s
+property float t
+element face 1
+property list uchar int vertex_indices
+end_header
+0.0 0.0 0.0 0.0 0.0
+1.0 0.0 0.0 1.0 0.0
+0.0 1.0 0.0 0.0 1.0
+3 0 1 2
+";
+        let ply_path = dir.path().join("model.ply");
+        std::fs::write(&ply_path, ply_content).unwrap();
+        let (mesh, materials) = load_ply(&ply_path, false).unwrap();
+
+        assert!(mesh.has_uvs());
+        assert_eq!(mesh.uvs, vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+        let (flipped, _) = load_ply(&ply_path, true).unwrap();
+        assert_eq!(flipped.uvs, vec![0.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(mesh.material_index, Some(0));
+        assert_eq!(materials.textures.len(), 1);
+        assert_eq!(materials.textures[0].width, 4);
+        assert_eq!(materials.materials[0].base_color_texture, Some(0));
+    }
+
+    #[test]
+    fn texture_u_v_keys_without_texture() {
+        let ply_content = "\
+ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float texture_u
+property float texture_v
+element face 1
+property list uchar int vertex_indices
+end_header
+0.0 0.0 0.0 0.25 0.25
+1.0 0.0 0.0 0.75 0.25
+0.0 1.0 0.0 0.25 0.75
+3 0 1 2
+";
+        let file = write_ascii_ply(ply_content);
+        let (mesh, materials) = load_ply(file.path(), false).unwrap();
+
+        assert_eq!(mesh.uvs.len(), 6);
+        assert!((mesh.uvs[1] - 0.25).abs() < 1e-6);
+        assert_eq!(mesh.material_index, None);
+        assert!(materials.textures.is_empty());
+    }
+
+    #[test]
+    fn texture_file_comment_parsing() {
+        let comments = vec![
+            "Created by scanner".to_string(),
+            "TextureFile  texture_0.jpg ".to_string(),
+        ];
+        assert_eq!(texture_file_comment(&comments), Some("texture_0.jpg"));
+        assert_eq!(texture_file_comment(&["TextureFile".to_string()]), None);
+    }
+
     #[test]
     fn color_normalization_uchar() {
         let mut element = DefaultElement::new();
@@ -277,9 +623,78 @@ end_header
         element.insert("green".to_string(), Property::UChar(0));
         element.insert("blue".to_string(), Property::UChar(255));
 
-        let (r, g, b) = get_color_property(&element).unwrap();
+        let (r, g, b, a) = get_color_property(&element).unwrap();
         assert!((r - 128.0 / 255.0).abs() < 1e-3);
         assert!((g - 0.0).abs() < 1e-3);
         assert!((b - 1.0).abs() < 1e-3);
+        assert!((a - 1.0).abs() < 1e-3); // defaults to opaque
+    }
+
+    #[test]
+    fn color_normalization_ushort_is_16_bit() {
+        let mut element = DefaultElement::new();
+        element.insert("red".to_string(), Property::UShort(65535));
+        element.insert("green".to_string(), Property::UShort(32768));
+        element.insert("blue".to_string(), Property::UShort(0));
+
+        let (r, g, b, _) = get_color_property(&element).unwrap();
+        assert!((r - 1.0).abs() < 1e-6);
+        assert!((g - 0.5).abs() < 1e-4);
+        assert_eq!(b, 0.0);
+    }
+
+    #[test]
+    fn color_normalization_signed_stays_in_range() {
+        let mut element = DefaultElement::new();
+        element.insert("red".to_string(), Property::Char(-128));
+        element.insert("green".to_string(), Property::Char(127));
+        element.insert("blue".to_string(), Property::Short(-40));
+
+        let (r, g, b, _) = get_color_property(&element).unwrap();
+        assert_eq!(r, 0.0);
+        assert!((g - 1.0).abs() < 1e-6);
+        assert_eq!(b, 0.0);
+    }
+
+    #[test]
+    fn load_ascii_ply_with_alpha() {
+        let ply_content = "\
+ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+property uchar alpha
+element face 1
+property list uchar int vertex_indices
+end_header
+0.0 0.0 0.0 255 0 0 255
+1.0 0.0 0.0 0 255 0 128
+0.0 1.0 0.0 0 0 255 0
+3 0 1 2
+";
+        let file = write_ascii_ply(ply_content);
+        let (mesh, _) = load_ply(file.path(), false).unwrap();
+
+        assert_eq!(mesh.colors.len(), 12);
+        assert!((mesh.colors[3] - 1.0).abs() < 1e-3);
+        assert!((mesh.colors[7] - 128.0 / 255.0).abs() < 1e-3);
+        assert!((mesh.colors[11] - 0.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn float_alpha_short_key() {
+        let mut element = DefaultElement::new();
+        element.insert("r".to_string(), Property::Float(0.5));
+        element.insert("g".to_string(), Property::Float(0.5));
+        element.insert("b".to_string(), Property::Float(0.5));
+        element.insert("a".to_string(), Property::Float(0.25));
+
+        let (_, _, _, a) = get_color_property(&element).unwrap();
+        assert!((a - 0.25).abs() < 1e-6);
     }
 }
@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::types::IndexedMesh;
+
+/// Share of edges that may be open or non-manifold before tiles are
+/// likely to show cracks.
+const CRACK_EDGE_RATIO: f64 = 0.01;
+
+/// Edge and face defects of the input meshes, for `--analyze-mesh`.
+///
+/// Vertices are welded by exact position first, so UV and normal seams do
+/// not count as open edges. Tile splitting also welds by position, so an
+/// edge that stays open or non-manifold after that can crack between tiles
+/// once borders are simplified.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeshTopology {
+    /// Distinct edges, after welding.
+    pub edges: usize,
+    /// Edges used by exactly one triangle.
+    pub boundary_edges: usize,
+    /// Edges shared by more than two triangles.
+    pub non_manifold_edges: usize,
+    /// Triangles repeating another triangle's vertices.
+    pub duplicate_faces: usize,
+}
+
+impl MeshTopology {
+    /// Whether the defects are frequent enough to predict cracks between
+    /// tiles.
+    pub fn likely_to_crack(&self) -> bool {
+        let open = (self.boundary_edges + self.non_manifold_edges) as f64;
+        self.duplicate_faces > 0 || open > self.edges as f64 * CRACK_EDGE_RATIO
+    }
+}
+
+/// Count boundary edges, non-manifold edges and duplicate faces over all
+/// `meshes`. Each mesh is analyzed on its own.
+pub fn analyze_topology(meshes: &[IndexedMesh]) -> MeshTopology {
+    let mut total = MeshTopology::default();
+    for mesh in meshes {
+        let topology = analyze_mesh(mesh);
+        total.edges += topology.edges;
+        total.boundary_edges += topology.boundary_edges;
+        total.non_manifold_edges += topology.non_manifold_edges;
+        total.duplicate_faces += topology.duplicate_faces;
+    }
+    total
+}
+
+fn analyze_mesh(mesh: &IndexedMesh) -> MeshTopology {
+    // Weld by position; adding 0.0 folds -0.0 into 0.0
+    let mut welded: HashMap<[u32; 3], u32> = HashMap::new();
+    let ids: Vec<u32> = mesh
+        .positions
+        .chunks_exact(3)
+        .map(|p| {
+            let key = [p[0], p[1], p[2]].map(|c| (c + 0.0).to_bits());
+            let next = welded.len() as u32;
+            *welded.entry(key).or_insert(next)
+        })
+        .collect();
+
+    let mut topology = MeshTopology::default();
+    let mut faces = HashSet::new();
+    let mut edges: HashMap<(u32, u32), u32> = HashMap::new();
+    for tri in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| ids[i as usize]);
+        if a == b || b == c || c == a {
+            continue;
+        }
+        let mut face = [a, b, c];
+        face.sort_unstable();
+        if !faces.insert(face) {
+            topology.duplicate_faces += 1;
+            continue;
+        }
+        for (u, v) in [(a, b), (b, c), (c, a)] {
+            *edges.entry((u.min(v), u.max(v))).or_default() += 1;
+        }
+    }
+
+    topology.edges = edges.len();
+    for &count in edges.values() {
+        match count {
+            1 => topology.boundary_edges += 1,
+            2 => {}
+            _ => topology.non_manifold_edges += 1,
+        }
+    }
+    topology
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh(positions: &[[f32; 3]], indices: &[u32]) -> IndexedMesh {
+        IndexedMesh {
+            positions: positions.iter().flatten().copied().collect(),
+            indices: indices.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    const TETRAHEDRON: [[f32; 3]; 4] = [
+        [0.0, 0.0, 0.0],
+        [1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0],
+    ];
+
+    #[test]
+    fn closed_mesh_has_no_defects() {
+        let closed = mesh(&TETRAHEDRON, &[0, 2, 1, 0, 1, 3, 1, 2, 3, 0, 3, 2]);
+        let topology = analyze_topology(&[closed]);
+        assert_eq!(topology.edges, 6);
+        assert_eq!(topology.boundary_edges, 0);
+        assert_eq!(topology.non_manifold_edges, 0);
+        assert_eq!(topology.duplicate_faces, 0);
+        assert!(!topology.likely_to_crack());
+    }
+
+    #[test]
+    fn seams_are_welded_by_position() {
+        // The tetrahedron's last face uses copies of its vertices, as a UV
+        // seam would
+        let mut positions = TETRAHEDRON.to_vec();
+        positions.extend([TETRAHEDRON[0], TETRAHEDRON[3], TETRAHEDRON[2]]);
+        let seamed = mesh(&positions, &[0, 2, 1, 0, 1, 3, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(analyze_topology(&[seamed]).boundary_edges, 0);
+    }
+
+    #[test]
+    fn counts_duplicate_faces_once() {
+        // The first face again, with either winding
+        let doubled = mesh(&TETRAHEDRON, &[0, 1, 2, 1, 2, 0, 0, 2, 1]);
+        let topology = analyze_topology(&[doubled]);
+        assert_eq!(topology.duplicate_faces, 2);
+        assert_eq!(topology.boundary_edges, 3);
+        assert!(topology.likely_to_crack());
+    }
+
+    #[test]
+    fn counts_edges_shared_by_three_faces() {
+        let mut positions = TETRAHEDRON.to_vec();
+        positions.push([0.0, -1.0, 0.0]);
+        let fan = mesh(&positions, &[0, 1, 2, 1, 0, 3, 1, 0, 4]);
+        let topology = analyze_topology(&[fan]);
+        assert_eq!(topology.edges, 7);
+        assert_eq!(topology.non_manifold_edges, 1);
+        assert_eq!(topology.boundary_edges, 6);
+    }
+}
@@ -0,0 +1,211 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use tracing::debug;
+
+use crate::config::{PipelineConfig, Units};
+use crate::ingestion::InputFormat;
+
+/// How many leading comment lines of an OBJ file are searched for a unit hint.
+const OBJ_HEADER_LINES: usize = 64;
+
+/// Detect the input units from metadata, for runs without `--units`.
+///
+/// Priority: glTF `asset.extras` or OBJ header comments > metadata.xml
+/// `<units>` tag > none
+pub fn detect_units(config: &PipelineConfig) -> Option<Units> {
+    // 1. Hints embedded in the primary input
+    let embedded = match InputFormat::from_path(&config.input).ok()? {
+        InputFormat::Gltf | InputFormat::Glb => gltf_extras_units(&config.input),
+        InputFormat::Obj => obj_comment_units(&config.input),
+        InputFormat::Ply => None,
+    };
+    if embedded.is_some() {
+        return embedded;
+    }
+
+    // 2. metadata.xml
+    let xml_path = config.metadata_xml.clone().or_else(|| {
+        let candidate = config
+            .input
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("metadata.xml");
+        candidate.exists().then_some(candidate)
+    });
+    let units = xml_path.and_then(|path| {
+        debug!(path = %path.display(), "Checking metadata.xml for units");
+        metadata_xml_units(&fs::read_to_string(path).ok()?)
+    });
+    if units.is_none() {
+        debug!("No unit hint detected");
+    }
+    units
+}
+
+/// Parse a unit name or abbreviation such as `mm`, `meters` or `Feet`.
+pub fn parse_unit_name(name: &str) -> Option<Units> {
+    match name.trim().to_lowercase().as_str() {
+        "mm" | "millimeter" | "millimeters" | "millimetre" | "millimetres" => {
+            Some(Units::Millimeters)
+        }
+        "cm" | "centimeter" | "centimeters" | "centimetre" | "centimetres" => {
+            Some(Units::Centimeters)
+        }
+        "m" | "meter" | "meters" | "metre" | "metres" => Some(Units::Meters),
+        "ft" | "foot" | "feet" => Some(Units::Feet),
+        "in" | "inch" | "inches" => Some(Units::Inches),
+        _ => None,
+    }
+}
+
+/// Read a `units` (or `unit`) string from the glTF `asset.extras`.
+///
+/// Only the JSON is read; for GLB that is the first chunk.
+fn gltf_extras_units(path: &Path) -> Option<Units> {
+    let json = read_gltf_json(path)?;
+    let extras = json.get("asset")?.get("extras")?;
+    let name = extras
+        .get("units")
+        .or_else(|| extras.get("unit"))?
+        .as_str()?;
+    let units = parse_unit_name(name);
+    debug!(hint = name, ?units, "Found unit hint in glTF asset.extras");
+    units
+}
+
+/// Parse the JSON of a `.gltf`, or the JSON chunk of a `.glb`.
+fn read_gltf_json(path: &Path) -> Option<serde_json::Value> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic).ok()?;
+    if &magic != b"glTF" {
+        let mut content = magic.to_vec();
+        file.read_to_end(&mut content).ok()?;
+        return serde_json::from_slice(&content).ok();
+    }
+
+    // GLB: 12-byte header, then the JSON chunk's length and type
+    let mut header = [0u8; 16];
+    file.read_exact(&mut header).ok()?;
+    let chunk_len = u32::from_le_bytes(header[8..12].try_into().ok()?);
+    if &header[12..16] != b"JSON" {
+        return None;
+    }
+    let mut chunk = vec![0u8; chunk_len as usize];
+    file.read_exact(&mut chunk).ok()?;
+    serde_json::from_slice(&chunk).ok()
+}
+
+/// Find a unit hint such as `# Units: millimeters` in the comment lines
+/// heading an OBJ file.
+fn obj_comment_units(path: &Path) -> Option<Units> {
+    let file = fs::File::open(path).ok()?;
+    BufReader::new(file)
+        .lines()
+        .take(OBJ_HEADER_LINES)
+        .map_while(|line| line.ok())
+        .take_while(|line| line.trim().is_empty() || line.trim_start().starts_with('#'))
+        .find_map(|line| {
+            let units = comment_units(line.trim_start().trim_start_matches('#'));
+            if units.is_some() {
+                debug!(
+                    comment = line.trim(),
+                    ?units,
+                    "Found unit hint in OBJ header"
+                );
+            }
+            units
+        })
+}
+
+/// Parse `units: mm`, `Unit = meters` or `units mm` from a comment.
+fn comment_units(comment: &str) -> Option<Units> {
+    let comment = comment.trim();
+    let lower = comment.to_lowercase();
+    let rest = lower
+        .strip_prefix("units")
+        .or_else(|| lower.strip_prefix("unit"))?;
+    let value = rest.trim_start_matches([':', '=', ' ', '\t']);
+    parse_unit_name(value.split_whitespace().next()?)
+}
+
+/// Read a `<units>` tag from metadata XML.
+fn metadata_xml_units(content: &str) -> Option<Units> {
+    let lower = content.to_lowercase();
+    let start = lower.find("<units>")? + "<units>".len();
+    let end = start + lower[start..].find("</units>")?;
+    let hint = &lower[start..end];
+    let units = parse_unit_name(hint);
+    debug!(hint = hint.trim(), ?units, "Found units in metadata.xml");
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parse_unit_names_and_abbreviations() {
+        assert_eq!(parse_unit_name("mm"), Some(Units::Millimeters));
+        assert_eq!(parse_unit_name(" Millimetres "), Some(Units::Millimeters));
+        assert_eq!(parse_unit_name("METERS"), Some(Units::Meters));
+        assert_eq!(parse_unit_name("feet"), Some(Units::Feet));
+        assert_eq!(parse_unit_name("furlong"), None);
+    }
+
+    #[test]
+    fn obj_header_comment() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("model.obj");
+        fs::write(&path, "# Exported by CAD\n# Units: millimeters\nv 0 0 0\n").unwrap();
+        assert_eq!(obj_comment_units(&path), Some(Units::Millimeters));
+
+        // Comments after the first statement are not part of the header
+        fs::write(&path, "v 0 0 0\n# units cm\n").unwrap();
+        assert_eq!(obj_comment_units(&path), None);
+    }
+
+    #[test]
+    fn glb_asset_extras() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("model.glb");
+        let json = br#"{"asset":{"version":"2.0","extras":{"unit":"cm"}}}"#.to_vec();
+        let glb = gltf::binary::Glb {
+            header: gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                length: 0,
+            },
+            json: json.into(),
+            bin: None,
+        };
+        fs::write(&path, glb.to_vec().unwrap()).unwrap();
+        assert_eq!(gltf_extras_units(&path), Some(Units::Centimeters));
+    }
+
+    #[test]
+    fn metadata_xml_tag() {
+        let xml = "<ModelMetadata><SRS>EPSG:32636</SRS><Units> ft </Units></ModelMetadata>";
+        assert_eq!(metadata_xml_units(xml), Some(Units::Feet));
+        assert_eq!(metadata_xml_units("<ModelMetadata/>"), None);
+    }
+
+    #[test]
+    fn obj_hint_takes_priority_over_metadata_xml() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("model.obj");
+        fs::write(&input, "# units: cm\nv 0 0 0\n").unwrap();
+        fs::write(dir.path().join("metadata.xml"), "<units>mm</units>").unwrap();
+        let config = PipelineConfig {
+            input: input.clone(),
+            ..Default::default()
+        };
+        assert_eq!(detect_units(&config), Some(Units::Centimeters));
+
+        fs::write(&input, "v 0 0 0\n").unwrap();
+        assert_eq!(detect_units(&config), Some(Units::Millimeters));
+    }
+}
@@ -1,5 +1,7 @@
+pub mod cache;
 pub mod config;
 pub mod error;
+pub mod hash;
 pub mod ingestion;
 pub mod pipeline;
 pub mod tiling;
@@ -15,19 +15,36 @@ fn main() -> anyhow::Result<()> {
     } else {
         EnvFilter::new("photo_tiler=info")
     };
-    tracing_subscriber::fmt().with_env_filter(filter).init();
+    // Keep stdout clean for JSON output
+    if args.json {
+        tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        tracing_subscriber::fmt().with_env_filter(filter).init();
+    }
 
     let config: PipelineConfig = args.into();
 
-    // Configure rayon thread pool
-    if let Some(threads) = config.threads {
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(threads)
-            .build_global()
-            .context("Failed to configure rayon thread pool")?;
-    }
+    // Run on a local rayon thread pool when the thread count is fixed
+    let outcome = match config.threads {
+        Some(threads) => {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .context("Failed to build rayon thread pool")?;
+            Pipeline::run_in(&config, &pool)
+        }
+        None => Pipeline::run(&config),
+    };
 
-    match Pipeline::run(&config) {
+    match outcome {
+        Ok(result) if config.validate_only.is_some() => {
+            println!("Valid: {} tiles checked", result.tile_count);
+            Ok(())
+        }
+        Ok(_) if config.json && (config.dry_run || config.show_georef) => Ok(()),
         Ok(result) => {
             println!(
                 "Done: {} tiles generated in {:.2}s",
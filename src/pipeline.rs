@@ -1,20 +1,92 @@
 use std::fs;
+use std::path::Path;
 use std::time::{Duration, Instant};
 
 use gltf::binary::Glb;
+use serde_json::json;
 use tracing::{info, warn};
 
+use crate::cache::{self, GeometryCache};
 use crate::config::PipelineConfig;
 use crate::error::{PhotoTilerError, Result};
 use crate::ingestion::{self, IngestionResult};
-use crate::tiling::{lod, tileset_writer};
+use crate::tiling::atlas_repacker::LOW_PACKING_EFFICIENCY;
+use crate::tiling::glb_writer::read_b3dm;
+use crate::tiling::lod;
+use crate::tiling::tileset_writer::{self, AtlasStats, with_uri_base};
+use crate::transform::coordinates::{MIN_HALF_EXTENT, compute_bounding_box, ensure_min_extent};
 use crate::transform::{self, TransformResult};
+use crate::types::IndexedMesh;
 
 /// Summary of a completed pipeline run.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ProcessingResult {
     pub tile_count: usize,
     pub duration: Duration,
+    /// Triangles read from the input.
+    pub triangles_in: usize,
+    /// Triangles written across all tiles, coarse LOD content included.
+    pub triangles_out: usize,
+    /// Tiles with content at each tree depth; index 0 is the root.
+    pub tiles_per_level: Vec<usize>,
+    pub atlas_stats: AtlasStats,
+    /// Bytes written to the output directory (GLBs, shared atlases, tileset.json).
+    pub bytes_written: u64,
+    pub stages: StageDurations,
+    /// Ingestion and transform were skipped, their result read from the
+    /// `--cache` directory.
+    pub geometry_cached: bool,
+}
+
+/// Wall-clock time spent in each pipeline stage.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageDurations {
+    pub ingestion: Duration,
+    pub transform: Duration,
+    pub tiling: Duration,
+    pub validation: Duration,
+}
+
+impl ProcessingResult {
+    /// Machine-readable summary of the run, as written by `--report`.
+    pub fn report_json(&self) -> serde_json::Value {
+        json!({
+            "tileCount": self.tile_count,
+            "tilesPerLevel": self.tiles_per_level,
+            "triangles": {
+                "input": self.triangles_in,
+                "output": self.triangles_out
+            },
+            "atlases": {
+                "count": self.atlas_stats.atlases,
+                "unique": self.atlas_stats.unique_atlases,
+                "duplicateBytes": self.atlas_stats.duplicate_bytes,
+                "texels": self.atlas_stats.texels,
+                "packingEfficiency": self.atlas_stats.packing_efficiency(),
+                "sparse": self.atlas_stats.sparse_atlases,
+                "budgetBytes": self.atlas_stats.budget_bytes,
+                "overBudget": self.atlas_stats.over_budget
+            },
+            "bytesWritten": self.bytes_written,
+            "geometryCached": self.geometry_cached,
+            "durationSeconds": {
+                "total": self.duration.as_secs_f64(),
+                "ingestion": self.stages.ingestion.as_secs_f64(),
+                "transform": self.stages.transform.as_secs_f64(),
+                "tiling": self.stages.tiling.as_secs_f64(),
+                "validation": self.stages.validation.as_secs_f64()
+            }
+        })
+    }
+
+    /// Serialize [`Self::report_json`] to `path`.
+    pub fn write_report(&self, path: &Path) -> Result<()> {
+        let json_string = serde_json::to_string_pretty(&self.report_json())
+            .map_err(|e| PhotoTilerError::Output(format!("Failed to serialize report: {e}")))?;
+        fs::write(path, json_string).map_err(|e| {
+            PhotoTilerError::Output(format!("Failed to write report {}: {e}", path.display()))
+        })
+    }
 }
 
 /// Pipeline orchestrator -- drives the four conversion stages.
@@ -28,76 +100,214 @@ impl Pipeline {
         info!(input = %config.input.display(), "Starting pipeline");
 
         // Early exits
+        if let Some(tileset_path) = &config.validate_only {
+            info!("--validate-only: validating {}", tileset_path.display());
+            let uri_base = config.tiling.uri_base.as_deref();
+            let tile_count = Self::validate_tileset_with_base(tileset_path, uri_base)?;
+            let duration = start.elapsed();
+            return Ok(ProcessingResult {
+                tile_count,
+                duration,
+                stages: StageDurations {
+                    validation: duration,
+                    ..Default::default()
+                },
+                ..Default::default()
+            });
+        }
+
         if config.show_georef {
             info!("--show-georef: detecting georeferencing information");
-            let result = ingestion::ingest(config)?;
-            print_georef(&result);
+            let result = Self::ingest(config)?;
+            if config.json {
+                print_json(&json!({ "georeference": result.georeference }))?;
+            } else {
+                print_georef(&result);
+            }
             return Ok(ProcessingResult {
-                tile_count: 0,
                 duration: start.elapsed(),
+                ..Default::default()
             });
         }
 
         if config.dry_run {
             info!("--dry-run: scanning input and computing transforms");
-            let ingestion_result = ingestion::ingest(config)?;
-            let transform_result = transform::transform(config, &ingestion_result)?;
-            print_dry_run_summary(&ingestion_result, &transform_result);
+            let ingestion_result = Self::ingest(config)?;
+            let transform_result = Self::transform(config, &ingestion_result)?;
+            if config.json {
+                print_json(&dry_run_json(&ingestion_result, &transform_result))?;
+            } else {
+                print_dry_run_summary(&ingestion_result, &transform_result);
+            }
             return Ok(ProcessingResult {
-                tile_count: 0,
                 duration: start.elapsed(),
+                ..Default::default()
             });
         }
 
-        // Full pipeline
-        info!("Stage 1/4: Ingestion");
-        let ingestion_result = ingestion::ingest(config)?;
+        // Refuse a populated output directory before any work is done
+        check_output_dir(&config.output, config.force)?;
+
+        // Full pipeline, resuming from cached geometry where possible
+        let cache = config.cache_dir.as_deref().map(GeometryCache::new);
+        let cache_key = cache.as_ref().and_then(|_| {
+            cache::cache_key(config)
+                .inspect_err(|e| warn!("Not caching geometry: {e}"))
+                .ok()
+        });
+        let cached = cache.as_ref().zip(cache_key.as_deref());
+        let (transform_result, ingestion_time, transform_time, geometry_cached) =
+            match cached.and_then(|(cache, key)| cache.load(key)) {
+                Some(transform_result) => {
+                    info!("Stages 1-2/4: Reusing cached geometry");
+                    (transform_result, Duration::ZERO, Duration::ZERO, true)
+                }
+                None => {
+                    info!("Stage 1/4: Ingestion");
+                    let stage_start = Instant::now();
+                    let mut ingestion_result = Self::ingest(config)?;
+                    let ingestion_time = stage_start.elapsed();
 
-        info!("Stage 2/4: Transform");
-        let transform_result = transform::transform(config, &ingestion_result)?;
+                    info!("Stage 2/4: Transform");
+                    let stage_start = Instant::now();
+                    let transform_result = Self::transform(config, &ingestion_result)?;
+                    let transform_time = stage_start.elapsed();
+                    let source_files = std::mem::take(&mut ingestion_result.source_files);
+                    drop(ingestion_result);
+
+                    if let Some((cache, key)) = cached {
+                        if let Err(e) = cache.store(key, &transform_result, &source_files) {
+                            warn!("Failed to cache geometry: {e}");
+                        }
+                    }
+                    (transform_result, ingestion_time, transform_time, false)
+                }
+            };
         print_transform_summary(&transform_result);
 
         info!("Stage 3/4: Tiling");
-        fs::create_dir_all(&config.output).map_err(|e| {
-            PhotoTilerError::Output(format!(
-                "Failed to create output directory {}: {e}",
-                config.output.display()
-            ))
-        })?;
-        let tile_count = Self::tile(config, transform_result)?;
+        let mut result = Self::tile_only(transform_result, config)?;
+        result.stages.ingestion = ingestion_time;
+        result.stages.transform = transform_time;
+        result.geometry_cached = geometry_cached;
 
         if config.validate {
             info!("Stage 4/4: Validation");
+            let stage_start = Instant::now();
             Self::validate(config)?;
+            result.stages.validation = stage_start.elapsed();
         }
 
-        let duration = start.elapsed();
-        info!(tiles = tile_count, elapsed = ?duration, "Pipeline complete");
+        result.duration = start.elapsed();
+        info!(tiles = result.tile_count, elapsed = ?result.duration, "Pipeline complete");
 
-        Ok(ProcessingResult {
-            tile_count,
-            duration,
-        })
+        if let Some(report_path) = &config.report {
+            result.write_report(report_path)?;
+            info!(path = %report_path.display(), "Wrote conversion report");
+        }
+
+        Ok(result)
+    }
+
+    /// Ingest and transform the input without tiling, and describe the
+    /// result as `--dry-run --json` prints it: ingestion stats, detected
+    /// georeference, output bounds and root transform.
+    pub fn dry_run_json(config: &PipelineConfig) -> Result<serde_json::Value> {
+        let ingestion_result = Self::ingest(config)?;
+        let transform_result = Self::transform(config, &ingestion_result)?;
+        Ok(dry_run_json(&ingestion_result, &transform_result))
+    }
+
+    /// Run the full conversion pipeline on `pool` instead of rayon's global
+    /// thread pool, leaving the global pool for the host application.
+    pub fn run_in(config: &PipelineConfig, pool: &rayon::ThreadPool) -> Result<ProcessingResult> {
+        pool.install(|| Self::run(config))
+    }
+
+    /// Load the input named by `config.input`.
+    ///
+    /// The result can be kept and passed to [`Self::transform`] repeatedly to
+    /// re-tile with different settings without re-parsing the input.
+    pub fn ingest(config: &PipelineConfig) -> Result<IngestionResult> {
+        ingestion::ingest(config)
     }
 
-    fn tile(config: &PipelineConfig, transform_result: TransformResult) -> Result<usize> {
+    /// Apply unit scaling and georeferencing to ingested meshes.
+    pub fn transform(
+        config: &PipelineConfig,
+        ingestion_result: &IngestionResult,
+    ) -> Result<TransformResult> {
+        transform::transform(config, ingestion_result)
+    }
+
+    /// Tile already-transformed meshes into `config.output`.
+    ///
+    /// Runs only the tiling stage: no validation and no report. The returned
+    /// result covers tiling alone, so only `stages.tiling` is set.
+    pub fn tile_only(
+        transform_result: TransformResult,
+        config: &PipelineConfig,
+    ) -> Result<ProcessingResult> {
+        let start = Instant::now();
+        prepare_output_dir(&config.output, config.force)?;
+
+        let triangles_in = transform_result
+            .meshes
+            .iter()
+            .map(|m| m.triangle_count())
+            .sum();
+        let mut result = ProcessingResult {
+            triangles_in,
+            ..Default::default()
+        };
+        Self::tile(config, transform_result, &mut result)?;
+        result.stages.tiling = start.elapsed();
+        result.duration = result.stages.tiling;
+
+        Ok(result)
+    }
+
+    /// Build and write the tileset, recording output statistics in `result`.
+    fn tile(
+        config: &PipelineConfig,
+        transform_result: TransformResult,
+        result: &mut ProcessingResult,
+    ) -> Result<()> {
         let max_lod_levels = 1;
 
         // Destructure to take ownership of fields individually
         let TransformResult {
-            meshes,
+            mut meshes,
             bounds,
-            materials,
+            mut materials,
             root_transform,
+            ecef_origin,
+            tile_transforms,
+            instances,
         } = transform_result;
 
         let mesh_count = meshes.len();
 
+        if config.tiling.meshopt && !config.tiling.quantize {
+            info!("Tiles require EXT_meshopt_compression in the viewer (see --no-meshopt)");
+        }
+        if config.tiling.oct_normal_bits.is_some()
+            && (!config.tiling.meshopt || config.tiling.quantize)
+        {
+            warn!("--oct-normals only applies to meshopt-compressed tiles; ignoring it");
+        }
+
+        if config.tiling.feature_ids || config.tiling.b3dm {
+            materials.features = assign_feature_ids(&mut meshes);
+            info!(features = materials.features.len(), "Assigned feature IDs");
+        }
+
         // Move meshes into LOD generation (no extra copies)
         let lod_chains: Vec<_> = meshes
             .into_iter()
+            .zip(instances)
             .enumerate()
-            .map(|(i, mesh)| {
+            .map(|(i, (mesh, instances))| {
                 info!(
                     mesh = i,
                     vertices = mesh.vertex_count(),
@@ -105,7 +315,17 @@ impl Pipeline {
                     "Generating LOD chain"
                 );
 
-                let chain = lod::generate_lod_chain(mesh, &bounds, max_lod_levels);
+                // Meshes under a tile transform are measured in their own frame
+                let mesh_bounds = match tile_transforms.get(i).copied().flatten() {
+                    Some(_) => ensure_min_extent(
+                        compute_bounding_box(std::slice::from_ref(&mesh)),
+                        MIN_HALF_EXTENT,
+                    ),
+                    None => bounds,
+                };
+                let mut chain =
+                    lod::generate_lod_chain(mesh, &mesh_bounds, max_lod_levels, &config.tiling);
+                chain.instances = instances;
 
                 for level in &chain.levels {
                     info!(
@@ -130,62 +350,104 @@ impl Pipeline {
 
         // Build tile hierarchy and write GLBs eagerly to disk
         info!("Building tile hierarchy");
-        let tileset_output = tileset_writer::build_tileset(
+        let mut tileset_output = tileset_writer::build_tileset_with_transforms(
             lod_chains,
+            &tile_transforms,
             &bounds,
             &config.tiling,
             &materials,
             &config.texture,
             &config.output,
         );
+        tileset_output.ecef_origin = ecef_origin;
+
+        let stats = &tileset_output.atlas_stats;
+        if stats.atlases > 0 {
+            info!(
+                atlases = stats.atlases,
+                unique = stats.unique_atlases,
+                duplicate_bytes = stats.duplicate_bytes,
+                shared = config.texture.share_atlases,
+                "Atlas deduplication"
+            );
+        }
+        if let Some(efficiency) = stats.packing_efficiency() {
+            info!(efficiency, sparse = stats.sparse_atlases, "Atlas packing");
+            if efficiency < LOW_PACKING_EFFICIENCY {
+                let hint = if config.texture.allow_rotation {
+                    "a smaller --texture-max-size"
+                } else {
+                    "--atlas-rotation or a smaller --texture-max-size"
+                };
+                warn!(
+                    "Atlases are only {:.0}% filled by islands ({} of {} sparse); try {hint}",
+                    efficiency * 100.0,
+                    stats.sparse_atlases,
+                    stats.atlases,
+                );
+            }
+        }
+
+        if let Some(budget) = config.texture.max_memory {
+            info!(bytes = stats.budget_bytes, budget, "Texture memory");
+            if stats.over_budget > 0 {
+                warn!(
+                    "{} tile(s) written untextured to stay within --max-texture-memory",
+                    stats.over_budget
+                );
+            }
+        }
 
         // Write tileset.json (GLBs already on disk)
         info!(output = %config.output.display(), "Writing tileset.json");
         let tile_count =
-            tileset_writer::write_tileset(&tileset_output, &root_transform, &config.output)?;
+            tileset_writer::write_tileset(&mut tileset_output, &root_transform, &config.output)?;
 
-        Ok(tile_count)
+        let tile_stats = tileset_output.tile_stats;
+        result.tile_count = tile_count;
+        result.triangles_out = tile_stats.triangles;
+        result.tiles_per_level = tile_stats.tiles_per_level;
+        result.bytes_written = tile_stats.bytes_written;
+        result.atlas_stats = tileset_output.atlas_stats;
+
+        Ok(())
     }
 
     fn validate(config: &PipelineConfig) -> Result<()> {
-        let out_dir = &config.output;
+        Self::validate_tileset_with_base(&config.output, config.tiling.uri_base.as_deref())
+            .map(|_| ())
+    }
 
-        // 1. tileset.json must exist and be valid JSON
-        let tileset_path = out_dir.join("tileset.json");
-        let json_str = fs::read_to_string(&tileset_path).map_err(|e| {
-            PhotoTilerError::Validation(format!(
-                "Cannot read tileset.json at {}: {e}",
-                tileset_path.display()
-            ))
-        })?;
-
-        let tileset: serde_json::Value = serde_json::from_str(&json_str).map_err(|e| {
-            PhotoTilerError::Validation(format!("tileset.json is not valid JSON: {e}"))
-        })?;
-
-        // 2. Required top-level fields
-        let asset = tileset
-            .get("asset")
-            .ok_or_else(|| PhotoTilerError::Validation("Missing 'asset' field".into()))?;
-        let version = asset
-            .get("version")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        if version != "1.1" {
-            return Err(PhotoTilerError::Validation(format!(
-                "Expected asset.version '1.1', got '{version}'"
-            )));
-        }
+    /// Validate an existing tileset, whether or not photo-tiler produced it.
+    ///
+    /// `path` is either a `tileset.json` or the directory containing one.
+    /// Content URIs are resolved relative to that directory, and every GLB
+    /// or b3dm must exist and parse. Returns the number of tiles checked.
+    pub fn validate_tileset(path: &Path) -> Result<usize> {
+        Self::validate_tileset_with_base(path, None)
+    }
 
-        let root = tileset
-            .get("root")
-            .ok_or_else(|| PhotoTilerError::Validation("Missing 'root' tile".into()))?;
+    /// Like [`Pipeline::validate_tileset`], for a tileset written with
+    /// `--uri-base`: content URIs starting with `uri_base` are resolved
+    /// relative to the tileset's directory with the base removed.
+    pub fn validate_tileset_with_base(path: &Path, uri_base: Option<&str>) -> Result<usize> {
+        let tileset_path = if path.is_dir() {
+            path.join("tileset.json")
+        } else {
+            path.to_path_buf()
+        };
 
-        // 3. Walk tile tree: validate each tile
         let mut tile_count = 0;
         let mut glb_count = 0;
         let mut errors = Vec::new();
-        validate_tile(root, out_dir, None, &mut tile_count, &mut glb_count, &mut errors);
+        validate_tileset_json(
+            &tileset_path,
+            uri_base,
+            None,
+            &mut tile_count,
+            &mut glb_count,
+            &mut errors,
+        )?;
 
         for err in &errors {
             warn!("Validation: {err}");
@@ -201,14 +463,148 @@ impl Pipeline {
             )));
         }
 
-        Ok(())
+        Ok(tile_count)
     }
 }
 
+/// Validate one tileset.json and the tile tree under it, collecting issues
+/// into `errors`.
+///
+/// Content URIs resolve relative to the file's directory. `parent_error` is
+/// the geometric error of the tile referencing it when this is an external
+/// tileset. Fails outright only when the file cannot be read as a tileset.
+fn validate_tileset_json(
+    tileset_path: &Path,
+    uri_base: Option<&str>,
+    parent_error: Option<f64>,
+    tile_count: &mut usize,
+    glb_count: &mut usize,
+    errors: &mut Vec<String>,
+) -> Result<()> {
+    // 1. tileset.json must exist and be valid JSON
+    let json_str = fs::read_to_string(tileset_path).map_err(|e| {
+        PhotoTilerError::Validation(format!(
+            "Cannot read tileset.json at {}: {e}",
+            tileset_path.display()
+        ))
+    })?;
+
+    let tileset: serde_json::Value = serde_json::from_str(&json_str)
+        .map_err(|e| PhotoTilerError::Validation(format!("tileset.json is not valid JSON: {e}")))?;
+
+    // 2. Required top-level fields
+    let asset = tileset
+        .get("asset")
+        .ok_or_else(|| PhotoTilerError::Validation("Missing 'asset' field".into()))?;
+    let version = asset.get("version").and_then(|v| v.as_str()).unwrap_or("");
+    if version != "1.0" && version != "1.1" {
+        return Err(PhotoTilerError::Validation(format!(
+            "Expected asset.version '1.0' or '1.1', got '{version}'"
+        )));
+    }
+
+    let root = tileset
+        .get("root")
+        .ok_or_else(|| PhotoTilerError::Validation("Missing 'root' tile".into()))?;
+
+    // 3. Walk tile tree: validate each tile
+    let out_dir = tileset_path.parent().unwrap_or(Path::new(""));
+    let glbs_before = *glb_count;
+    validate_tile(
+        root,
+        out_dir,
+        uri_base,
+        parent_error,
+        tile_count,
+        glb_count,
+        errors,
+    );
+
+    // 4. 3D Tiles 1.0 has no glTF content without the extension
+    let declares_gltf_content = tileset
+        .get("extensionsRequired")
+        .and_then(|e| e.as_array())
+        .is_some_and(|e| e.iter().any(|name| name == "3DTILES_content_gltf"));
+    if version == "1.0" && *glb_count > glbs_before && !declares_gltf_content {
+        errors.push(
+            "3D Tiles 1.0 tileset has GLB content but does not require 3DTILES_content_gltf".into(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Refuse to write into a non-empty output directory unless `force` is set.
+fn check_output_dir(out_dir: &Path, force: bool) -> Result<()> {
+    if is_populated(out_dir) && !force {
+        return Err(PhotoTilerError::Output(format!(
+            "Output directory {} is not empty (use --force to overwrite)",
+            out_dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Whether `dir` exists and has any entries.
+fn is_populated(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// Create the output directory, refusing to write into a non-empty one.
+///
+/// With `force`, the previous run's `tiles/` directory and `tileset.json`
+/// are removed first so no orphaned GLBs survive; other files are kept.
+fn prepare_output_dir(out_dir: &Path, force: bool) -> Result<()> {
+    let output_err = |what: &str, e: std::io::Error| {
+        PhotoTilerError::Output(format!("Failed to {what} {}: {e}", out_dir.display()))
+    };
+
+    check_output_dir(out_dir, force)?;
+    if is_populated(out_dir) {
+        let tiles_dir = out_dir.join("tiles");
+        if tiles_dir.exists() {
+            fs::remove_dir_all(&tiles_dir).map_err(|e| output_err("clear tiles in", e))?;
+        }
+        let tileset_path = out_dir.join("tileset.json");
+        if tileset_path.exists() {
+            fs::remove_file(&tileset_path).map_err(|e| output_err("remove tileset.json in", e))?;
+        }
+        warn!(path = %out_dir.display(), "--force: replaced previous tileset");
+    }
+
+    fs::create_dir_all(out_dir).map_err(|e| output_err("create output directory", e))
+}
+
+/// Tag every vertex with the feature ID of its mesh's source object.
+///
+/// Meshes sharing a name (e.g. one object split by material) share an ID;
+/// unnamed meshes each get their own, named after their index. Returns the
+/// feature names indexed by ID.
+fn assign_feature_ids(meshes: &mut [IndexedMesh]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (i, mesh) in meshes.iter_mut().enumerate() {
+        let name = mesh
+            .name
+            .clone()
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| format!("mesh_{i}"));
+        let id = match names.iter().position(|n| *n == name) {
+            Some(id) => id,
+            None => {
+                names.push(name);
+                names.len() - 1
+            }
+        };
+        mesh.feature_ids = vec![id as u32; mesh.vertex_count()];
+    }
+    names
+}
+
 /// Recursively validate a tile node from tileset.json.
 fn validate_tile(
     tile: &serde_json::Value,
     out_dir: &std::path::Path,
+    uri_base: Option<&str>,
     parent_error: Option<f64>,
     tile_count: &mut usize,
     glb_count: &mut usize,
@@ -216,9 +612,29 @@ fn validate_tile(
 ) {
     *tile_count += 1;
 
-    // Bounding volume must exist
-    if tile.get("boundingVolume").is_none() {
-        errors.push(format!("Tile {tile_count}: missing boundingVolume"));
+    // Identify tiles by their content URI where they have one
+    let uri = tile
+        .get("content")
+        .and_then(|c| c.get("uri"))
+        .and_then(|u| u.as_str());
+    let label = match uri {
+        Some(uri) => format!("Tile {tile_count} ({uri})"),
+        None => format!("Tile {tile_count}"),
+    };
+
+    // Bounding volume must exist and be well-formed
+    match tile.get("boundingVolume") {
+        Some(volume) => validate_bounding_volume(volume, &label, errors),
+        None => errors.push(format!("{label}: missing boundingVolume")),
+    }
+    if let Some(volume) = tile.get("content").and_then(|c| c.get("boundingVolume")) {
+        validate_bounding_volume(volume, &format!("{label} content"), errors);
+    }
+
+    if let Some(transform) = tile.get("transform") {
+        if let Err(e) = finite_numbers(transform, 16) {
+            errors.push(format!("{label}: invalid transform: {e}"));
+        }
     }
 
     // Geometric error must be non-negative
@@ -227,37 +643,63 @@ fn validate_tile(
         .and_then(|v| v.as_f64())
         .unwrap_or(-1.0);
     if geo_error < 0.0 {
-        errors.push(format!("Tile {tile_count}: invalid geometricError {geo_error}"));
+        errors.push(format!("{label}: invalid geometricError {geo_error}"));
     }
 
     // Geometric error should not exceed parent's
     if let Some(parent_err) = parent_error {
         if geo_error > parent_err + 1e-6 {
             errors.push(format!(
-                "Tile {tile_count}: geometricError {geo_error} > parent {parent_err}"
+                "{label}: geometricError {geo_error} > parent {parent_err}"
             ));
         }
     }
 
-    // If tile has content, verify the GLB file
-    if let Some(content) = tile.get("content") {
-        if let Some(uri) = content.get("uri").and_then(|u| u.as_str()) {
-            let glb_path = out_dir.join(uri);
-            if !glb_path.exists() {
-                errors.push(format!("Tile {tile_count}: GLB not found: {uri}"));
-            } else {
-                *glb_count += 1;
-                // Try to parse the GLB
-                match fs::read(&glb_path) {
-                    Ok(data) => {
-                        if Glb::from_slice(&data).is_err() {
-                            errors.push(format!("Tile {tile_count}: GLB not parseable: {uri}"));
-                        }
-                    }
-                    Err(e) => {
-                        errors.push(format!("Tile {tile_count}: cannot read {uri}: {e}"));
+    // If tile has content, verify the GLB file or external tileset
+    if let Some(uri) = uri {
+        let prefix = with_uri_base(uri_base, "");
+        let content_path = out_dir.join(uri.strip_prefix(prefix.as_str()).unwrap_or(uri));
+        if uri.ends_with(".json") {
+            if tile["children"].as_array().is_some_and(|c| !c.is_empty()) {
+                errors.push(format!("{label}: external tileset tile has children"));
+            }
+            // Its content URIs are relative to its own location
+            if let Err(e) = validate_tileset_json(
+                &content_path,
+                uri_base,
+                Some(geo_error),
+                tile_count,
+                glb_count,
+                errors,
+            ) {
+                errors.push(format!("{label}: external tileset {uri}: {e}"));
+            }
+        } else if !content_path.exists() {
+            errors.push(format!("{label}: GLB not found: {uri}"));
+        } else if uri.ends_with(".b3dm") {
+            // Batched 3D Models are native 1.0 content wrapping a GLB
+            match fs::read(&content_path) {
+                Ok(data) => match read_b3dm(&data) {
+                    Some(b3dm) if Glb::from_slice(b3dm.glb).is_ok() => {}
+                    Some(_) => errors.push(format!("{label}: b3dm GLB not parseable: {uri}")),
+                    None => errors.push(format!("{label}: b3dm not parseable: {uri}")),
+                },
+                Err(e) => {
+                    errors.push(format!("{label}: cannot read {uri}: {e}"));
+                }
+            }
+        } else {
+            *glb_count += 1;
+            // Try to parse the GLB
+            match fs::read(&content_path) {
+                Ok(data) => {
+                    if Glb::from_slice(&data).is_err() {
+                        errors.push(format!("{label}: GLB not parseable: {uri}"));
                     }
                 }
+                Err(e) => {
+                    errors.push(format!("{label}: cannot read {uri}: {e}"));
+                }
             }
         }
     }
@@ -265,11 +707,75 @@ fn validate_tile(
     // Recurse into children
     if let Some(children) = tile.get("children").and_then(|c| c.as_array()) {
         for child in children {
-            validate_tile(child, out_dir, Some(geo_error), tile_count, glb_count, errors);
+            validate_tile(
+                child,
+                out_dir,
+                uri_base,
+                Some(geo_error),
+                tile_count,
+                glb_count,
+                errors,
+            );
         }
     }
 }
 
+/// Check the shape of a `boundingVolume`: a `box` of 12 finite numbers whose
+/// axis-aligned half-extents are non-negative, a `region` of 6, or a
+/// `sphere` of 4 with a non-negative radius.
+fn validate_bounding_volume(volume: &serde_json::Value, label: &str, errors: &mut Vec<String>) {
+    let checked = if let Some(bbox) = volume.get("box") {
+        finite_numbers(bbox, 12).and_then(|b| {
+            // Each half-axis is a column; an axis-aligned one must point along +axis
+            for axis in 0..3 {
+                let half = &b[3 + axis * 3..6 + axis * 3];
+                let off_axis = (0..3).filter(|&i| i != axis).all(|i| half[i] == 0.0);
+                if off_axis && half[axis] < 0.0 {
+                    return Err(format!(
+                        "box half-extent {axis} is negative ({})",
+                        half[axis]
+                    ));
+                }
+            }
+            Ok(())
+        })
+    } else if let Some(region) = volume.get("region") {
+        finite_numbers(region, 6).map(|_| ())
+    } else if let Some(sphere) = volume.get("sphere") {
+        finite_numbers(sphere, 4).and_then(|s| {
+            if s[3] < 0.0 {
+                Err(format!("sphere radius is negative ({})", s[3]))
+            } else {
+                Ok(())
+            }
+        })
+    } else {
+        Err("expected box, region or sphere".into())
+    };
+
+    if let Err(e) = checked {
+        errors.push(format!("{label}: invalid boundingVolume: {e}"));
+    }
+}
+
+/// Read `value` as an array of exactly `len` finite numbers.
+fn finite_numbers(value: &serde_json::Value, len: usize) -> std::result::Result<Vec<f64>, String> {
+    let array = value
+        .as_array()
+        .ok_or_else(|| "expected an array".to_string())?;
+    if array.len() != len {
+        return Err(format!("expected {len} numbers, got {}", array.len()));
+    }
+    array
+        .iter()
+        .enumerate()
+        .map(|(i, v)| match v.as_f64() {
+            Some(x) if x.is_finite() => Ok(x),
+            _ => Err(format!("element {i} is not a finite number ({v})")),
+        })
+        .collect()
+}
+
 /// Print georeferencing information and exit.
 fn print_georef(result: &IngestionResult) {
     println!("=== Georeferencing ===");
@@ -307,7 +813,9 @@ fn print_transform_summary(result: &TransformResult) {
         && rt[13] == 0.0
         && rt[14] == 0.0;
 
-    if is_identity {
+    if let Some([x, y, z]) = result.ecef_origin {
+        println!("  Root transform: identity (local ENU, origin ECEF ({x:.1}, {y:.1}, {z:.1}))");
+    } else if is_identity {
         println!("  Root transform: identity (local coordinates)");
     } else {
         println!(
@@ -325,13 +833,195 @@ fn print_dry_run_summary(ingestion: &IngestionResult, transform: &TransformResul
     println!("  Meshes:    {}", stats.total_meshes);
     println!("  Vertices:  {}", stats.total_vertices);
     println!("  Triangles: {}", stats.total_triangles);
+    if stats.dropped_triangles > 0 {
+        println!(
+            "  Dropped:   {} (non-finite or zero-area)",
+            stats.dropped_triangles
+        );
+    }
     println!("  Normals:   {}", if stats.has_normals { "yes" } else { "no" });
     println!("  UVs:       {}", if stats.has_uvs { "yes" } else { "no" });
     println!("  Colors:    {}", if stats.has_colors { "yes" } else { "no" });
     println!("  Materials: {}", stats.material_count);
     println!("  Textures:  {}", stats.texture_count);
+    if stats.missing_textures > 0 {
+        println!("  Missing:   {} textures", stats.missing_textures);
+    }
+    if let Some(topology) = stats.topology {
+        println!(
+            "  Topology:  {} boundary / {} non-manifold of {} edges, {} duplicate faces",
+            topology.boundary_edges,
+            topology.non_manifold_edges,
+            topology.edges,
+            topology.duplicate_faces
+        );
+    }
     println!();
     print_georef(ingestion);
     println!();
     print_transform_summary(transform);
 }
+
+/// Dry-run summary as JSON, for `--dry-run --json`.
+fn dry_run_json(ingestion: &IngestionResult, transform: &TransformResult) -> serde_json::Value {
+    json!({
+        "stats": ingestion.stats,
+        "georeference": ingestion.georeference,
+        "detectedUnits": ingestion.units.map(|u| u.to_string()),
+        "bounds": transform.bounds,
+        "diagonal": transform.bounds.diagonal(),
+        "rootTransform": transform.root_transform,
+        "ecefOrigin": transform.ecef_origin
+    })
+}
+
+/// Pretty-print `value` to stdout.
+fn print_json(value: &serde_json::Value) -> Result<()> {
+    let json_string = serde_json::to_string_pretty(value)
+        .map_err(|e| PhotoTilerError::Output(format!("Failed to serialize JSON: {e}")))?;
+    println!("{json_string}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn tile(bounding_volume: Value) -> Value {
+        json!({
+            "boundingVolume": bounding_volume,
+            "geometricError": 0.0,
+            "content": { "uri": "tiles/0_1.glb" }
+        })
+    }
+
+    fn validate(tile: &Value) -> Vec<String> {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut errors = Vec::new();
+        validate_tile(tile, tmp.path(), None, None, &mut 0, &mut 0, &mut errors);
+        // The GLB is never written; only keep the structural errors
+        errors.retain(|e| !e.contains("GLB not found"));
+        errors
+    }
+
+    #[test]
+    fn well_formed_volumes_pass() {
+        let boxed = tile(json!({ "box": [0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0] }));
+        assert!(validate(&boxed).is_empty());
+
+        let sphere = tile(json!({ "sphere": [0, 0, 0, 5] }));
+        assert!(validate(&sphere).is_empty());
+
+        // Rotated half-axes may have negative components
+        let rotated = tile(json!({ "box": [0, 0, 0, 0, -1, 0, 1, 0, 0, 0, 0, 1] }));
+        assert!(validate(&rotated).is_empty());
+    }
+
+    #[test]
+    fn short_box_is_flagged_with_uri() {
+        let short = tile(json!({ "box": [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0] }));
+        let errors = validate(&short);
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("tiles/0_1.glb"), "{}", errors[0]);
+        assert!(
+            errors[0].contains("expected 12 numbers, got 11"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn negative_half_extent_is_flagged() {
+        let flipped = tile(json!({ "box": [0, 0, 0, 1, 0, 0, 0, -1, 0, 0, 0, 1] }));
+        let errors = validate(&flipped);
+
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].contains("half-extent 1 is negative"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn external_tileset_error_is_checked_against_parent() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tiles_dir = tmp.path().join("tiles/0_1");
+        fs::create_dir_all(&tiles_dir).unwrap();
+        let volume = json!({ "box": [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1] });
+        let child = json!({
+            "asset": { "version": "1.1" },
+            "geometricError": 8.0,
+            "root": { "boundingVolume": volume, "geometricError": 8.0 }
+        });
+        fs::write(tiles_dir.join("tileset.json"), child.to_string()).unwrap();
+        let root = json!({
+            "asset": { "version": "1.1" },
+            "geometricError": 16.0,
+            "root": {
+                "boundingVolume": volume,
+                "geometricError": 16.0,
+                "children": [{
+                    "boundingVolume": volume,
+                    "geometricError": 4.0,
+                    "content": { "uri": "/assets/tiles/0_1/tileset.json" }
+                }]
+            }
+        });
+        let tileset_path = tmp.path().join("tileset.json");
+        fs::write(&tileset_path, root.to_string()).unwrap();
+
+        let err = Pipeline::validate_tileset_with_base(&tileset_path, Some("/assets"))
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("geometricError 8 > parent 4"), "{err}");
+    }
+
+    #[test]
+    fn feature_ids_follow_mesh_names() {
+        let mesh = |name: Option<&str>| IndexedMesh {
+            positions: vec![0.0; 9],
+            indices: vec![0, 1, 2],
+            name: name.map(str::to_string),
+            ..Default::default()
+        };
+        // "wall" split by material shares one ID; unnamed meshes get their own
+        let mut meshes = vec![
+            mesh(Some("wall")),
+            mesh(None),
+            mesh(Some("wall")),
+            mesh(Some("")),
+        ];
+
+        let names = assign_feature_ids(&mut meshes);
+
+        assert_eq!(names, ["wall", "mesh_1", "mesh_3"]);
+        let ids: Vec<u32> = meshes.iter().map(|m| m.feature_ids[0]).collect();
+        assert_eq!(ids, [0, 1, 0, 2]);
+        assert!(
+            meshes
+                .iter()
+                .all(|m| m.feature_ids.len() == m.vertex_count())
+        );
+    }
+
+    #[test]
+    fn nan_transform_is_flagged() {
+        let mut transform = vec![json!(0.0); 16];
+        // JSON has no NaN, so serializers emit null in its place
+        transform[5] = json!(f64::NAN);
+        let mut bad = tile(json!({ "box": [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 1] }));
+        bad["transform"] = Value::Array(transform);
+        let errors = validate(&bad);
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("invalid transform"), "{}", errors[0]);
+        assert!(errors[0].contains("element 5"), "{}", errors[0]);
+
+        bad["transform"] = json!([1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert!(validate(&bad)[0].contains("expected 16 numbers, got 15"));
+    }
+}
@@ -1,11 +1,20 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use image::RgbaImage;
+use rayon::prelude::*;
 use tracing::warn;
 
 use crate::config::TextureConfig;
 use crate::tiling::texture_compress;
-use crate::types::{IndexedMesh, MaterialLibrary, TextureData};
+use crate::tiling::triangle_clipper::clip_faces_to_uv_rects;
+use crate::types::{
+    AlphaMode, IndexedMesh, MaterialGroup, MaterialLibrary, PBRMaterial, TextureData,
+};
+
+/// Source textures decoded for earlier atlases, keyed by texture index, so
+/// the tiles of a build decode each source once.
+pub type DecodedSources = Mutex<HashMap<usize, Arc<RgbaImage>>>;
 
 /// Result of atlas repacking for a single tile.
 pub struct AtlasResult {
@@ -13,28 +22,109 @@ pub struct AtlasResult {
     pub mesh: IndexedMesh,
     /// Composited and compressed atlas texture.
     pub atlas_texture: TextureData,
+    /// Metallic-roughness atlas with the same island layout as
+    /// `atlas_texture`, when the material has a metallic-roughness map.
+    pub metallic_roughness_texture: Option<TextureData>,
+    /// Normal map atlas with the same island layout, when the material has
+    /// a normal map.
+    pub normal_texture: Option<TextureData>,
+    /// Further atlases, when one would exceed `hard_max_size`. The textures
+    /// above then cover the mesh's triangles up to the first page.
+    pub pages: Vec<AtlasPage>,
+    /// Texels of island content (bleed padding excluded) in the packed
+    /// atlases, before any downscale to `max_size`.
+    pub island_texels: u64,
+    /// Texels of the packed atlases, before any downscale to `max_size`.
+    pub atlas_texels: u64,
+}
+
+/// An atlas texturing the triangles of `AtlasResult::mesh` from
+/// `first_index` (into its indices) up to the next page. Its material
+/// groups never straddle a page boundary.
+pub struct AtlasPage {
+    pub first_index: usize,
+    pub atlas_texture: TextureData,
+    pub metallic_roughness_texture: Option<TextureData>,
+    pub normal_texture: Option<TextureData>,
+}
+
+impl AtlasResult {
+    /// Fraction of the packed atlas covered by islands.
+    pub fn packing_efficiency(&self) -> f64 {
+        self.island_texels as f64 / self.atlas_texels.max(1) as f64
+    }
+
+    /// The base color, metallic-roughness and normal atlases of each page,
+    /// with the first index of the triangles they texture.
+    pub fn page_textures(&self) -> impl Iterator<Item = (usize, [Option<&TextureData>; 3])> {
+        let first = [
+            Some(&self.atlas_texture),
+            self.metallic_roughness_texture.as_ref(),
+            self.normal_texture.as_ref(),
+        ];
+        let pages = self.pages.iter().map(|page| {
+            let textures = [
+                Some(&page.atlas_texture),
+                page.metallic_roughness_texture.as_ref(),
+                page.normal_texture.as_ref(),
+            ];
+            (page.first_index, textures)
+        });
+        std::iter::once((0, first)).chain(pages)
+    }
 }
 
+/// Packing efficiency below which an atlas counts as sparse and the run
+/// warns about wasted texture space.
+pub const LOW_PACKING_EFFICIENCY: f64 = 0.5;
+
+/// Largest atlas side the packer grows to before it shrinks islands instead.
+const MAX_ATLAS_SIZE: u32 = 16384;
+
+/// Share of a page's area filled with islands before the next page opens,
+/// leaving the packer room for the gaps between them.
+const PAGE_FILL: f64 = 0.7;
+
+/// How far UVs may stray outside `[0, 1]` through rounding alone before the
+/// texture counts as repeated.
+const UV_TILE_EPSILON: f32 = 1e-4;
+
 /// A connected component of UV-space triangles.
 struct UvIsland {
     /// Face indices belonging to this island.
     faces: Vec<usize>,
+    /// Index of the source texture the island samples.
+    source: usize,
     /// UV bounding rect: (min_u, min_v, max_u, max_v).
     uv_min: [f32; 2],
     uv_max: [f32; 2],
 }
 
+impl UvIsland {
+    /// Whether the island's UVs leave the `[0, 1]` tile, i.e. the source
+    /// texture repeats (or wraps) across it.
+    fn is_tiled(&self) -> bool {
+        self.uv_max[0] - self.uv_min[0] > 1.0
+            || self.uv_max[1] - self.uv_min[1] > 1.0
+            || self.uv_min[0].floor() != (self.uv_max[0] - f32::EPSILON).floor()
+            || self.uv_min[1].floor() != (self.uv_max[1] - f32::EPSILON).floor()
+    }
+}
+
 /// Placement result from the bin packer.
 struct Placement {
     island_idx: usize,
     /// Position in pixels (top-left of padded region).
     x: u32,
     y: u32,
-    /// Inner (content) dimensions in pixels.
+    /// Inner (content) dimensions in pixels, as laid out in the atlas.
     inner_w: u32,
     inner_h: u32,
     /// Padding in pixels.
     padding: u32,
+    /// Island is stored transposed: its U axis runs along atlas Y and its
+    /// V axis along atlas X (`inner_w`/`inner_h` are already swapped).
+    rotated: bool,
 }
 
 /// A free rectangle in the guillotine packer.
@@ -48,29 +138,128 @@ struct FreeRect {
 
 /// Repack textures for a tile mesh into a single atlas.
 ///
-/// Returns `None` if the mesh has no UVs, no material, or the material has no texture.
+/// The islands of every material with a base color texture share the atlas.
+/// Their faces collapse onto the first material of the same atlas group,
+/// made of the materials that differ only in their textures (see
+/// [`shares_atlas`]); faces of other materials keep theirs. Returns `None`
+/// if the mesh has no UVs or no face has a textured material.
 pub fn repack_atlas(
     mesh: &IndexedMesh,
     materials: &MaterialLibrary,
     config: &TextureConfig,
+) -> Option<AtlasResult> {
+    repack_atlas_scaled(mesh, materials, config, 1.0, &DecodedSources::default())
+}
+
+/// Repack textures at a reduced texel density.
+///
+/// `texel_scale` (in `(0, 1]`) scales each island's pixel size and the
+/// effective `max_size`, so coarse LOD tiles get proportionally smaller
+/// atlases than full-detail tiles. Sources are taken from `decoded` when an
+/// earlier atlas decoded them, and added to it otherwise; all atlases
+/// sharing `decoded` must use the same `config.max_source_size`.
+pub fn repack_atlas_scaled(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    config: &TextureConfig,
+    texel_scale: f32,
+    decoded: &DecodedSources,
 ) -> Option<AtlasResult> {
     if !mesh.has_uvs() {
         return None;
     }
 
-    let mat_idx = mesh.material_index?;
-    let mat = materials.materials.get(mat_idx)?;
-    let tex_idx = mat.base_color_texture?;
-    let tex = materials.textures.get(tex_idx)?;
+    // Each face samples the base color texture of its material; every
+    // distinct texture is decoded once per atlas group as an island source
+    let tri_materials = mesh.triangle_materials();
+    let mut atlas_materials: Vec<usize> = Vec::new();
+    let mut textures: Vec<(&PBRMaterial, usize, usize)> = Vec::new();
+    let mut slot_of_texture: HashMap<(usize, usize), usize> = HashMap::new();
+    let face_slots: Vec<Option<usize>> = tri_materials
+        .iter()
+        .map(|&material| {
+            let material = material?;
+            let mat = materials.materials.get(material)?;
+            let tex_idx = mat.base_color_texture?;
+            let group = atlas_materials
+                .iter()
+                .position(|&m| shares_atlas(&materials.materials[m], mat))
+                .unwrap_or_else(|| {
+                    atlas_materials.push(material);
+                    atlas_materials.len() - 1
+                });
+            Some(*slot_of_texture.entry((tex_idx, group)).or_insert_with(|| {
+                textures.push((mat, tex_idx, group));
+                textures.len() - 1
+            }))
+        })
+        .collect();
+
+    // Decoding dominates for large sources, so distinct ones decode in
+    // parallel; they keep first-use order, as do the sources that decoded
+    let slot_images: Vec<Option<(&PBRMaterial, &TextureData, Arc<RgbaImage>)>> = textures
+        .par_iter()
+        .map(|&(mat, tex_idx, _)| {
+            let tex = materials.textures.get(tex_idx)?;
+            Some((mat, tex, decode_shared(decoded, tex_idx, tex, config)?))
+        })
+        .collect();
+    let mut sources = Vec::with_capacity(slot_images.len());
+    let mut source_materials = Vec::with_capacity(slot_images.len());
+    let source_of_slot: Vec<Option<usize>> = slot_images
+        .into_iter()
+        .zip(&textures)
+        .map(|(source, &(_, _, group))| {
+            sources.push(source?);
+            source_materials.push(atlas_materials[group]);
+            Some(sources.len() - 1)
+        })
+        .collect();
+    let face_sources: Vec<Option<usize>> = face_slots
+        .iter()
+        .map(|slot| source_of_slot[(*slot)?])
+        .collect();
+    let source_images: Vec<&RgbaImage> = sources.iter().map(|(_, _, image)| &**image).collect();
+
+    // Faces without a texture stay out of the atlas, in runs per material
+    let mut untextured: Vec<(Vec<usize>, Option<usize>)> = Vec::new();
+    for (face, (source, &material)) in face_sources.iter().zip(&tri_materials).enumerate() {
+        if source.is_some() {
+            continue;
+        }
+        match untextured.last_mut() {
+            Some((faces, run_material)) if *run_material == material => faces.push(face),
+            _ => untextured.push((vec![face], material)),
+        }
+    }
 
-    let source_image = decode_texture(tex)?;
-    let (src_w, src_h) = source_image.dimensions();
+    let texel_scale = texel_scale.clamp(f32::EPSILON, 1.0);
+    let max_size = scaled_max_size(config.max_size, texel_scale);
 
     // 1. Build edge adjacency
     let adjacency = build_edge_adjacency(mesh);
 
     // 2. BFS island detection
-    let islands = detect_islands(mesh, &adjacency);
+    let mut islands = detect_islands(mesh, &adjacency, &face_sources);
+
+    // Optionally shrink islands to their non-transparent texels
+    let trimmed_mesh = if config.trim_transparent {
+        // Opaque materials ignore alpha, so their "transparent" texels show
+        let trimmable: Vec<bool> = source_materials
+            .iter()
+            .map(|&m| materials.materials[m].alpha_mode != AlphaMode::Opaque)
+            .collect();
+        trim_islands(
+            mesh,
+            &mut islands,
+            &source_images,
+            &trimmable,
+            &mut untextured,
+        )
+    } else {
+        None
+    };
+    let mesh = trimmed_mesh.as_ref().unwrap_or(mesh);
 
     if islands.is_empty() {
         return None;
@@ -81,71 +270,429 @@ pub fn repack_atlas(
         .iter()
         .enumerate()
         .map(|(i, island)| {
+            let (src_w, src_h) = source_images[island.source].dimensions();
             let u_range = island.uv_max[0] - island.uv_min[0];
             let v_range = island.uv_max[1] - island.uv_min[1];
 
-            let mut px_w = (u_range * src_w as f32).ceil().max(1.0) as u32;
-            let mut px_h = (v_range * src_h as f32).ceil().max(1.0) as u32;
+            let mut px_w = (u_range * src_w as f32 * texel_scale).ceil().max(1.0) as u32;
+            let mut px_h = (v_range * src_h as f32 * texel_scale).ceil().max(1.0) as u32;
+
+            if island.is_tiled() {
+                // Tiled (repeating) UVs are baked into the island at the true
+                // texel density; `composite_atlas` wraps while sampling. Cap
+                // both axes by the same factor so every repeat keeps its aspect.
+                let fit = (max_size as f32 / px_w.max(px_h) as f32).min(1.0);
+                px_w = ((px_w as f32 * fit).floor() as u32).max(1);
+                px_h = ((px_h as f32 * fit).floor() as u32).max(1);
+            }
 
             // Cap to max_size
-            if px_w > config.max_size {
-                px_w = config.max_size;
+            if px_w > max_size {
+                px_w = max_size;
             }
-            if px_h > config.max_size {
-                px_h = config.max_size;
+            if px_h > max_size {
+                px_h = max_size;
             }
 
-            // Bleed padding: 2-5 px based on island size
+            // Bleed padding: configured, or 2-5 px based on island size
             let max_dim = px_w.max(px_h);
-            let padding = if max_dim > 512 {
+            let padding = config.bleed_padding.unwrap_or(if max_dim > 512 {
                 5
             } else if max_dim > 128 {
                 3
             } else {
                 2
-            };
+            });
 
             (i, px_w, px_h, padding)
         })
         .collect();
 
-    // 4. Guillotine bin packing
-    let placements = guillotine_pack(&sized);
+    // 4. Guillotine bin packing, over several pages when a single atlas
+    // would still exceed the GPU limit after the downscale to `max_size`
+    let placements = guillotine_pack(&sized, config.allow_rotation);
     let atlas_size = compute_atlas_size(&placements);
+    let pages: Vec<(Vec<Placement>, u32)> = if atlas_size.min(max_size) > config.hard_max_size {
+        let pages = paginate(&sized, config.allow_rotation, config.hard_max_size);
+        pages
+            .into_iter()
+            .map(|placements| {
+                let size = compute_atlas_size(&placements);
+                (placements, size)
+            })
+            .collect()
+    } else {
+        vec![(placements, atlas_size)]
+    };
 
     // 5. UV remapping with vertex deduplication for shared vertices across islands
-    let new_mesh = remap_uvs_with_dedup(mesh, &islands, &placements, atlas_size);
+    let page_refs: Vec<(&[Placement], u32)> = pages.iter().map(|(p, s)| (&p[..], *s)).collect();
+    let mut new_mesh = remap_uvs_with_dedup(mesh, &islands, &page_refs);
 
-    // 6. Atlas compositing
-    let atlas_image = composite_atlas(&source_image, &islands, &placements, atlas_size);
-
-    // Downscale if the atlas exceeds the configured max_size
-    let atlas_image = if atlas_size > config.max_size {
-        image::imageops::resize(
-            &atlas_image,
-            config.max_size,
-            config.max_size,
-            image::imageops::FilterType::Lanczos3,
-        )
+    let mut face_materials = vec![None; new_mesh.triangle_count()];
+    for island in &islands {
+        for &face in &island.faces {
+            face_materials[face] = Some(source_materials[island.source]);
+        }
+    }
+    for (faces, material) in &untextured {
+        for &face in faces {
+            face_materials[face] = *material;
+        }
+    }
+    let first_indices = if pages.len() > 1 {
+        let mut face_pages = vec![0; new_mesh.triangle_count()];
+        for (page, (placements, _)) in pages.iter().enumerate() {
+            for p in placements {
+                for &face in &islands[p.island_idx].faces {
+                    face_pages[face] = page;
+                }
+            }
+        }
+        group_faces_by_page(&mut new_mesh, &face_materials, &face_pages, pages.len())
     } else {
-        atlas_image
+        new_mesh.set_triangle_materials(&face_materials);
+        vec![0]
+    };
+
+    // 6. Atlas compositing
+    let source_textures: Vec<&TextureData> = sources.iter().map(|(_, tex, _)| *tex).collect();
+
+    // Secondary maps reuse the placements so the remapped UVs address them
+    // all. They are written when an atlas material has the map; islands of
+    // sources without it get a neutral texel.
+    let secondary_sources = |map: fn(&PBRMaterial) -> Option<usize>, neutral: [u8; 4]| {
+        let mapped = |&m: &usize| map(&materials.materials[m]).is_some();
+        if !source_materials.iter().any(mapped) {
+            return None;
+        }
+        let textures: Vec<Option<(usize, &TextureData)>> = sources
+            .iter()
+            .map(|(mat, _, _)| {
+                let tex_idx = map(mat)?;
+                Some((tex_idx, materials.textures.get(tex_idx)?))
+            })
+            .collect();
+        let images: Vec<Arc<RgbaImage>> = textures
+            .par_iter()
+            .map(|tex| {
+                tex.and_then(|(tex_idx, tex)| decode_shared(decoded, tex_idx, tex, config))
+                    .unwrap_or_else(|| Arc::new(RgbaImage::from_pixel(1, 1, image::Rgba(neutral))))
+            })
+            .collect();
+        let textures = textures.into_iter().flatten().map(|(_, tex)| tex);
+        Some((images, textures.collect::<Vec<_>>()))
     };
+    let metallic_roughness_sources =
+        secondary_sources(|m| m.metallic_roughness_texture, [255, 255, 255, 255]);
+    let normal_sources = secondary_sources(|m| m.normal_texture, [128, 128, 255, 255]);
 
-    let atlas_texture = texture_compress::compress_texture(&atlas_image, config);
+    let mut atlases: Vec<AtlasPage> = pages
+        .iter()
+        .zip(first_indices)
+        .map(|((placements, atlas_size), first_index)| {
+            let layout = AtlasLayout {
+                islands: &islands,
+                placements,
+                atlas_size: *atlas_size,
+                max_size,
+            };
+            let secondary = |sources: &Option<(Vec<Arc<RgbaImage>>, Vec<&TextureData>)>, map| {
+                let (images, textures) = sources.as_ref()?;
+                let images: Vec<&RgbaImage> = images.iter().map(|image| &**image).collect();
+                Some(layout.composite(&images, textures, map, config))
+            };
+            AtlasPage {
+                first_index,
+                atlas_texture: layout.composite(
+                    &source_images,
+                    &source_textures,
+                    AtlasMap::BaseColor,
+                    config,
+                ),
+                metallic_roughness_texture: secondary(
+                    &metallic_roughness_sources,
+                    AtlasMap::MetallicRoughness,
+                ),
+                normal_texture: secondary(&normal_sources, AtlasMap::Normal),
+            }
+        })
+        .collect();
+    let first = atlases.remove(0);
 
+    let island_texels = pages
+        .iter()
+        .flat_map(|(placements, _)| placements)
+        .map(|p| u64::from(p.inner_w) * u64::from(p.inner_h))
+        .sum();
+    let atlas_texels = pages
+        .iter()
+        .map(|&(_, size)| u64::from(size) * u64::from(size))
+        .sum();
     Some(AtlasResult {
         mesh: new_mesh,
-        atlas_texture,
+        atlas_texture: first.atlas_texture,
+        metallic_roughness_texture: first.metallic_roughness_texture,
+        normal_texture: first.normal_texture,
+        pages: atlases,
+        island_texels,
+        atlas_texels,
     })
 }
 
+/// Spread islands over atlas pages of at most `limit` a side, in detection
+/// order so neighbouring islands tend to share a page. A page takes islands
+/// until they cover `PAGE_FILL` of it; an island larger than a page gets
+/// one of its own and is shrunk to fit.
+fn paginate(
+    sized: &[(usize, u32, u32, u32)],
+    allow_rotation: bool,
+    limit: u32,
+) -> Vec<Vec<Placement>> {
+    let budget = f64::from(limit) * f64::from(limit) * PAGE_FILL;
+    let mut pages = Vec::new();
+    let mut page: Vec<(usize, u32, u32, u32)> = Vec::new();
+    let mut area = 0.0;
+    for &island in sized {
+        let (_, w, h, pad) = island;
+        let footprint = f64::from(w + pad * 2) * f64::from(h + pad * 2);
+        if !page.is_empty() && area + footprint > budget {
+            pages.push(guillotine_pack_within(&page, allow_rotation, limit));
+            page.clear();
+            area = 0.0;
+        }
+        page.push(island);
+        area += footprint;
+    }
+    pages.push(guillotine_pack_within(&page, allow_rotation, limit));
+    pages
+}
+
+/// Reorder the faces of `mesh` so each page's faces are contiguous, and give
+/// it material groups split at page boundaries. Returns the first index of
+/// each page.
+fn group_faces_by_page(
+    mesh: &mut IndexedMesh,
+    face_materials: &[Option<usize>],
+    face_pages: &[usize],
+    page_count: usize,
+) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..face_pages.len()).collect();
+    order.sort_by_key(|&face| face_pages[face]);
+    mesh.indices = order
+        .iter()
+        .flat_map(|&face| &mesh.indices[face * 3..face * 3 + 3])
+        .copied()
+        .collect();
+
+    let mut first_indices = vec![mesh.indices.len(); page_count];
+    let mut groups: Vec<MaterialGroup> = Vec::new();
+    for (tri, &face) in order.iter().enumerate() {
+        let (material_index, page) = (face_materials[face], face_pages[face]);
+        let first = &mut first_indices[page];
+        let page_start = *first > tri * 3;
+        *first = (*first).min(tri * 3);
+        match groups.last_mut() {
+            Some(group) if group.material_index == material_index && !page_start => {
+                group.indices.end = (tri + 1) * 3;
+            }
+            _ => groups.push(MaterialGroup {
+                indices: tri * 3..(tri + 1) * 3,
+                material_index,
+            }),
+        }
+    }
+    mesh.material_index = groups.first().and_then(|g| g.material_index);
+    mesh.material_groups = groups;
+    first_indices
+}
+
+/// Which of a material's maps an atlas holds.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AtlasMap {
+    BaseColor,
+    MetallicRoughness,
+    Normal,
+}
+
+/// Whether faces of `b` may be drawn with material `a` once their textures
+/// are atlased: every parameter besides the texture indices matches, and
+/// both have the same secondary maps (or lack them).
+fn shares_atlas(a: &PBRMaterial, b: &PBRMaterial) -> bool {
+    a.base_color == b.base_color
+        && a.metallic == b.metallic
+        && a.roughness == b.roughness
+        && a.metallic_roughness_texture.is_some() == b.metallic_roughness_texture.is_some()
+        && a.normal_texture.is_some() == b.normal_texture.is_some()
+        && a.normal_scale == b.normal_scale
+        && a.alpha_mode == b.alpha_mode
+        && a.emissive == b.emissive
+}
+
+/// Island placements of one atlas, shared by all of a material's maps.
+struct AtlasLayout<'a> {
+    islands: &'a [UvIsland],
+    placements: &'a [Placement],
+    atlas_size: u32,
+    max_size: u32,
+}
+
+impl AtlasLayout<'_> {
+    /// Composite `sources` (one per island source) into an atlas with this layout, downscale it to
+    /// `max_size` if needed and compress it. `originals` are the textures the
+    /// sources were decoded from, whose encoding `TextureFormat::Original` keeps.
+    /// Only the base colour `map` is encoded as sRGB; the others hold linear data.
+    ///
+    /// A transposed island swaps its tangent and bitangent, so for a
+    /// tangent-space normal map its X and Y (red and green) are swapped too.
+    fn composite(
+        &self,
+        sources: &[&RgbaImage],
+        originals: &[&TextureData],
+        map: AtlasMap,
+        config: &TextureConfig,
+    ) -> TextureData {
+        let mut atlas_image =
+            composite_atlas(sources, self.islands, self.placements, self.atlas_size);
+        if map == AtlasMap::Normal {
+            for p in self.placements.iter().filter(|p| p.rotated) {
+                let (w, h) = (p.inner_w + 2 * p.padding, p.inner_h + 2 * p.padding);
+                for y in p.y..(p.y + h).min(self.atlas_size) {
+                    for x in p.x..(p.x + w).min(self.atlas_size) {
+                        atlas_image.get_pixel_mut(x, y).0.swap(0, 1);
+                    }
+                }
+            }
+        }
+
+        // Downscale if the atlas exceeds the (scaled) max_size
+        let atlas_image = if self.atlas_size > self.max_size {
+            image::imageops::resize(
+                &atlas_image,
+                self.max_size,
+                self.max_size,
+                image::imageops::FilterType::Lanczos3,
+            )
+        } else {
+            atlas_image
+        };
+
+        let linear = map != AtlasMap::BaseColor;
+        texture_compress::compress_texture_like(&atlas_image, config, originals, linear)
+    }
+}
+
+/// The tile's source texture, re-encoded for output, when its sampler
+/// repeats and the mesh's UVs leave `[0, 1]`.
+///
+/// An atlas bakes repeats into clamped islands, which mirrored repeats don't
+/// survive and which caps their resolution, so such tiles keep the source
+/// texture and its wrap modes instead. Returns `None` when the mesh should be
+/// atlased as usual.
+pub fn repeating_source_texture(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    config: &TextureConfig,
+    texel_scale: f32,
+) -> Option<TextureData> {
+    // Tiles mixing materials are always atlased
+    let material = mesh.material_index;
+    if mesh
+        .material_groups
+        .iter()
+        .any(|g| g.material_index != material)
+    {
+        return None;
+    }
+    let mat = materials.materials.get(material?)?;
+    let tex = materials.textures.get(mat.base_color_texture?)?;
+    let in_tile = -UV_TILE_EPSILON..=1.0 + UV_TILE_EPSILON;
+    if !tex.repeats() || mesh.uvs.iter().all(|c| in_tile.contains(c)) {
+        return None;
+    }
+    reencode_source(tex, config, texel_scale, false)
+}
+
+/// One of the tile material's other maps (picked by `map`), re-encoded for
+/// output, for tiles that keep their source texture (see
+/// [`repeating_source_texture`]). These maps (metallic-roughness, normals)
+/// hold linear data.
+pub fn source_map_texture(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    config: &TextureConfig,
+    texel_scale: f32,
+    map: impl Fn(&PBRMaterial) -> Option<usize>,
+) -> Option<TextureData> {
+    let mat = materials.materials.get(mesh.material_index?)?;
+    let tex = materials.textures.get(map(mat)?)?;
+    reencode_source(tex, config, texel_scale, true)
+}
+
+/// Decode `tex` within the scaled `max_size` and compress it, keeping its
+/// wrap modes, as linear data when `linear` is set. With
+/// `TextureFormat::Original`, a source that already fits is passed through
+/// untouched.
+fn reencode_source(
+    tex: &TextureData,
+    config: &TextureConfig,
+    texel_scale: f32,
+    linear: bool,
+) -> Option<TextureData> {
+    let max_size = scaled_max_size(config.max_size, texel_scale.clamp(f32::EPSILON, 1.0));
+    if let Some(texture) = texture_compress::pass_through(tex, config, max_size) {
+        return Some(texture);
+    }
+    let image = decode_texture(tex, max_size)?;
+
+    let mut texture = texture_compress::compress_texture_like(&image, config, &[tex], linear);
+    texture.wrap = tex.wrap;
+    Some(texture)
+}
+
+/// Scale `max_size` by `texel_scale`, rounded down to a power of two.
+fn scaled_max_size(max_size: u32, texel_scale: f32) -> u32 {
+    let scaled = ((max_size as f32 * texel_scale) as u32).max(1);
+    if scaled >= max_size {
+        return max_size;
+    }
+    1 << (31 - scaled.leading_zeros())
+}
+
+/// Texture `tex_idx` (`tex`) decoded as by [`decode_texture`], from
+/// `decoded` when an earlier atlas already did. Concurrent first uses may
+/// both decode; the first to finish is kept.
+fn decode_shared(
+    decoded: &DecodedSources,
+    tex_idx: usize,
+    tex: &TextureData,
+    config: &TextureConfig,
+) -> Option<Arc<RgbaImage>> {
+    let cache = decoded.lock().expect("decoded sources poisoned");
+    if let Some(image) = cache.get(&tex_idx) {
+        return Some(Arc::clone(image));
+    }
+    // Not held while decoding, so other sources decode meanwhile
+    drop(cache);
+    let image = Arc::new(decode_texture(tex, config.max_source_size)?);
+    let mut decoded = decoded.lock().expect("decoded sources poisoned");
+    Some(Arc::clone(decoded.entry(tex_idx).or_insert(image)))
+}
+
 /// Decode a TextureData into an RgbaImage.
 ///
 /// Tries encoded image formats first, falls back to raw RGBA/RGB interpretation.
-fn decode_texture(tex: &TextureData) -> Option<RgbaImage> {
+/// Images larger than `max_dim` are downscaled to fit. Oversized PNGs are
+/// downscaled row by row as they decode, so their full-resolution pixels are
+/// never held in memory; other formats are downscaled right after decoding.
+fn decode_texture(tex: &TextureData, max_dim: u32) -> Option<RgbaImage> {
+    if let Some(img) = decode_png_downscaled(&tex.data, max_dim) {
+        return Some(img);
+    }
+
     // Try decoding as an encoded image (PNG, JPEG, WebP, etc.)
     if let Ok(img) = image::load_from_memory(&tex.data) {
-        return Some(img.to_rgba8());
+        return Some(fit_within(img.to_rgba8(), max_dim));
     }
 
     // Fall back to raw pixel interpretation
@@ -153,7 +700,8 @@ fn decode_texture(tex: &TextureData) -> Option<RgbaImage> {
 
     if tex.data.len() == pixel_count * 4 {
         // Raw RGBA
-        return RgbaImage::from_raw(tex.width, tex.height, tex.data.clone());
+        return RgbaImage::from_raw(tex.width, tex.height, tex.data.clone())
+            .map(|img| fit_within(img, max_dim));
     }
 
     if tex.data.len() == pixel_count * 3 {
@@ -163,7 +711,8 @@ fn decode_texture(tex: &TextureData) -> Option<RgbaImage> {
             rgba.extend_from_slice(chunk);
             rgba.push(255);
         }
-        return RgbaImage::from_raw(tex.width, tex.height, rgba);
+        return RgbaImage::from_raw(tex.width, tex.height, rgba)
+            .map(|img| fit_within(img, max_dim));
     }
 
     warn!(
@@ -175,6 +724,93 @@ fn decode_texture(tex: &TextureData) -> Option<RgbaImage> {
     None
 }
 
+/// Dimensions of a `width` x `height` image scaled to fit within `max_dim`.
+fn fit_dimensions(width: u32, height: u32, max_dim: u32) -> (u32, u32) {
+    let fit = max_dim as f32 / width.max(height) as f32;
+    (
+        ((width as f32 * fit) as u32).max(1),
+        ((height as f32 * fit) as u32).max(1),
+    )
+}
+
+/// Downscale `image` to fit within `max_dim`, keeping its aspect ratio.
+fn fit_within(image: RgbaImage, max_dim: u32) -> RgbaImage {
+    let (w, h) = image.dimensions();
+    if w.max(h) <= max_dim {
+        return image;
+    }
+    let (fit_w, fit_h) = fit_dimensions(w, h, max_dim);
+    image::imageops::resize(&image, fit_w, fit_h, image::imageops::FilterType::Lanczos3)
+}
+
+/// Decode a PNG larger than `max_dim` straight into a box-filtered image that
+/// fits within it, one source row at a time.
+///
+/// Returns `None` for anything else (other formats, PNGs that already fit,
+/// interlaced PNGs, decode errors) so the caller can decode normally.
+fn decode_png_downscaled(data: &[u8], max_dim: u32) -> Option<RgbaImage> {
+    if image::guess_format(data).ok()? != image::ImageFormat::Png {
+        return None;
+    }
+
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(data));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let info = reader.info();
+    let (src_w, src_h) = (info.width, info.height);
+    if src_w.max(src_h) <= max_dim || info.interlaced {
+        return None;
+    }
+    let channels = match reader.output_color_type().0 {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => return None,
+    };
+
+    let (out_w, out_h) = fit_dimensions(src_w, src_h, max_dim);
+    let mut out = RgbaImage::new(out_w, out_h);
+    // Per-column channel sums and sample counts of the output row being filled
+    let mut sums = vec![0u64; out_w as usize * 4];
+    let mut counts = vec![0u64; out_w as usize];
+    let flush = |out: &mut RgbaImage, y: u32, sums: &mut [u64], counts: &mut [u64]| {
+        for (x, &count) in counts.iter().enumerate().filter(|(_, c)| **c > 0) {
+            let px = std::array::from_fn(|c| (sums[x * 4 + c] / count) as u8);
+            out.put_pixel(x as u32, y, image::Rgba(px));
+        }
+        sums.fill(0);
+        counts.fill(0);
+    };
+
+    let mut out_y = 0;
+    let mut src_y = 0u64;
+    while let Some(row) = reader.next_row().ok()? {
+        let y = (src_y * out_h as u64 / src_h as u64) as u32;
+        if y != out_y {
+            flush(&mut out, out_y, &mut sums, &mut counts);
+            out_y = y;
+        }
+        for (src_x, px) in row.data().chunks_exact(channels).enumerate() {
+            let x = src_x * out_w as usize / src_w as usize;
+            let rgba = match channels {
+                1 => [px[0], px[0], px[0], 255],
+                2 => [px[0], px[0], px[0], px[1]],
+                3 => [px[0], px[1], px[2], 255],
+                _ => [px[0], px[1], px[2], px[3]],
+            };
+            for (sum, value) in sums[x * 4..x * 4 + 4].iter_mut().zip(rgba) {
+                *sum += u64::from(value);
+            }
+            counts[x] += 1;
+        }
+        src_y += 1;
+    }
+    flush(&mut out, out_y, &mut sums, &mut counts);
+
+    Some(out)
+}
+
 /// Build edge adjacency map.
 ///
 /// Maps sorted edge vertex pairs to face indices.
@@ -249,8 +885,13 @@ fn uv_close(a: &[f32; 2], b: &[f32; 2], eps: f32) -> bool {
 
 /// BFS island detection.
 ///
-/// Returns connected components via BFS over face adjacency.
-fn detect_islands(mesh: &IndexedMesh, adjacency: &HashMap<(u32, u32), Vec<usize>>) -> Vec<UvIsland> {
+/// Returns connected components via BFS over face adjacency. Islands never
+/// span two entries of `face_sources`; faces without a source are skipped.
+fn detect_islands(
+    mesh: &IndexedMesh,
+    adjacency: &HashMap<(u32, u32), Vec<usize>>,
+    face_sources: &[Option<usize>],
+) -> Vec<UvIsland> {
     let num_faces = mesh.triangle_count();
     let mut visited = vec![false; num_faces];
     let mut islands = Vec::new();
@@ -271,8 +912,16 @@ fn detect_islands(mesh: &IndexedMesh, adjacency: &HashMap<(u32, u32), Vec<usize>
             }
         }
     }
+    // The edge map iterates in hash order; sort so the flood fill visits faces
+    // (and therefore duplicates seam vertices) in the same order on every run
+    for neighbors in &mut face_adj {
+        neighbors.sort_unstable();
+    }
 
     for start in 0..num_faces {
+        let Some(source) = face_sources[start] else {
+            continue;
+        };
         if visited[start] {
             continue;
         }
@@ -300,7 +949,7 @@ fn detect_islands(mesh: &IndexedMesh, adjacency: &HashMap<(u32, u32), Vec<usize>
             }
 
             for &neighbor in &face_adj[face] {
-                if !visited[neighbor] {
+                if !visited[neighbor] && face_sources[neighbor] == Some(source) {
                     visited[neighbor] = true;
                     queue.push_back(neighbor);
                 }
@@ -309,6 +958,7 @@ fn detect_islands(mesh: &IndexedMesh, adjacency: &HashMap<(u32, u32), Vec<usize>
 
         islands.push(UvIsland {
             faces: island_faces,
+            source,
             uv_min,
             uv_max,
         });
@@ -317,12 +967,204 @@ fn detect_islands(mesh: &IndexedMesh, adjacency: &HashMap<(u32, u32), Vec<usize>
     islands
 }
 
+/// UV rectangle `(uv_min, uv_max)` covering the non-transparent source
+/// texels under an island's UV rect, or `None` when every texel is fully
+/// transparent. Texel edges are clamped to the island's own UV range.
+fn opaque_uv_rect(source: &RgbaImage, island: &UvIsland) -> Option<([f32; 2], [f32; 2])> {
+    let (src_w, src_h) = source.dimensions();
+    let texel_range = |min: f32, max: f32, size: u32| {
+        let first = ((min * size as f32).floor().max(0.0) as u32).min(size - 1);
+        let last = ((max * size as f32).ceil() as u32).clamp(first + 1, size);
+        first..last
+    };
+    let xs = texel_range(island.uv_min[0], island.uv_max[0], src_w);
+    let ys = texel_range(island.uv_min[1], island.uv_max[1], src_h);
+
+    let mut texels: Option<([u32; 2], [u32; 2])> = None;
+    for y in ys {
+        for x in xs.clone() {
+            if source.get_pixel(x, y)[3] == 0 {
+                continue;
+            }
+            let (lo, hi) = texels.get_or_insert(([x, y], [x, y]));
+            *lo = [lo[0].min(x), lo[1].min(y)];
+            *hi = [hi[0].max(x), hi[1].max(y)];
+        }
+    }
+
+    let (lo, hi) = texels?;
+    let to_uv = |texel: u32, size: u32| texel as f32 / size as f32;
+    Some((
+        [
+            to_uv(lo[0], src_w).max(island.uv_min[0]),
+            to_uv(lo[1], src_h).max(island.uv_min[1]),
+        ],
+        [
+            to_uv(hi[0] + 1, src_w).min(island.uv_max[0]),
+            to_uv(hi[1] + 1, src_h).min(island.uv_max[1]),
+        ],
+    ))
+}
+
+/// What `trim_islands` does with one island.
+enum IslandTrim {
+    /// Nothing to trim (or a tiled or opaque island); keep its faces
+    /// unchanged.
+    Keep,
+    /// Every texel is fully transparent; drop the island.
+    Drop,
+    /// Clip the island to this smaller `(uv_min, uv_max)` rectangle.
+    To([f32; 2], [f32; 2]),
+}
+
+/// Shrink islands to the rectangle of their non-transparent texels.
+///
+/// Faces are clipped in UV space to the trimmed rectangle, so only geometry
+/// showing fully transparent texels is removed; islands with no visible
+/// texels are dropped. Tiled islands and those of sources not flagged in
+/// `trimmable` are left alone. The `untextured` face
+/// runs are carried over unclipped. Returns the rebuilt mesh (with `islands`
+/// and `untextured` updated to index into it), or `None` when no island
+/// could be trimmed.
+fn trim_islands(
+    mesh: &IndexedMesh,
+    islands: &mut Vec<UvIsland>,
+    sources: &[&RgbaImage],
+    trimmable: &[bool],
+    untextured: &mut [(Vec<usize>, Option<usize>)],
+) -> Option<IndexedMesh> {
+    let trims: Vec<IslandTrim> = islands
+        .iter()
+        .map(|island| {
+            if island.is_tiled() || !trimmable[island.source] {
+                return IslandTrim::Keep;
+            }
+            let Some((uv_min, uv_max)) = opaque_uv_rect(sources[island.source], island) else {
+                return IslandTrim::Drop;
+            };
+            let shrinks = uv_min[0] > island.uv_min[0]
+                || uv_min[1] > island.uv_min[1]
+                || uv_max[0] < island.uv_max[0]
+                || uv_max[1] < island.uv_max[1];
+            if shrinks {
+                IslandTrim::To(uv_min, uv_max)
+            } else {
+                IslandTrim::Keep
+            }
+        })
+        .collect();
+
+    if trims.iter().all(|t| matches!(t, IslandTrim::Keep)) {
+        return None;
+    }
+
+    let kept: Vec<(&UvIsland, Option<([f32; 2], [f32; 2])>)> = islands
+        .iter()
+        .zip(&trims)
+        .filter_map(|(island, trim)| match *trim {
+            IslandTrim::Keep => Some((island, None)),
+            IslandTrim::Drop => None,
+            IslandTrim::To(uv_min, uv_max) => Some((island, Some((uv_min, uv_max)))),
+        })
+        .collect();
+    let groups: Vec<(&[usize], Option<([f32; 2], [f32; 2])>)> = kept
+        .iter()
+        .map(|(island, rect)| (island.faces.as_slice(), *rect))
+        .chain(untextured.iter().map(|(faces, _)| (faces.as_slice(), None)))
+        .collect();
+    let (trimmed, mut ranges) = clip_faces_to_uv_rects(mesh, &groups);
+
+    for ((faces, _), range) in untextured.iter_mut().zip(ranges.drain(kept.len()..)) {
+        *faces = range.collect();
+    }
+    let new_islands = kept
+        .iter()
+        .zip(ranges)
+        .filter(|(_, faces)| !faces.is_empty())
+        .map(|((island, rect), faces)| {
+            let (uv_min, uv_max) = rect.unwrap_or((island.uv_min, island.uv_max));
+            UvIsland {
+                faces: faces.collect(),
+                source: island.source,
+                uv_min,
+                uv_max,
+            }
+        })
+        .collect();
+    *islands = new_islands;
+
+    Some(trimmed)
+}
+
 /// Guillotine bin packing with Best Short Side Fit.
 ///
 /// Sorts islands by max dimension descending, places each using BSSF.
-/// Grows atlas (doubles smaller dimension) if needed.
-fn guillotine_pack(sized: &[(usize, u32, u32, u32)]) -> Vec<Placement> {
-    // Sort by max dimension descending
+/// With `allow_rotation`, each island may also be placed rotated by 90°
+/// when that orientation fits better.
+/// Starts from the smallest power-of-two atlas that could hold the total
+/// island area and grows it (doubling the smaller dimension) if needed.
+fn guillotine_pack(sized: &[(usize, u32, u32, u32)], allow_rotation: bool) -> Vec<Placement> {
+    guillotine_pack_within(sized, allow_rotation, MAX_ATLAS_SIZE)
+}
+
+/// Like [`guillotine_pack`], but never grow the atlas past `limit` on a
+/// side. Islands that don't fit are all shrunk by the same factor (bleed
+/// padding kept) until they do; as a last resort the padding is dropped too.
+/// Placements then carry the shrunk sizes, so no island is ever left out.
+fn guillotine_pack_within(
+    sized: &[(usize, u32, u32, u32)],
+    allow_rotation: bool,
+    limit: u32,
+) -> Vec<Placement> {
+    let area = |sized: &[(usize, u32, u32, u32)]| -> u64 {
+        sized
+            .iter()
+            .map(|&(_, w, h, pad)| u64::from(w + pad * 2) * u64::from(h + pad * 2))
+            .sum()
+    };
+    // Start from the scale at which the islands' area alone would fit
+    let limit_area = u64::from(limit) * u64::from(limit);
+    let mut scale = (limit_area as f64 / area(sized) as f64).sqrt().min(1.0);
+    let mut keep_padding = true;
+    loop {
+        let scaled: Vec<(usize, u32, u32, u32)> = sized
+            .iter()
+            .map(|&(i, w, h, pad)| {
+                let shrink = |d: u32| ((d as f64 * scale).floor() as u32).clamp(1, d);
+                (i, shrink(w), shrink(h), if keep_padding { pad } else { 0 })
+            })
+            .collect();
+        if let Some(placements) = pack_grown(&scaled, allow_rotation, limit) {
+            if scale < 1.0 || !keep_padding {
+                warn!(
+                    limit,
+                    scale,
+                    keep_padding,
+                    "Atlas islands exceed the maximum atlas size; downscaling them to fit"
+                );
+            }
+            return placements;
+        }
+        if scaled.iter().all(|&(_, w, h, _)| w == 1 && h == 1) {
+            if !keep_padding {
+                warn!(limit, "Atlas islands cannot fit even at one texel each");
+                return Vec::new();
+            }
+            keep_padding = false;
+        }
+        scale *= 0.9;
+    }
+}
+
+/// Pack `sized` into the smallest power-of-two atlas it fits, growing up to
+/// `limit` on a side; `None` if it doesn't fit even then.
+fn pack_grown(
+    sized: &[(usize, u32, u32, u32)],
+    allow_rotation: bool,
+    limit: u32,
+) -> Option<Vec<Placement>> {
+    // Sort by max dimension descending; the stable sort keeps equal-sized
+    // islands in detection order so placements are reproducible
     let mut order: Vec<usize> = (0..sized.len()).collect();
     order.sort_by(|&a, &b| {
         let max_a = (sized[a].1 + sized[a].3 * 2).max(sized[a].2 + sized[a].3 * 2);
@@ -330,14 +1172,26 @@ fn guillotine_pack(sized: &[(usize, u32, u32, u32)]) -> Vec<Placement> {
         max_b.cmp(&max_a)
     });
 
-    // Start with an initial atlas size
+    // Start with the largest island's footprint, grown until the atlas is at
+    // least as large as the total island area (nothing smaller can fit).
     let first = order[0];
-    let mut atlas_w = (sized[first].1 + sized[first].3 * 2).next_power_of_two().max(64);
-    let mut atlas_h = (sized[first].2 + sized[first].3 * 2).next_power_of_two().max(64);
+    let mut atlas_w = (sized[first].1 + sized[first].3 * 2).next_power_of_two();
+    let mut atlas_h = (sized[first].2 + sized[first].3 * 2).next_power_of_two();
+    let total_area: u64 = sized
+        .iter()
+        .map(|&(_, w, h, pad)| (w + pad * 2) as u64 * (h + pad * 2) as u64)
+        .sum();
+    while (atlas_w as u64) * (atlas_h as u64) < total_area {
+        if atlas_w <= atlas_h {
+            atlas_w *= 2;
+        } else {
+            atlas_h *= 2;
+        }
+    }
 
-    loop {
-        if let Some(placements) = try_pack(&order, sized, atlas_w, atlas_h) {
-            return placements;
+    while atlas_w <= limit && atlas_h <= limit {
+        if let Some(placements) = try_pack(&order, sized, atlas_w, atlas_h, allow_rotation) {
+            return Some(placements);
         }
         // Grow: double the smaller dimension
         if atlas_w <= atlas_h {
@@ -345,17 +1199,8 @@ fn guillotine_pack(sized: &[(usize, u32, u32, u32)]) -> Vec<Placement> {
         } else {
             atlas_h *= 2;
         }
-
-        // Safety limit
-        if atlas_w > 16384 || atlas_h > 16384 {
-            warn!(
-                atlas_w,
-                atlas_h, "Atlas size exceeded 16384, forcing placement"
-            );
-            // Force-pack with large atlas
-            return try_pack(&order, sized, atlas_w, atlas_h).unwrap_or_default();
-        }
     }
+    None
 }
 
 fn try_pack(
@@ -363,6 +1208,7 @@ fn try_pack(
     sized: &[(usize, u32, u32, u32)],
     atlas_w: u32,
     atlas_h: u32,
+    allow_rotation: bool,
 ) -> Option<Vec<Placement>> {
     let mut free_rects = vec![FreeRect {
         x: 0,
@@ -379,11 +1225,17 @@ fn try_pack(
         let total_h = inner_h + padding * 2;
 
         // Find best short side fit
-        let best = find_bssf(&free_rects, total_w, total_h);
+        let best = find_bssf(&free_rects, total_w, total_h, allow_rotation);
         let best = best?;
 
         let rect = free_rects.remove(best.rect_idx);
 
+        let (inner_w, inner_h, total_w, total_h) = if best.rotated {
+            (inner_h, inner_w, total_h, total_w)
+        } else {
+            (inner_w, inner_h, total_w, total_h)
+        };
+
         placements.push(Placement {
             island_idx,
             x: rect.x,
@@ -391,6 +1243,7 @@ fn try_pack(
             inner_w,
             inner_h,
             padding,
+            rotated: best.rotated,
         });
 
         // Guillotine split
@@ -402,23 +1255,35 @@ fn try_pack(
 
 struct BssfResult {
     rect_idx: usize,
+    /// Best fit is the `h x w` (rotated) orientation.
+    rotated: bool,
 }
 
-fn find_bssf(free_rects: &[FreeRect], w: u32, h: u32) -> Option<BssfResult> {
-    let mut best_idx = None;
+fn find_bssf(free_rects: &[FreeRect], w: u32, h: u32, allow_rotation: bool) -> Option<BssfResult> {
+    let mut best = None;
     let mut best_short_side = u32::MAX;
 
+    let mut orientations = vec![(w, h, false)];
+    if allow_rotation && w != h {
+        orientations.push((h, w, true));
+    }
+
     for (i, rect) in free_rects.iter().enumerate() {
-        if rect.w >= w && rect.h >= h {
-            let short_side = (rect.w - w).min(rect.h - h);
-            if short_side < best_short_side {
-                best_short_side = short_side;
-                best_idx = Some(i);
+        for &(ow, oh, rotated) in &orientations {
+            if rect.w >= ow && rect.h >= oh {
+                let short_side = (rect.w - ow).min(rect.h - oh);
+                if short_side < best_short_side {
+                    best_short_side = short_side;
+                    best = Some(BssfResult {
+                        rect_idx: i,
+                        rotated,
+                    });
+                }
             }
         }
     }
 
-    best_idx.map(|rect_idx| BssfResult { rect_idx })
+    best
 }
 
 fn guillotine_split(free_rects: &mut Vec<FreeRect>, rect: &FreeRect, w: u32, h: u32) {
@@ -427,21 +1292,62 @@ fn guillotine_split(free_rects: &mut Vec<FreeRect>, rect: &FreeRect, w: u32, h:
     let below_h = rect.h - h;
 
     if right_w > 0 {
-        free_rects.push(FreeRect {
-            x: rect.x + w,
-            y: rect.y,
-            w: right_w,
-            h: h,
-        });
+        push_merged(
+            free_rects,
+            FreeRect {
+                x: rect.x + w,
+                y: rect.y,
+                w: right_w,
+                h,
+            },
+        );
     }
 
     if below_h > 0 {
-        free_rects.push(FreeRect {
-            x: rect.x,
-            y: rect.y + h,
-            w: rect.w,
-            h: below_h,
-        });
+        push_merged(
+            free_rects,
+            FreeRect {
+                x: rect.x,
+                y: rect.y + h,
+                w: rect.w,
+                h: below_h,
+            },
+        );
+    }
+}
+
+/// Add a free rect, first absorbing any existing free rect that shares a
+/// full edge with it.
+///
+/// Without this, leftovers from neighbouring splits (e.g. the tails of
+/// consecutive shelf rows) stay separate slivers that no island fits in,
+/// and the packer grows the atlas although the space is available.
+fn push_merged(free_rects: &mut Vec<FreeRect>, mut rect: FreeRect) {
+    while let Some(i) = free_rects.iter().position(|r| merge_rects(r, &rect).is_some()) {
+        let other = free_rects.remove(i);
+        rect = merge_rects(&other, &rect).expect("rects share an edge");
+    }
+    free_rects.push(rect);
+}
+
+/// Union of two free rects if they share a full edge, `None` otherwise.
+fn merge_rects(a: &FreeRect, b: &FreeRect) -> Option<FreeRect> {
+    if a.y == b.y && a.h == b.h && (a.x + a.w == b.x || b.x + b.w == a.x) {
+        Some(FreeRect {
+            x: a.x.min(b.x),
+            y: a.y,
+            w: a.w + b.w,
+            h: a.h,
+        })
+    } else if a.x == b.x && a.w == b.w && (a.y + a.h == b.y || b.y + b.h == a.y) {
+        Some(FreeRect {
+            x: a.x,
+            y: a.y.min(b.y),
+            w: a.w,
+            h: a.h + b.h,
+        })
+    } else {
+        None
     }
 }
 
@@ -473,30 +1379,31 @@ fn compute_atlas_size(placements: &[Placement]) -> u32 {
 fn remap_uvs_with_dedup(
     mesh: &IndexedMesh,
     islands: &[UvIsland],
-    placements: &[Placement],
-    atlas_size: u32,
+    pages: &[(&[Placement], u32)],
 ) -> IndexedMesh {
-    let atlas_f = atlas_size as f32;
-
-    // Build island_idx -> placement lookup
-    let mut placement_map: HashMap<usize, &Placement> = HashMap::new();
-    for p in placements {
-        placement_map.insert(p.island_idx, p);
-    }
+    // Build island_idx -> (placement, atlas size) lookup
+    let mut placement_map: HashMap<usize, (&Placement, f32)> = HashMap::new();
+    for &(placements, atlas_size) in pages {
+        for p in placements {
+            placement_map.insert(p.island_idx, (p, atlas_size as f32));
+        }
+    }
 
     // Clone mesh data for mutation
     let mut new_positions = mesh.positions.clone();
     let mut new_normals = mesh.normals.clone();
     let mut new_uvs = mesh.uvs.clone();
+    let mut new_uvs1 = mesh.uvs1.clone();
     let mut new_colors = mesh.colors.clone();
+    let mut new_feature_ids = mesh.feature_ids.clone();
     let mut new_indices = mesh.indices.clone();
 
     // Track which island owns each vertex: None = unassigned
     let mut vertex_island: Vec<Option<usize>> = vec![None; mesh.vertex_count()];
 
     for (island_idx, island) in islands.iter().enumerate() {
-        let placement = match placement_map.get(&island_idx) {
-            Some(p) => p,
+        let (placement, atlas_f) = match placement_map.get(&island_idx) {
+            Some(&(p, atlas_f)) => (p, atlas_f),
             None => continue,
         };
 
@@ -538,6 +1445,13 @@ fn remap_uvs_with_dedup(
                         mesh.uvs[original_vi * 2],
                         mesh.uvs[original_vi * 2 + 1],
                     ]);
+                    // The second UV set is carried over unchanged
+                    if mesh.has_uvs1() {
+                        new_uvs1.extend_from_slice(&[
+                            mesh.uvs1[original_vi * 2],
+                            mesh.uvs1[original_vi * 2 + 1],
+                        ]);
+                    }
                     if mesh.has_colors() {
                         new_colors.extend_from_slice(&[
                             mesh.colors[original_vi * 4],
@@ -546,6 +1460,9 @@ fn remap_uvs_with_dedup(
                             mesh.colors[original_vi * 4 + 3],
                         ]);
                     }
+                    if mesh.has_feature_ids() {
+                        new_feature_ids.push(mesh.feature_ids[original_vi]);
+                    }
                     // Update this face's index to point to the new vertex
                     new_indices[fi] = new_vi as u32;
                     new_vi
@@ -558,12 +1475,17 @@ fn remap_uvs_with_dedup(
                 let norm_u = (old_u - island.uv_min[0]) / uv_range_u;
                 let norm_v = (old_v - island.uv_min[1]) / uv_range_v;
 
+                // Rotated islands are stored transposed in the atlas
+                let (norm_u, norm_v) = if placement.rotated {
+                    (norm_v, norm_u)
+                } else {
+                    (norm_u, norm_v)
+                };
+
                 // Map to atlas pixel coords with half-texel inset, then back to [0,1]
-                let new_u = (norm_u * (placement.inner_w as f32 - 1.0) + 0.5
-                    + (placement.x + placement.padding) as f32)
+                let new_u = inset_texel(norm_u, placement.inner_w, placement.x + placement.padding)
                     / atlas_f;
-                let new_v = (norm_v * (placement.inner_h as f32 - 1.0) + 0.5
-                    + (placement.y + placement.padding) as f32)
+                let new_v = inset_texel(norm_v, placement.inner_h, placement.y + placement.padding)
                     / atlas_f;
 
                 new_uvs[vi * 2] = new_u;
@@ -576,21 +1498,35 @@ fn remap_uvs_with_dedup(
         positions: new_positions,
         normals: new_normals,
         uvs: new_uvs,
+        uvs1: new_uvs1,
         colors: new_colors,
+        feature_ids: new_feature_ids,
         indices: new_indices,
         material_index: mesh.material_index,
+        name: mesh.name.clone(),
+        material_groups: mesh.material_groups.clone(),
     }
 }
 
-/// Composite the atlas image from source texture + island placements.
+/// Map a normalized island coordinate to an atlas pixel coordinate, inset by
+/// half a texel so bilinear filtering never reaches into the padding.
+///
+/// `[0, 1]` maps to `[origin + 0.5, origin + inner - 0.5]`. An island only
+/// one texel wide (or high) maps every coordinate to that texel's centre.
+fn inset_texel(norm: f32, inner: u32, origin: u32) -> f32 {
+    let span = if inner > 1 { (inner - 1) as f32 } else { 0.0 };
+    norm.clamp(0.0, 1.0) * span + 0.5 + origin as f32
+}
+
+/// Composite the atlas image from the islands' source textures + island
+/// placements.
 fn composite_atlas(
-    source: &RgbaImage,
+    sources: &[&RgbaImage],
     islands: &[UvIsland],
     placements: &[Placement],
     atlas_size: u32,
 ) -> RgbaImage {
     let mut atlas = RgbaImage::new(atlas_size, atlas_size);
-    let (src_w, src_h) = source.dimensions();
 
     // Build island_idx -> placement lookup
     let mut placement_map: HashMap<usize, &Placement> = HashMap::new();
@@ -603,6 +1539,8 @@ fn composite_atlas(
             Some(p) => p,
             None => continue,
         };
+        let source = sources[island.source];
+        let (src_w, src_h) = source.dimensions();
 
         let uv_range_u = island.uv_max[0] - island.uv_min[0];
         let uv_range_v = island.uv_max[1] - island.uv_min[1];
@@ -615,6 +1553,28 @@ fn composite_atlas(
         let dest_x0 = placement.x + pad;
         let dest_y0 = placement.y + pad;
 
+        if placement.rotated {
+            // Transposed island: atlas X follows island V, atlas Y follows island U
+            for py in 0..inner_h {
+                let u = island.uv_min[0] + (py as f32 / inner_h.max(1) as f32) * uv_range_u;
+                let su = ((u.fract() + 1.0).fract() * src_w as f32) as u32 % src_w;
+                let ay = dest_y0 + py;
+                if ay >= atlas_size {
+                    continue;
+                }
+                for px in 0..inner_w {
+                    let v = island.uv_min[1] + (px as f32 / inner_w.max(1) as f32) * uv_range_v;
+                    let sv = ((v.fract() + 1.0).fract() * src_h as f32) as u32 % src_h;
+                    let ax = dest_x0 + px;
+                    if ax < atlas_size {
+                        atlas.put_pixel(ax, ay, *source.get_pixel(su, sv));
+                    }
+                }
+            }
+            fill_bleed(&mut atlas, placement, atlas_size);
+            continue;
+        }
+
         for py in 0..inner_h {
             let v = island.uv_min[1] + (py as f32 / inner_h.max(1) as f32) * uv_range_v;
             let sv = ((v.fract() + 1.0).fract() * src_h as f32) as u32 % src_h;
@@ -629,11 +1589,12 @@ fn composite_atlas(
             let su_start = ((u_start.fract() + 1.0).fract() * src_w as f32) as u32 % src_w;
             let su_end_raw = ((u_end.fract() + 1.0).fract() * src_w as f32) as u32 % src_w;
 
-            // Fast path: contiguous source scanline (no UV wrapping within row)
+            // Fast path: contiguous source scanline at 1:1 scale (no UV
+            // wrapping or resampling within the row)
             let scanline_end_x = (dest_x0 + inner_w).min(atlas_size);
             if su_start < su_end_raw
                 && su_end_raw <= src_w
-                && (su_end_raw - su_start) as usize >= inner_w as usize
+                && su_end_raw - su_start == inner_w
                 && dest_x0 < scanline_end_x
             {
                 let src_row =
@@ -773,25 +1734,17 @@ fn fill_bleed(atlas: &mut RgbaImage, placement: &Placement, atlas_size: u32) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::PBRMaterial;
+    use crate::tiling::test_textures::png_texture;
 
     /// Create a simple 4x4 checkerboard PNG texture.
     fn checkerboard_texture(size: u32) -> TextureData {
-        let img = RgbaImage::from_fn(size, size, |x, y| {
+        png_texture(&RgbaImage::from_fn(size, size, |x, y| {
             if (x + y) % 2 == 0 {
                 image::Rgba([255, 0, 0, 255])
             } else {
                 image::Rgba([0, 0, 255, 255])
             }
-        });
-        let mut buf = std::io::Cursor::new(Vec::new());
-        img.write_to(&mut buf, image::ImageFormat::Png).unwrap();
-        TextureData {
-            data: buf.into_inner(),
-            mime_type: "image/png".into(),
-            width: size,
-            height: size,
-        }
+        }))
     }
 
     fn make_textured_quad() -> (IndexedMesh, MaterialLibrary) {
@@ -801,9 +1754,13 @@ mod tests {
             ],
             normals: vec![],
             uvs: vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2, 0, 2, 3],
             material_index: Some(0),
+            name: None,
+            material_groups: Vec::new(),
         };
 
         let mut materials = MaterialLibrary::default();
@@ -833,9 +1790,13 @@ mod tests {
                 // Quad 2 UVs: [0.5,1] x [0.5,1]
                 0.5, 0.5, 1.0, 0.5, 1.0, 1.0, 0.5, 1.0,
             ],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7],
             material_index: Some(0),
+            name: None,
+            material_groups: Vec::new(),
         };
 
         let mut materials = MaterialLibrary::default();
@@ -864,7 +1825,7 @@ mod tests {
     fn island_detection_single() {
         let (mesh, _) = make_textured_quad();
         let adj = build_edge_adjacency(&mesh);
-        let islands = detect_islands(&mesh, &adj);
+        let islands = detect_islands(&mesh, &adj, &vec![Some(0); mesh.triangle_count()]);
 
         assert_eq!(islands.len(), 1, "quad should produce 1 UV island");
         assert_eq!(islands[0].faces.len(), 2, "island should have 2 faces");
@@ -874,16 +1835,38 @@ mod tests {
     fn island_detection_multiple() {
         let (mesh, _) = make_two_island_mesh();
         let adj = build_edge_adjacency(&mesh);
-        let islands = detect_islands(&mesh, &adj);
+        let islands = detect_islands(&mesh, &adj, &vec![Some(0); mesh.triangle_count()]);
 
         assert_eq!(islands.len(), 2, "two separated quads should produce 2 UV islands");
     }
 
+    #[test]
+    fn island_face_order_is_stable() {
+        // A three-face strip listed middle face first: the flood fill branches
+        // to both neighbours, which must come out in the same order every run
+        let mesh = IndexedMesh {
+            positions: vec![
+                0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 2.0, 0.0, 0.0,
+            ],
+            uvs: vec![0.0, 0.0, 0.0, 0.5, 0.5, 0.0, 0.5, 0.5, 1.0, 0.0],
+            indices: vec![1, 3, 2, 0, 1, 2, 2, 3, 4],
+            material_index: Some(0),
+            ..Default::default()
+        };
+
+        for _ in 0..16 {
+            let adj = build_edge_adjacency(&mesh);
+            let islands = detect_islands(&mesh, &adj, &vec![Some(0); mesh.triangle_count()]);
+            assert_eq!(islands.len(), 1);
+            assert_eq!(islands[0].faces, vec![0, 1, 2]);
+        }
+    }
+
     #[test]
     fn island_uv_bounds() {
         let (mesh, _) = make_textured_quad();
         let adj = build_edge_adjacency(&mesh);
-        let islands = detect_islands(&mesh, &adj);
+        let islands = detect_islands(&mesh, &adj, &vec![Some(0); mesh.triangle_count()]);
 
         let island = &islands[0];
         assert!(island.uv_min[0] >= 0.0);
@@ -895,7 +1878,7 @@ mod tests {
     #[test]
     fn packer_single_island() {
         let sized = vec![(0, 16, 16, 2)];
-        let placements = guillotine_pack(&sized);
+        let placements = guillotine_pack(&sized, false);
         assert_eq!(placements.len(), 1);
         assert_eq!(placements[0].island_idx, 0);
     }
@@ -903,7 +1886,7 @@ mod tests {
     #[test]
     fn packer_multiple_islands() {
         let sized = vec![(0, 32, 32, 2), (1, 16, 16, 2), (2, 8, 8, 2)];
-        let placements = guillotine_pack(&sized);
+        let placements = guillotine_pack(&sized, false);
         assert_eq!(placements.len(), 3);
 
         // All islands should be placed
@@ -916,13 +1899,558 @@ mod tests {
     fn packer_grows_atlas() {
         // Large islands that won't fit in a small atlas
         let sized = vec![(0, 128, 128, 2), (1, 128, 128, 2), (2, 128, 128, 2)];
-        let placements = guillotine_pack(&sized);
+        let placements = guillotine_pack(&sized, false);
         assert_eq!(placements.len(), 3);
 
         let atlas_size = compute_atlas_size(&placements);
         assert!(atlas_size >= 256, "atlas should have grown to fit all islands");
     }
 
+    #[test]
+    fn packer_many_small_islands_stay_compact() {
+        // 64 uniform 4x4 islands tile an 8x8 grid exactly. The old packer
+        // started from a fixed 64px atlas and spread them into a 64x16 strip.
+        let sized: Vec<_> = (0..64).map(|i| (i, 4, 4, 0)).collect();
+        let placements = guillotine_pack(&sized, false);
+        assert_eq!(placements.len(), 64);
+        assert_eq!(compute_atlas_size(&placements), 32);
+    }
+
+    #[test]
+    fn packer_merges_adjacent_free_rects() {
+        // The third island only fits in the column formed by merging the
+        // leftovers to the right of the first two islands.
+        let sized = vec![(0, 32, 12, 0), (1, 32, 24, 0), (2, 24, 32, 0)];
+        let placements = guillotine_pack(&sized, false);
+        assert_eq!(placements.len(), 3);
+        assert_eq!(compute_atlas_size(&placements), 64);
+    }
+
+    #[test]
+    fn packer_downscales_islands_past_max_atlas_size() {
+        // 80 islands of 4096² can't fit in 16384² (room for 16); the old
+        // packer force-packed and dropped them all.
+        let sized: Vec<_> = (0..80).map(|i| (i, 4096, 4096, 5)).collect();
+        let placements = guillotine_pack(&sized, false);
+
+        let mut placed_ids: Vec<usize> = placements.iter().map(|p| p.island_idx).collect();
+        placed_ids.sort();
+        assert_eq!(placed_ids, (0..80).collect::<Vec<_>>());
+        assert!(compute_atlas_size(&placements) <= MAX_ATLAS_SIZE);
+        for p in &placements {
+            assert!(p.inner_w < 4096 && p.inner_w == p.inner_h);
+        }
+    }
+
+    #[test]
+    fn oversized_islands_keep_valid_atlas_uvs() {
+        let (mesh, _) = make_two_island_mesh();
+        let adjacency = build_edge_adjacency(&mesh);
+        let islands = detect_islands(&mesh, &adjacency, &vec![Some(0); mesh.triangle_count()]);
+        assert_eq!(islands.len(), 2);
+
+        // Two 64px islands into an atlas capped at 64px
+        let sized = vec![(0, 64, 64, 2), (1, 64, 64, 2)];
+        let placements = guillotine_pack_within(&sized, false, 64);
+        assert_eq!(placements.len(), 2);
+        let atlas_size = compute_atlas_size(&placements);
+        assert!(atlas_size <= 64);
+
+        let remapped = remap_uvs_with_dedup(&mesh, &islands, &[(&placements, atlas_size)]);
+        for face in 0..remapped.triangle_count() {
+            let island = islands.iter().position(|i| i.faces.contains(&face));
+            let island = island.unwrap();
+            let p = placements.iter().find(|p| p.island_idx == island).unwrap();
+            let x0 = (p.x + p.padding) as f32 / atlas_size as f32;
+            let y0 = (p.y + p.padding) as f32 / atlas_size as f32;
+            let x1 = x0 + p.inner_w as f32 / atlas_size as f32;
+            let y1 = y0 + p.inner_h as f32 / atlas_size as f32;
+            for &v in &remapped.indices[face * 3..face * 3 + 3] {
+                let uv = &remapped.uvs[v as usize * 2..v as usize * 2 + 2];
+                assert!(uv[0] >= x0 && uv[0] <= x1, "u {} outside island", uv[0]);
+                assert!(uv[1] >= y0 && uv[1] <= y1, "v {} outside island", uv[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_rects_requires_full_shared_edge() {
+        let a = FreeRect {
+            x: 0,
+            y: 0,
+            w: 8,
+            h: 4,
+        };
+        let right = FreeRect {
+            x: 8,
+            y: 0,
+            w: 2,
+            h: 4,
+        };
+        let below = FreeRect {
+            x: 0,
+            y: 4,
+            w: 8,
+            h: 3,
+        };
+        let offset = FreeRect {
+            x: 8,
+            y: 1,
+            w: 2,
+            h: 4,
+        };
+
+        let m = merge_rects(&a, &right).unwrap();
+        assert_eq!((m.x, m.y, m.w, m.h), (0, 0, 10, 4));
+        let m = merge_rects(&below, &a).unwrap();
+        assert_eq!((m.x, m.y, m.w, m.h), (0, 0, 8, 7));
+        assert!(merge_rects(&a, &offset).is_none());
+    }
+
+    #[test]
+    fn packer_rotation_shrinks_atlas() {
+        // One wide and one tall strip: only fit in a 256px atlas if the
+        // second is rotated to lie under the first.
+        let sized = vec![(0, 256, 16, 0), (1, 16, 256, 0)];
+
+        let plain = guillotine_pack(&sized, false);
+        assert!(plain.iter().all(|p| !p.rotated));
+        let plain_size = compute_atlas_size(&plain);
+
+        let rotated = guillotine_pack(&sized, true);
+        assert_eq!(rotated.len(), 2);
+        assert!(rotated.iter().any(|p| p.rotated));
+        let rotated_size = compute_atlas_size(&rotated);
+
+        assert_eq!(rotated_size, 256);
+        assert!(
+            rotated_size < plain_size,
+            "rotation should shrink the atlas ({rotated_size} vs {plain_size})"
+        );
+
+        // Rotated placement swaps its footprint
+        let p = rotated.iter().find(|p| p.island_idx == 1).unwrap();
+        assert!(p.rotated);
+        assert_eq!((p.inner_w, p.inner_h), (256, 16));
+    }
+
+    #[test]
+    fn find_bssf_prefers_rotation_when_only_it_fits() {
+        let free = vec![FreeRect {
+            x: 0,
+            y: 0,
+            w: 64,
+            h: 8,
+        }];
+        assert!(find_bssf(&free, 8, 64, false).is_none());
+        let best = find_bssf(&free, 8, 64, true).expect("rotated fit");
+        assert!(best.rotated);
+    }
+
+    #[test]
+    fn rotated_island_samples_correct_texels() {
+        // Source texture encodes its own coordinates: R = x, G = y.
+        let img = RgbaImage::from_fn(256, 256, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        let mut materials = MaterialLibrary::default();
+        materials.textures.push(png_texture(&img));
+        materials.materials.push(PBRMaterial {
+            name: "coords".into(),
+            base_color_texture: Some(0),
+            ..Default::default()
+        });
+
+        // A wide strip and a tall strip with disconnected UV islands.
+        let mesh = IndexedMesh {
+            positions: vec![
+                0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0, //
+                2.0, 0.0, 0.0, 3.0, 0.0, 0.0, 3.0, 1.0, 0.0, 2.0, 1.0, 0.0,
+            ],
+            uvs: vec![
+                0.0, 0.0, 0.9, 0.0, 0.9, 0.05, 0.0, 0.05, //
+                0.9, 0.05, 0.95, 0.05, 0.95, 0.95, 0.9, 0.95,
+            ],
+            indices: vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7],
+            material_index: Some(0),
+            ..Default::default()
+        };
+        let config = TextureConfig {
+            format: crate::config::TextureFormat::Original,
+            allow_rotation: true,
+            ..Default::default()
+        };
+
+        let result = repack_atlas(&mesh, &materials, &config).expect("should produce atlas");
+        let atlas = image::load_from_memory(&result.atlas_texture.data)
+            .unwrap()
+            .to_rgba8();
+        let (aw, ah) = atlas.dimensions();
+
+        // Every face corner must sample (nearly) the same source texel
+        for (fi, &vi) in result.mesh.indices.iter().enumerate() {
+            let src_vi = mesh.indices[fi] as usize;
+            let (su, sv) = (mesh.uvs[src_vi * 2], mesh.uvs[src_vi * 2 + 1]);
+            let vi = vi as usize;
+            let (u, v) = (result.mesh.uvs[vi * 2], result.mesh.uvs[vi * 2 + 1]);
+            let ax = ((u * aw as f32) as u32).min(aw - 1);
+            let ay = ((v * ah as f32) as u32).min(ah - 1);
+            let px = atlas.get_pixel(ax, ay);
+            let ex = (su * 256.0).min(255.0);
+            let ey = (sv * 256.0).min(255.0);
+            assert!(
+                (px[0] as f32 - ex).abs() <= 2.0 && (px[1] as f32 - ey).abs() <= 2.0,
+                "corner {fi}: atlas texel {:?} should match source ({ex}, {ey})",
+                px
+            );
+        }
+    }
+
+    #[test]
+    fn scaled_island_samples_correct_texels() {
+        // Source texture encodes its own coordinates: R = x, G = y.
+        let img = RgbaImage::from_fn(256, 256, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        let mut materials = MaterialLibrary::default();
+        materials.textures.push(png_texture(&img));
+        materials.materials.push(PBRMaterial {
+            name: "coords".into(),
+            base_color_texture: Some(0),
+            ..Default::default()
+        });
+
+        // A wide island whose rows don't wrap, so they are resampled at half scale
+        let mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+            uvs: vec![0.1, 0.1, 0.9, 0.1, 0.9, 0.3, 0.1, 0.3],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            material_index: Some(0),
+            ..Default::default()
+        };
+        let config = TextureConfig {
+            format: crate::config::TextureFormat::Original,
+            ..Default::default()
+        };
+
+        let result = repack_atlas_scaled(&mesh, &materials, &config, 0.5, &Default::default())
+            .expect("atlas");
+        let atlas = image::load_from_memory(&result.atlas_texture.data)
+            .unwrap()
+            .to_rgba8();
+        let (aw, ah) = atlas.dimensions();
+
+        // Corners and the midpoints between them sample the same source texel
+        let uv = |uvs: &[f32], i: u32| [uvs[i as usize * 2], uvs[i as usize * 2 + 1]];
+        for (fi, &vi) in result.mesh.indices.iter().enumerate() {
+            let next = result.mesh.indices[fi / 3 * 3 + (fi + 1) % 3];
+            let src_next = mesh.indices[fi / 3 * 3 + (fi + 1) % 3];
+            let (a, b) = (uv(&result.mesh.uvs, vi), uv(&result.mesh.uvs, next));
+            let (sa, sb) = (uv(&mesh.uvs, mesh.indices[fi]), uv(&mesh.uvs, src_next));
+            for t in [0.0, 0.5] {
+                let lerp = |p: [f32; 2], q: [f32; 2], i: usize| p[i] + (q[i] - p[i]) * t;
+                let ax = ((lerp(a, b, 0) * aw as f32) as u32).min(aw - 1);
+                let ay = ((lerp(a, b, 1) * ah as f32) as u32).min(ah - 1);
+                let px = atlas.get_pixel(ax, ay);
+                let ex = (lerp(sa, sb, 0) * 256.0).min(255.0);
+                let ey = (lerp(sa, sb, 1) * 256.0).min(255.0);
+                assert!(
+                    (px[0] as f32 - ex).abs() <= 4.0 && (px[1] as f32 - ey).abs() <= 4.0,
+                    "corner {fi} at {t}: atlas texel {px:?} should match source ({ex}, {ey})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn scaled_max_size_rounds_down_to_pot() {
+        assert_eq!(scaled_max_size(2048, 1.0), 2048);
+        assert_eq!(scaled_max_size(2048, 0.5), 1024);
+        assert_eq!(scaled_max_size(2048, 0.7), 1024);
+        assert_eq!(scaled_max_size(2048, 0.0001), 1);
+        // Non-POT max_size is left alone at full scale
+        assert_eq!(scaled_max_size(1000, 1.0), 1000);
+    }
+
+    #[test]
+    fn atlases_sharing_decoded_sources_decode_each_once() {
+        let (mesh, mut materials) = make_textured_quad();
+        let config = TextureConfig {
+            format: crate::config::TextureFormat::Original,
+            ..Default::default()
+        };
+        let decoded = DecodedSources::default();
+
+        let first = repack_atlas_scaled(&mesh, &materials, &config, 1.0, &decoded).unwrap();
+        let cached = Arc::clone(&decoded.lock().unwrap()[&0]);
+        // Later atlases sample the decoded image, not the source bytes
+        materials.textures[0].data.clear();
+        let second = repack_atlas_scaled(&mesh, &materials, &config, 1.0, &decoded).unwrap();
+        assert!(Arc::ptr_eq(&cached, &decoded.lock().unwrap()[&0]));
+        assert_eq!(first.atlas_texture.data, second.atlas_texture.data);
+        assert!(repack_atlas(&mesh, &materials, &config).is_none());
+    }
+
+    #[test]
+    fn scaled_repack_produces_smaller_atlas() {
+        let (mesh, materials) = make_textured_quad();
+        let config = TextureConfig {
+            format: crate::config::TextureFormat::Original,
+            ..Default::default()
+        };
+
+        let full = repack_atlas(&mesh, &materials, &config).expect("full atlas");
+        let half = repack_atlas_scaled(&mesh, &materials, &config, 0.5, &Default::default())
+            .expect("half atlas");
+        assert!(
+            half.atlas_texture.width < full.atlas_texture.width,
+            "scaled atlas {}px should be smaller than full {}px",
+            half.atlas_texture.width,
+            full.atlas_texture.width
+        );
+    }
+
+    #[test]
+    fn trimming_packs_only_opaque_quarter() {
+        // 64x64 texture that is only opaque in its top-left quarter
+        let img = RgbaImage::from_fn(64, 64, |x, y| {
+            if x < 32 && y < 32 {
+                image::Rgba([0, 255, 0, 255])
+            } else {
+                image::Rgba([0, 0, 0, 0])
+            }
+        });
+        let (mesh, mut materials) = make_textured_quad();
+        materials.textures[0] = png_texture(&img);
+        materials.materials[0].alpha_mode = AlphaMode::Mask { cutoff: 0.5 };
+
+        // Width of the placed region in atlas pixels, from the remapped UVs
+        let placed_width = |result: &AtlasResult| {
+            let us = result.mesh.uvs.iter().step_by(2);
+            let (lo, hi) = us.fold((f32::MAX, f32::MIN), |(lo, hi), &u| (lo.min(u), hi.max(u)));
+            (hi - lo) * result.atlas_texture.width as f32 + 1.0
+        };
+
+        let config = TextureConfig {
+            format: crate::config::TextureFormat::Original,
+            ..Default::default()
+        };
+        let full = repack_atlas(&mesh, &materials, &config).expect("full atlas");
+        let trim_config = TextureConfig {
+            trim_transparent: true,
+            ..config
+        };
+        let trimmed = repack_atlas(&mesh, &materials, &trim_config).expect("trimmed atlas");
+
+        assert!((placed_width(&full) - 64.0).abs() < 0.5);
+        assert!((placed_width(&trimmed) - 32.0).abs() < 0.5);
+        assert!(trimmed.atlas_texture.width < full.atlas_texture.width);
+
+        // Geometry over the transparent texels is clipped away
+        let max_x = trimmed
+            .mesh
+            .positions
+            .iter()
+            .step_by(3)
+            .fold(f32::MIN, |a, &b| a.max(b));
+        assert!((max_x - 0.5).abs() < 1e-6);
+
+        // Opaque materials draw transparent texels too, so nothing is trimmed
+        materials.materials[0].alpha_mode = AlphaMode::Opaque;
+        let opaque = repack_atlas(&mesh, &materials, &trim_config).expect("opaque atlas");
+        assert!((placed_width(&opaque) - 64.0).abs() < 0.5);
+        assert_eq!(opaque.mesh.indices, full.mesh.indices);
+    }
+
+    #[test]
+    fn configured_bleed_padding_widens_border() {
+        let img = RgbaImage::from_pixel(16, 16, image::Rgba([0, 255, 0, 255]));
+        let (mesh, mut materials) = make_textured_quad();
+        materials.textures[0] = png_texture(&img);
+
+        // Rows of the atlas the island and its bleed border cover
+        let covered_rows = |bleed_padding| {
+            let config = TextureConfig {
+                format: crate::config::TextureFormat::Original,
+                bleed_padding,
+                ..Default::default()
+            };
+            let result = repack_atlas(&mesh, &materials, &config).expect("atlas");
+            let atlas = decode_texture(&result.atlas_texture, u32::MAX).unwrap();
+            (0..atlas.height())
+                .filter(|&y| atlas.get_pixel(0, y)[3] == 255)
+                .count()
+        };
+
+        assert_eq!(covered_rows(None), 16 + 2 * 2);
+        assert_eq!(covered_rows(Some(8)), 16 + 2 * 8);
+    }
+
+    #[test]
+    fn inset_texel_single_pixel_island() {
+        // 1px island at origin 10: every coordinate lands on the texel centre
+        assert_eq!(inset_texel(0.0, 1, 10), 10.5);
+        assert_eq!(inset_texel(1.0, 1, 10), 10.5);
+        // Wider island spans first to last texel centre
+        assert_eq!(inset_texel(0.0, 4, 10), 10.5);
+        assert_eq!(inset_texel(1.0, 4, 10), 13.5);
+    }
+
+    #[test]
+    fn thin_island_uvs_land_inside_placed_region() {
+        // A quad whose U range covers less than one texel of a 16px texture,
+        // producing a 1px-wide island.
+        let mesh = IndexedMesh {
+            positions: vec![
+                0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+            uvs: vec![0.5, 0.0, 0.53, 0.0, 0.53, 1.0, 0.5, 1.0],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            material_index: Some(0),
+            ..Default::default()
+        };
+        let mut materials = MaterialLibrary::default();
+        materials.textures.push(checkerboard_texture(16));
+        materials.materials.push(PBRMaterial {
+            name: "thin".into(),
+            base_color_texture: Some(0),
+            ..Default::default()
+        });
+
+        let adjacency = build_edge_adjacency(&mesh);
+        let islands = detect_islands(&mesh, &adjacency, &vec![Some(0); mesh.triangle_count()]);
+        assert_eq!(islands.len(), 1);
+        let placements = guillotine_pack(&[(0, 1, 16, 2)], false);
+        let atlas_size = compute_atlas_size(&placements);
+        let p = &placements[0];
+        assert_eq!(p.inner_w, 1);
+
+        let remapped = remap_uvs_with_dedup(&mesh, &islands, &[(&placements, atlas_size)]);
+        let inner_x0 = (p.x + p.padding) as f32;
+        let inner_y0 = (p.y + p.padding) as f32;
+        for uv in remapped.uvs.chunks_exact(2) {
+            let px = uv[0] * atlas_size as f32;
+            let py = uv[1] * atlas_size as f32;
+            // Strictly inside the island's single texel column, at its centre
+            assert!((px - (inner_x0 + 0.5)).abs() < 1e-4, "u pixel {px} off texel centre");
+            assert!(px > inner_x0 && px < inner_x0 + p.inner_w as f32);
+            assert!(py > inner_y0 && py < inner_y0 + p.inner_h as f32);
+        }
+    }
+
+    /// 16px texture: left half red, right half blue.
+    fn half_split_texture() -> TextureData {
+        png_texture(&RgbaImage::from_fn(16, 16, |x, _| {
+            if x < 8 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        }))
+    }
+
+    #[test]
+    fn tiled_uvs_keep_repeated_pattern() {
+        // Quad with UVs 0..2 in U: the texture repeats twice across it.
+        let mesh = IndexedMesh {
+            positions: vec![
+                0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+            uvs: vec![0.0, 0.0, 2.0, 0.0, 2.0, 1.0, 0.0, 1.0],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            material_index: Some(0),
+            ..Default::default()
+        };
+        let mut materials = MaterialLibrary::default();
+        materials.textures.push(half_split_texture());
+        materials.materials.push(PBRMaterial {
+            name: "tiled".into(),
+            base_color_texture: Some(0),
+            ..Default::default()
+        });
+        let config = TextureConfig {
+            format: crate::config::TextureFormat::Original,
+            ..Default::default()
+        };
+
+        let result = repack_atlas(&mesh, &materials, &config).expect("should produce atlas");
+        let atlas = image::load_from_memory(&result.atlas_texture.data)
+            .unwrap()
+            .to_rgba8();
+        let size = atlas.width() as f32;
+
+        // Island span in the atlas, from the remapped quad corners
+        let us: Vec<f32> = result.mesh.uvs.chunks_exact(2).map(|uv| uv[0]).collect();
+        let vs: Vec<f32> = result.mesh.uvs.chunks_exact(2).map(|uv| uv[1]).collect();
+        let u0 = us.iter().cloned().fold(f32::MAX, f32::min);
+        let u1 = us.iter().cloned().fold(f32::MIN, f32::max);
+        let v_mid = (vs.iter().cloned().fold(f32::MAX, f32::min)
+            + vs.iter().cloned().fold(f32::MIN, f32::max))
+            / 2.0;
+
+        // Quarter points across U alternate red / blue / red / blue
+        let expected = [[255, 0, 0], [0, 0, 255], [255, 0, 0], [0, 0, 255]];
+        for (i, want) in expected.iter().enumerate() {
+            let t = (i as f32 + 0.5) / 4.0;
+            let u = u0 + (u1 - u0) * t;
+            let px = atlas.get_pixel((u * size) as u32, (v_mid * size) as u32);
+            assert_eq!(&px.0[..3], want, "quarter {i} of the tiled island");
+        }
+    }
+
+    #[test]
+    fn island_tiling_detection() {
+        let island = |uv_min: [f32; 2], uv_max: [f32; 2]| UvIsland {
+            faces: vec![],
+            source: 0,
+            uv_min,
+            uv_max,
+        };
+        assert!(!island([0.0, 0.0], [1.0, 1.0]).is_tiled());
+        assert!(!island([1.25, 0.0], [1.75, 0.5]).is_tiled());
+        assert!(island([0.0, 0.0], [2.0, 1.0]).is_tiled());
+        assert!(island([0.75, 0.0], [1.25, 0.5]).is_tiled());
+        assert!(island([0.0, -0.5], [0.5, 0.5]).is_tiled());
+    }
+
+    #[test]
+    fn heavily_tiled_island_capped_uniformly() {
+        // 8 repeats across U of a 16px texture = 128px wide, capped at 64px:
+        // both axes shrink by half so each repeat stays square.
+        let mesh = IndexedMesh {
+            positions: vec![
+                0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+            uvs: vec![0.0, 0.0, 8.0, 0.0, 8.0, 1.0, 0.0, 1.0],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            material_index: Some(0),
+            ..Default::default()
+        };
+        let mut materials = MaterialLibrary::default();
+        materials.textures.push(half_split_texture());
+        materials.materials.push(PBRMaterial {
+            name: "tiled".into(),
+            base_color_texture: Some(0),
+            ..Default::default()
+        });
+        let config = TextureConfig {
+            format: crate::config::TextureFormat::Original,
+            max_size: 64,
+            ..Default::default()
+        };
+
+        let result = repack_atlas(&mesh, &materials, &config).expect("should produce atlas");
+        let us: Vec<f32> = result.mesh.uvs.chunks_exact(2).map(|uv| uv[0]).collect();
+        let vs: Vec<f32> = result.mesh.uvs.chunks_exact(2).map(|uv| uv[1]).collect();
+        let span = |xs: &[f32]| {
+            xs.iter().cloned().fold(f32::MIN, f32::max) - xs.iter().cloned().fold(f32::MAX, f32::min)
+        };
+        let size = result.atlas_texture.width as f32;
+        let island_w = span(&us) * size + 1.0;
+        let island_h = span(&vs) * size + 1.0;
+        // 8:1 repeat strip keeps its aspect (within a texel)
+        assert!(
+            (island_w / island_h - 8.0).abs() < 1.0,
+            "tiled island should keep 8:1 aspect, got {island_w}x{island_h}"
+        );
+    }
+
     #[test]
     fn uv_remapping_range() {
         let (mesh, materials) = make_textured_quad();
@@ -980,6 +2508,193 @@ mod tests {
         assert!(!result.atlas_texture.data.is_empty());
     }
 
+    #[test]
+    fn metallic_roughness_map_is_atlased_with_base_color_layout() {
+        let (mesh, mut materials) = make_two_island_mesh();
+        // Lower resolution than the base color map; sampled by UV all the same
+        let mr = RgbaImage::from_pixel(8, 8, image::Rgba([0, 128, 255, 255]));
+        materials.textures.push(png_texture(&mr));
+        materials.materials[0].metallic_roughness_texture = Some(1);
+
+        let result = repack_atlas(&mesh, &materials, &TextureConfig::default()).unwrap();
+        let mr_atlas = result
+            .metallic_roughness_texture
+            .as_ref()
+            .expect("should produce a metallic-roughness atlas");
+        assert_eq!(
+            (mr_atlas.width, mr_atlas.height),
+            (result.atlas_texture.width, result.atlas_texture.height)
+        );
+
+        // Every remapped UV lands on metallic-roughness texels
+        let decoded = image::load_from_memory(&mr_atlas.data).unwrap().to_rgba8();
+        for uv in result.mesh.uvs.chunks_exact(2) {
+            let x = ((uv[0] * decoded.width() as f32) as u32).min(decoded.width() - 1);
+            let y = ((uv[1] * decoded.height() as f32) as u32).min(decoded.height() - 1);
+            let px = decoded.get_pixel(x, y);
+            assert!(
+                px[0] < 8 && px[1].abs_diff(128) < 8 && px[2] > 247,
+                "{px:?}"
+            );
+        }
+
+        let images = crate::tiling::glb_writer::TileImages {
+            base_color: Some(crate::tiling::glb_writer::ImageSource::Embedded(
+                &result.atlas_texture,
+            )),
+            metallic_roughness: Some(crate::tiling::glb_writer::ImageSource::Embedded(mr_atlas)),
+            ..Default::default()
+        };
+        let glb = crate::tiling::glb_writer::write_glb(&result.mesh, &materials, images);
+        let doc = gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(doc.textures().count(), 2);
+        let material = doc.materials().next().unwrap();
+        let pbr = material.pbr_metallic_roughness();
+        let base = pbr.base_color_texture().unwrap().texture().index();
+        let mr = pbr.metallic_roughness_texture().unwrap().texture().index();
+        assert_ne!(base, mr);
+    }
+
+    /// Three quads sharing edges and the full `[0, 1]` UV square, drawn with
+    /// materials 0, 1 and 2 in turn.
+    fn make_three_material_strip() -> (IndexedMesh, MaterialLibrary) {
+        let mut mesh = IndexedMesh::default();
+        for q in 0..3u32 {
+            let x = q as f32;
+            for [px, py] in [[x, 0.0], [x + 1.0, 0.0], [x + 1.0, 1.0], [x, 1.0]] {
+                mesh.positions.extend_from_slice(&[px, py, 0.0]);
+                mesh.uvs.extend_from_slice(&[px - x, py]);
+            }
+            let v = q * 4;
+            mesh.indices
+                .extend_from_slice(&[v, v + 1, v + 2, v, v + 2, v + 3]);
+        }
+        mesh.set_triangle_materials(&[Some(0), Some(0), Some(1), Some(1), Some(2), Some(2)]);
+
+        let solid = |rgba: [u8; 4]| png_texture(&RgbaImage::from_pixel(8, 8, image::Rgba(rgba)));
+        let mut materials = MaterialLibrary::default();
+        materials.textures.push(solid([255, 0, 0, 255]));
+        materials.textures.push(solid([0, 255, 0, 255]));
+        for base_color_texture in [Some(0), Some(1), Some(1)] {
+            materials.materials.push(PBRMaterial {
+                base_color_texture,
+                ..Default::default()
+            });
+        }
+        (mesh, materials)
+    }
+
+    #[test]
+    fn two_texture_tile_shares_one_atlas_and_material() {
+        let (mut mesh, materials) = make_three_material_strip();
+        // Only the first two quads: red and green textures
+        mesh.indices.truncate(12);
+        mesh.set_triangle_materials(&[Some(0), Some(0), Some(1), Some(1)]);
+
+        let result = repack_atlas(&mesh, &materials, &TextureConfig::default()).unwrap();
+        assert_eq!(result.mesh.material_index, Some(0));
+        assert!(result.mesh.material_groups.is_empty());
+
+        // Each quad's remapped UVs land on its own texture's texels
+        let atlas = image::load_from_memory(&result.atlas_texture.data)
+            .unwrap()
+            .to_rgba8();
+        let size = atlas.width() as f32;
+        for (face, tri) in result.mesh.indices.chunks_exact(3).enumerate() {
+            let want = if face < 2 { [255, 0, 0] } else { [0, 255, 0] };
+            for &vi in tri {
+                let uv = &result.mesh.uvs[vi as usize * 2..vi as usize * 2 + 2];
+                let px = atlas.get_pixel((uv[0] * size) as u32, (uv[1] * size) as u32);
+                assert_eq!(&px.0[..3], want, "face {face}");
+            }
+        }
+
+        let glb = crate::tiling::glb_writer::write_glb(
+            &result.mesh,
+            &materials,
+            Some(&result.atlas_texture),
+        );
+        let doc = gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(doc.materials().count(), 1);
+        assert_eq!(doc.textures().count(), 1);
+    }
+
+    #[test]
+    fn materials_differing_beyond_textures_keep_their_own_atlas_material() {
+        let (mesh, mut materials) = make_three_material_strip();
+        // The green quad is tinted and the blue one blends; only red stays plain
+        materials.materials[1].base_color = [0.5, 0.5, 0.5, 1.0];
+        materials.materials[2].alpha_mode = crate::types::AlphaMode::Blend;
+
+        let result = repack_atlas(&mesh, &materials, &TextureConfig::default()).unwrap();
+        assert_eq!(
+            result.mesh.triangle_materials(),
+            vec![Some(0), Some(0), Some(1), Some(1), Some(2), Some(2)]
+        );
+
+        // Untinted, the green quad joins the red one's material
+        materials.materials[1].base_color = [1.0; 4];
+        let result = repack_atlas(&mesh, &materials, &TextureConfig::default()).unwrap();
+        assert_eq!(
+            result.mesh.triangle_materials(),
+            vec![Some(0), Some(0), Some(0), Some(0), Some(2), Some(2)]
+        );
+
+        // All three share the one atlas
+        let glb = crate::tiling::glb_writer::write_glb(
+            &result.mesh,
+            &materials,
+            Some(&result.atlas_texture),
+        );
+        let doc = gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(doc.materials().count(), 2);
+        assert_eq!(doc.textures().count(), 1);
+    }
+
+    #[test]
+    fn untextured_material_keeps_its_group_outside_the_atlas() {
+        let (mesh, mut materials) = make_three_material_strip();
+        materials.materials[2].base_color_texture = None;
+
+        let result = repack_atlas(&mesh, &materials, &TextureConfig::default()).unwrap();
+        assert_eq!(
+            result.mesh.triangle_materials(),
+            vec![Some(0), Some(0), Some(0), Some(0), Some(2), Some(2)]
+        );
+
+        let glb = crate::tiling::glb_writer::write_glb(
+            &result.mesh,
+            &materials,
+            Some(&result.atlas_texture),
+        );
+        let doc = gltf::Gltf::from_slice(&glb).unwrap();
+        let textured: Vec<bool> = doc
+            .materials()
+            .map(|m| m.pbr_metallic_roughness().base_color_texture().is_some())
+            .collect();
+        assert_eq!(textured, vec![true, false]);
+    }
+
+    #[test]
+    fn packing_efficiency_reports_island_share_of_atlas() {
+        // A full-width sliver and a single-texel island leave most of the
+        // atlas empty: 32x1 and 1x1 texels of a 32px texture
+        let (mut mesh, materials) = make_two_island_mesh();
+        let (s, t) = (1.0 / 32.0, 0.5 + 1.0 / 32.0);
+        mesh.uvs = vec![
+            0.0, 0.0, 1.0, 0.0, 1.0, s, 0.0, s, // sliver
+            0.5, 0.5, t, 0.5, t, t, 0.5, t, // single texel
+        ];
+
+        let result = repack_atlas(&mesh, &materials, &TextureConfig::default()).unwrap();
+        assert_eq!(result.island_texels, 32 + 1);
+        let atlas_side = u64::from(result.atlas_texture.width);
+        assert_eq!(result.atlas_texels, atlas_side * atlas_side);
+        let expected = 33.0 / (atlas_side * atlas_side) as f64;
+        assert!((result.packing_efficiency() - expected).abs() < 1e-12);
+        assert!(result.packing_efficiency() < LOW_PACKING_EFFICIENCY);
+    }
+
     #[test]
     fn no_uvs_returns_none() {
         let mesh = IndexedMesh {
@@ -1053,12 +2768,16 @@ mod tests {
                 1.0, 0.5,  // v4
                 // v2 reused with island A's UV, but island B wants different mapping
             ],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices: vec![
                 0, 1, 2, // Triangle 1 (island A)
                 3, 4, 2, // Triangle 2 (island B) — shares v2!
             ],
             material_index: Some(0),
+            name: None,
+            material_groups: Vec::new(),
         };
 
         let mut materials = MaterialLibrary::default();
@@ -1128,10 +2847,32 @@ mod tests {
     #[test]
     fn decode_texture_png() {
         let tex = checkerboard_texture(8);
-        let img = decode_texture(&tex).expect("should decode PNG");
+        let img = decode_texture(&tex, u32::MAX).expect("should decode PNG");
         assert_eq!(img.dimensions(), (8, 8));
     }
 
+    #[test]
+    fn decode_large_png_streams_into_bounded_image() {
+        // Left half black, right half white; the streamed downscale must
+        // never exceed the cap and keep the halves apart.
+        let img = RgbaImage::from_fn(1024, 512, |x, _| {
+            let v = if x < 512 { 0 } else { 255 };
+            image::Rgba([v, v, v, 255])
+        });
+        let tex = png_texture(&img);
+
+        let streamed = decode_png_downscaled(&tex.data, 64).expect("should stream");
+        assert_eq!(streamed.dimensions(), (64, 32));
+        assert_eq!(streamed.get_pixel(0, 0), &image::Rgba([0, 0, 0, 255]));
+        assert_eq!(streamed.get_pixel(63, 31), &image::Rgba([255, 255, 255, 255]));
+
+        let decoded = decode_texture(&tex, 64).unwrap();
+        assert_eq!(decoded.dimensions(), (64, 32));
+        // Sources that already fit are decoded as usual
+        assert!(decode_png_downscaled(&tex.data, 1024).is_none());
+        assert_eq!(decode_texture(&tex, 1024).unwrap().dimensions(), (1024, 512));
+    }
+
     #[test]
     fn decode_texture_raw_rgba() {
         let tex = TextureData {
@@ -1139,8 +2880,9 @@ mod tests {
             mime_type: "image/raw".into(),
             width: 2,
             height: 2,
+            wrap: Default::default(),
         };
-        let img = decode_texture(&tex).expect("should decode raw RGBA");
+        let img = decode_texture(&tex, u32::MAX).expect("should decode raw RGBA");
         assert_eq!(img.dimensions(), (2, 2));
         assert_eq!(img.get_pixel(0, 0), &image::Rgba([255, 0, 0, 255]));
     }
@@ -1152,8 +2894,9 @@ mod tests {
             mime_type: "image/raw".into(),
             width: 2,
             height: 2,
+            wrap: Default::default(),
         };
-        let img = decode_texture(&tex).expect("should decode raw RGB");
+        let img = decode_texture(&tex, u32::MAX).expect("should decode raw RGB");
         assert_eq!(img.dimensions(), (2, 2));
         assert_eq!(img.get_pixel(0, 0), &image::Rgba([255, 0, 0, 255]));
     }
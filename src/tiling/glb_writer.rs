@@ -1,6 +1,7 @@
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
+use glam::{DMat4, DQuat, DVec3};
 use gltf::binary::Glb;
 use gltf_json::accessor::{ComponentType, GenericComponentType, Type as AccessorType};
 use gltf_json::buffer::Target;
@@ -8,26 +9,52 @@ use gltf_json::mesh::{Mode, Primitive, Semantic};
 use gltf_json::validation::{Checked, USize64};
 use gltf_json::Index;
 
-use crate::types::{IndexedMesh, MaterialLibrary, TextureData};
+use crate::types::{AlphaMode, IndexedMesh, MaterialLibrary, TextureData, WrapMode};
+
+/// Material extension carrying emission brighter than `emissiveFactor` allows.
+const EMISSIVE_STRENGTH: &str = "KHR_materials_emissive_strength";
 
 /// Serialize an `IndexedMesh` into a binary GLB (glTF 2.0) byte buffer.
 ///
 /// Produces a valid, self-contained GLB with:
 /// - 1 buffer (positions + optional normals/UVs/colors + indices + optional texture)
 /// - BufferViews and Accessors for each attribute present
-/// - 1 Mesh with 1 Primitive (mode = Triangles)
+/// - 1 Mesh with 1 Primitive (mode = Triangles) per material group, all
+///   sharing the vertex attribute accessors
 /// - 1 Node → 1 Scene
-/// - Material if `material_index` is set and present in `materials`
-/// - Texture if `atlas_texture` is provided
+/// - A Material per group whose material index is present in `materials`
+/// - A texture per image in `textures` (an `Option<&TextureData>` converts
+///   to a base color atlas)
 ///
 /// Colors are stored as u8 normalized (4 bytes/vertex instead of 16).
 /// Indices use u16 when vertex_count <= 65535.
-pub fn write_glb(
+pub fn write_glb<'a>(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    textures: impl Into<TileImages<'a>>,
+) -> Vec<u8> {
+    write_glb_impl(
+        mesh,
+        materials,
+        textures.into(),
+        VertexEncoding::Float,
+    )
+}
+
+/// Serialize an uncompressed GLB whose texture image lives in a separate file.
+///
+/// See [`write_glb_compressed_external`].
+pub fn write_glb_external(
     mesh: &IndexedMesh,
     materials: &MaterialLibrary,
-    atlas_texture: Option<&TextureData>,
+    image_uri: &str,
+    mime_type: &str,
 ) -> Vec<u8> {
-    write_glb_impl(mesh, materials, atlas_texture, false)
+    let image = ImageSource::External {
+        uri: image_uri,
+        mime_type,
+    };
+    write_glb_impl(mesh, materials, image.into(), VertexEncoding::Float)
 }
 
 /// Serialize an `IndexedMesh` into a compressed GLB with EXT_meshopt_compression.
@@ -35,24 +62,243 @@ pub fn write_glb(
 /// Same as `write_glb` but applies meshopt buffer encoding to vertex attribute
 /// and index buffers. Viewers must support EXT_meshopt_compression to load these.
 /// Achieves 50-70% size reduction compared to uncompressed GLB.
-pub fn write_glb_compressed(
+pub fn write_glb_compressed<'a>(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    textures: impl Into<TileImages<'a>>,
+) -> Vec<u8> {
+    write_glb_impl(
+        mesh,
+        materials,
+        textures.into(),
+        VertexEncoding::Meshopt { normal_bits: None },
+    )
+}
+
+/// Serialize a compressed GLB whose texture image lives in a separate file.
+///
+/// `image_uri` is written verbatim into `images[0].uri` and must be relative
+/// to the GLB's own location. Used for atlases shared between several tiles.
+pub fn write_glb_compressed_external(
     mesh: &IndexedMesh,
     materials: &MaterialLibrary,
-    atlas_texture: Option<&TextureData>,
+    image_uri: &str,
+    mime_type: &str,
 ) -> Vec<u8> {
-    write_glb_impl(mesh, materials, atlas_texture, true)
+    let image = ImageSource::External {
+        uri: image_uri,
+        mime_type,
+    };
+    write_glb_impl(
+        mesh,
+        materials,
+        image.into(),
+        VertexEncoding::Meshopt { normal_bits: None },
+    )
+}
+
+/// Serialize a compressed GLB whose normals are octahedral-encoded.
+///
+/// Like [`write_glb_compressed`], but normals go through the meshopt
+/// OCTAHEDRAL filter: each vertex stores its two octahedral coordinates in
+/// `normal_bits` bits (4..=16), followed by the filter's decode scale, in
+/// 4 bytes (8 above 8 bits) instead of 12. Viewers decode it to a
+/// normalized integer VEC3, so KHR_mesh_quantization is also required.
+pub fn write_glb_compressed_oct<'a>(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    textures: impl Into<TileImages<'a>>,
+    normal_bits: u8,
+) -> Vec<u8> {
+    write_glb_impl(
+        mesh,
+        materials,
+        textures.into(),
+        VertexEncoding::Meshopt {
+            normal_bits: Some(normal_bits),
+        },
+    )
+}
+
+/// Serialize an octahedral-normal GLB whose texture image lives in a
+/// separate file.
+///
+/// See [`write_glb_compressed_oct`] and [`write_glb_compressed_external`].
+pub fn write_glb_compressed_oct_external(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    image_uri: &str,
+    mime_type: &str,
+    normal_bits: u8,
+) -> Vec<u8> {
+    let image = ImageSource::External {
+        uri: image_uri,
+        mime_type,
+    };
+    write_glb_impl(
+        mesh,
+        materials,
+        image.into(),
+        VertexEncoding::Meshopt {
+            normal_bits: Some(normal_bits),
+        },
+    )
+}
+
+/// Serialize an `IndexedMesh` into a GLB with KHR_mesh_quantization.
+///
+/// Intended for viewers without EXT_meshopt_compression support. Positions
+/// are stored as int16 on a uniform grid whose dequantization scale and
+/// offset live in the node transform, normals as normalized int8 per
+/// component and UVs as normalized uint16 (UVs outside [0, 1] stay f32).
+/// Normals are not octahedral-encoded here: without the meshopt filter to
+/// decode them, glTF only accepts VEC3 normals.
+pub fn write_glb_quantized<'a>(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    textures: impl Into<TileImages<'a>>,
+) -> Vec<u8> {
+    write_glb_impl(
+        mesh,
+        materials,
+        textures.into(),
+        VertexEncoding::Quantized,
+    )
+}
+
+/// Serialize a quantized GLB whose texture image lives in a separate file.
+///
+/// See [`write_glb_quantized`] and [`write_glb_compressed_external`].
+pub fn write_glb_quantized_external(
+    mesh: &IndexedMesh,
+    materials: &MaterialLibrary,
+    image_uri: &str,
+    mime_type: &str,
+) -> Vec<u8> {
+    let image = ImageSource::External {
+        uri: image_uri,
+        mime_type,
+    };
+    write_glb_impl(mesh, materials, image.into(), VertexEncoding::Quantized)
+}
+
+/// How vertex attributes are stored in the binary buffer.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VertexEncoding {
+    /// Plain f32 attributes.
+    Float,
+    /// Attributes encoded with EXT_meshopt_compression: f32, except normals
+    /// octahedral-encoded in `normal_bits` per coordinate when set.
+    Meshopt { normal_bits: Option<u8> },
+    /// Integer attributes per KHR_mesh_quantization, normals per component.
+    Quantized,
+}
+
+/// Where a tile texture image is stored.
+#[derive(Clone, Copy)]
+pub enum ImageSource<'a> {
+    /// Image bytes are embedded in the GLB binary chunk.
+    Embedded(&'a TextureData),
+    /// Image is referenced by a relative URI.
+    External { uri: &'a str, mime_type: &'a str },
+}
+
+impl ImageSource<'_> {
+    fn mime_type(&self) -> &str {
+        match self {
+            ImageSource::Embedded(tex) => &tex.mime_type,
+            ImageSource::External { mime_type, .. } => mime_type,
+        }
+    }
+
+    /// Sampler wrap modes; external images are always shared atlases.
+    fn wrap(&self) -> [WrapMode; 2] {
+        match self {
+            ImageSource::Embedded(tex) => tex.wrap,
+            ImageSource::External { .. } => [WrapMode::ClampToEdge; 2],
+        }
+    }
+}
+
+/// Images for a tile material's texture slots.
+///
+/// All images share the base color's texture coordinates, so they must come
+/// from the same atlas layout.
+#[derive(Clone, Copy, Default)]
+pub struct TileImages<'a> {
+    pub base_color: Option<ImageSource<'a>>,
+    pub metallic_roughness: Option<ImageSource<'a>>,
+    pub normal: Option<ImageSource<'a>>,
+    /// Images of further atlas pages, each with the first index of the
+    /// triangles it textures. Triangles before the first page use the images
+    /// above.
+    pub pages: &'a [(usize, TileImages<'a>)],
+}
+
+impl<'a> TileImages<'a> {
+    fn iter(&self) -> impl Iterator<Item = ImageSource<'a>> {
+        let pages = self.pages.iter().flat_map(|(_, page)| page.slots());
+        self.slots().chain(pages)
+    }
+
+    fn slots(&self) -> impl Iterator<Item = ImageSource<'a>> {
+        [self.base_color, self.metallic_roughness, self.normal]
+            .into_iter()
+            .flatten()
+    }
+}
+
+/// Texture indices for each material slot, as written to the glTF root.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct MaterialTextures {
+    base_color: Option<Index<gltf_json::Texture>>,
+    metallic_roughness: Option<Index<gltf_json::Texture>>,
+    normal: Option<Index<gltf_json::Texture>>,
+}
+
+impl<'a> From<ImageSource<'a>> for TileImages<'a> {
+    fn from(base_color: ImageSource<'a>) -> Self {
+        Self {
+            base_color: Some(base_color),
+            ..Default::default()
+        }
+    }
+}
+
+impl<'a> From<Option<&'a TextureData>> for TileImages<'a> {
+    fn from(base_color: Option<&'a TextureData>) -> Self {
+        Self {
+            base_color: base_color.map(ImageSource::Embedded),
+            ..Default::default()
+        }
+    }
+}
+
+fn wrapping_mode(mode: WrapMode) -> gltf_json::texture::WrappingMode {
+    match mode {
+        WrapMode::Repeat => gltf_json::texture::WrappingMode::Repeat,
+        WrapMode::MirroredRepeat => gltf_json::texture::WrappingMode::MirroredRepeat,
+        WrapMode::ClampToEdge => gltf_json::texture::WrappingMode::ClampToEdge,
+    }
 }
 
 fn write_glb_impl(
     mesh: &IndexedMesh,
     materials: &MaterialLibrary,
-    atlas_texture: Option<&TextureData>,
-    compress: bool,
+    images: TileImages<'_>,
+    encoding: VertexEncoding,
 ) -> Vec<u8> {
     if mesh.is_empty() {
         return write_empty_glb();
     }
 
+    let compress = matches!(encoding, VertexEncoding::Meshopt { .. });
+    let quantize = encoding == VertexEncoding::Quantized;
+    let oct_normal_bits = match encoding {
+        VertexEncoding::Meshopt { normal_bits } if mesh.has_normals() => normal_bits,
+        _ => None,
+    };
+
     let mut root = gltf_json::Root {
         asset: gltf_json::Asset {
             version: "2.0".into(),
@@ -69,7 +315,23 @@ fn write_glb_impl(
     let buffer_idx = Index::new(0); // will push buffer at end
 
     // --- Positions (required) ---
-    let (pos_min, pos_max) = compute_position_bounds(&mesh.positions);
+    let pos_grid = quantize.then(|| PositionGrid::new(&mesh.positions));
+    let pos_layout = match &pos_grid {
+        Some(grid) => grid.quantize(&mesh.positions),
+        None => AttributeLayout::float(&mesh.positions, 3),
+    };
+    // Accessor bounds are in stored (quantized) units
+    let (pos_min, pos_max) = match &pos_grid {
+        Some(_) => {
+            let q: &[[i16; 4]] = bytemuck::cast_slice(&pos_layout.bytes);
+            let as_f32: Vec<f32> = q
+                .iter()
+                .flat_map(|v| [v[0], v[1], v[2]].map(f32::from))
+                .collect();
+            compute_position_bounds(&as_f32)
+        }
+        None => compute_position_bounds(&mesh.positions),
+    };
     let pos_encoded = if compress {
         encode_f32x3(&mesh.positions)
     } else {
@@ -79,8 +341,8 @@ fn write_glb_impl(
         &mut root,
         &mut bin_data,
         buffer_idx,
-        bytemuck::cast_slice(&mesh.positions),
-        12, // stride: 3 * f32
+        &pos_layout.bytes,
+        pos_layout.stride,
         mesh.vertex_count(),
         pos_encoded,
     );
@@ -89,12 +351,12 @@ fn write_glb_impl(
         buffer_view: Some(pos_view),
         byte_offset: Some(USize64(0)),
         count: USize64::from(mesh.vertex_count()),
-        component_type: Checked::Valid(GenericComponentType(ComponentType::F32)),
+        component_type: Checked::Valid(GenericComponentType(pos_layout.component_type)),
         type_: Checked::Valid(AccessorType::Vec3),
         min: Some(serde_json::json!(pos_min)),
         max: Some(serde_json::json!(pos_max)),
         name: None,
-        normalized: false,
+        normalized: pos_layout.normalized,
         sparse: None,
         extensions: Default::default(),
         extras: Default::default(),
@@ -103,31 +365,39 @@ fn write_glb_impl(
 
     // --- Normals (optional) ---
     if mesh.has_normals() {
-        let normals_encoded = if compress {
-            encode_f32x3(&mesh.normals)
-        } else {
-            None
+        let layout = match oct_normal_bits {
+            Some(bits) => oct_encode_normals(&mesh.normals, bits),
+            None if quantize => quantize_normals(&mesh.normals),
+            None => AttributeLayout::float(&mesh.normals, 3),
+        };
+        let normals_encoded = match oct_normal_bits {
+            Some(_) => meshopt_encode_bytes(&layout),
+            None if compress => encode_f32x3(&mesh.normals),
+            None => None,
         };
         let view = write_vertex_attribute_view(
             &mut root,
             &mut bin_data,
             buffer_idx,
-            bytemuck::cast_slice(&mesh.normals),
-            12, // stride: 3 * f32
+            &layout.bytes,
+            layout.stride,
             mesh.vertex_count(),
             normals_encoded,
         );
+        if oct_normal_bits.is_some() {
+            set_meshopt_filter(&mut root, view, "OCTAHEDRAL");
+        }
 
         let accessor = root.push(gltf_json::Accessor {
             buffer_view: Some(view),
             byte_offset: Some(USize64(0)),
             count: USize64::from(mesh.vertex_count()),
-            component_type: Checked::Valid(GenericComponentType(ComponentType::F32)),
+            component_type: Checked::Valid(GenericComponentType(layout.component_type)),
             type_: Checked::Valid(AccessorType::Vec3),
             min: None,
             max: None,
             name: None,
-            normalized: false,
+            normalized: layout.normalized,
             sparse: None,
             extensions: Default::default(),
             extras: Default::default(),
@@ -137,6 +407,10 @@ fn write_glb_impl(
 
     // --- UVs (optional) ---
     if mesh.has_uvs() {
+        let layout = quantize
+            .then(|| quantize_uvs(&mesh.uvs))
+            .flatten()
+            .unwrap_or_else(|| AttributeLayout::float(&mesh.uvs, 2));
         let uvs_encoded = if compress {
             encode_f32x2(&mesh.uvs)
         } else {
@@ -146,8 +420,8 @@ fn write_glb_impl(
             &mut root,
             &mut bin_data,
             buffer_idx,
-            bytemuck::cast_slice(&mesh.uvs),
-            8, // stride: 2 * f32
+            &layout.bytes,
+            layout.stride,
             mesh.vertex_count(),
             uvs_encoded,
         );
@@ -156,12 +430,12 @@ fn write_glb_impl(
             buffer_view: Some(view),
             byte_offset: Some(USize64(0)),
             count: USize64::from(mesh.vertex_count()),
-            component_type: Checked::Valid(GenericComponentType(ComponentType::F32)),
+            component_type: Checked::Valid(GenericComponentType(layout.component_type)),
             type_: Checked::Valid(AccessorType::Vec2),
             min: None,
             max: None,
             name: None,
-            normalized: false,
+            normalized: layout.normalized,
             sparse: None,
             extensions: Default::default(),
             extras: Default::default(),
@@ -169,6 +443,44 @@ fn write_glb_impl(
         attributes.insert(Checked::Valid(Semantic::TexCoords(0)), accessor);
     }
 
+    // --- Second UV set (optional, passed through untouched) ---
+    if mesh.has_uvs1() {
+        let layout = quantize
+            .then(|| quantize_uvs(&mesh.uvs1))
+            .flatten()
+            .unwrap_or_else(|| AttributeLayout::float(&mesh.uvs1, 2));
+        let uvs1_encoded = if compress {
+            encode_f32x2(&mesh.uvs1)
+        } else {
+            None
+        };
+        let view = write_vertex_attribute_view(
+            &mut root,
+            &mut bin_data,
+            buffer_idx,
+            &layout.bytes,
+            layout.stride,
+            mesh.vertex_count(),
+            uvs1_encoded,
+        );
+
+        let accessor = root.push(gltf_json::Accessor {
+            buffer_view: Some(view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(mesh.vertex_count()),
+            component_type: Checked::Valid(GenericComponentType(layout.component_type)),
+            type_: Checked::Valid(AccessorType::Vec2),
+            min: None,
+            max: None,
+            name: None,
+            normalized: layout.normalized,
+            sparse: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
+        attributes.insert(Checked::Valid(Semantic::TexCoords(1)), accessor);
+    }
+
     // --- Colors (optional, stored as u8 normalized) ---
     if mesh.has_colors() {
         // Convert f32 colors to u8 (4 bytes per vertex instead of 16)
@@ -210,122 +522,196 @@ fn write_glb_impl(
         attributes.insert(Checked::Valid(Semantic::Colors(0)), accessor);
     }
 
-    // --- Indices (u16 when vertex_count <= 65535, else u32) ---
-    let use_u16_indices = mesh.vertex_count() <= 65535;
-    let idx_encoded = if compress {
-        meshopt::encode_index_buffer(&mesh.indices, mesh.vertex_count()).ok()
-    } else {
-        None
-    };
-    let idx_view = write_index_view(
-        &mut root,
-        &mut bin_data,
-        buffer_idx,
-        &mesh.indices,
-        mesh.vertex_count(),
-        use_u16_indices,
-        idx_encoded,
-    );
-
-    let idx_component_type = if use_u16_indices {
-        ComponentType::U16
-    } else {
-        ComponentType::U32
-    };
-
-    let idx_accessor = root.push(gltf_json::Accessor {
-        buffer_view: Some(idx_view),
-        byte_offset: Some(USize64(0)),
-        count: USize64::from(mesh.indices.len()),
-        component_type: Checked::Valid(GenericComponentType(idx_component_type)),
-        type_: Checked::Valid(AccessorType::Scalar),
-        min: None,
-        max: None,
-        name: None,
-        normalized: false,
-        sparse: None,
-        extensions: Default::default(),
-        extras: Default::default(),
-    });
-
-    // --- Texture (optional) ---
-    let texture_index = if let Some(tex) = atlas_texture {
-        // Pad to 4-byte alignment before texture data
-        while bin_data.len() % 4 != 0 {
-            bin_data.push(0);
-        }
-        let tex_byte_offset = bin_data.len();
-        bin_data.extend_from_slice(&tex.data);
-        let tex_byte_length = tex.data.len();
+    // --- Feature IDs (optional, f32 scalars per EXT_mesh_features) ---
+    let write_features = mesh.has_feature_ids() && !materials.features.is_empty();
+    if write_features {
+        let ids: Vec<f32> = mesh.feature_ids.iter().map(|&id| id as f32).collect();
+        let layout = AttributeLayout::float(&ids, 1);
+        let ids_encoded = if compress {
+            meshopt_encode_bytes(&layout)
+        } else {
+            None
+        };
+        let view = write_vertex_attribute_view(
+            &mut root,
+            &mut bin_data,
+            buffer_idx,
+            &layout.bytes,
+            layout.stride,
+            mesh.vertex_count(),
+            ids_encoded,
+        );
 
-        let tex_view = root.push(gltf_json::buffer::View {
-            buffer: buffer_idx,
-            byte_length: USize64::from(tex_byte_length),
-            byte_offset: Some(USize64::from(tex_byte_offset)),
-            byte_stride: None,
+        let accessor = root.push(gltf_json::Accessor {
+            buffer_view: Some(view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(mesh.vertex_count()),
+            component_type: Checked::Valid(GenericComponentType(layout.component_type)),
+            type_: Checked::Valid(AccessorType::Scalar),
+            min: None,
+            max: None,
             name: None,
-            target: None, // no target for image buffer views
+            normalized: false,
+            sparse: None,
             extensions: Default::default(),
             extras: Default::default(),
         });
+        attributes.insert(
+            Checked::Valid(Semantic::Extras("FEATURE_ID_0".into())),
+            accessor,
+        );
+    }
 
-        let image_idx = root.push(gltf_json::Image {
-            buffer_view: Some(tex_view),
-            mime_type: Some(gltf_json::image::MimeType(tex.mime_type.clone())),
-            uri: None,
-            name: None,
-            extensions: Default::default(),
-            extras: Default::default(),
-        });
+    // --- Indices (u16 when vertex_count <= 65535, else u32), one accessor per material group ---
+    let use_u16_indices = mesh.vertex_count() <= 65535;
+    let idx_component_type = if use_u16_indices {
+        ComponentType::U16
+    } else {
+        ComponentType::U32
+    };
 
-        let sampler_idx = root.push(gltf_json::texture::Sampler {
-            mag_filter: Some(Checked::Valid(gltf_json::texture::MagFilter::Linear)),
-            min_filter: Some(Checked::Valid(gltf_json::texture::MinFilter::LinearMipmapLinear)),
-            wrap_s: Checked::Valid(gltf_json::texture::WrappingMode::ClampToEdge),
-            wrap_t: Checked::Valid(gltf_json::texture::WrappingMode::ClampToEdge),
-            name: None,
-            extensions: Default::default(),
-            extras: Default::default(),
-        });
+    let groups: Vec<_> = mesh
+        .material_ranges()
+        .into_iter()
+        .filter(|group| !group.indices.is_empty())
+        .collect();
+    let mut idx_accessors = Vec::with_capacity(groups.len());
+    for group in &groups {
+        let indices = &mesh.indices[group.indices.clone()];
+        let idx_encoded = if compress {
+            meshopt::encode_index_buffer(indices, mesh.vertex_count()).ok()
+        } else {
+            None
+        };
+        let idx_view = write_index_view(
+            &mut root,
+            &mut bin_data,
+            buffer_idx,
+            indices,
+            mesh.vertex_count(),
+            use_u16_indices,
+            idx_encoded,
+        );
 
-        let tex_idx = root.push(gltf_json::Texture {
-            sampler: Some(sampler_idx),
-            source: image_idx,
+        idx_accessors.push(root.push(gltf_json::Accessor {
+            buffer_view: Some(idx_view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(indices.len()),
+            component_type: Checked::Valid(GenericComponentType(idx_component_type)),
+            type_: Checked::Valid(AccessorType::Scalar),
+            min: None,
+            max: None,
             name: None,
+            normalized: false,
+            sparse: None,
             extensions: Default::default(),
             extras: Default::default(),
-        });
+        }));
+    }
 
-        Some(tex_idx)
-    } else {
-        None
-    };
+    // --- Textures (optional) ---
+    let mut push_image = |image| push_texture(&mut root, &mut bin_data, buffer_idx, image);
+    let pages = std::iter::once((0, images)).chain(images.pages.iter().copied());
+    let page_textures: Vec<(usize, MaterialTextures)> = pages
+        .map(|(first_index, images)| {
+            let textures = MaterialTextures {
+                base_color: images.base_color.map(&mut push_image),
+                metallic_roughness: images.metallic_roughness.map(&mut push_image),
+                normal: images.normal.map(&mut push_image),
+            };
+            (first_index, textures)
+        })
+        .collect();
 
-    // --- Material (optional) ---
-    let material_index = build_material(&mut root, mesh.material_index, materials, texture_index);
+    // --- Feature names (property table of EXT_structural_metadata) ---
+    let feature_extension = write_features.then(|| {
+        let (values, offsets) = string_property(&materials.features);
+        let values_view = write_plain_view(&mut root, &mut bin_data, buffer_idx, &values);
+        let offsets_view = write_plain_view(
+            &mut root,
+            &mut bin_data,
+            buffer_idx,
+            bytemuck::cast_slice(&offsets),
+        );
+        let mut ext = gltf_json::extensions::root::Root::default();
+        ext.others.insert(
+            "EXT_structural_metadata".into(),
+            serde_json::json!({
+                "schema": feature_schema(),
+                "propertyTables": [{
+                    "class": FEATURE_CLASS,
+                    "count": materials.features.len(),
+                    "properties": {
+                        "name": {
+                            "values": values_view.value(),
+                            "stringOffsets": offsets_view.value(),
+                            "stringOffsetType": "UINT32"
+                        }
+                    }
+                }]
+            }),
+        );
+        root.extensions = Some(ext);
+
+        // All primitives share the ID attribute and the property table
+        let mut distinct = mesh.feature_ids.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        let mut ext = gltf_json::extensions::mesh::Primitive::default();
+        ext.others.insert(
+            "EXT_mesh_features".into(),
+            serde_json::json!({
+                "featureIds": [{
+                    "featureCount": distinct.len(),
+                    "attribute": 0,
+                    "propertyTable": 0
+                }]
+            }),
+        );
+        ext
+    });
 
-    // --- Mesh ---
-    let primitive = Primitive {
-        attributes,
-        indices: Some(idx_accessor),
-        material: material_index,
-        mode: Checked::Valid(Mode::Triangles),
-        targets: None,
-        extensions: Default::default(),
-        extras: Default::default(),
-    };
+    // --- Mesh (one primitive and optional material per group) ---
+    // Groups with the same material and textures share one glTF material
+    let mut built_materials = HashMap::new();
+    let primitives = groups
+        .iter()
+        .zip(idx_accessors)
+        .map(|(group, idx_accessor)| {
+            let textures = page_textures
+                .iter()
+                .rfind(|(first_index, _)| *first_index <= group.indices.start)
+                .map_or_else(MaterialTextures::default, |&(_, textures)| textures);
+            let material = *built_materials
+                .entry((group.material_index, textures))
+                .or_insert_with(|| {
+                    build_material(&mut root, group.material_index, materials, textures)
+                });
+            Primitive {
+                attributes: attributes.clone(),
+                indices: Some(idx_accessor),
+                material,
+                mode: Checked::Valid(Mode::Triangles),
+                targets: None,
+                extensions: feature_extension.clone(),
+                extras: Default::default(),
+            }
+        })
+        .collect();
 
     let mesh_idx = root.push(gltf_json::Mesh {
-        primitives: vec![primitive],
+        primitives,
         weights: None,
         name: None,
         extensions: Default::default(),
         extras: Default::default(),
     });
 
-    // --- Node ---
+    // --- Node (carries the position dequantization transform) ---
     let node_idx = root.push(gltf_json::Node {
         mesh: Some(mesh_idx),
+        translation: pos_grid.as_ref().map(|grid| grid.offset),
+        scale: pos_grid.as_ref().map(|grid| [grid.scale; 3]),
         ..Default::default()
     });
 
@@ -344,14 +730,31 @@ fn write_glb_impl(
         root.extensions_used.push(ext.clone());
         root.extensions_required.push(ext);
     }
+    if quantize || oct_normal_bits.is_some() {
+        let ext = "KHR_mesh_quantization".to_string();
+        root.extensions_used.push(ext.clone());
+        root.extensions_required.push(ext);
+    }
 
-    // KHR_texture_basisu when atlas texture is KTX2/Basis
-    if let Some(tex) = atlas_texture {
-        if tex.mime_type == "image/ktx2" {
-            let ext = "KHR_texture_basisu".to_string();
-            root.extensions_used.push(ext.clone());
-            root.extensions_required.push(ext);
-        }
+    let emissive_strength = |m: &gltf_json::Material| {
+        m.extensions
+            .as_ref()
+            .is_some_and(|ext| ext.others.contains_key(EMISSIVE_STRENGTH))
+    };
+    if root.materials.iter().any(emissive_strength) {
+        root.extensions_used.push(EMISSIVE_STRENGTH.into());
+    }
+
+    if write_features {
+        root.extensions_used.push("EXT_mesh_features".into());
+        root.extensions_used.push("EXT_structural_metadata".into());
+    }
+
+    // KHR_texture_basisu when atlas textures are KTX2/Basis
+    if images.iter().any(|image| image.mime_type() == "image/ktx2") {
+        let ext = "KHR_texture_basisu".to_string();
+        root.extensions_used.push(ext.clone());
+        root.extensions_required.push(ext);
     }
 
     // --- Buffer (the one buffer holding all data) ---
@@ -368,8 +771,12 @@ fn write_glb_impl(
         extras: Default::default(),
     });
 
-    // --- Assemble GLB ---
-    let json_string = gltf_json::serialize::to_string(&root).expect("gltf-json serialization");
+    assemble_glb(&root, bin_data)
+}
+
+/// Serialize `root` and its 4-byte aligned binary buffer into a GLB.
+fn assemble_glb(root: &gltf_json::Root, bin_data: Vec<u8>) -> Vec<u8> {
+    let json_string = gltf_json::serialize::to_string(root).expect("gltf-json serialization");
     let mut json_bytes = json_string.into_bytes();
     // Pad JSON to 4-byte alignment with spaces (per GLB spec)
     while json_bytes.len() % 4 != 0 {
@@ -386,7 +793,290 @@ fn write_glb_impl(
         bin: Some(Cow::Owned(bin_data)),
     };
 
-    glb.to_vec().expect("GLB serialization")
+    glb.to_vec().expect("GLB serialization")
+}
+
+/// Draw the mesh of a GLB from [`write_glb`] and friends once per
+/// column-major matrix in `instances`, through EXT_mesh_gpu_instancing.
+///
+/// The matrices must be translation, rotation and positive scale. The
+/// node's own transform (the dequantization grid of quantized tiles) is
+/// folded into every instance, since instance transforms apply before it.
+/// Empty GLBs are returned unchanged.
+pub fn add_gpu_instancing(glb: &[u8], instances: &[[f64; 16]]) -> Vec<u8> {
+    let parsed = Glb::from_slice(glb).expect("GLB written by write_glb");
+    let mut root: gltf_json::Root =
+        serde_json::from_slice(&parsed.json).expect("glTF JSON written by write_glb");
+    let (Some(node), Some(bin_data)) = (root.nodes.first_mut(), parsed.bin) else {
+        return glb.to_vec();
+    };
+
+    let local = DMat4::from_scale_rotation_translation(
+        DVec3::from_array(node.scale.unwrap_or([1.0; 3]).map(f64::from)),
+        DQuat::from_array(
+            node.rotation
+                .map_or([0.0, 0.0, 0.0, 1.0], |q| q.0)
+                .map(f64::from),
+        ),
+        DVec3::from_array(node.translation.unwrap_or([0.0; 3]).map(f64::from)),
+    );
+    node.translation = None;
+    node.rotation = None;
+    node.scale = None;
+
+    let mut translations = Vec::with_capacity(instances.len() * 3);
+    let mut rotations = Vec::with_capacity(instances.len() * 4);
+    let mut scales = Vec::with_capacity(instances.len() * 3);
+    for matrix in instances {
+        let (scale, rotation, translation) =
+            (DMat4::from_cols_array(matrix) * local).to_scale_rotation_translation();
+        translations.extend(translation.to_array().map(|v| v as f32));
+        rotations.extend(rotation.normalize().to_array().map(|v| v as f32));
+        scales.extend(scale.to_array().map(|v| v as f32));
+    }
+
+    let mut bin_data = bin_data.into_owned();
+    let buffer_idx = Index::new(0);
+    let mut instance_accessor = |data: &[f32], type_: AccessorType| {
+        let view = write_plain_view(
+            &mut root,
+            &mut bin_data,
+            buffer_idx,
+            bytemuck::cast_slice(data),
+        );
+        root.push(gltf_json::Accessor {
+            buffer_view: Some(view),
+            byte_offset: Some(USize64(0)),
+            count: USize64::from(instances.len()),
+            component_type: Checked::Valid(GenericComponentType(ComponentType::F32)),
+            type_: Checked::Valid(type_),
+            min: None,
+            max: None,
+            name: None,
+            normalized: false,
+            sparse: None,
+            extensions: Default::default(),
+            extras: Default::default(),
+        })
+    };
+    let translation = instance_accessor(&translations, AccessorType::Vec3);
+    let rotation = instance_accessor(&rotations, AccessorType::Vec4);
+    let scale = instance_accessor(&scales, AccessorType::Vec3);
+
+    let mut ext = gltf_json::extensions::scene::Node::default();
+    ext.others.insert(
+        "EXT_mesh_gpu_instancing".into(),
+        serde_json::json!({
+            "attributes": {
+                "TRANSLATION": translation.value(),
+                "ROTATION": rotation.value(),
+                "SCALE": scale.value()
+            }
+        }),
+    );
+    root.nodes[0].extensions = Some(ext);
+    let ext = "EXT_mesh_gpu_instancing".to_string();
+    root.extensions_used.push(ext.clone());
+    root.extensions_required.push(ext);
+
+    while bin_data.len() % 4 != 0 {
+        bin_data.push(0);
+    }
+    root.buffers[0].byte_length = USize64::from(bin_data.len());
+    assemble_glb(&root, bin_data)
+}
+
+/// Add a `_BATCHID` vertex attribute holding `batch_ids` (one per vertex)
+/// to every primitive of a GLB from [`write_glb`] and friends, for
+/// [`write_b3dm`]. Empty GLBs are returned unchanged.
+pub fn add_batch_ids(glb: &[u8], batch_ids: &[u32]) -> Vec<u8> {
+    let parsed = Glb::from_slice(glb).expect("GLB written by write_glb");
+    let mut root: gltf_json::Root =
+        serde_json::from_slice(&parsed.json).expect("glTF JSON written by write_glb");
+    let Some(bin_data) = parsed.bin.filter(|_| !root.meshes.is_empty()) else {
+        return glb.to_vec();
+    };
+
+    let ids: Vec<f32> = batch_ids.iter().map(|&id| id as f32).collect();
+    let mut bin_data = bin_data.into_owned();
+    let view = write_plain_view(
+        &mut root,
+        &mut bin_data,
+        Index::new(0),
+        bytemuck::cast_slice(&ids),
+    );
+    let accessor = root.push(gltf_json::Accessor {
+        buffer_view: Some(view),
+        byte_offset: Some(USize64(0)),
+        count: USize64::from(ids.len()),
+        component_type: Checked::Valid(GenericComponentType(ComponentType::F32)),
+        type_: Checked::Valid(AccessorType::Scalar),
+        min: None,
+        max: None,
+        name: None,
+        normalized: false,
+        sparse: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+    for primitive in root.meshes.iter_mut().flat_map(|m| m.primitives.iter_mut()) {
+        primitive
+            .attributes
+            .insert(Checked::Valid(Semantic::Extras("BATCHID".into())), accessor);
+    }
+
+    while bin_data.len() % 4 != 0 {
+        bin_data.push(0);
+    }
+    root.buffers[0].byte_length = USize64::from(bin_data.len());
+    assemble_glb(&root, bin_data)
+}
+
+/// Length of the b3dm header: magic, version, byte length and the four
+/// feature/batch table section lengths.
+const B3DM_HEADER_LEN: usize = 28;
+
+/// Wrap a GLB into a 3D Tiles 1.0 Batched 3D Model with `batch_length`
+/// batches and `batch_table` (omitted when `None`) describing them.
+///
+/// Both JSON tables are padded with spaces so the GLB starts 8-byte aligned.
+pub fn write_b3dm(
+    glb: &[u8],
+    batch_length: usize,
+    batch_table: Option<&serde_json::Value>,
+) -> Vec<u8> {
+    let padded_json = |value: &serde_json::Value, offset: usize| {
+        let mut bytes = serde_json::to_vec(value).expect("b3dm table serialization");
+        while (offset + bytes.len()) % 8 != 0 {
+            bytes.push(b' ');
+        }
+        bytes
+    };
+    let feature_table = padded_json(
+        &serde_json::json!({ "BATCH_LENGTH": batch_length }),
+        B3DM_HEADER_LEN,
+    );
+    let batch_table = batch_table
+        .map(|table| padded_json(table, B3DM_HEADER_LEN + feature_table.len()))
+        .unwrap_or_default();
+
+    let length = B3DM_HEADER_LEN + feature_table.len() + batch_table.len() + glb.len();
+    let mut b3dm = Vec::with_capacity(length);
+    b3dm.extend_from_slice(b"b3dm");
+    let lengths = [feature_table.len(), 0, batch_table.len(), 0];
+    for value in [1, length].into_iter().chain(lengths) {
+        b3dm.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+    b3dm.extend_from_slice(&feature_table);
+    b3dm.extend_from_slice(&batch_table);
+    b3dm.extend_from_slice(glb);
+    b3dm
+}
+
+/// Sections of a Batched 3D Model read by [`read_b3dm`].
+pub struct B3dm<'a> {
+    pub feature_table: serde_json::Value,
+    /// `None` when the tile has no batch table.
+    pub batch_table: Option<serde_json::Value>,
+    pub glb: &'a [u8],
+}
+
+/// Split a b3dm into its JSON tables and embedded GLB, or `None` when the
+/// header or tables are malformed. Binary table bodies are skipped.
+pub fn read_b3dm(data: &[u8]) -> Option<B3dm<'_>> {
+    let header = data.get(..B3DM_HEADER_LEN)?;
+    let field = |i: usize| {
+        let bytes = &header[4 + i * 4..8 + i * 4];
+        u32::from_le_bytes(bytes.try_into().expect("4-byte field")) as usize
+    };
+    if &header[..4] != b"b3dm" || field(0) != 1 || field(1) != data.len() {
+        return None;
+    }
+
+    let mut offset = B3DM_HEADER_LEN;
+    let mut section = |len: usize| {
+        let bytes = data.get(offset..offset.checked_add(len)?)?;
+        offset += len;
+        Some(bytes)
+    };
+    let feature_table = serde_json::from_slice(section(field(2))?).ok()?;
+    section(field(3))?;
+    let batch_table = match section(field(4))? {
+        [] => None,
+        json => Some(serde_json::from_slice(json).ok()?),
+    };
+    section(field(5))?;
+
+    Some(B3dm {
+        feature_table,
+        batch_table,
+        glb: &data[offset..],
+    })
+}
+
+/// Metadata class of the features written with [`feature_schema`].
+const FEATURE_CLASS: &str = "object";
+
+/// EXT_structural_metadata schema of the per-object feature table: one
+/// class whose only property is the source object's name.
+///
+/// Tiles embed it in their property table; 3D Tiles 1.1 tilesets repeat it
+/// as the top-level `schema`.
+pub fn feature_schema() -> serde_json::Value {
+    serde_json::json!({
+        "id": "photo_tiler",
+        "classes": {
+            FEATURE_CLASS: {
+                "name": "Source object",
+                "properties": {
+                    "name": {
+                        "description": "Object or mesh name in the source model",
+                        "type": "STRING"
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Concatenate strings into a STRING property's UTF-8 values and its
+/// `count + 1` UINT32 offsets.
+fn string_property(strings: &[String]) -> (Vec<u8>, Vec<u32>) {
+    let mut values = Vec::new();
+    let mut offsets = Vec::with_capacity(strings.len() + 1);
+    offsets.push(0);
+    for s in strings {
+        values.extend_from_slice(s.as_bytes());
+        offsets.push(values.len() as u32);
+    }
+    (values, offsets)
+}
+
+/// Write a buffer view without stride or target, for non-vertex data.
+/// Starts on an 8-byte boundary, as EXT_structural_metadata requires for
+/// property tables.
+fn write_plain_view(
+    root: &mut gltf_json::Root,
+    bin_data: &mut Vec<u8>,
+    buffer_idx: Index<gltf_json::Buffer>,
+    bytes: &[u8],
+) -> Index<gltf_json::buffer::View> {
+    while bin_data.len() % 8 != 0 {
+        bin_data.push(0);
+    }
+    let byte_offset = bin_data.len();
+    bin_data.extend_from_slice(bytes);
+
+    root.push(gltf_json::buffer::View {
+        buffer: buffer_idx,
+        byte_length: USize64::from(bytes.len()),
+        byte_offset: Some(USize64::from(byte_offset)),
+        byte_stride: None,
+        name: None,
+        target: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    })
 }
 
 /// Encode a flat f32 array as [f32; 3] vertex data using meshopt.
@@ -401,12 +1091,37 @@ fn encode_f32x2(data: &[f32]) -> Option<Vec<u8>> {
     meshopt::encode_vertex_buffer(vertices).ok()
 }
 
+/// Encode integer vertex data of 4 or 8 bytes per vertex using meshopt.
+fn meshopt_encode_bytes(layout: &AttributeLayout<'_>) -> Option<Vec<u8>> {
+    match layout.stride {
+        4 => meshopt::encode_vertex_buffer::<[u8; 4]>(bytemuck::cast_slice(&layout.bytes)).ok(),
+        8 => meshopt::encode_vertex_buffer::<[u8; 8]>(bytemuck::cast_slice(&layout.bytes)).ok(),
+        _ => None,
+    }
+}
+
 /// Encode a flat u8 array as [u8; 4] vertex data using meshopt.
 fn encode_u8x4(data: &[u8]) -> Option<Vec<u8>> {
     let vertices: &[[u8; 4]] = bytemuck::cast_slice(data);
     meshopt::encode_vertex_buffer(vertices).ok()
 }
 
+/// Set the EXT_meshopt_compression `filter` of a compressed buffer view,
+/// which viewers apply after decoding.
+fn set_meshopt_filter(
+    root: &mut gltf_json::Root,
+    view: Index<gltf_json::buffer::View>,
+    filter: &str,
+) {
+    let ext = root.buffer_views[view.value()]
+        .extensions
+        .as_mut()
+        .and_then(|e| e.others.get_mut("EXT_meshopt_compression"));
+    if let Some(ext) = ext {
+        ext["filter"] = serde_json::json!(filter);
+    }
+}
+
 /// Write a vertex attribute buffer view, optionally with meshopt compression.
 ///
 /// Returns the buffer view index. When compressed, the buffer view has the
@@ -466,7 +1181,7 @@ fn write_vertex_attribute_view(
             buffer: buffer_idx,
             byte_length: USize64::from(byte_length),
             byte_offset: Some(USize64::from(byte_offset)),
-            byte_stride: None,
+            byte_stride: Some(gltf_json::buffer::Stride(stride)),
             name: None,
             target: Some(Checked::Valid(Target::ArrayBuffer)),
             extensions: Default::default(),
@@ -590,50 +1305,296 @@ fn write_empty_glb() -> Vec<u8> {
     glb.to_vec().expect("GLB serialization")
 }
 
+/// Store `image` (in `bin_data` when embedded) and add an image, sampler
+/// and texture referencing it.
+fn push_texture(
+    root: &mut gltf_json::Root,
+    bin_data: &mut Vec<u8>,
+    buffer_idx: Index<gltf_json::Buffer>,
+    image: ImageSource<'_>,
+) -> Index<gltf_json::Texture> {
+    let (buffer_view, uri) = match image {
+        ImageSource::Embedded(tex) => {
+            // Pad to 4-byte alignment before texture data
+            while bin_data.len() % 4 != 0 {
+                bin_data.push(0);
+            }
+            let tex_byte_offset = bin_data.len();
+            bin_data.extend_from_slice(&tex.data);
+            let tex_byte_length = tex.data.len();
+
+            let tex_view = root.push(gltf_json::buffer::View {
+                buffer: buffer_idx,
+                byte_length: USize64::from(tex_byte_length),
+                byte_offset: Some(USize64::from(tex_byte_offset)),
+                byte_stride: None,
+                name: None,
+                target: None, // no target for image buffer views
+                extensions: Default::default(),
+                extras: Default::default(),
+            });
+            (Some(tex_view), None)
+        }
+        ImageSource::External { uri, .. } => (None, Some(uri.to_string())),
+    };
+
+    let image_idx = root.push(gltf_json::Image {
+        buffer_view,
+        mime_type: Some(gltf_json::image::MimeType(image.mime_type().to_string())),
+        uri,
+        name: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+
+    let [wrap_s, wrap_t] = image.wrap().map(wrapping_mode);
+    let sampler_idx = root.push(gltf_json::texture::Sampler {
+        mag_filter: Some(Checked::Valid(gltf_json::texture::MagFilter::Linear)),
+        min_filter: Some(Checked::Valid(
+            gltf_json::texture::MinFilter::LinearMipmapLinear,
+        )),
+        wrap_s: Checked::Valid(wrap_s),
+        wrap_t: Checked::Valid(wrap_t),
+        name: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    });
+
+    root.push(gltf_json::Texture {
+        sampler: Some(sampler_idx),
+        source: image_idx,
+        name: None,
+        extensions: Default::default(),
+        extras: Default::default(),
+    })
+}
+
 /// Build a gltf-json Material if the mesh references one in the library.
 fn build_material(
     root: &mut gltf_json::Root,
     material_index: Option<usize>,
     materials: &MaterialLibrary,
-    texture_index: Option<Index<gltf_json::Texture>>,
+    textures: MaterialTextures,
 ) -> Option<Index<gltf_json::Material>> {
     let mat_idx = material_index?;
     let mat = materials.materials.get(mat_idx)?;
 
-    let base_color_texture = texture_index.map(|idx| gltf_json::texture::Info {
+    let info = |idx| gltf_json::texture::Info {
         index: idx,
         tex_coord: 0,
         extensions: Default::default(),
         extras: Default::default(),
-    });
+    };
+    // Only materials that had a texture or map get its atlas; a tile's
+    // untextured materials share the GLB with the textured ones
+    let base_color_texture = textures
+        .base_color
+        .filter(|_| mat.base_color_texture.is_some())
+        .map(info);
+    let metallic_roughness_texture = textures
+        .metallic_roughness
+        .filter(|_| mat.metallic_roughness_texture.is_some())
+        .map(info);
+    let normal_texture = textures
+        .normal
+        .filter(|_| mat.normal_texture.is_some())
+        .map(|index| gltf_json::material::NormalTexture {
+            index,
+            scale: mat.normal_scale,
+            tex_coord: 0,
+            extensions: Default::default(),
+            extras: Default::default(),
+        });
 
     let pbr = gltf_json::material::PbrMetallicRoughness {
         base_color_factor: gltf_json::material::PbrBaseColorFactor(mat.base_color),
         metallic_factor: gltf_json::material::StrengthFactor(mat.metallic),
         roughness_factor: gltf_json::material::StrengthFactor(mat.roughness),
         base_color_texture,
-        metallic_roughness_texture: None,
+        metallic_roughness_texture,
         extensions: Default::default(),
         extras: Default::default(),
     };
 
+    let (alpha_mode, alpha_cutoff) = match mat.alpha_mode {
+        AlphaMode::Opaque => (gltf_json::material::AlphaMode::Opaque, None),
+        AlphaMode::Mask { cutoff } => (
+            gltf_json::material::AlphaMode::Mask,
+            Some(gltf_json::material::AlphaCutoff(cutoff)),
+        ),
+        AlphaMode::Blend => (gltf_json::material::AlphaMode::Blend, None),
+    };
+
+    // emissiveFactor is clamped to [0, 1]; brighter emission moves its
+    // magnitude into KHR_materials_emissive_strength
+    let strength = mat.emissive.into_iter().fold(1.0f32, f32::max);
+    let extensions = (strength > 1.0).then(|| {
+        let mut ext = gltf_json::extensions::material::Material::default();
+        ext.others.insert(
+            EMISSIVE_STRENGTH.into(),
+            serde_json::json!({ "emissiveStrength": strength }),
+        );
+        ext
+    });
+
     let gltf_mat = gltf_json::Material {
         pbr_metallic_roughness: pbr,
-        alpha_mode: Checked::Valid(gltf_json::material::AlphaMode::Opaque),
-        alpha_cutoff: None,
+        alpha_mode: Checked::Valid(alpha_mode),
+        alpha_cutoff,
         double_sided: false,
-        normal_texture: None,
+        normal_texture,
         occlusion_texture: None,
         emissive_texture: None,
-        emissive_factor: gltf_json::material::EmissiveFactor([0.0, 0.0, 0.0]),
-        name: None,
-        extensions: Default::default(),
+        emissive_factor: gltf_json::material::EmissiveFactor(mat.emissive.map(|c| c / strength)),
+        name: (!mat.name.is_empty()).then(|| mat.name.clone()),
+        extensions,
         extras: Default::default(),
     };
 
     Some(root.push(gltf_mat))
 }
 
+/// Vertex attribute bytes together with their accessor layout.
+struct AttributeLayout<'a> {
+    bytes: Cow<'a, [u8]>,
+    stride: usize,
+    component_type: ComponentType,
+    normalized: bool,
+}
+
+impl<'a> AttributeLayout<'a> {
+    /// Tightly packed f32 data with `components` values per vertex.
+    fn float(data: &'a [f32], components: usize) -> Self {
+        Self {
+            bytes: Cow::Borrowed(bytemuck::cast_slice(data)),
+            stride: components * 4,
+            component_type: ComponentType::F32,
+            normalized: false,
+        }
+    }
+}
+
+/// Uniform int16 grid for quantized positions: `p = offset + q * scale`.
+///
+/// A single scale for all axes keeps the node transform a similarity, so
+/// viewers don't skew the (separately quantized) normals.
+struct PositionGrid {
+    offset: [f32; 3],
+    scale: f32,
+}
+
+impl PositionGrid {
+    fn new(positions: &[f32]) -> Self {
+        let (min, max) = compute_position_bounds(positions);
+        let offset = std::array::from_fn(|i| (min[i] + max[i]) * 0.5);
+        let extent = (0..3).map(|i| max[i] - min[i]).fold(0.0f32, f32::max);
+        let scale = if extent > 0.0 {
+            extent / (2.0 * i16::MAX as f32)
+        } else {
+            1.0
+        };
+        Self { offset, scale }
+    }
+
+    /// Quantize to SHORT VEC3, padded to 8 bytes for 4-byte alignment.
+    fn quantize(&self, positions: &[f32]) -> AttributeLayout<'static> {
+        let quantized: Vec<[i16; 4]> = positions
+            .chunks_exact(3)
+            .map(|p| {
+                let q = |i: usize| {
+                    ((p[i] - self.offset[i]) / self.scale)
+                        .round()
+                        .clamp(-(i16::MAX as f32), i16::MAX as f32) as i16
+                };
+                [q(0), q(1), q(2), 0]
+            })
+            .collect();
+        AttributeLayout {
+            bytes: Cow::Owned(bytemuck::cast_slice(&quantized).to_vec()),
+            stride: 8,
+            component_type: ComponentType::I16,
+            normalized: false,
+        }
+    }
+}
+
+/// Quantize unit normals per component to normalized BYTE VEC3, padded to
+/// 4 bytes.
+///
+/// KHR_mesh_quantization only allows VEC3 normals, so octahedral encoding
+/// (which needs the meshopt filter to decode) is not an option here.
+fn quantize_normals(normals: &[f32]) -> AttributeLayout<'static> {
+    let bytes = normals
+        .chunks_exact(3)
+        .flat_map(|n| {
+            let q = |c: f32| (c * i8::MAX as f32).round().clamp(-127.0, 127.0) as i8 as u8;
+            [q(n[0]), q(n[1]), q(n[2]), 0]
+        })
+        .collect();
+    AttributeLayout {
+        bytes: Cow::Owned(bytes),
+        stride: 4,
+        component_type: ComponentType::I8,
+        normalized: true,
+    }
+}
+
+/// Octahedral-encode unit normals for the meshopt OCTAHEDRAL filter, as
+/// normalized BYTE (up to 8 bits) or SHORT components padded to 4 per vertex.
+///
+/// The stored layout is what viewers see after decoding: a normalized
+/// integer VEC3, so only meshopt-compressed views can use it.
+fn oct_encode_normals(normals: &[f32], bits: u8) -> AttributeLayout<'static> {
+    let (stride, component_type) = if bits <= 8 {
+        (4, ComponentType::I8)
+    } else {
+        (8, ComponentType::I16)
+    };
+    let vertex_count = normals.len() / 3;
+    let padded: Vec<f32> = normals
+        .chunks_exact(3)
+        .flat_map(|n| [n[0], n[1], n[2], 0.0])
+        .collect();
+    let mut bytes = vec![0u8; vertex_count * stride];
+    // SAFETY: `bytes` holds `vertex_count` vertices of `stride` bytes and
+    // `padded` 4 floats per vertex, as meshopt_encodeFilterOct requires
+    unsafe {
+        meshopt::ffi::meshopt_encodeFilterOct(
+            bytes.as_mut_ptr().cast(),
+            vertex_count,
+            stride,
+            i32::from(bits.clamp(1, 16)),
+            padded.as_ptr(),
+        );
+    }
+    AttributeLayout {
+        bytes: Cow::Owned(bytes),
+        stride,
+        component_type,
+        normalized: true,
+    }
+}
+
+/// Quantize UVs to normalized UNSIGNED_SHORT VEC2.
+///
+/// Returns `None` when any coordinate lies outside [0, 1] (e.g. repeating
+/// textures), which normalized integers cannot represent.
+fn quantize_uvs(uvs: &[f32]) -> Option<AttributeLayout<'static>> {
+    if uvs.iter().any(|&c| !(0.0..=1.0).contains(&c)) {
+        return None;
+    }
+    let quantized: Vec<u16> = uvs
+        .iter()
+        .map(|&c| (c * u16::MAX as f32).round() as u16)
+        .collect();
+    Some(AttributeLayout {
+        bytes: Cow::Owned(bytemuck::cast_slice(&quantized).to_vec()),
+        stride: 4,
+        component_type: ComponentType::U16,
+        normalized: true,
+    })
+}
+
 /// Compute min/max for a flat positions array (stride 3).
 fn compute_position_bounds(positions: &[f32]) -> ([f32; 3], [f32; 3]) {
     let mut min = [f32::INFINITY; 3];
@@ -652,16 +1613,21 @@ fn compute_position_bounds(positions: &[f32]) -> ([f32; 3], [f32; 3]) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::PBRMaterial;
+    use crate::tiling::test_textures::png_texture;
+    use crate::types::{MaterialGroup, PBRMaterial};
 
     fn make_triangle() -> IndexedMesh {
         IndexedMesh {
             positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
             normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
             uvs: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2],
             material_index: None,
+            name: None,
+            material_groups: Vec::new(),
         }
     }
 
@@ -670,13 +1636,49 @@ mod tests {
             positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
             normals: vec![],
             uvs: vec![],
+            uvs1: Vec::new(),
             colors: vec![
                 1.0, 0.0, 0.0, 1.0, // red
                 0.0, 1.0, 0.0, 1.0, // green
                 0.0, 0.0, 1.0, 1.0, // blue
             ],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2],
             material_index: None,
+            name: None,
+            material_groups: Vec::new(),
+        }
+    }
+
+    /// Unit grid of `n x n` quads with +Z normals and [0, 1] UVs.
+    fn make_grid(n: usize) -> IndexedMesh {
+        let verts_per_side = n + 1;
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        for y in 0..verts_per_side {
+            for x in 0..verts_per_side {
+                let fx = x as f32 / n as f32;
+                let fy = y as f32 / n as f32;
+                positions.extend_from_slice(&[fx, fy, 0.0]);
+                uvs.extend_from_slice(&[fx, fy]);
+            }
+        }
+
+        let mut indices = Vec::new();
+        for y in 0..n {
+            for x in 0..n {
+                let tl = (y * verts_per_side + x) as u32;
+                let bl = tl + verts_per_side as u32;
+                indices.extend_from_slice(&[tl, bl, tl + 1, tl + 1, bl, bl + 1]);
+            }
+        }
+
+        IndexedMesh {
+            normals: [0.0, 0.0, 1.0].repeat(positions.len() / 3),
+            positions,
+            uvs,
+            indices,
+            ..Default::default()
         }
     }
 
@@ -839,7 +1841,7 @@ mod tests {
             base_color: [0.8, 0.2, 0.1, 1.0],
             metallic: 0.5,
             roughness: 0.7,
-            base_color_texture: None,
+            ..Default::default()
         });
 
         let bytes = write_glb(&mesh, &materials, None);
@@ -854,6 +1856,130 @@ mod tests {
         assert!((pbr.roughness_factor() - 0.7).abs() < 1e-3);
     }
 
+    #[test]
+    fn glb_moves_bright_emission_into_emissive_strength() {
+        let mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            indices: vec![0, 1, 2],
+            material_index: Some(0),
+            ..Default::default()
+        };
+        let mut materials = MaterialLibrary::default();
+        materials.materials.push(PBRMaterial {
+            emissive: [5.0, 2.5, 0.0],
+            ..Default::default()
+        });
+
+        let bytes = write_glb(&mesh, &materials, None);
+
+        let (doc, _buffers, _images) = gltf::import_slice(&bytes).unwrap();
+        assert!(doc.extensions_used().any(|ext| ext == EMISSIVE_STRENGTH));
+        let mat = doc.materials().next().expect("should have material");
+        assert_eq!(mat.emissive_factor(), [1.0, 0.5, 0.0]);
+        let ext = mat.extensions().unwrap().get(EMISSIVE_STRENGTH).unwrap();
+        assert_eq!(ext["emissiveStrength"], 5.0);
+
+        // Emission within [0, 1] stays in emissiveFactor alone
+        materials.materials[0].emissive = [0.5; 3];
+        let bytes = write_glb(&mesh, &materials, None);
+        let (doc, _buffers, _images) = gltf::import_slice(&bytes).unwrap();
+        assert_eq!(doc.extensions_used().count(), 0);
+        let mat = doc.materials().next().unwrap();
+        assert_eq!(mat.emissive_factor(), [0.5; 3]);
+        assert!(mat.extensions().is_none());
+    }
+
+    #[test]
+    fn glb_names_materials_and_shares_them_between_primitives() {
+        let mut mesh = make_grid(2);
+        let len = mesh.indices.len();
+        let groups = [(0..9, Some(0)), (9..18, Some(1)), (18..len, Some(0))];
+        mesh.material_groups = groups
+            .into_iter()
+            .map(|(indices, material_index)| MaterialGroup {
+                indices,
+                material_index,
+            })
+            .collect();
+        let mut materials = MaterialLibrary::default();
+        for name in ["stone", "brick"] {
+            materials.materials.push(PBRMaterial {
+                name: name.into(),
+                ..Default::default()
+            });
+        }
+
+        let bytes = write_glb(&mesh, &materials, None);
+        let glb = Glb::from_slice(&bytes).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&glb.json).unwrap();
+        let names: Vec<_> = json["materials"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["stone", "brick"]);
+        let primitives = json["meshes"][0]["primitives"].as_array().unwrap();
+        assert_eq!(primitives.len(), 3);
+        assert_eq!(primitives[0]["material"], 0);
+        assert_eq!(primitives[1]["material"], 1);
+        assert_eq!(primitives[2]["material"], 0);
+    }
+
+    #[test]
+    fn glb_one_primitive_per_material_group() {
+        let mut mesh = make_grid(2);
+        let half = mesh.indices.len() / 2;
+        mesh.material_groups = vec![
+            MaterialGroup {
+                indices: 0..half,
+                material_index: Some(0),
+            },
+            MaterialGroup {
+                indices: half..mesh.indices.len(),
+                material_index: Some(1),
+            },
+        ];
+        let mut materials = MaterialLibrary::default();
+        for (name, red) in [("stone", 0.2), ("brick", 0.9)] {
+            materials.materials.push(PBRMaterial {
+                name: name.into(),
+                base_color: [red, 0.1, 0.1, 1.0],
+                metallic: 0.0,
+                roughness: 1.0,
+                ..Default::default()
+            });
+        }
+
+        for bytes in [
+            write_glb(&mesh, &materials, None),
+            write_glb_compressed(&mesh, &materials, None),
+        ] {
+            let glb = Glb::from_slice(&bytes).unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&glb.json).unwrap();
+            assert_eq!(json["materials"].as_array().unwrap().len(), 2);
+            let primitives = json["meshes"][0]["primitives"].as_array().unwrap();
+            assert_eq!(primitives.len(), 2);
+            assert_eq!(primitives[0]["material"], 0);
+            assert_eq!(primitives[1]["material"], 1);
+            assert_eq!(primitives[0]["attributes"], primitives[1]["attributes"]);
+            assert_ne!(primitives[0]["indices"], primitives[1]["indices"]);
+        }
+
+        let bytes = write_glb(&mesh, &materials, None);
+        let (doc, buffers, _images) = gltf::import_slice(&bytes).unwrap();
+        let gltf_mesh = doc.meshes().next().unwrap();
+        let mut reds = Vec::new();
+        let mut triangles = 0;
+        for prim in gltf_mesh.primitives() {
+            let reader = prim.reader(|b| Some(&buffers[b.index()]));
+            triangles += reader.read_indices().unwrap().into_u32().count() / 3;
+            reds.push(prim.material().pbr_metallic_roughness().base_color_factor()[0]);
+        }
+        assert_eq!(triangles, mesh.triangle_count());
+        assert_eq!(reds, vec![0.2, 0.9]);
+    }
+
     #[test]
     fn glb_larger_mesh_roundtrip() {
         let n = 10;
@@ -887,9 +2013,13 @@ mod tests {
             positions,
             normals,
             uvs,
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices,
             material_index: None,
+            name: None,
+            material_groups: Vec::new(),
         };
 
         let materials = MaterialLibrary::default();
@@ -944,14 +2074,7 @@ mod tests {
                 image::Rgba([0, 255, 0, 255])
             }
         });
-        let mut buf = std::io::Cursor::new(Vec::new());
-        img.write_to(&mut buf, image::ImageFormat::Png).unwrap();
-        let atlas = TextureData {
-            data: buf.into_inner(),
-            mime_type: "image/png".into(),
-            width: 4,
-            height: 4,
-        };
+        let atlas = png_texture(&img);
 
         let bytes = write_glb(&mesh, &materials, Some(&atlas));
 
@@ -976,6 +2099,41 @@ mod tests {
         assert_eq!(images[0].height, 4);
     }
 
+    #[test]
+    fn glb_writes_normal_texture_with_scale() {
+        let mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            uvs: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            indices: vec![0, 1, 2],
+            material_index: Some(0),
+            ..Default::default()
+        };
+        let mut materials = MaterialLibrary::default();
+        materials.materials.push(PBRMaterial {
+            base_color_texture: Some(0),
+            normal_texture: Some(1),
+            normal_scale: 0.5,
+            ..Default::default()
+        });
+        let png =
+            |rgba: [u8; 4]| png_texture(&image::RgbaImage::from_pixel(2, 2, image::Rgba(rgba)));
+        let base = png([200, 100, 50, 255]);
+        let normal = png([128, 128, 255, 255]);
+        let images = TileImages {
+            base_color: Some(ImageSource::Embedded(&base)),
+            normal: Some(ImageSource::Embedded(&normal)),
+            ..Default::default()
+        };
+
+        let bytes = write_glb(&mesh, &materials, images);
+        let (doc, _, _) = gltf::import_slice(&bytes).unwrap();
+        assert_eq!(doc.images().count(), 2);
+        let mat = doc.materials().next().unwrap();
+        let normal_info = mat.normal_texture().expect("normal texture");
+        assert_eq!(normal_info.scale(), 0.5);
+        assert_eq!(normal_info.texture().index(), 1);
+    }
+
     #[test]
     fn glb_compressed_parseable() {
         let mesh = make_triangle();
@@ -1028,9 +2186,13 @@ mod tests {
             positions,
             normals,
             uvs: vec![],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices,
             material_index: None,
+            name: None,
+            material_groups: Vec::new(),
         };
 
         let materials = MaterialLibrary::default();
@@ -1045,6 +2207,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn glb_oct_normals_decode_within_quantization_error() {
+        // Directions spread over the whole sphere, both hemispheres included
+        let count = 300;
+        let normals: Vec<f32> = (0..count)
+            .flat_map(|i| {
+                let z = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+                let r = (1.0 - z * z).sqrt();
+                let phi = i as f32 * 2.399_963;
+                [r * phi.cos(), r * phi.sin(), z]
+            })
+            .collect();
+        let mesh = IndexedMesh {
+            positions: normals.clone(),
+            normals: normals.clone(),
+            indices: (0..count as u32).collect(),
+            ..Default::default()
+        };
+
+        let materials = MaterialLibrary::default();
+        let float_len = write_glb_compressed(&mesh, &materials, None).len();
+
+        for (bits, max_degrees) in [(8, 1.5), (12, 0.1)] {
+            let bytes = write_glb_compressed_oct(&mesh, &materials, None, bits);
+            assert!(bytes.len() < float_len);
+
+            let glb = Glb::from_slice(&bytes).unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&glb.json).unwrap();
+            assert!(
+                json["extensionsRequired"]
+                    .as_array()
+                    .unwrap()
+                    .contains(&"KHR_mesh_quantization".into())
+            );
+            let attributes = &json["meshes"][0]["primitives"][0]["attributes"];
+            let accessor = &json["accessors"][attributes["NORMAL"].as_u64().unwrap() as usize];
+            assert_eq!(accessor["type"], "VEC3");
+            assert_eq!(accessor["normalized"], true);
+            let view = json["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize]
+                ["extensions"]["EXT_meshopt_compression"]
+                .clone();
+            assert_eq!(view["filter"], "OCTAHEDRAL");
+
+            // Decode like a viewer: meshopt buffer, then the filter in place
+            let offset = view["byteOffset"].as_u64().unwrap() as usize;
+            let length = view["byteLength"].as_u64().unwrap() as usize;
+            let encoded = &glb.bin.as_ref().unwrap()[offset..offset + length];
+            let stride = view["byteStride"].as_u64().unwrap() as usize;
+            let mut decoded: Vec<u8> = if stride == 4 {
+                let vertices = meshopt::decode_vertex_buffer::<[u8; 4]>(encoded, count);
+                bytemuck::cast_vec(vertices.unwrap())
+            } else {
+                let vertices = meshopt::decode_vertex_buffer::<[u8; 8]>(encoded, count);
+                bytemuck::cast_vec(vertices.unwrap())
+            };
+            // Octahedral, not per-component: the third component of every
+            // vertex is the filter's decode scale rather than the normal's Z
+            let scale = (1i32 << (bits - 1)) - 1;
+            for i in 0..count {
+                let third = if stride == 4 {
+                    i32::from(decoded[i * 4 + 2] as i8)
+                } else {
+                    i32::from(i16::from_le_bytes([decoded[i * 8 + 4], decoded[i * 8 + 5]]))
+                };
+                assert_eq!(third, scale, "{bits} bits, normal {i}");
+            }
+            // SAFETY: `decoded` holds `count` vertices of `stride` bytes
+            unsafe {
+                meshopt::ffi::meshopt_decodeFilterOct(decoded.as_mut_ptr().cast(), count, stride);
+            }
+
+            for (i, original) in normals.chunks_exact(3).enumerate() {
+                let n: [f32; 3] = if stride == 4 {
+                    std::array::from_fn(|c| decoded[i * 4 + c] as i8 as f32 / 127.0)
+                } else {
+                    let v: &[i16] = bytemuck::cast_slice(&decoded[i * 8..i * 8 + 6]);
+                    std::array::from_fn(|c| v[c] as f32 / 32767.0)
+                };
+                let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                let dot = (0..3).map(|c| n[c] * original[c]).sum::<f32>() / len;
+                let degrees = dot.clamp(-1.0, 1.0).acos().to_degrees();
+                assert!(degrees < max_degrees, "{bits} bits, normal {i}: {degrees}°");
+            }
+        }
+    }
+
     #[test]
     fn glb_compressed_with_colors() {
         let mesh = make_colored_triangle();
@@ -1055,4 +2303,180 @@ mod tests {
         let glb = Glb::from_slice(&bytes).expect("compressed GLB with colors should be parseable");
         assert!(glb.bin.is_some());
     }
+
+    #[test]
+    fn glb_external_texture_references_uri() {
+        let mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            uvs: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            indices: vec![0, 1, 2],
+            material_index: Some(0),
+            ..Default::default()
+        };
+        let mut materials = MaterialLibrary::default();
+        materials.materials.push(PBRMaterial {
+            name: "textured".into(),
+            base_color_texture: Some(0),
+            ..Default::default()
+        });
+
+        let bytes = write_glb_compressed_external(
+            &mesh,
+            &materials,
+            "../shared/0123456789abcdef.png",
+            "image/png",
+        );
+
+        let gltf = gltf::Gltf::from_slice_without_validation(&bytes).unwrap();
+        let image = gltf.document.images().next().expect("should have 1 image");
+        match image.source() {
+            gltf::image::Source::Uri { uri, mime_type } => {
+                assert_eq!(uri, "../shared/0123456789abcdef.png");
+                assert_eq!(mime_type, Some("image/png"));
+            }
+            gltf::image::Source::View { .. } => panic!("image should not be embedded"),
+        }
+        assert!(
+            gltf.document
+                .materials()
+                .next()
+                .unwrap()
+                .pbr_metallic_roughness()
+                .base_color_texture()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn glb_quantized_roundtrip_within_step() {
+        // Off-origin geometry so the dequantization offset matters
+        let mut mesh = make_grid(8);
+        for p in mesh.positions.chunks_exact_mut(3) {
+            p[0] = p[0] * 37.0 + 1000.0;
+            p[1] = p[1] * 11.0 - 250.0;
+            p[2] = (p[0] * 0.1).sin() * 3.0;
+        }
+        mesh.normals = [0.0, 0.6, 0.8].repeat(mesh.vertex_count());
+
+        let bytes = write_glb_quantized(&mesh, &MaterialLibrary::default(), None);
+        let json = Glb::from_slice(&bytes).unwrap().json.into_owned();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["extensionsRequired"][0], "KHR_mesh_quantization");
+
+        // Decode through the ingestion path, which bakes the node transform
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quantized.glb");
+        std::fs::write(&path, &bytes).unwrap();
+        let (meshes, _) = crate::ingestion::gltf_loader::load_gltf(&path, false).unwrap();
+        let decoded = &meshes[0];
+
+        // Largest extent 37 spread over 2 * 32767 steps; allow half a step
+        let step = 37.0 / (2.0 * i16::MAX as f32);
+        for (got, want) in decoded.positions.iter().zip(&mesh.positions) {
+            assert!(
+                (got - want).abs() <= step * 0.5 + want.abs() * f32::EPSILON * 4.0,
+                "{got} vs {want}"
+            );
+        }
+        for (got, want) in decoded.normals.iter().zip(&mesh.normals) {
+            assert!((got - want).abs() < 1.0 / 127.0, "{got} vs {want}");
+        }
+        for (got, want) in decoded.uvs.iter().zip(&mesh.uvs) {
+            assert!((got - want).abs() <= 0.5 / u16::MAX as f32);
+        }
+    }
+
+    #[test]
+    fn gpu_instancing_folds_node_transform_into_instances() {
+        let mut mesh = make_grid(4);
+        for p in mesh.positions.chunks_exact_mut(3) {
+            p[0] += 100.0;
+        }
+        let quantized = write_glb_quantized(&mesh, &MaterialLibrary::default(), None);
+        let parse = |bytes: &[u8]| {
+            let glb = Glb::from_slice(bytes).unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&glb.json).unwrap();
+            (json, glb.bin.unwrap().into_owned())
+        };
+        let (before, _) = parse(&quantized);
+        let offset = before["nodes"][0]["translation"].clone();
+        let scale = before["nodes"][0]["scale"][0].as_f64().unwrap();
+
+        let shifted = glam::DMat4::from_translation(glam::DVec3::new(5.0, 0.0, 0.0));
+        let instances = [glam::DMat4::IDENTITY, shifted].map(|m| m.to_cols_array());
+        let (json, bin) = parse(&add_gpu_instancing(&quantized, &instances));
+
+        assert_eq!(json["meshes"].as_array().unwrap().len(), 1);
+        assert!(json["nodes"][0].get("translation").is_none());
+        assert!(json["extensionsRequired"].as_array().unwrap().contains(&"EXT_mesh_gpu_instancing".into()));
+        let attributes = &json["nodes"][0]["extensions"]["EXT_mesh_gpu_instancing"]["attributes"];
+        let floats = |name: &str| -> Vec<f64> {
+            let accessor = &json["accessors"][attributes[name].as_u64().unwrap() as usize];
+            assert_eq!(accessor["count"], 2);
+            let view = &json["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+            let start = view["byteOffset"].as_u64().unwrap() as usize;
+            let end = start + view["byteLength"].as_u64().unwrap() as usize;
+            bin[start..end]
+                .chunks_exact(4)
+                .map(|b| f64::from(f32::from_le_bytes(b.try_into().unwrap())))
+                .collect()
+        };
+
+        // Each instance applies the dequantization grid first
+        let translations = floats("TRANSLATION");
+        for axis in 0..3 {
+            let grid = offset[axis].as_f64().unwrap();
+            let shift = if axis == 0 { 5.0 } else { 0.0 };
+            assert!((translations[axis] - grid).abs() < 1e-4);
+            assert!((translations[3 + axis] - grid - shift).abs() < 1e-4);
+        }
+        assert!(floats("SCALE").iter().all(|s| (s - scale).abs() < 1e-9));
+        assert_eq!(&floats("ROTATION")[..4], &[0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn b3dm_round_trips_tables_and_aligns_glb() {
+        let mesh = make_grid(4);
+        let batch_ids: Vec<u32> = (0..mesh.vertex_count() as u32).map(|i| i % 2).collect();
+        let glb = write_glb(&mesh, &MaterialLibrary::default(), None);
+        let glb = add_batch_ids(&glb, &batch_ids);
+        let table = serde_json::json!({ "name": ["a", "b"] });
+        let b3dm = write_b3dm(&glb, 2, Some(&table));
+
+        let parsed = read_b3dm(&b3dm).expect("b3dm parses");
+        assert_eq!(parsed.feature_table["BATCH_LENGTH"], 2);
+        assert_eq!(parsed.batch_table, Some(table));
+        assert_eq!(parsed.glb, &glb[..]);
+        assert_eq!((b3dm.len() - glb.len()) % 8, 0, "GLB starts 8-byte aligned");
+
+        let glb = Glb::from_slice(parsed.glb).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&glb.json).unwrap();
+        let attributes = &json["meshes"][0]["primitives"][0]["attributes"];
+        let accessor = &json["accessors"][attributes["_BATCHID"].as_u64().unwrap() as usize];
+        assert_eq!(accessor["count"], mesh.vertex_count());
+
+        // Truncated or foreign data is rejected
+        assert!(read_b3dm(&b3dm[..b3dm.len() - 1]).is_none());
+        assert!(read_b3dm(parsed.glb).is_none());
+    }
+
+    #[test]
+    fn glb_quantized_is_smaller() {
+        let mesh = make_grid(32);
+        let materials = MaterialLibrary::default();
+        let plain = write_glb(&mesh, &materials, None);
+        let quantized = write_glb_quantized(&mesh, &materials, None);
+        assert!(
+            quantized.len() < plain.len(),
+            "quantized ({}) should be smaller than plain ({})",
+            quantized.len(),
+            plain.len()
+        );
+    }
+
+    #[test]
+    fn quantize_uvs_rejects_repeating_coordinates() {
+        assert!(quantize_uvs(&[0.0, 0.5, 1.0, 1.0]).is_some());
+        assert!(quantize_uvs(&[0.0, 0.5, 2.0, 1.0]).is_none());
+    }
 }
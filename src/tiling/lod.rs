@@ -1,5 +1,6 @@
 use tracing::info;
 
+use crate::config::TilingConfig;
 use crate::types::{BoundingBox, IndexedMesh};
 
 use super::simplifier::simplify_mesh;
@@ -20,21 +21,66 @@ pub struct LodLevel {
 pub struct LodChain {
     pub levels: Vec<LodLevel>,
     pub bounds: BoundingBox,
+    /// Column-major matrices drawing the chain's mesh once each through
+    /// EXT_mesh_gpu_instancing; empty for a mesh drawn once.
+    pub instances: Vec<[f64; 16]>,
 }
 
 /// Minimum triangle count before we stop generating coarser LODs.
 const MIN_TRIANGLE_COUNT: usize = 1000;
 
+/// Viewport height in pixels assumed when calibrating against a target SSE.
+pub const ASSUMED_VIEWPORT_HEIGHT: f64 = 1080.0;
+
+/// Vertical field of view (radians) assumed when calibrating against a
+/// target SSE.
+pub const ASSUMED_FOV_Y: f64 = std::f64::consts::FRAC_PI_3;
+
+/// Maximum screen-space error at which viewers refine by default (CesiumJS
+/// `maximumScreenSpaceError`).
+pub const VIEWER_MAX_SSE: f64 = 16.0;
+
+/// Screen-space error in pixels of a metric error seen from `distance` in
+/// the assumed viewport, using the 3D Tiles perspective SSE formula.
+pub fn screen_space_error(geometric_error: f64, distance: f64) -> f64 {
+    let pixels_per_radian = ASSUMED_VIEWPORT_HEIGHT / (2.0 * (ASSUMED_FOV_Y / 2.0).tan());
+    geometric_error * pixels_per_radian / distance
+}
+
+/// Estimate the metric error of rendering `content_triangles` in place of
+/// `full_triangles` over a region with bounding-box diagonal `diagonal`.
+///
+/// Vertex spacing on a surface mesh grows as `diagonal / sqrt(triangles)`;
+/// the error is the spacing lost by the reduction.
+pub fn reduction_error(diagonal: f64, full_triangles: usize, content_triangles: usize) -> f64 {
+    if full_triangles == 0 || content_triangles == 0 {
+        return 0.0;
+    }
+    let spacing = |triangles: usize| diagonal / (triangles as f64).sqrt();
+    (spacing(content_triangles) - spacing(full_triangles)).max(0.0)
+}
+
+/// `geometricError` that makes a viewer at `VIEWER_MAX_SSE` refine at the
+/// distance where `content_error` projects to `target_sse` pixels.
+pub fn calibrated_geometric_error(content_error: f64, target_sse: f64) -> f64 {
+    content_error * VIEWER_MAX_SSE / target_sse
+}
+
 /// Generate a chain of LOD levels by repeatedly simplifying the mesh.
 ///
 /// LOD 0 = original mesh (geometric_error = 0, finest detail).
 /// LOD N = simplified at ratio `0.25^N` of the original index count.
 ///
-/// `geometric_error` is derived from meshopt's achieved simplification
-/// error (relative) scaled by the bounding-box diagonal to produce a
-/// value in the same units as the mesh (meters after transform).
-/// This matches the 3D Tiles spec where `geometricError` is the metric
-/// error introduced by rendering this LOD instead of a finer one.
+/// `geometric_error` accumulates meshopt's achieved simplification error,
+/// converted from meshopt's relative scale to the mesh's own coordinates,
+/// with a floor derived from the bounding-box diagonal. Both are in output
+/// space, like the octree errors in `tileset_writer`: meters when `--units`
+/// is given, otherwise the input's own units. This matches the 3D Tiles
+/// spec where `geometricError` is the error introduced by rendering this
+/// LOD instead of a finer one.
+///
+/// With `config.target_sse` set, the error is instead calibrated from the
+/// triangle reduction relative to LOD 0 (see `calibrated_geometric_error`).
 ///
 /// Stops when `max_levels` is reached, triangle count drops below 1000,
 /// or simplification can't reduce further.
@@ -42,6 +88,7 @@ pub fn generate_lod_chain(
     mesh: IndexedMesh,
     bounds: &BoundingBox,
     max_levels: u32,
+    config: &TilingConfig,
 ) -> LodChain {
     let diagonal = bounds.diagonal();
     let mut levels = Vec::new();
@@ -58,10 +105,12 @@ pub fn generate_lod_chain(
         return LodChain {
             levels,
             bounds: *bounds,
+            instances: Vec::new(),
         };
     }
 
-    let mut prev_triangle_count = levels[0].mesh.triangle_count();
+    let original_triangle_count = levels[0].mesh.triangle_count();
+    let mut prev_triangle_count = original_triangle_count;
     let mut cumulative_error = 0.0_f64;
 
     for n in 1..max_levels {
@@ -77,7 +126,7 @@ pub fn generate_lod_chain(
             "Generating LOD level (cascaded)"
         );
 
-        let simplified = simplify_mesh(&prev_level.mesh, ratio, true);
+        let simplified = simplify_mesh(&prev_level.mesh, ratio, true, config);
 
         // Stop if simplification couldn't reduce meaningfully (< 5% reduction)
         let new_triangle_count = simplified.mesh.triangle_count();
@@ -95,12 +144,18 @@ pub fn generate_lod_chain(
 
         // Compound error: each level accumulates error from all previous
         // simplification steps.
-        let measured_error = simplified.achieved_error as f64 * diagonal;
+        let measured_error = simplified.absolute_error;
         cumulative_error += measured_error;
         // Heuristic minimum based on overall reduction from the original
         let overall_ratio = 0.25_f64.powi(n as i32);
         let min_heuristic_error = diagonal * (1.0 - overall_ratio) * 0.5;
-        let geometric_error = cumulative_error.max(min_heuristic_error);
+        let geometric_error = match config.target_sse {
+            Some(target_sse) => calibrated_geometric_error(
+                reduction_error(diagonal, original_triangle_count, new_triangle_count),
+                target_sse,
+            ),
+            None => cumulative_error.max(min_heuristic_error),
+        };
 
         levels.push(LodLevel {
             level: n,
@@ -124,6 +179,7 @@ pub fn generate_lod_chain(
     LodChain {
         levels,
         bounds: *bounds,
+        instances: Vec::new(),
     }
 }
 
@@ -174,7 +230,7 @@ mod tests {
     fn lod_chain_levels_decrease_in_triangles() {
         let mesh = make_grid(100); // 20000 triangles
         let bounds = unit_bounds();
-        let chain = generate_lod_chain(mesh, &bounds, 4);
+        let chain = generate_lod_chain(mesh, &bounds, 4, &TilingConfig::default());
 
         assert!(chain.levels.len() >= 2, "Should produce at least 2 LOD levels");
 
@@ -195,7 +251,7 @@ mod tests {
     fn lod_chain_geometric_error_increases() {
         let mesh = make_grid(100);
         let bounds = unit_bounds();
-        let chain = generate_lod_chain(mesh, &bounds, 4);
+        let chain = generate_lod_chain(mesh, &bounds, 4, &TilingConfig::default());
 
         for i in 1..chain.levels.len() {
             assert!(
@@ -209,12 +265,77 @@ mod tests {
         }
     }
 
+    #[test]
+    fn target_sse_selects_coarser_lods_farther_away() {
+        let target_sse = 4.0;
+        let config = TilingConfig {
+            target_sse: Some(target_sse),
+            ..Default::default()
+        };
+        let mesh = make_grid(100);
+        let original_tris = mesh.triangle_count();
+        let bounds = unit_bounds();
+        let chain = generate_lod_chain(mesh, &bounds, 4, &config);
+        assert!(chain.levels.len() >= 3);
+
+        // A viewer keeps refining while a level's projected error exceeds its
+        // maximum SSE, so it draws the coarsest level that doesn't.
+        let select = |distance: f64| {
+            chain
+                .levels
+                .iter()
+                .rev()
+                .find(|l| screen_space_error(l.geometric_error, distance) <= VIEWER_MAX_SSE)
+                .map_or(0, |l| l.level)
+        };
+
+        let mut previous = 0;
+        for distance in [0.01, 0.1, 1.0, 10.0, 100.0] {
+            let level = select(distance);
+            assert!(level >= previous, "LOD {level} at {distance} m");
+            previous = level;
+        }
+        assert_eq!(select(1e-4), 0);
+        assert_eq!(select(1e4), chain.levels.last().unwrap().level);
+
+        // Each level takes over where its own error projects to the target.
+        for level in &chain.levels[1..] {
+            let tris = level.mesh.triangle_count();
+            let error = reduction_error(bounds.diagonal(), original_tris, tris);
+            let swap = screen_space_error(error, 1.0) / target_sse;
+            assert!(select(swap * 1.01) >= level.level);
+            assert!(select(swap * 0.99) < level.level);
+        }
+    }
+
+    #[test]
+    fn lod_error_scales_with_input_units() {
+        let mesh = make_grid(100);
+        let mut scaled = mesh.clone();
+        scaled.positions.iter_mut().for_each(|p| *p *= 10.0);
+        let bounds = unit_bounds();
+        let scaled_bounds = BoundingBox {
+            min: bounds.min,
+            max: bounds.max.map(|v| v * 10.0),
+        };
+
+        let config = TilingConfig::default();
+        let chain = generate_lod_chain(mesh, &bounds, 4, &config);
+        let scaled_chain = generate_lod_chain(scaled, &scaled_bounds, 4, &config);
+
+        assert_eq!(chain.levels.len(), scaled_chain.levels.len());
+        for (level, scaled_level) in chain.levels.iter().zip(&scaled_chain.levels).skip(1) {
+            let ratio = scaled_level.geometric_error / level.geometric_error;
+            assert!((ratio - 10.0).abs() < 1e-3, "LOD {} ratio {ratio}", level.level);
+        }
+    }
+
     #[test]
     fn lod_chain_lod0_is_original() {
         let mesh = make_grid(20);
         let tris = mesh.triangle_count();
         let bounds = unit_bounds();
-        let chain = generate_lod_chain(mesh, &bounds, 4);
+        let chain = generate_lod_chain(mesh, &bounds, 4, &TilingConfig::default());
 
         assert_eq!(chain.levels[0].level, 0);
         assert_eq!(chain.levels[0].mesh.triangle_count(), tris);
@@ -227,7 +348,7 @@ mod tests {
             min: [0.0; 3],
             max: [0.0; 3],
         };
-        let chain = generate_lod_chain(mesh, &bounds, 4);
+        let chain = generate_lod_chain(mesh, &bounds, 4, &TilingConfig::default());
         assert_eq!(chain.levels.len(), 1); // Only LOD 0
     }
 
@@ -235,7 +356,7 @@ mod tests {
     fn lod_chain_respects_max_levels() {
         let mesh = make_grid(100);
         let bounds = unit_bounds();
-        let chain = generate_lod_chain(mesh, &bounds, 2);
+        let chain = generate_lod_chain(mesh, &bounds, 2, &TilingConfig::default());
         assert!(chain.levels.len() <= 2);
     }
 
@@ -243,7 +364,7 @@ mod tests {
     fn lod_chain_bounds_preserved() {
         let bounds = unit_bounds();
         let mesh = make_grid(20);
-        let chain = generate_lod_chain(mesh, &bounds, 4);
+        let chain = generate_lod_chain(mesh, &bounds, 4, &TilingConfig::default());
         assert_eq!(chain.bounds, bounds);
     }
 }
@@ -3,6 +3,10 @@ pub mod glb_writer;
 pub mod lod;
 pub mod octree;
 pub mod simplifier;
+pub mod sink;
 pub mod texture_compress;
 pub mod tileset_writer;
 pub mod triangle_clipper;
+
+#[cfg(test)]
+mod test_textures;
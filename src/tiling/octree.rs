@@ -1,5 +1,6 @@
 use rayon::prelude::*;
 
+use crate::tiling::simplifier::compact_mesh;
 use crate::types::{BoundingBox, IndexedMesh};
 
 /// A node in the octree spatial hierarchy.
@@ -38,6 +39,77 @@ impl OctreeNode {
     }
 }
 
+/// Limits that decide whether an octree node becomes a leaf.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeafBudget {
+    pub max_triangles: usize,
+    /// Upper bound on [`estimate_tile_bytes`]; `None` splits on triangle count alone.
+    pub max_bytes: Option<u64>,
+    /// Source texture dimensions, used to estimate the atlas a tile will need.
+    pub texture_size: Option<(u32, u32)>,
+}
+
+impl LeafBudget {
+    /// Budget that only limits the triangle count.
+    pub fn triangles(max_triangles: usize) -> Self {
+        Self {
+            max_triangles,
+            max_bytes: None,
+            texture_size: None,
+        }
+    }
+
+    /// Whether `mesh` fits in a single tile under this budget.
+    pub fn fits(&self, mesh: &IndexedMesh) -> bool {
+        mesh.triangle_count() <= self.max_triangles
+            && self
+                .max_bytes
+                .is_none_or(|max| estimate_tile_bytes(mesh, self.texture_size) <= max)
+    }
+}
+
+/// Estimate the decoded size of a tile in bytes.
+///
+/// Counts vertex attributes and indices as stored in `IndexedMesh`, plus RGBA
+/// texels for the share of `texture_size` covered by the mesh's UVs. Two meshes
+/// with equal triangle counts can differ a lot here: fans share vertices,
+/// disconnected slivers don't, and a tile's texture footprint is independent
+/// of either.
+pub fn estimate_tile_bytes(mesh: &IndexedMesh, texture_size: Option<(u32, u32)>) -> u64 {
+    let attributes = mesh.positions.len()
+        + mesh.normals.len()
+        + mesh.uvs.len()
+        + mesh.uvs1.len()
+        + mesh.colors.len()
+        + mesh.feature_ids.len();
+    let geometry = (attributes * 4 + mesh.indices.len() * 4) as u64;
+
+    let texels = match texture_size {
+        Some((w, h)) if mesh.has_uvs() => {
+            let coverage = uv_area(mesh).min(1.0);
+            (coverage * f64::from(w) * f64::from(h)).ceil() as u64
+        }
+        _ => 0,
+    };
+
+    geometry + texels * 4
+}
+
+/// Total UV-space area of a mesh's triangles (1.0 = the whole texture once).
+fn uv_area(mesh: &IndexedMesh) -> f64 {
+    mesh.indices
+        .chunks_exact(3)
+        .map(|tri| {
+            let uv = |i: u32| {
+                let i = i as usize * 2;
+                [f64::from(mesh.uvs[i]), f64::from(mesh.uvs[i + 1])]
+            };
+            let (a, b, c) = (uv(tri[0]), uv(tri[1]), uv(tri[2]));
+            ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() * 0.5
+        })
+        .sum()
+}
+
 /// Compute the octant index (0..7) for a point relative to the center of a bounding box.
 ///
 /// Octant layout (bit pattern: z_hi | y_hi | x_hi):
@@ -73,6 +145,75 @@ pub(crate) fn child_bounds(parent: &BoundingBox, octant: usize) -> BoundingBox {
     }
 }
 
+/// Position of a point along a Hilbert curve through `frame`.
+///
+/// `dims` is 3, or 2 to ignore Z (quadtree tiles). Each axis is quantized to
+/// as many bits as fit a `u64` index. Unlike Morton (octant) order, cells
+/// that follow each other on the curve always share a face.
+pub(crate) fn hilbert_index(point: &[f64; 3], frame: &BoundingBox, dims: usize) -> u64 {
+    let bits = 63 / dims as u32;
+    let top = (1u64 << bits) - 1;
+    let mut x = [0u64; 3];
+    for (axis, x) in x.iter_mut().enumerate().take(dims) {
+        let extent = frame.max[axis] - frame.min[axis];
+        if extent > 0.0 {
+            let t = ((point[axis] - frame.min[axis]) / extent).clamp(0.0, 1.0);
+            *x = (t * top as f64) as u64;
+        }
+    }
+    let x = &mut x[..dims];
+
+    // Skilling's "axes to transpose": undo the excess work of the curve...
+    let mut q = 1u64 << (bits - 1);
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..dims {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+    // ...then Gray-encode
+    for i in 1..dims {
+        x[i] ^= x[i - 1];
+    }
+    let mut t = 0;
+    q = 1u64 << (bits - 1);
+    while q > 1 {
+        if x[dims - 1] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for x in x.iter_mut() {
+        *x ^= t;
+    }
+
+    // Interleave the transposed bits, most significant first
+    let mut index = 0u64;
+    for b in (0..bits).rev() {
+        for x in x.iter() {
+            index = (index << 1) | ((x >> b) & 1);
+        }
+    }
+    index
+}
+
+/// Compute the child bounding box for a quadtree quadrant (0..3).
+///
+/// X/Y are halved as for octants 0..3; Z keeps the parent's full extent.
+pub(crate) fn quadrant_bounds(parent: &BoundingBox, quadrant: usize) -> BoundingBox {
+    let mut bounds = child_bounds(parent, quadrant);
+    bounds.min[2] = parent.min[2];
+    bounds.max[2] = parent.max[2];
+    bounds
+}
+
 /// Split a mesh into 8 octant sub-meshes using Sutherland-Hodgman clipping.
 ///
 /// Triangles straddling octant boundaries are clipped at the boundary planes
@@ -82,6 +223,65 @@ pub fn split_mesh(mesh: &IndexedMesh, bounds: &BoundingBox) -> [IndexedMesh; 8]
     crate::tiling::triangle_clipper::split_mesh_clipping(mesh, bounds)
 }
 
+/// Split a mesh into 4 quadrant sub-meshes along X and Y only, for 2.5D
+/// terrain where a vertical split would just create thin cells.
+pub fn split_mesh_quadtree(mesh: &IndexedMesh, bounds: &BoundingBox) -> [IndexedMesh; 4] {
+    crate::tiling::triangle_clipper::split_mesh_clipping_xy(mesh, bounds)
+}
+
+/// [`split_mesh`] (`N = 8`) or [`split_mesh_quadtree`] (`N = 4`) without
+/// welding vertices, see `TilingConfig::clip_weld`.
+pub fn split_mesh_unwelded<const N: usize>(
+    mesh: &IndexedMesh,
+    bounds: &BoundingBox,
+) -> [IndexedMesh; N] {
+    crate::tiling::triangle_clipper::split_mesh_clipping_unwelded(mesh, bounds)
+}
+
+/// Split a mesh into `N` cells (8 octants, or 4 X/Y quadrants) without
+/// clipping: each whole triangle goes to the cell holding its centroid.
+///
+/// The total triangle count never grows, but triangles near a boundary
+/// reach past their cell, so a child's bounds must be grown to its vertices.
+pub fn split_mesh_centroid<const N: usize>(
+    mesh: &IndexedMesh,
+    bounds: &BoundingBox,
+) -> [IndexedMesh; N] {
+    let center = bounds.center();
+    let position = |i: u32| {
+        let i = i as usize * 3;
+        [
+            mesh.positions[i] as f64,
+            mesh.positions[i + 1] as f64,
+            mesh.positions[i + 2] as f64,
+        ]
+    };
+
+    // Per-triangle materials of each cell, tracked only for multi-material meshes
+    let tri_materials = (!mesh.material_groups.is_empty()).then(|| mesh.triangle_materials());
+    let mut cell_indices: [Vec<u32>; N] = std::array::from_fn(|_| Vec::new());
+    let mut cell_materials: [Vec<Option<usize>>; N] = std::array::from_fn(|_| Vec::new());
+    for (t, tri) in mesh.indices.chunks_exact(3).enumerate() {
+        let (a, b, c) = (position(tri[0]), position(tri[1]), position(tri[2]));
+        let centroid = std::array::from_fn(|k| (a[k] + b[k] + c[k]) / 3.0);
+        let cell = octant_index(center, centroid) & (N - 1);
+        cell_indices[cell].extend_from_slice(tri);
+        if let Some(materials) = &tri_materials {
+            cell_materials[cell].push(materials[t]);
+        }
+    }
+
+    let mut cell_materials = cell_materials.into_iter();
+    cell_indices.map(|indices| {
+        let mut child = compact_mesh(indices, mesh);
+        let materials = cell_materials.next().unwrap_or_default();
+        if tri_materials.is_some() {
+            child.set_triangle_materials(&materials);
+        }
+        child
+    })
+}
+
 /// Recursively build an octree from a mesh.
 ///
 /// Takes ownership of the mesh to avoid unnecessary clones of large buffers.
@@ -93,7 +293,40 @@ pub fn build_octree(
     max_depth: u32,
     max_triangles: usize,
 ) -> OctreeNode {
-    build_octree_recursive(mesh, bounds, 0, max_depth, max_triangles)
+    build_octree_with_budget(
+        mesh,
+        bounds,
+        max_depth,
+        &LeafBudget::triangles(max_triangles),
+    )
+}
+
+/// Like [`build_octree`], but a node only becomes a leaf once its mesh fits
+/// every limit in `budget` (or `max_depth` is reached).
+pub fn build_octree_with_budget(
+    mesh: IndexedMesh,
+    bounds: &BoundingBox,
+    max_depth: u32,
+    budget: &LeafBudget,
+) -> OctreeNode {
+    build_octree_recursive(mesh, bounds, 0, max_depth, budget)
+}
+
+/// Deepest level [`auto_max_depth`] picks, however dense the mesh.
+pub const AUTO_DEPTH_LIMIT: u32 = 12;
+
+/// Estimate the depth at which `triangles`, split `branching` ways per
+/// level, come down to `max_triangles` per leaf: the ceiling of the base
+/// `branching` log of `triangles / max_triangles`, capped at
+/// [`AUTO_DEPTH_LIMIT`]. Assumes the triangles spread evenly over the cells.
+pub fn auto_max_depth(triangles: usize, max_triangles: usize, branching: usize) -> u32 {
+    let mut depth = 0;
+    let mut capacity = max_triangles.max(1);
+    while capacity < triangles && depth < AUTO_DEPTH_LIMIT {
+        capacity = capacity.saturating_mul(branching);
+        depth += 1;
+    }
+    depth
 }
 
 fn build_octree_recursive(
@@ -101,10 +334,10 @@ fn build_octree_recursive(
     bounds: &BoundingBox,
     depth: u32,
     max_depth: u32,
-    max_triangles: usize,
+    budget: &LeafBudget,
 ) -> OctreeNode {
-    // Leaf condition: few enough triangles or at max depth
-    if mesh.triangle_count() <= max_triangles || depth >= max_depth {
+    // Leaf condition: within budget or at max depth
+    if budget.fits(&mesh) || depth >= max_depth {
         return OctreeNode {
             bounds: *bounds,
             mesh, // move, no clone
@@ -132,7 +365,7 @@ fn build_octree_recursive(
                     &cb,
                     depth + 1,
                     max_depth,
-                    max_triangles,
+                    budget,
                 )))
             }
         })
@@ -153,6 +386,31 @@ fn build_octree_recursive(
 mod tests {
     use super::*;
 
+    #[test]
+    fn hilbert_index_steps_between_face_neighbours() {
+        // Every cell of a 4x4x4 (and 4x4) grid, visited in curve order,
+        // sits next to the one before it.
+        for dims in [2, 3] {
+            let frame = BoundingBox {
+                min: [0.0; 3],
+                max: [4.0; 3],
+            };
+            let n = if dims == 3 { 64 } else { 16 };
+            let mut cells: Vec<[i32; 3]> = (0..n)
+                .map(|i| [i % 4, i / 4 % 4, if dims == 3 { i / 16 } else { 0 }])
+                .collect();
+            cells.sort_by_key(|c| {
+                let center = c.map(|v| v as f64 + 0.5);
+                hilbert_index(&center, &frame, dims)
+            });
+            assert_eq!(cells[0], [0, 0, 0]);
+            for pair in cells.windows(2) {
+                let step: i32 = (0..3).map(|a| (pair[0][a] - pair[1][a]).abs()).sum();
+                assert_eq!(step, 1, "{dims}D curve jumps at {pair:?}");
+            }
+        }
+    }
+
     /// Generate a 3D grid mesh spanning [0,1]^3.
     /// Creates `n x n x n` cubes, each face triangulated as 2 triangles.
     /// For simpler tests, we use a flat XY grid at varying Z.
@@ -253,6 +511,60 @@ mod tests {
         assert!(total >= original_tris, "clipped output ({total}) must have >= original ({original_tris}) triangles");
     }
 
+    #[test]
+    fn splits_keep_triangle_materials() {
+        let (mut mesh, bounds) = make_flat_grid(4);
+        let centroid_x = |m: &IndexedMesh, tri: &[u32]| {
+            let xs = tri.iter().map(|&i| m.positions[i as usize * 3]);
+            xs.sum::<f32>() / 3.0
+        };
+        let material_at = |x: f32| Some(usize::from(x > 0.5));
+        let materials: Vec<_> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|tri| material_at(centroid_x(&mesh, tri)))
+            .collect();
+        mesh.set_triangle_materials(&materials);
+        assert!(!mesh.material_groups.is_empty());
+
+        let clipped: Vec<IndexedMesh> = split_mesh(&mesh, &bounds).into();
+        let centroid: Vec<IndexedMesh> = split_mesh_centroid::<8>(&mesh, &bounds).into();
+        for child in clipped.iter().chain(&centroid).filter(|c| !c.is_empty()) {
+            let tris = child.indices.chunks_exact(3);
+            for (tri, material) in tris.zip(child.triangle_materials()) {
+                assert_eq!(material, material_at(centroid_x(child, tri)));
+            }
+        }
+    }
+
+    #[test]
+    fn centroid_split_keeps_each_triangle_once() {
+        let (mesh, bounds) = make_3d_grid(5);
+        let children = split_mesh_centroid::<8>(&mesh, &bounds);
+
+        let total: usize = children.iter().map(|m| m.triangle_count()).sum();
+        assert_eq!(total, mesh.triangle_count());
+
+        // Key triangles by their exact corner positions
+        let key = |m: &IndexedMesh, tri: &[u32]| -> [[u32; 3]; 3] {
+            let mut corners = [0, 1, 2].map(|k| {
+                let i = tri[k] as usize * 3;
+                [0, 1, 2].map(|axis| m.positions[i + axis].to_bits())
+            });
+            corners.sort();
+            corners
+        };
+        let mut seen = std::collections::HashMap::new();
+        for (octant, child) in children.iter().enumerate() {
+            for tri in child.indices.chunks_exact(3) {
+                assert!(seen.insert(key(child, tri), octant).is_none());
+            }
+        }
+        for tri in mesh.indices.chunks_exact(3) {
+            assert!(seen.contains_key(&key(&mesh, tri)));
+        }
+    }
+
     #[test]
     fn split_mesh_clipping_no_gaps() {
         // Every original vertex position should appear in the output
@@ -420,6 +732,72 @@ mod tests {
         assert!(tree.total_triangles() >= original_tris);
     }
 
+    #[test]
+    fn auto_max_depth_follows_triangle_density() {
+        assert_eq!(auto_max_depth(50, 100, 8), 0);
+        assert_eq!(auto_max_depth(800, 100, 8), 1);
+        assert_eq!(auto_max_depth(801, 100, 8), 2);
+        assert_eq!(auto_max_depth(1600, 100, 4), 2);
+        assert_eq!(auto_max_depth(usize::MAX, 1, 8), AUTO_DEPTH_LIMIT);
+
+        let (mesh, bounds) = make_3d_grid(17);
+        let tris = mesh.triangle_count();
+        assert!(tris > 10_000);
+        let depth = auto_max_depth(tris, 100, 8);
+        assert_eq!(depth, 3);
+
+        fn largest_leaf(node: &OctreeNode) -> usize {
+            let children = node.children.iter().flatten();
+            children
+                .map(|c| largest_leaf(c))
+                .max()
+                .unwrap_or(node.mesh.triangle_count())
+        }
+        let tree = build_octree(mesh, &bounds, depth, 100);
+        assert!(largest_leaf(&tree) <= 100, "{}", largest_leaf(&tree));
+    }
+
+    #[test]
+    fn tile_bytes_count_vertices_and_texels() {
+        let (mesh, _) = make_flat_grid(4); // 25 vertices, 32 triangles
+        let geometry = (25 * 3 * 4 + 32 * 3 * 4) as u64;
+        assert_eq!(estimate_tile_bytes(&mesh, None), geometry);
+
+        let mut textured = mesh.clone();
+        textured.uvs = textured
+            .positions
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1]])
+            .collect();
+        // UVs cover the whole 64x64 texture once
+        let bytes = estimate_tile_bytes(&textured, Some((64, 64)));
+        assert_eq!(bytes, geometry + 25 * 2 * 4 + 64 * 64 * 4);
+    }
+
+    #[test]
+    fn byte_budget_splits_tile_under_triangle_limit() {
+        let (mesh, bounds) = make_3d_grid(4);
+        let tris = mesh.triangle_count();
+        let bytes = estimate_tile_bytes(&mesh, None);
+
+        let by_triangles = build_octree(mesh.clone(), &bounds, 4, tris);
+        assert!(
+            by_triangles.is_leaf(),
+            "triangle limit alone keeps one tile"
+        );
+
+        let budget = LeafBudget {
+            max_bytes: Some(bytes / 2),
+            ..LeafBudget::triangles(tris)
+        };
+        let tree = build_octree_with_budget(mesh, &bounds, 4, &budget);
+        assert!(
+            !tree.is_leaf(),
+            "tile over the byte budget should subdivide"
+        );
+        assert!(tree.total_triangles() >= tris);
+    }
+
     #[test]
     fn build_octree_preserves_attributes() {
         let n = 4;
@@ -461,9 +839,13 @@ mod tests {
             positions,
             normals,
             uvs,
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices,
             material_index: Some(0),
+            name: None,
+            material_groups: Vec::new(),
         };
 
         let bounds = BoundingBox {
@@ -1,29 +1,125 @@
+use std::collections::HashMap;
+
 use meshopt::{self, SimplifyOptions, VertexDataAdapter};
+use rayon::prelude::*;
+
+use crate::config::TilingConfig;
+use crate::tiling::octree::{child_bounds, octant_index};
+use crate::types::{BoundingBox, IndexedMesh};
+
+/// `simplify` results above `target_count * SLOPPY_FALLBACK_FACTOR` indices
+/// are considered stalled and trigger the sloppy fallback (when allowed).
+const SLOPPY_FALLBACK_FACTOR: f64 = 1.5;
 
-use crate::types::IndexedMesh;
+/// Depth limit of the chunk octree, so coincident triangles can't recurse
+/// forever.
+const MAX_CHUNK_DEPTH: u32 = 16;
 
 /// Result of mesh simplification: new mesh + achieved error.
 #[derive(Debug, Clone)]
 pub struct SimplifiedMesh {
     pub mesh: IndexedMesh,
+    /// Error relative to the mesh extent, as reported by meshopt.
     pub achieved_error: f32,
+    /// `achieved_error` in the mesh's own coordinate units.
+    pub absolute_error: f64,
 }
 
 /// Simplify a mesh to `target_ratio` of its original index count.
 ///
+/// When the mesh carries normals or UVs (and their weights in `config` are
+/// non-zero), meshopt's attribute-aware simplifier is used so that UV seams
+/// and sharp normals are penalised rather than smeared. Otherwise only
+/// positions are considered.
+///
+/// If topology constraints (borders, seams, locked vertices) stall the
+/// simplifier well above the target and `config.allow_sloppy` is set, the
+/// mesh is re-simplified with `meshopt::simplify_sloppy`, which ignores
+/// topology. The fallback is skipped with `lock_border`, since sloppy
+/// simplification would move the locked border. `achieved_error` always reports the error of the path whose
+/// result is returned.
+///
+/// Meshes above `config.simplify_chunk_triangles` triangles are simplified
+/// chunk by chunk instead, see `simplify_chunked`. Meshes with several
+/// materials are simplified one material at a time, see
+/// `simplify_by_material`, so every triangle keeps its material.
+///
 /// Only indices change; vertex attribute arrays are compacted to remove
 /// unreferenced vertices via `compact_mesh`.
-pub fn simplify_mesh(mesh: &IndexedMesh, target_ratio: f32, lock_border: bool) -> SimplifiedMesh {
+pub fn simplify_mesh(
+    mesh: &IndexedMesh,
+    target_ratio: f32,
+    lock_border: bool,
+    config: &TilingConfig,
+) -> SimplifiedMesh {
     if mesh.is_empty() {
         return SimplifiedMesh {
             mesh: IndexedMesh::default(),
             achieved_error: 0.0,
+            absolute_error: 0.0,
         };
     }
 
+    let chunk = config.simplify_chunk_triangles;
+    let (runs, result_error) = if !mesh.material_groups.is_empty() {
+        simplify_by_material(mesh, target_ratio, lock_border, config)
+    } else if chunk > 0 && mesh.triangle_count() > chunk {
+        let (indices, error) = simplify_chunked(mesh, target_ratio, lock_border, config);
+        (vec![(mesh.material_index, indices)], error)
+    } else {
+        let (indices, error) = simplify_indices(
+            mesh,
+            target_ratio,
+            lock_border,
+            config.allow_sloppy,
+            None,
+            config,
+        );
+        (vec![(mesh.material_index, indices)], error)
+    };
+
+    // Optimize for GPU: vertex cache (within each material, so its triangles
+    // stay together) then compact unused vertices
+    let mut new_indices = Vec::new();
+    let mut materials = Vec::new();
+    for (material, run) in runs {
+        let run = meshopt::optimize_vertex_cache(&run, mesh.vertex_count());
+        materials.extend(std::iter::repeat_n(material, run.len() / 3));
+        new_indices.extend(run);
+    }
+
+    let compacted = compact_mesh_with_materials(new_indices, &materials, mesh);
+
+    SimplifiedMesh {
+        mesh: compacted,
+        achieved_error: result_error,
+        absolute_error: result_error as f64 * simplify_scale(mesh) as f64,
+    }
+}
+
+fn position_adapter(mesh: &IndexedMesh) -> VertexDataAdapter<'_> {
     let positions_bytes = meshopt::typed_to_bytes(&mesh.positions);
-    let adapter = VertexDataAdapter::new(positions_bytes, 12, 0)
-        .expect("positions buffer should be valid for VertexDataAdapter");
+    VertexDataAdapter::new(positions_bytes, 12, 0)
+        .expect("positions buffer should be valid for VertexDataAdapter")
+}
+
+/// Extent of `mesh` that meshopt's relative errors are measured against.
+fn simplify_scale(mesh: &IndexedMesh) -> f32 {
+    meshopt::simplify_scale(&position_adapter(mesh))
+}
+
+/// Simplified index buffer of `mesh`, still referencing its vertices, and
+/// the error relative to the mesh extent. Vertices flagged in `vertex_lock`
+/// are never moved or removed.
+fn simplify_indices(
+    mesh: &IndexedMesh,
+    target_ratio: f32,
+    lock_border: bool,
+    allow_sloppy: bool,
+    vertex_lock: Option<&[bool]>,
+    config: &TilingConfig,
+) -> (Vec<u32>, f32) {
+    let adapter = position_adapter(mesh);
 
     let target_count = (mesh.indices.len() as f64 * target_ratio as f64) as usize;
     // Ensure target_count is a multiple of 3 (whole triangles)
@@ -37,34 +133,315 @@ pub fn simplify_mesh(mesh: &IndexedMesh, target_ratio: f32, lock_border: bool) -
     };
 
     let mut result_error: f32 = 0.0;
-    let new_indices = meshopt::simplify(
-        &mesh.indices,
-        &adapter,
-        target_count,
-        target_error,
-        options,
-        Some(&mut result_error),
+    let new_indices = match interleave_attributes(mesh, config) {
+        Some((attributes, weights)) => {
+            let stride = weights.len() * std::mem::size_of::<f32>();
+            let unlocked;
+            let vertex_lock = match vertex_lock {
+                Some(locks) => locks,
+                None => {
+                    unlocked = vec![false; mesh.vertex_count()];
+                    &unlocked
+                }
+            };
+            meshopt::simplify_with_attributes_and_locks(
+                &mesh.indices,
+                &adapter,
+                &attributes,
+                &weights,
+                stride,
+                vertex_lock,
+                target_count,
+                target_error,
+                options,
+                Some(&mut result_error),
+            )
+        }
+        None => match vertex_lock {
+            Some(vertex_lock) => meshopt::simplify_with_locks(
+                &mesh.indices,
+                &adapter,
+                vertex_lock,
+                target_count,
+                target_error,
+                options,
+                Some(&mut result_error),
+            ),
+            None => meshopt::simplify(
+                &mesh.indices,
+                &adapter,
+                target_count,
+                target_error,
+                options,
+                Some(&mut result_error),
+            ),
+        },
+    };
+
+    let stall_limit = (target_count as f64 * SLOPPY_FALLBACK_FACTOR) as usize;
+    let new_indices = if allow_sloppy && !lock_border && new_indices.len() > stall_limit {
+        let mut sloppy_error: f32 = 0.0;
+        // Sloppy simplification is driven by the target count; the error
+        // bound is left wide open (1.0 = mesh extent) so it never stops early.
+        let sloppy_indices = meshopt::simplify_sloppy(
+            &mesh.indices,
+            &adapter,
+            target_count,
+            1.0,
+            Some(&mut sloppy_error),
+        );
+        if !sloppy_indices.is_empty() && sloppy_indices.len() < new_indices.len() {
+            tracing::debug!(
+                target = target_count / 3,
+                stalled = new_indices.len() / 3,
+                sloppy = sloppy_indices.len() / 3,
+                "Simplification stalled, using sloppy fallback"
+            );
+            result_error = sloppy_error;
+            sloppy_indices
+        } else {
+            new_indices
+        }
+    } else {
+        new_indices
+    };
+
+    (new_indices, result_error)
+}
+
+/// Simplify a mesh too large for one meshopt pass by splitting it into an
+/// octree of chunks of at most `config.simplify_chunk_triangles` triangles
+/// and simplifying each chunk on its own.
+///
+/// Triangles go whole to the chunk holding their centroid, so chunks share
+/// the vertices along their seams. Those vertices are locked in every chunk
+/// (and the sloppy fallback, which ignores locks, is off), so they survive
+/// on both sides and the stitched index buffer stays watertight. The mesh's
+/// own outer border is locked only with `lock_border`, as for a single pass.
+/// The price is a little lost reduction along the seams; meshopt only ever
+/// holds one chunk per thread.
+///
+/// Returns the stitched index buffer, referencing `mesh`'s vertices, and
+/// the largest chunk error relative to the whole mesh's extent.
+fn simplify_chunked(
+    mesh: &IndexedMesh,
+    target_ratio: f32,
+    lock_border: bool,
+    config: &TilingConfig,
+) -> (Vec<u32>, f32) {
+    let mut chunks = Vec::new();
+    partition_triangles(
+        mesh,
+        mesh.indices.clone(),
+        &mesh_bounds(mesh),
+        config.simplify_chunk_triangles,
+        0,
+        &mut chunks,
+    );
+    tracing::debug!(
+        triangles = mesh.triangle_count(),
+        chunks = chunks.len(),
+        "Simplifying in chunks"
     );
 
-    // Optimize for GPU: vertex cache then compact unused vertices
-    let new_indices = meshopt::optimize_vertex_cache(&new_indices, mesh.vertex_count());
+    let (simplified, max_error) = simplify_parts(mesh, chunks, target_ratio, lock_border, config);
+    (simplified.concat(), max_error)
+}
 
-    let compacted = compact_mesh(new_indices, mesh);
+/// Simplify a mesh with several materials one material at a time, so no
+/// triangle changes material. Like chunks, materials share the vertices
+/// along their boundaries, and those are locked; large materials are
+/// chunked as well.
+///
+/// Returns each material's index buffer, referencing `mesh`'s vertices, and
+/// the largest error relative to the whole mesh's extent.
+fn simplify_by_material(
+    mesh: &IndexedMesh,
+    target_ratio: f32,
+    lock_border: bool,
+    config: &TilingConfig,
+) -> (Vec<(Option<usize>, Vec<u32>)>, f32) {
+    let mut by_material: Vec<(Option<usize>, Vec<u32>)> = Vec::new();
+    let triangles = mesh.indices.chunks_exact(3);
+    for (tri, material) in triangles.zip(mesh.triangle_materials()) {
+        match by_material.iter_mut().find(|(m, _)| *m == material) {
+            Some((_, indices)) => indices.extend_from_slice(tri),
+            None => by_material.push((material, tri.to_vec())),
+        }
+    }
 
-    SimplifiedMesh {
-        mesh: compacted,
-        achieved_error: result_error,
+    let bounds = mesh_bounds(mesh);
+    let chunk = config.simplify_chunk_triangles;
+    let mut parts = Vec::new();
+    let mut part_materials = Vec::new();
+    for (material, indices) in by_material {
+        if chunk > 0 {
+            partition_triangles(mesh, indices, &bounds, chunk, 0, &mut parts);
+        } else {
+            parts.push(indices);
+        }
+        part_materials.resize(parts.len(), material);
+    }
+
+    let (simplified, max_error) = simplify_parts(mesh, parts, target_ratio, lock_border, config);
+    let mut runs: Vec<(Option<usize>, Vec<u32>)> = Vec::new();
+    for (material, indices) in part_materials.into_iter().zip(simplified) {
+        match runs.last_mut() {
+            Some((m, run)) if *m == material => run.extend(indices),
+            _ => runs.push((material, indices)),
+        }
+    }
+    (runs, max_error)
+}
+
+/// Simplify each part (a set of whole triangles of `mesh`) on its own, in
+/// parallel, locking the vertices parts share so they stay stitched.
+///
+/// Returns each part's index buffer, referencing `mesh`'s vertices, and the
+/// largest part error relative to the whole mesh's extent.
+fn simplify_parts(
+    mesh: &IndexedMesh,
+    parts: Vec<Vec<u32>>,
+    target_ratio: f32,
+    lock_border: bool,
+    config: &TilingConfig,
+) -> (Vec<Vec<u32>>, f32) {
+    // The part using each vertex, or SEAM once a second part uses it too
+    const UNUSED: u32 = u32::MAX;
+    const SEAM: u32 = u32::MAX - 1;
+    let mut owner = vec![UNUSED; mesh.vertex_count()];
+    for (part, indices) in parts.iter().enumerate() {
+        for &i in indices {
+            let slot = &mut owner[i as usize];
+            if *slot == UNUSED {
+                *slot = part as u32;
+            } else if *slot != part as u32 {
+                *slot = SEAM;
+            }
+        }
+    }
+
+    let scale = simplify_scale(mesh);
+    let simplified: Vec<(Vec<u32>, f32)> = parts
+        .into_par_iter()
+        .map(|indices| {
+            // Local vertex i of the part is `originals[i]` of the mesh
+            let (part, originals) = extract_chunk(indices, mesh);
+            let seams: Vec<bool> = originals
+                .iter()
+                .map(|&i| owner[i as usize] == SEAM)
+                .collect();
+            let (local, error) = simplify_indices(
+                &part,
+                target_ratio,
+                lock_border,
+                false,
+                Some(&seams),
+                config,
+            );
+            let error = error * simplify_scale(&part) / scale;
+            let indices = local.iter().map(|&i| originals[i as usize]).collect();
+            (indices, error)
+        })
+        .collect();
+
+    let max_error = simplified.iter().fold(0.0_f32, |max, (_, e)| max.max(*e));
+    let indices = simplified.into_iter().map(|(indices, _)| indices).collect();
+    (indices, max_error)
+}
+
+/// Axis-aligned bounds of every vertex of `mesh`.
+fn mesh_bounds(mesh: &IndexedMesh) -> BoundingBox {
+    let mut bounds = BoundingBox {
+        min: [f64::INFINITY; 3],
+        max: [f64::NEG_INFINITY; 3],
+    };
+    for p in mesh.positions.chunks_exact(3) {
+        for (axis, &c) in p.iter().enumerate() {
+            bounds.min[axis] = bounds.min[axis].min(c as f64);
+            bounds.max[axis] = bounds.max[axis].max(c as f64);
+        }
+    }
+    bounds
+}
+
+/// Split the triangles of `indices` by centroid octant until each chunk has
+/// at most `max_triangles` triangles, appending the chunks to `chunks`.
+fn partition_triangles(
+    mesh: &IndexedMesh,
+    indices: Vec<u32>,
+    bounds: &BoundingBox,
+    max_triangles: usize,
+    depth: u32,
+    chunks: &mut Vec<Vec<u32>>,
+) {
+    if indices.len() / 3 <= max_triangles || depth >= MAX_CHUNK_DEPTH {
+        chunks.push(indices);
+        return;
+    }
+
+    let center = bounds.center();
+    let mut cells: [Vec<u32>; 8] = std::array::from_fn(|_| Vec::new());
+    for tri in indices.chunks_exact(3) {
+        let coord = |i: u32, k: usize| mesh.positions[i as usize * 3 + k] as f64;
+        let centroid = std::array::from_fn(|k| tri.iter().map(|&i| coord(i, k)).sum::<f64>() / 3.0);
+        cells[octant_index(center, centroid)].extend_from_slice(tri);
+    }
+    drop(indices);
+
+    for (octant, cell) in cells.into_iter().enumerate() {
+        if !cell.is_empty() {
+            let cell_bounds = child_bounds(bounds, octant);
+            partition_triangles(mesh, cell, &cell_bounds, max_triangles, depth + 1, chunks);
+        }
+    }
+}
+
+/// Interleave the weighted vertex attributes (normals, then UVs) into a single
+/// per-vertex buffer for `simplify_with_attributes_and_locks`.
+///
+/// Returns `None` when no attribute is present with a non-zero weight.
+fn interleave_attributes(
+    mesh: &IndexedMesh,
+    config: &TilingConfig,
+) -> Option<(Vec<f32>, Vec<f32>)> {
+    let use_normals = mesh.has_normals() && config.normal_weight > 0.0;
+    let use_uvs = mesh.has_uvs() && config.uv_weight > 0.0;
+
+    let mut weights = Vec::with_capacity(5);
+    if use_normals {
+        weights.extend_from_slice(&[config.normal_weight; 3]);
     }
+    if use_uvs {
+        weights.extend_from_slice(&[config.uv_weight; 2]);
+    }
+    if weights.is_empty() {
+        return None;
+    }
+
+    let vertex_count = mesh.vertex_count();
+    let mut attributes = Vec::with_capacity(vertex_count * weights.len());
+    for v in 0..vertex_count {
+        if use_normals {
+            attributes.extend_from_slice(&mesh.normals[v * 3..v * 3 + 3]);
+        }
+        if use_uvs {
+            attributes.extend_from_slice(&mesh.uvs[v * 2..v * 2 + 2]);
+        }
+    }
+
+    Some((attributes, weights))
 }
 
 /// Remap indices to remove unreferenced vertices and rebuild attribute arrays.
 ///
 /// Scans the index buffer to find referenced vertices, builds a compact remap,
-/// then rebuilds positions/normals/uvs/colors with only referenced vertices.
+/// then rebuilds positions/normals/uvs/uvs1/colors/feature IDs with only referenced vertices.
 pub fn compact_mesh(indices: Vec<u32>, source: &IndexedMesh) -> IndexedMesh {
     if indices.is_empty() {
         return IndexedMesh {
             material_index: source.material_index,
+            name: source.name.clone(),
             ..Default::default()
         };
     }
@@ -73,19 +450,58 @@ pub fn compact_mesh(indices: Vec<u32>, source: &IndexedMesh) -> IndexedMesh {
 
     // Build remap: old_index -> new_index (u32::MAX if unreferenced)
     let mut remap = vec![u32::MAX; vertex_count];
-    let mut next_vertex: u32 = 0;
+    let mut originals = Vec::new();
     for &idx in &indices {
         let i = idx as usize;
         if remap[i] == u32::MAX {
-            remap[i] = next_vertex;
-            next_vertex += 1;
+            remap[i] = originals.len() as u32;
+            originals.push(idx);
         }
     }
-    let new_vertex_count = next_vertex as usize;
 
     // Remap indices
     let new_indices: Vec<u32> = indices.iter().map(|&i| remap[i as usize]).collect();
 
+    gather_vertices(new_indices, &originals, source)
+}
+
+/// Like [`compact_mesh`], but keep the material of each triangle:
+/// `materials[t]` is the material of triangle `t` of `indices`.
+pub fn compact_mesh_with_materials(
+    indices: Vec<u32>,
+    materials: &[Option<usize>],
+    source: &IndexedMesh,
+) -> IndexedMesh {
+    let mut mesh = compact_mesh(indices, source);
+    mesh.set_triangle_materials(materials);
+    mesh
+}
+
+/// Copy one chunk of `mesh` out as its own mesh, like [`compact_mesh`], and
+/// return it with the mesh vertex each of its vertices came from.
+///
+/// Chunks are extracted in parallel, so the remap is a map over the chunk's
+/// own vertices rather than a table over the whole mesh.
+fn extract_chunk(indices: Vec<u32>, mesh: &IndexedMesh) -> (IndexedMesh, Vec<u32>) {
+    let mut remap: HashMap<u32, u32> = HashMap::with_capacity(indices.len() / 2);
+    let mut originals = Vec::new();
+    let local: Vec<u32> = indices
+        .iter()
+        .map(|&i| {
+            *remap.entry(i).or_insert_with(|| {
+                originals.push(i);
+                originals.len() as u32 - 1
+            })
+        })
+        .collect();
+    (gather_vertices(local, &originals, mesh), originals)
+}
+
+/// Build a mesh from `indices` over the vertices `originals` of `source`:
+/// new vertex i copies every attribute of `source` vertex `originals[i]`.
+fn gather_vertices(indices: Vec<u32>, originals: &[u32], source: &IndexedMesh) -> IndexedMesh {
+    let new_vertex_count = originals.len();
+
     // Rebuild attribute arrays
     let mut new_positions = vec![0.0f32; new_vertex_count * 3];
     let mut new_normals = if source.has_normals() {
@@ -98,17 +514,24 @@ pub fn compact_mesh(indices: Vec<u32>, source: &IndexedMesh) -> IndexedMesh {
     } else {
         vec![]
     };
+    let mut new_uvs1 = if source.has_uvs1() {
+        vec![0.0f32; new_vertex_count * 2]
+    } else {
+        vec![]
+    };
     let mut new_colors = if source.has_colors() {
         vec![0.0f32; new_vertex_count * 4]
     } else {
         vec![]
     };
+    let mut new_feature_ids = if source.has_feature_ids() {
+        vec![0u32; new_vertex_count]
+    } else {
+        vec![]
+    };
 
-    for (old_idx, &new_idx) in remap.iter().enumerate() {
-        if new_idx == u32::MAX {
-            continue;
-        }
-        let ni = new_idx as usize;
+    for (ni, &old_idx) in originals.iter().enumerate() {
+        let old_idx = old_idx as usize;
 
         // Positions (stride 3)
         new_positions[ni * 3] = source.positions[old_idx * 3];
@@ -128,6 +551,12 @@ pub fn compact_mesh(indices: Vec<u32>, source: &IndexedMesh) -> IndexedMesh {
             new_uvs[ni * 2 + 1] = source.uvs[old_idx * 2 + 1];
         }
 
+        // Second UV set (stride 2)
+        if source.has_uvs1() {
+            new_uvs1[ni * 2] = source.uvs1[old_idx * 2];
+            new_uvs1[ni * 2 + 1] = source.uvs1[old_idx * 2 + 1];
+        }
+
         // Colors (stride 4)
         if source.has_colors() {
             new_colors[ni * 4] = source.colors[old_idx * 4];
@@ -135,15 +564,24 @@ pub fn compact_mesh(indices: Vec<u32>, source: &IndexedMesh) -> IndexedMesh {
             new_colors[ni * 4 + 2] = source.colors[old_idx * 4 + 2];
             new_colors[ni * 4 + 3] = source.colors[old_idx * 4 + 3];
         }
+
+        // Feature IDs (stride 1)
+        if source.has_feature_ids() {
+            new_feature_ids[ni] = source.feature_ids[old_idx];
+        }
     }
 
     IndexedMesh {
         positions: new_positions,
         normals: new_normals,
         uvs: new_uvs,
+        uvs1: new_uvs1,
         colors: new_colors,
-        indices: new_indices,
+        feature_ids: new_feature_ids,
+        indices,
         material_index: source.material_index,
+        name: source.name.clone(),
+        material_groups: Vec::new(),
     }
 }
 
@@ -184,9 +622,13 @@ mod tests {
             positions,
             normals,
             uvs,
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices,
             material_index: None,
+            name: None,
+            material_groups: Vec::new(),
         }
     }
 
@@ -195,7 +637,7 @@ mod tests {
         let mesh = make_grid(50); // 50x50 = 2500 quads = 5000 triangles
         assert_eq!(mesh.triangle_count(), 5000);
 
-        let result = simplify_mesh(&mesh, 0.5, false);
+        let result = simplify_mesh(&mesh, 0.5, false, &TilingConfig::default());
         // Should have meaningfully fewer triangles
         assert!(result.mesh.triangle_count() < mesh.triangle_count());
         assert!(result.mesh.triangle_count() > 0);
@@ -204,7 +646,7 @@ mod tests {
     #[test]
     fn simplify_preserves_attributes() {
         let mesh = make_grid(20);
-        let result = simplify_mesh(&mesh, 0.5, false);
+        let result = simplify_mesh(&mesh, 0.5, false, &TilingConfig::default());
 
         // Simplified mesh should still have normals and UVs
         assert!(result.mesh.has_normals());
@@ -220,19 +662,92 @@ mod tests {
     #[test]
     fn simplify_empty_mesh() {
         let mesh = IndexedMesh::default();
-        let result = simplify_mesh(&mesh, 0.5, false);
+        let result = simplify_mesh(&mesh, 0.5, false, &TilingConfig::default());
         assert!(result.mesh.is_empty());
         assert_eq!(result.achieved_error, 0.0);
     }
 
+    #[test]
+    fn simplify_keeps_each_triangles_material() {
+        // Left half of the grid is material 0, right half material 1
+        let n = 30;
+        let mut mesh = make_grid(n);
+        let materials: Vec<Option<usize>> = (0..mesh.triangle_count())
+            .map(|t| Some(usize::from((t / 2) % n >= n / 2)))
+            .collect();
+        mesh.set_triangle_materials(&materials);
+
+        let result = simplify_mesh(&mesh, 0.25, false, &TilingConfig::default());
+        let simplified = &result.mesh;
+        assert!(simplified.triangle_count() < mesh.triangle_count() / 2);
+
+        // One contiguous group per material, each still on its own half
+        assert_eq!(simplified.material_groups.len(), 2);
+        for (tri, material) in simplified
+            .indices
+            .chunks_exact(3)
+            .zip(simplified.triangle_materials())
+        {
+            let xs = tri.iter().map(|&i| simplified.positions[i as usize * 3]);
+            let x = xs.sum::<f32>() / 3.0;
+            assert_eq!(material, Some(usize::from(x > 0.5)), "centroid x {x}");
+        }
+    }
+
     #[test]
     fn simplify_with_lock_border() {
         let mesh = make_grid(30);
-        let result = simplify_mesh(&mesh, 0.25, true);
+        let result = simplify_mesh(&mesh, 0.25, true, &TilingConfig::default());
         assert!(result.mesh.triangle_count() < mesh.triangle_count());
         assert!(result.mesh.triangle_count() > 0);
     }
 
+    #[test]
+    fn chunked_simplification_matches_whole_mesh() {
+        let n = 200;
+        let mesh = make_grid(n);
+        let whole = simplify_mesh(&mesh, 0.25, true, &TilingConfig::default());
+        let config = TilingConfig {
+            simplify_chunk_triangles: 10_000,
+            ..Default::default()
+        };
+        let chunked = simplify_mesh(&mesh, 0.25, true, &config);
+
+        // Seams keep a little more, but the reduction is comparable
+        let whole_tris = whole.mesh.triangle_count();
+        let chunked_tris = chunked.mesh.triangle_count();
+        assert!(chunked_tris < mesh.triangle_count() / 2, "{chunked_tris}");
+        assert!(chunked_tris <= whole_tris * 3 / 2, "{chunked_tris}");
+
+        // Both keep every vertex of the grid's outer border
+        let border = |m: &IndexedMesh| {
+            let mut border: Vec<[u32; 2]> = m
+                .positions
+                .chunks_exact(3)
+                .filter(|p| p[0] == 0.0 || p[0] == 1.0 || p[1] == 0.0 || p[1] == 1.0)
+                .map(|p| [p[0].to_bits(), p[1].to_bits()])
+                .collect();
+            border.sort_unstable();
+            border
+        };
+        assert_eq!(border(&chunked.mesh).len(), 4 * n);
+        assert_eq!(border(&chunked.mesh), border(&whole.mesh));
+
+        // Chunks share their seam vertices, so only the outer border is open
+        let topology = crate::ingestion::topology::analyze_topology(&[chunked.mesh]);
+        assert_eq!(topology.boundary_edges, 4 * n);
+        assert_eq!(topology.non_manifold_edges, 0);
+
+        // Without lock_border the outer border may simplify too, but the
+        // seams stay shut: the only open edges still run along it
+        let unlocked = simplify_mesh(&mesh, 0.25, false, &config);
+        let border_vertices = border(&unlocked.mesh).len();
+        assert!(border_vertices < 4 * n, "{border_vertices}");
+        let topology = crate::ingestion::topology::analyze_topology(&[unlocked.mesh]);
+        assert_eq!(topology.boundary_edges, border_vertices);
+        assert_eq!(topology.non_manifold_edges, 0);
+    }
+
     #[test]
     fn compact_mesh_removes_unreferenced() {
         // Create a mesh with 4 vertices but only use 3 (one triangle)
@@ -247,9 +762,13 @@ mod tests {
                 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0,
             ],
             uvs: vec![],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2],
             material_index: Some(0),
+            name: None,
+            material_groups: Vec::new(),
         };
 
         let compacted = compact_mesh(vec![0, 1, 2], &source);
@@ -262,9 +781,209 @@ mod tests {
     #[test]
     fn simplify_aggressive_ratio() {
         let mesh = make_grid(100); // 10000 quads = 20000 triangles
-        let result = simplify_mesh(&mesh, 0.01, false);
+        let result = simplify_mesh(&mesh, 0.01, false, &TilingConfig::default());
         // Even at 1% target, should produce valid geometry
         assert!(result.mesh.triangle_count() > 0);
         assert!(result.mesh.triangle_count() < mesh.triangle_count());
     }
+
+    /// Build a unit cube where each face is an `n x n` grid with its own UV
+    /// chart and flat normal. Faces share no vertices, so every cube edge is a
+    /// UV/normal seam.
+    fn make_seamed_cube(n: usize) -> IndexedMesh {
+        // (normal axis, sign, u axis, v axis)
+        let faces = [
+            (0, 1.0, 1, 2),
+            (0, -1.0, 2, 1),
+            (1, 1.0, 2, 0),
+            (1, -1.0, 0, 2),
+            (2, 1.0, 0, 1),
+            (2, -1.0, 1, 0),
+        ];
+        let mut mesh = IndexedMesh::default();
+        let side = n + 1;
+
+        for (face, &(axis, sign, ua, va)) in faces.iter().enumerate() {
+            let base = mesh.vertex_count() as u32;
+            for y in 0..side {
+                for x in 0..side {
+                    let fu = x as f32 / n as f32;
+                    let fv = y as f32 / n as f32;
+                    let mut p = [0.0f32; 3];
+                    p[axis] = sign;
+                    p[ua] = fu * 2.0 - 1.0;
+                    p[va] = fv * 2.0 - 1.0;
+                    let mut nrm = [0.0f32; 3];
+                    nrm[axis] = sign;
+                    mesh.positions.extend_from_slice(&p);
+                    mesh.normals.extend_from_slice(&nrm);
+                    // Each face gets its own (inset) column of the texture
+                    mesh.uvs
+                        .extend_from_slice(&[(face as f32 + 0.1 + 0.8 * fu) / 6.0, fv]);
+                }
+            }
+            for y in 0..n {
+                for x in 0..n {
+                    let tl = base + (y * side + x) as u32;
+                    let tr = tl + 1;
+                    let bl = tl + side as u32;
+                    let br = bl + 1;
+                    mesh.indices.extend_from_slice(&[tl, bl, tr, tr, bl, br]);
+                }
+            }
+        }
+
+        mesh
+    }
+
+    #[test]
+    fn simplify_preserves_uv_seams_on_cube() {
+        let mesh = make_seamed_cube(8);
+        let result = simplify_mesh(&mesh, 0.3, false, &TilingConfig::default());
+        let out = &result.mesh;
+        assert!(out.triangle_count() < mesh.triangle_count());
+        assert!(out.triangle_count() > 0);
+
+        // Recover the face (UV chart) of each output vertex from its UV.
+        let face_of_out: Vec<usize> = (0..out.vertex_count())
+            .map(|v| (out.uvs[v * 2] * 6.0).floor() as usize)
+            .collect();
+
+        // No triangle may straddle a seam: all corners must share a chart.
+        for tri in out.indices.chunks_exact(3) {
+            let f = face_of_out[tri[0] as usize];
+            assert_eq!(face_of_out[tri[1] as usize], f, "triangle spans a UV seam");
+            assert_eq!(face_of_out[tri[2] as usize], f, "triangle spans a UV seam");
+        }
+
+        // Every cube corner survives on every face that touches it.
+        let mut corners = std::collections::HashSet::new();
+        for (p, &face) in out.positions.chunks_exact(3).zip(&face_of_out) {
+            if p.iter().all(|c| (c.abs() - 1.0).abs() < 1e-6) {
+                let key: Vec<i32> = p.iter().map(|c| c.signum() as i32).collect();
+                corners.insert((key, face));
+            }
+        }
+        assert_eq!(
+            corners.len(),
+            8 * 3,
+            "each corner should survive on its 3 faces"
+        );
+    }
+
+    #[test]
+    fn simplify_zero_weights_falls_back_to_positions() {
+        let mesh = make_grid(20);
+        let config = TilingConfig {
+            normal_weight: 0.0,
+            uv_weight: 0.0,
+            ..Default::default()
+        };
+        assert!(interleave_attributes(&mesh, &config).is_none());
+        let result = simplify_mesh(&mesh, 0.5, false, &config);
+        assert!(result.mesh.triangle_count() < mesh.triangle_count());
+    }
+
+    #[test]
+    fn interleave_attributes_layout() {
+        let mesh = make_grid(1);
+        let (attrs, weights) = interleave_attributes(&mesh, &TilingConfig::default()).unwrap();
+        assert_eq!(weights, vec![0.5, 0.5, 0.5, 1.0, 1.0]);
+        assert_eq!(attrs.len(), mesh.vertex_count() * 5);
+        // Vertex 1 of a 1x1 grid: normal (0,0,1), uv (1,0)
+        assert_eq!(&attrs[5..10], &[0.0, 0.0, 1.0, 1.0, 0.0]);
+    }
+
+    /// Build an `n x n` grid with every other quad removed (checkerboard),
+    /// giving a perforated, gently curved sheet where nearly every vertex lies
+    /// on a border.
+    fn make_perforated_grid(n: usize) -> IndexedMesh {
+        let mut mesh = make_grid(n);
+        let side = n + 1;
+        for p in mesh.positions.chunks_exact_mut(3) {
+            p[2] = 0.05 * (p[0] * std::f32::consts::TAU).sin();
+        }
+        mesh.indices.clear();
+        for y in 0..n {
+            for x in 0..n {
+                if (x + y) % 2 == 1 {
+                    continue;
+                }
+                let tl = (y * side + x) as u32;
+                let tr = tl + 1;
+                let bl = tl + side as u32;
+                let br = bl + 1;
+                mesh.indices.extend_from_slice(&[tl, bl, tr, tr, bl, br]);
+            }
+        }
+        mesh
+    }
+
+    #[test]
+    fn sloppy_fallback_reaches_aggressive_target() {
+        let mesh = make_perforated_grid(60);
+        let ratio = 0.05;
+        let target_tris = (mesh.triangle_count() as f32 * ratio) as usize;
+
+        let strict = simplify_mesh(&mesh, ratio, false, &TilingConfig::default());
+        assert!(
+            strict.mesh.triangle_count() > target_tris * 3 / 2,
+            "perforated mesh should stall ({} tris, target {target_tris})",
+            strict.mesh.triangle_count()
+        );
+
+        let config = TilingConfig {
+            allow_sloppy: true,
+            ..Default::default()
+        };
+        let sloppy = simplify_mesh(&mesh, ratio, false, &config);
+        assert!(sloppy.mesh.triangle_count() > 0);
+        assert!(
+            sloppy.mesh.triangle_count() <= target_tris,
+            "sloppy fallback should reach target ({} tris, target {target_tris})",
+            sloppy.mesh.triangle_count()
+        );
+        assert!(sloppy.achieved_error > 0.0);
+    }
+
+    #[test]
+    fn sloppy_fallback_keeps_locked_border() {
+        let mesh = make_perforated_grid(60);
+        let config = TilingConfig {
+            allow_sloppy: true,
+            ..Default::default()
+        };
+        let with = simplify_mesh(&mesh, 0.05, true, &config);
+        let without = simplify_mesh(&mesh, 0.05, true, &TilingConfig::default());
+        assert_eq!(with.mesh.indices, without.mesh.indices);
+
+        // Every vertex the input uses on the grid's outer border survives
+        let border = |m: &IndexedMesh| {
+            let mut border: Vec<[u32; 2]> = m
+                .indices
+                .iter()
+                .map(|&i| &m.positions[i as usize * 3..i as usize * 3 + 3])
+                .filter(|p| p[0] == 0.0 || p[0] == 1.0 || p[1] == 0.0 || p[1] == 1.0)
+                .map(|p| [p[0].to_bits(), p[1].to_bits()])
+                .collect();
+            border.sort_unstable();
+            border.dedup();
+            border
+        };
+        assert!(!border(&mesh).is_empty());
+        assert_eq!(border(&with.mesh), border(&mesh));
+    }
+
+    #[test]
+    fn sloppy_fallback_not_used_when_target_reached() {
+        let mesh = make_grid(50);
+        let config = TilingConfig {
+            allow_sloppy: true,
+            ..Default::default()
+        };
+        let with = simplify_mesh(&mesh, 0.5, false, &config);
+        let without = simplify_mesh(&mesh, 0.5, false, &TilingConfig::default());
+        assert_eq!(with.mesh.indices, without.mesh.indices);
+        assert_eq!(with.achieved_error, without.achieved_error);
+    }
 }
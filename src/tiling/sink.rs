@@ -0,0 +1,115 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Destination for the files of a tileset: tiles, shared atlases and
+/// tileset.json documents.
+///
+/// Paths are relative to the tileset root and use `/` separators, like the
+/// URIs in tileset.json. Tiles are written from several threads at once.
+pub trait TileSink: Sync {
+    /// Store `bytes` at `path`, replacing any previous file. The parent
+    /// directory has been created with [`TileSink::create_dir`].
+    fn write(&self, path: &str, bytes: &[u8]) -> io::Result<()>;
+
+    /// Create the directory `path` and its parents. Creating an existing
+    /// directory succeeds.
+    fn create_dir(&self, path: &str) -> io::Result<()>;
+}
+
+/// Write `bytes` to `path` in `sink`, creating its parent directory first.
+pub(crate) fn write_file(sink: &dyn TileSink, path: &str, bytes: &[u8]) -> io::Result<()> {
+    if let Some((dir, _)) = path.rsplit_once('/') {
+        sink.create_dir(dir)?;
+    }
+    sink.write(path, bytes)
+}
+
+/// Writes files under a directory on the local filesystem.
+#[derive(Debug, Clone)]
+pub struct FsSink {
+    root: PathBuf,
+}
+
+impl FsSink {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Filesystem path of the file at `path` in the tileset.
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+impl TileSink for FsSink {
+    fn write(&self, path: &str, bytes: &[u8]) -> io::Result<()> {
+        write_atomic(&self.path(path), bytes)
+    }
+
+    fn create_dir(&self, path: &str) -> io::Result<()> {
+        fs::create_dir_all(self.path(path))
+    }
+}
+
+/// Attempts made by `write_atomic` before giving up on a transient error.
+const WRITE_ATTEMPTS: usize = 3;
+
+/// Write `data` to `path` via a temporary sibling file and a rename, so a
+/// reader never observes a half-written file. Transient errors are retried
+/// a few times.
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut attempt = 1;
+    loop {
+        let result = fs::write(&tmp_path, data).and_then(|()| fs::rename(&tmp_path, path));
+        match result {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < WRITE_ATTEMPTS && is_transient(&e) => attempt += 1,
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(e);
+            }
+        }
+    }
+}
+
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_atomic_replaces_file_without_leftovers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.glb");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert!(!tmp.path().join("a.glb.tmp").exists());
+    }
+
+    #[test]
+    fn fs_sink_creates_parent_directories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sink = FsSink::new(tmp.path());
+
+        write_file(&sink, "tiles/0/0_3/tile.glb", b"glb").unwrap();
+        write_file(&sink, "tileset.json", b"{}").unwrap();
+
+        let tile = tmp.path().join("tiles/0/0_3/tile.glb");
+        assert_eq!(fs::read(tile).unwrap(), b"glb");
+        assert_eq!(fs::read(sink.path("tileset.json")).unwrap(), b"{}");
+    }
+}
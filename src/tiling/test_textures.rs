@@ -0,0 +1,25 @@
+//! Texture fixtures shared by the tiling tests.
+
+use image::RgbaImage;
+
+use crate::types::TextureData;
+
+/// `image` encoded as an in-memory PNG texture with the default wrap modes.
+pub(crate) fn png_texture(image: &RgbaImage) -> TextureData {
+    let mut buf = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut buf, image::ImageFormat::Png).unwrap();
+    TextureData {
+        data: buf.into_inner(),
+        mime_type: "image/png".into(),
+        width: image.width(),
+        height: image.height(),
+        wrap: Default::default(),
+    }
+}
+
+/// A `size`² PNG whose texels all differ, so it compresses poorly.
+pub(crate) fn gradient_texture(size: u32) -> TextureData {
+    png_texture(&RgbaImage::from_fn(size, size, |x, y| {
+        image::Rgba([(x * 7 + y * 13) as u8, (x * y) as u8, (x ^ y) as u8, 255])
+    }))
+}
@@ -4,27 +4,89 @@ use image::{ImageFormat, RgbaImage};
 use tracing::warn;
 
 use crate::config::{TextureConfig, TextureFormat};
-use crate::types::TextureData;
+use crate::types::{TextureData, WrapMode};
 
-/// Encode an RGBA image according to the given texture configuration.
+/// Encode an sRGB colour image according to the given texture configuration.
+///
+/// The result is set up for clamped sampling, as atlases need.
 pub fn compress_texture(image: &RgbaImage, config: &TextureConfig) -> TextureData {
+    encode_texture(image, config, false)
+}
+
+/// Encode an image built from `sources` like [`compress_texture`], except
+/// that [`TextureFormat::Original`] keeps the sources' encoding: JPEG when
+/// every source is a JPEG, PNG otherwise. `linear` marks non-colour data
+/// (metallic-roughness and normal maps), which KTX2 must not treat as sRGB.
+pub fn compress_texture_like(
+    image: &RgbaImage,
+    config: &TextureConfig,
+    sources: &[&TextureData],
+    linear: bool,
+) -> TextureData {
+    let all_jpeg = !sources.is_empty()
+        && sources
+            .iter()
+            .all(|s| encoded_format(s) == Some(ImageFormat::Jpeg));
+    if config.format == TextureFormat::Original && all_jpeg {
+        let (width, height) = image.dimensions();
+        return encode_jpeg(image, width, height, config.quality);
+    }
+    encode_texture(image, config, linear)
+}
+
+fn encode_texture(image: &RgbaImage, config: &TextureConfig, linear: bool) -> TextureData {
     let (width, height) = image.dimensions();
 
     match config.format {
         TextureFormat::WebP => encode_webp(image, width, height),
         TextureFormat::Original => encode_png(image, width, height),
-        TextureFormat::Ktx2 => encode_ktx2(image, width, height, config.quality),
+        TextureFormat::Ktx2 => encode_ktx2(image, width, height, config.quality, linear),
     }
 }
 
-/// Encode an RGBA image to Basis Universal format (UASTC mode for high quality).
+/// `source` unchanged when [`TextureFormat::Original`] is selected and it is
+/// an encoded PNG or JPEG no larger than `max_dim`, so its bytes are written
+/// as they were read. Otherwise it has to be decoded and re-encoded.
+pub fn pass_through(
+    source: &TextureData,
+    config: &TextureConfig,
+    max_dim: u32,
+) -> Option<TextureData> {
+    if config.format != TextureFormat::Original || source.width.max(source.height) > max_dim {
+        return None;
+    }
+    let mime_type = match encoded_format(source)? {
+        ImageFormat::Png => "image/png",
+        ImageFormat::Jpeg => "image/jpeg",
+        _ => return None,
+    };
+    Some(TextureData {
+        mime_type: mime_type.into(),
+        ..source.clone()
+    })
+}
+
+/// Format of `source`'s bytes when they are an encoded image. Decoded
+/// pixel buffers (as the glTF loader produces) sniff as nothing.
+fn encoded_format(source: &TextureData) -> Option<ImageFormat> {
+    image::guess_format(&source.data).ok()
+}
+
+/// Encode an RGBA image to Basis Universal format (UASTC mode for high quality),
+/// in the linear colour space when `linear` is set and sRGB otherwise.
 ///
 /// When the `ktx2` feature is enabled, uses the basis-universal crate.
 /// Otherwise, falls back to WebP with a warning.
-fn encode_ktx2(image: &RgbaImage, width: u32, height: u32, quality: u8) -> TextureData {
+fn encode_ktx2(
+    image: &RgbaImage,
+    width: u32,
+    height: u32,
+    quality: u8,
+    linear: bool,
+) -> TextureData {
     #[cfg(feature = "ktx2")]
     {
-        match encode_basis_universal(image, width, height, quality) {
+        match encode_basis_universal(image, width, height, quality, linear) {
             Ok(data) => data,
             Err(e) => {
                 warn!("Basis Universal encoding failed ({e}), falling back to WebP");
@@ -35,18 +97,39 @@ fn encode_ktx2(image: &RgbaImage, width: u32, height: u32, quality: u8) -> Textu
 
     #[cfg(not(feature = "ktx2"))]
     {
-        let _ = quality;
+        let _ = (quality, linear);
         warn!("KTX2 support requires the 'ktx2' feature flag, falling back to WebP");
         encode_webp(image, width, height)
     }
 }
 
+/// Full mip chain for `image`: level 0 is the image itself, each further
+/// level halves both dimensions (Lanczos3) down to 1x1.
+#[cfg_attr(not(feature = "ktx2"), allow(dead_code))]
+fn mip_chain(image: &RgbaImage) -> Vec<RgbaImage> {
+    let mut levels = vec![image.clone()];
+    loop {
+        let (w, h) = levels[levels.len() - 1].dimensions();
+        if w <= 1 && h <= 1 {
+            return levels;
+        }
+        let next = image::imageops::resize(
+            &levels[levels.len() - 1],
+            (w / 2).max(1),
+            (h / 2).max(1),
+            image::imageops::FilterType::Lanczos3,
+        );
+        levels.push(next);
+    }
+}
+
 #[cfg(feature = "ktx2")]
 fn encode_basis_universal(
     image: &RgbaImage,
     width: u32,
     height: u32,
     quality: u8,
+    linear: bool,
 ) -> std::result::Result<TextureData, String> {
     use basis_universal::encoding::{
         encoder_init, ColorSpace, Compressor, CompressorParams,
@@ -71,13 +154,29 @@ fn encode_basis_universal(
 
     // Enable RDO for better compression ratios
     params.set_rdo_uastc(Some(1.0));
+    // The glTF sampler asks for trilinear filtering, so ship every mip level
+    // instead of leaving runtimes to build them at load time.
     params.set_generate_mipmaps(false);
-    params.set_color_space(ColorSpace::Srgb);
+    params.set_color_space(if linear {
+        ColorSpace::Linear
+    } else {
+        ColorSpace::Srgb
+    });
 
-    // Set source image data
+    // Set source image data: level 0, then levels 1.. as mipmap images
     let rgba_bytes = image.as_raw();
     params.source_image_mut(0).init(rgba_bytes, width, height, 4);
 
+    let levels = mip_chain(image);
+    params.resize_source_mipmap_image_list(1);
+    params.resize_source_mipmap_image_level_list(0, levels.len() as u32 - 1);
+    for (i, level) in levels.iter().enumerate().skip(1) {
+        let (w, h) = level.dimensions();
+        params
+            .source_mipmap_image_mut(0, i as u32 - 1)
+            .init(level.as_raw(), w, h, 4);
+    }
+
     // Compress
     let mut compressor = Compressor::new(4); // Use up to 4 threads
     // SAFETY: params and compressor are valid, encoder_init() was called
@@ -98,6 +197,7 @@ fn encode_basis_universal(
         mime_type: "image/ktx2".into(),
         width,
         height,
+        wrap: [WrapMode::ClampToEdge; 2],
     })
 }
 
@@ -109,6 +209,7 @@ fn encode_webp(image: &RgbaImage, width: u32, height: u32) -> TextureData {
             mime_type: "image/webp".into(),
             width,
             height,
+            wrap: [WrapMode::ClampToEdge; 2],
         },
         Err(e) => {
             warn!("WebP encoding failed ({e}), falling back to PNG");
@@ -117,6 +218,26 @@ fn encode_webp(image: &RgbaImage, width: u32, height: u32) -> TextureData {
     }
 }
 
+fn encode_jpeg(image: &RgbaImage, width: u32, height: u32, quality: u8) -> TextureData {
+    let mut buf = Cursor::new(Vec::new());
+    let rgb = image::DynamicImage::ImageRgba8(image.clone()).into_rgb8();
+    let quality = quality.clamp(1, 100);
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality);
+    match rgb.write_with_encoder(encoder) {
+        Ok(()) => TextureData {
+            data: buf.into_inner(),
+            mime_type: "image/jpeg".into(),
+            width,
+            height,
+            wrap: [WrapMode::ClampToEdge; 2],
+        },
+        Err(e) => {
+            warn!("JPEG encoding failed ({e}), falling back to PNG");
+            encode_png(image, width, height)
+        }
+    }
+}
+
 fn encode_png(image: &RgbaImage, width: u32, height: u32) -> TextureData {
     let mut buf = Cursor::new(Vec::new());
     image
@@ -127,12 +248,14 @@ fn encode_png(image: &RgbaImage, width: u32, height: u32) -> TextureData {
         mime_type: "image/png".into(),
         width,
         height,
+        wrap: [WrapMode::ClampToEdge; 2],
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tiling::test_textures::png_texture;
 
     fn checkerboard(size: u32) -> RgbaImage {
         RgbaImage::from_fn(size, size, |x, y| {
@@ -201,6 +324,76 @@ mod tests {
         assert_eq!(webp_td.mime_type, "image/webp");
     }
 
+    #[test]
+    fn original_format_keeps_source_encoding() {
+        let img = checkerboard(8);
+        let original = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let webp = TextureConfig {
+            format: TextureFormat::WebP,
+            ..Default::default()
+        };
+        let png = png_texture(&img);
+
+        // Encoded sources within the size limit are passed through byte for byte
+        let kept = pass_through(&png, &original, 8).unwrap();
+        assert_eq!(kept.data, png.data);
+        assert_eq!(kept.wrap, png.wrap);
+        assert!(pass_through(&png, &original, 4).is_none());
+        assert!(pass_through(&png, &webp, 8).is_none());
+        // Decoded pixels (glTF images) can't be written as they are
+        let raw = TextureData {
+            data: img.as_raw().clone(),
+            ..png.clone()
+        };
+        assert!(pass_through(&raw, &original, 8).is_none());
+
+        // Re-encoded images stay JPEG when built from JPEGs only
+        let jpeg = encode_jpeg(&img, 8, 8, 90);
+        assert_eq!(image::guess_format(&jpeg.data).unwrap(), ImageFormat::Jpeg);
+        let like =
+            |sources: &[&TextureData]| compress_texture_like(&img, &original, sources, false);
+        assert_eq!(like(&[&jpeg]).mime_type, "image/jpeg");
+        assert_eq!(like(&[&jpeg, &png]).mime_type, "image/png");
+        assert_eq!(like(&[]).mime_type, "image/png");
+        let webp_td = compress_texture_like(&img, &webp, &[&jpeg], false);
+        assert_eq!(webp_td.mime_type, "image/webp");
+    }
+
+    #[test]
+    fn mip_chain_halves_down_to_one_pixel() {
+        let levels = mip_chain(&checkerboard(256));
+        assert_eq!(levels.len(), 9);
+        for (i, level) in levels.iter().enumerate() {
+            let size = 256 >> i;
+            assert_eq!(level.dimensions(), (size, size));
+        }
+
+        // Non-square images keep halving the longer side after the short one hits 1
+        let dims: Vec<_> = mip_chain(&RgbaImage::new(8, 2))
+            .iter()
+            .map(|l| l.dimensions())
+            .collect();
+        assert_eq!(dims, [(8, 2), (4, 1), (2, 1), (1, 1)]);
+    }
+
+    #[cfg(feature = "ktx2")]
+    #[test]
+    fn ktx2_atlas_has_mip_levels() {
+        let config = TextureConfig {
+            format: TextureFormat::Ktx2,
+            ..Default::default()
+        };
+        let td = compress_texture(&checkerboard(256), &config);
+        assert_eq!(td.mime_type, "image/ktx2");
+
+        basis_universal::transcoder_init();
+        let transcoder = basis_universal::Transcoder::new();
+        assert!(transcoder.image_level_count(&td.data, 0) > 1);
+    }
+
     #[test]
     fn ktx2_encoding() {
         let img = checkerboard(4);
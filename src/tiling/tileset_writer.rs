@@ -1,23 +1,123 @@
-use std::fs;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::ops::Range;
 use std::path::Path;
+use std::sync::Mutex;
 
 use rayon::prelude::*;
 use serde_json::json;
-use tracing::info;
+use tracing::{debug, info};
 
-use crate::config::{TextureConfig, TilingConfig};
+use crate::cache;
+use crate::config::{Refine, Scheme, Split, TextureConfig, TileOrder, TilesVersion, TilingConfig};
 use crate::error::{PhotoTilerError, Result};
+use crate::hash::xxh64;
 use crate::tiling::atlas_repacker;
-use crate::tiling::glb_writer::write_glb_compressed;
-use crate::tiling::lod::LodChain;
-use crate::tiling::octree::{child_bounds, split_mesh};
-use crate::tiling::simplifier::simplify_mesh;
-use crate::types::{BoundingBox, IndexedMesh, MaterialLibrary, TileContent, TileNode};
+use crate::tiling::glb_writer::{
+    ImageSource, TileImages, add_batch_ids, add_gpu_instancing, feature_schema, write_b3dm,
+    write_glb, write_glb_compressed, write_glb_compressed_oct, write_glb_quantized,
+};
+use crate::tiling::lod::{LodChain, calibrated_geometric_error, reduction_error};
+use crate::tiling::octree::{
+    LeafBudget, auto_max_depth, child_bounds, hilbert_index, quadrant_bounds, split_mesh,
+    split_mesh_centroid, split_mesh_quadtree, split_mesh_unwelded,
+};
+use crate::tiling::simplifier::{compact_mesh_with_materials, simplify_mesh};
+use crate::tiling::sink::{FsSink, TileSink, write_file};
+use crate::transform::coordinates::{MIN_HALF_EXTENT, compute_bounding_box, ensure_min_extent};
+use crate::types::{
+    BoundingBox, IndexedMesh, MaterialGroup, MaterialLibrary, OrientedBox, PBRMaterial,
+    TextureData, TileContent, TileNode,
+};
 
 /// Intermediate output of tile hierarchy construction.
 pub struct TilesetOutput {
     pub root: TileNode,
     pub root_transform: [f64; 16],
+    pub atlas_stats: AtlasStats,
+    pub tile_stats: TileStats,
+    /// Version written to `asset.version` by `write_tileset`.
+    pub tiles_version: TilesVersion,
+    /// Refinement strategy written to every tile's `refine`.
+    pub refine: Refine,
+    /// Metadata schema of the tiles' feature tables, written as the 1.1
+    /// top-level `schema`. `None` when tiles carry no feature IDs.
+    pub schema: Option<serde_json::Value>,
+    /// Tiles are b3dm rather than GLB content, see `TilingConfig::b3dm`.
+    pub b3dm: bool,
+    /// Prefix for content URIs in tileset.json, see `TilingConfig::uri_base`.
+    pub uri_base: Option<String>,
+    /// Tiles at multiples of this depth are written as external tilesets,
+    /// see `TilingConfig::external_tileset_depth`.
+    pub external_tileset_depth: Option<u32>,
+    /// Tag every tile with its level and triangle count, see
+    /// `TilingConfig::tile_extras`.
+    pub tile_extras: bool,
+    /// ECEF origin of a local ENU output frame, written to the root
+    /// tileset's `extras` (see `TransformResult::ecef_origin`).
+    pub ecef_origin: Option<[f64; 3]>,
+    /// Files that could not be written during the build. When non-empty,
+    /// `write_tileset` refuses to produce `tileset.json`.
+    pub write_errors: Vec<String>,
+}
+
+/// Atlas deduplication statistics collected while building the tileset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AtlasStats {
+    /// Number of tiles that produced an atlas texture.
+    pub atlases: usize,
+    /// Number of distinct atlas images (by content hash).
+    pub unique_atlases: usize,
+    /// Encoded bytes of atlases identical to one produced earlier.
+    /// With `TextureConfig::share_atlases` these bytes are actually saved.
+    pub duplicate_bytes: u64,
+    /// Total pixel count over all atlases, duplicates included.
+    pub texels: u64,
+    /// Texels of island content over all packed atlases.
+    pub island_texels: u64,
+    /// Texels of all packed atlases, before any downscale to the max size.
+    pub packed_texels: u64,
+    /// Packed atlases whose packing efficiency is below
+    /// [`atlas_repacker::LOW_PACKING_EFFICIENCY`].
+    pub sparse_atlases: usize,
+    /// Encoded atlas bytes counted against `TextureConfig::max_memory`.
+    pub budget_bytes: u64,
+    /// Tiles written untextured because even their smallest atlas would
+    /// have exceeded `TextureConfig::max_memory`.
+    pub over_budget: usize,
+}
+
+impl AtlasStats {
+    /// Fraction of all packed atlas texels covered by islands, or `None`
+    /// when no atlas was packed.
+    pub fn packing_efficiency(&self) -> Option<f64> {
+        (self.packed_texels > 0).then(|| self.island_texels as f64 / self.packed_texels as f64)
+    }
+}
+
+/// Output statistics collected while tiles are written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TileStats {
+    /// Tiles with content at each tree depth; index 0 is the root.
+    pub tiles_per_level: Vec<usize>,
+    /// Triangles written across all tile GLBs.
+    pub triangles: usize,
+    /// Bytes written for tile GLBs and shared atlas files, plus tileset.json
+    /// and any external tilesets once [`write_tileset`] has run.
+    pub bytes_written: u64,
+}
+
+impl TileStats {
+    fn record_tile(&mut self, level: usize, triangles: usize, bytes: u64) {
+        if self.tiles_per_level.len() <= level {
+            self.tiles_per_level.resize(level + 1, 0);
+        }
+        self.tiles_per_level[level] += 1;
+        self.triangles += triangles;
+        self.bytes_written += bytes;
+    }
 }
 
 /// Convert a tile address to a hierarchical URI path.
@@ -50,58 +150,812 @@ fn address_to_uri(address: &str) -> String {
     format!("tiles/{dir_path}/tile.glb")
 }
 
+/// Content URI of the tile at `address`: [`address_to_uri`], with a
+/// `.b3dm` extension when tiles are written as Batched 3D Models.
+fn content_uri(address: &str, tiling: &TilingConfig) -> String {
+    let uri = address_to_uri(address);
+    match uri.strip_suffix(".glb") {
+        Some(stem) if tiling.b3dm => format!("{stem}.b3dm"),
+        _ => uri,
+    }
+}
+
+/// Tree depth of a tile address (`"root"` is 0, `"0_3"` is 2).
+fn address_depth(address: &str) -> usize {
+    if address == "root" {
+        0
+    } else {
+        address.split('_').count()
+    }
+}
+
+/// Shared state threaded through the recursive tile build.
+struct BuildContext<'a> {
+    tiling: &'a TilingConfig,
+    materials: &'a MaterialLibrary,
+    texture_config: &'a TextureConfig,
+    sink: &'a dyn TileSink,
+    atlases: Mutex<AtlasRegistry>,
+    stats: Mutex<TileStats>,
+    write_errors: Mutex<Vec<String>>,
+    /// Textured tiles set aside for `write_budgeted_tiles`.
+    budgeted_tiles: Mutex<Vec<BudgetedTile>>,
+    /// Meshes of the budgeted tiles, kept on disk until they are written.
+    spilled_meshes: MeshSpill,
+    /// Source textures decoded by the atlases written so far.
+    decoded_sources: atlas_repacker::DecodedSources,
+}
+
+/// Smallest max size `fit_atlas_to_budget` shrinks an atlas to before the
+/// tile goes untextured.
+const MIN_BUDGET_ATLAS_SIZE: u32 = 32;
+
+/// Meshes parked in an anonymous temporary file, created on first use and
+/// deleted when dropped, so tiles waiting for the end of the build don't
+/// keep their geometry in memory.
+#[derive(Default)]
+struct MeshSpill {
+    file: Mutex<Option<File>>,
+}
+
+impl MeshSpill {
+    /// Append `mesh` to the file and return where it was written.
+    fn store(&self, mesh: &IndexedMesh) -> io::Result<Range<u64>> {
+        let data = cache::encode_mesh(mesh);
+        let mut file = self.file.lock().expect("mesh spill poisoned");
+        let file = match &mut *file {
+            Some(file) => file,
+            None => file.insert(tempfile::tempfile()?),
+        };
+        let start = file.seek(SeekFrom::End(0))?;
+        file.write_all(&data)?;
+        Ok(start..start + data.len() as u64)
+    }
+
+    /// Read back the mesh [`MeshSpill::store`] wrote at `range`.
+    fn load(&self, range: &Range<u64>) -> io::Result<IndexedMesh> {
+        let mut data = vec![0; (range.end - range.start) as usize];
+        {
+            let mut file = self.file.lock().expect("mesh spill poisoned");
+            let file = file
+                .as_mut()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no spilled meshes"))?;
+            file.seek(SeekFrom::Start(range.start))?;
+            file.read_exact(&mut data)?;
+        }
+        cache::decode_mesh(&data)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "corrupt spilled mesh"))
+    }
+}
+
+/// Distinct atlases seen so far, by content hash.
+#[derive(Default)]
+struct AtlasRegistry {
+    seen: HashMap<u64, Vec<SeenAtlas>>,
+    stats: AtlasStats,
+}
+
+/// An atlas in [`AtlasRegistry::seen`]. Shared atlases keep their bytes,
+/// so an atlas whose hash collides with another's gets its own file;
+/// embedded ones only feed the statistics and make do with the length.
+struct SeenAtlas {
+    len: usize,
+    data: Option<Vec<u8>>,
+}
+
+impl SeenAtlas {
+    fn matches(&self, data: &[u8]) -> bool {
+        self.len == data.len() && self.data.as_ref().is_none_or(|seen| seen == data)
+    }
+}
+
+impl BuildContext<'_> {
+    /// Log a failed tile or atlas write and remember it for `write_tileset`.
+    fn record_write_error(&self, path: &str, err: std::io::Error) {
+        let message = format!("{path}: {err}");
+        tracing::error!("Failed to write {message}");
+        self.write_errors
+            .lock()
+            .expect("write errors poisoned")
+            .push(message);
+    }
+
+    /// Record an atlas and return a name derived from its content, the same
+    /// for identical bytes and distinct otherwise, plus whether it is the
+    /// first occurrence of these bytes.
+    fn register_atlas(&self, atlas: &TextureData) -> (String, bool) {
+        let hash = xxh64(&atlas.data);
+
+        let mut registry = self.atlases.lock().expect("atlas registry poisoned");
+        let registry = &mut *registry;
+        registry.stats.atlases += 1;
+        registry.stats.texels += u64::from(atlas.width) * u64::from(atlas.height);
+        let seen = registry.seen.entry(hash).or_default();
+        let found = seen.iter().position(|seen| seen.matches(&atlas.data));
+        let variant = found.unwrap_or(seen.len());
+        if found.is_none() {
+            seen.push(SeenAtlas {
+                len: atlas.data.len(),
+                data: self
+                    .texture_config
+                    .share_atlases
+                    .then(|| atlas.data.clone()),
+            });
+            registry.stats.unique_atlases += 1;
+        } else {
+            registry.stats.duplicate_bytes += atlas.data.len() as u64;
+        }
+        let name = match variant {
+            0 => format!("{hash:016x}"),
+            n => format!("{hash:016x}-{n}"),
+        };
+        (name, found.is_none())
+    }
+
+    /// Add a packed atlas to the packing efficiency statistics.
+    fn record_packing(&self, result: &atlas_repacker::AtlasResult, address: &str) {
+        let efficiency = result.packing_efficiency();
+        debug!(tile = address, efficiency, "Packed atlas");
+        let mut registry = self.atlases.lock().expect("atlas registry poisoned");
+        registry.stats.island_texels += result.island_texels;
+        registry.stats.packed_texels += result.atlas_texels;
+        if efficiency < atlas_repacker::LOW_PACKING_EFFICIENCY {
+            registry.stats.sparse_atlases += 1;
+        }
+    }
+
+    /// Max atlas size for the next tile under a texture memory `budget`:
+    /// the configured one while at most half the budget is used, halved
+    /// each time what is left halves again.
+    fn budget_max_size(&self, budget: u64) -> u32 {
+        let registry = self.atlases.lock().expect("atlas registry poisoned");
+        let left = budget.saturating_sub(registry.stats.budget_bytes);
+        let mut max_size = self.texture_config.max_size;
+        let mut share = budget / 2;
+        while left < share && max_size > MIN_BUDGET_ATLAS_SIZE {
+            max_size /= 2;
+            share /= 2;
+        }
+        max_size
+    }
+
+    /// Count `bytes` of atlases against a texture memory `budget`, unless
+    /// they would exceed it.
+    fn reserve_texture_bytes(&self, bytes: u64, budget: u64) -> bool {
+        let mut registry = self.atlases.lock().expect("atlas registry poisoned");
+        let fits = registry.stats.budget_bytes + bytes <= budget;
+        if fits {
+            registry.stats.budget_bytes += bytes;
+        }
+        fits
+    }
+
+    /// Leaf budget for `mesh`, using its material's source texture size to
+    /// estimate atlas texels when textures are written.
+    fn leaf_budget(&self, mesh: &IndexedMesh) -> LeafBudget {
+        let texture_size = if self.texture_config.enabled {
+            mesh.material_index
+                .and_then(|i| self.materials.materials.get(i))
+                .and_then(|m| m.base_color_texture)
+                .and_then(|t| self.materials.textures.get(t))
+                .map(|t| (t.width, t.height))
+        } else {
+            None
+        };
+        LeafBudget {
+            max_triangles: self.tiling.max_triangles_per_tile,
+            max_bytes: self.tiling.max_tile_bytes,
+            texture_size,
+        }
+    }
+}
+
+/// File name for a shared atlas under `tiles/shared/`, from the name
+/// [`BuildContext::register_atlas`] gave its content.
+fn shared_atlas_name(content_name: &str, mime_type: &str) -> String {
+    let ext = match mime_type {
+        "image/webp" => "webp",
+        "image/ktx2" => "ktx2",
+        "image/jpeg" => "jpg",
+        _ => "png",
+    };
+    format!("{content_name}.{ext}")
+}
+
+/// URI of a shared atlas relative to the GLB at `glb_uri`.
+///
+/// - `"tiles/root.glb"` → `"shared/<name>"`
+/// - `"tiles/0/0_3/tile.glb"` → `"../../shared/<name>"`
+fn shared_atlas_uri(glb_uri: &str, name: &str) -> String {
+    let depth = glb_uri.matches('/').count().saturating_sub(1);
+    format!("{}shared/{name}", "../".repeat(depth))
+}
+
+/// Write an atlas under `tiles/shared/` once and return its URI relative to
+/// the GLB at `glb_uri`.
+fn share_atlas(atlas: &TextureData, ctx: &BuildContext, glb_uri: &str) -> String {
+    let (content_name, is_new) = ctx.register_atlas(atlas);
+    let name = shared_atlas_name(&content_name, &atlas.mime_type);
+
+    if is_new {
+        let path = format!("tiles/shared/{name}");
+        match write_file(ctx.sink, &path, &atlas.data) {
+            Ok(()) => {
+                let mut stats = ctx.stats.lock().expect("tile stats poisoned");
+                stats.bytes_written += atlas.data.len() as u64;
+            }
+            Err(e) => ctx.record_write_error(&path, e),
+        }
+    }
+
+    shared_atlas_uri(glb_uri, &name)
+}
+
+/// Write a tile's atlases as shared files, then build the GLB that
+/// references them.
+fn write_glb_with_shared_atlas(
+    atlas: &atlas_repacker::AtlasResult,
+    ctx: &BuildContext,
+    glb_uri: &str,
+) -> EncodedTile {
+    let share = |texture| (share_atlas(texture, ctx, glb_uri), texture);
+    let shared: Vec<_> = atlas
+        .page_textures()
+        .map(|(first_index, textures)| (first_index, textures.map(|t| t.map(share))))
+        .collect();
+    let (images, pages) = paged_images(&shared, shared_image);
+    let images = TileImages {
+        pages: &pages,
+        ..images
+    };
+    encode_tile_glb(&atlas.mesh, ctx, images)
+}
+
+/// An image for an atlas written by [`share_atlas`] to `uri`.
+fn shared_image<'a>((uri, texture): &'a (String, &'a TextureData)) -> ImageSource<'a> {
+    ImageSource::External {
+        uri,
+        mime_type: &texture.mime_type,
+    }
+}
+
+/// Images for the textures of each atlas page, as listed by
+/// [`atlas_repacker::AtlasResult::page_textures`]: the first page's images
+/// and those of the pages after it.
+fn paged_images<'a, T>(
+    pages: &'a [(usize, [Option<T>; 3])],
+    image: impl Fn(&'a T) -> ImageSource<'a>,
+) -> (TileImages<'a>, Vec<(usize, TileImages<'a>)>) {
+    let mut images = pages.iter().map(|(first_index, [base, mr, normal])| {
+        let images = TileImages {
+            base_color: base.as_ref().map(&image),
+            metallic_roughness: mr.as_ref().map(&image),
+            normal: normal.as_ref().map(&image),
+            pages: &[],
+        };
+        (*first_index, images)
+    });
+    let (_, first) = images.next().unwrap_or_default();
+    (first, images.collect())
+}
+
+/// A tile's GLB and, for b3dm tiles, the batches of the mesh it encodes.
+struct EncodedTile {
+    glb: Vec<u8>,
+    batches: Option<TileBatches>,
+}
+
+/// Encode a tile GLB with the vertex encoding selected in the tiling config.
+///
+/// For b3dm tiles, feature IDs are written as `_BATCHID` indices into the
+/// tile's [`TileBatches`] instead of EXT_mesh_features. The batches are
+/// those of `mesh`, which an atlas repack may have renumbered, so the
+/// batch table written by [`finish_tile`] is taken from here too.
+fn encode_tile_glb(mesh: &IndexedMesh, ctx: &BuildContext, images: TileImages<'_>) -> EncodedTile {
+    if !ctx.tiling.b3dm {
+        let glb = encode_plain_tile_glb(mesh, ctx, images);
+        return EncodedTile { glb, batches: None };
+    }
+    let batches = TileBatches::new(mesh);
+    let glb = if mesh.has_feature_ids() {
+        let batch_ids = mesh.feature_ids.iter().map(|&id| batches.batch_id(id));
+        let batch_ids: Vec<u32> = batch_ids.collect();
+        let plain = IndexedMesh {
+            feature_ids: Vec::new(),
+            ..mesh.clone()
+        };
+        add_batch_ids(&encode_plain_tile_glb(&plain, ctx, images), &batch_ids)
+    } else {
+        encode_plain_tile_glb(mesh, ctx, images)
+    };
+    EncodedTile {
+        glb,
+        batches: Some(batches),
+    }
+}
+
+fn encode_plain_tile_glb(
+    mesh: &IndexedMesh,
+    ctx: &BuildContext,
+    images: TileImages<'_>,
+) -> Vec<u8> {
+    if ctx.tiling.quantize {
+        write_glb_quantized(mesh, ctx.materials, images)
+    } else if ctx.tiling.meshopt {
+        match ctx.tiling.oct_normal_bits {
+            Some(bits) => write_glb_compressed_oct(mesh, ctx.materials, images, bits),
+            None => write_glb_compressed(mesh, ctx.materials, images),
+        }
+    } else {
+        write_glb(mesh, ctx.materials, images)
+    }
+}
+
+/// Source objects in a b3dm tile: batch `i` is the `i`-th smallest feature
+/// ID in the tile mesh.
+struct TileBatches {
+    feature_ids: Vec<u32>,
+    triangles: Vec<usize>,
+}
+
+impl TileBatches {
+    fn new(mesh: &IndexedMesh) -> Self {
+        let mut feature_ids = mesh.feature_ids.clone();
+        feature_ids.sort_unstable();
+        feature_ids.dedup();
+        let mut batches = Self {
+            triangles: vec![0; feature_ids.len()],
+            feature_ids,
+        };
+        // Clipping keeps objects apart, so a triangle's first vertex names it
+        for tri in mesh.indices.chunks_exact(3) {
+            let batch = batches.batch_id(mesh.feature_ids[tri[0] as usize]);
+            batches.triangles[batch as usize] += 1;
+        }
+        batches
+    }
+
+    fn batch_id(&self, feature_id: u32) -> u32 {
+        self.feature_ids
+            .binary_search(&feature_id)
+            .expect("feature ID of the tile mesh") as u32
+    }
+
+    /// Batch table with each batch's object name and triangle count.
+    fn batch_table(&self, materials: &MaterialLibrary) -> serde_json::Value {
+        let features = &materials.features;
+        let name = |&id: &u32| features.get(id as usize).map_or("", String::as_str);
+        let names: Vec<&str> = self.feature_ids.iter().map(name).collect();
+        json!({ "name": names, "triangleCount": self.triangles })
+    }
+}
+
+/// Partition a mesh for additive refinement: the largest `ratio` of its
+/// triangles by area, which give the best coarse coverage, and the rest.
+fn take_largest_triangles(mesh: &IndexedMesh, ratio: f32) -> (IndexedMesh, IndexedMesh) {
+    let area = |tri: &[u32]| {
+        let p = |i: u32| {
+            let i = i as usize * 3;
+            [
+                mesh.positions[i] as f64,
+                mesh.positions[i + 1] as f64,
+                mesh.positions[i + 2] as f64,
+            ]
+        };
+        let (a, b, c) = (p(tri[0]), p(tri[1]), p(tri[2]));
+        let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+        let cross = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]
+    };
+
+    let triangle = |t: usize| &mesh.indices[t * 3..t * 3 + 3];
+    let mut triangles: Vec<usize> = (0..mesh.triangle_count()).collect();
+    // Stable sort keeps ties in their original order for deterministic output
+    triangles.sort_by(|&a, &b| area(triangle(b)).total_cmp(&area(triangle(a))));
+    let keep = ((triangles.len() as f32 * ratio).ceil() as usize).min(triangles.len());
+
+    // Each half keeps the materials of its triangles
+    let materials = mesh.triangle_materials();
+    let compact = |triangles: &[usize]| {
+        let indices = triangles
+            .iter()
+            .flat_map(|&t| triangle(t))
+            .copied()
+            .collect();
+        let materials: Vec<_> = triangles.iter().map(|&t| materials[t]).collect();
+        compact_mesh_with_materials(indices, &materials, mesh)
+    };
+    let (kept, rest) = triangles.split_at(keep);
+    (compact(kept), compact(rest))
+}
+
+/// Shrink every tile's box to the union of its content and its children, so
+/// a tile subdivided in cubed cells still has a volume that reflects what
+/// it holds. Children with a transform count with their box mapped into
+/// the tile's frame.
+fn fit_bounds_to_content(node: &mut TileNode) {
+    let mut fitted = node
+        .content
+        .as_ref()
+        .map(|c| c.bounds.unwrap_or(node.bounds));
+    for child in &mut node.children {
+        fit_bounds_to_content(child);
+        let child_bounds = match &child.transform {
+            Some(transform) => child.bounds.transformed(transform),
+            None => child.bounds,
+        };
+        fitted = Some(fitted.map_or(child_bounds, |b| b.merge(&child_bounds)));
+    }
+    if let Some(bounds) = fitted {
+        node.bounds = bounds;
+    }
+}
+
+/// Texel density for a simplified LOD tile relative to full detail.
+///
+/// Triangle count scales with area, texel density with length, so a tile
+/// simplified to ratio `r` gets `sqrt(r)` of the full texture resolution.
+fn lod_texel_scale(
+    simplified: &IndexedMesh,
+    original: &IndexedMesh,
+    config: &TextureConfig,
+) -> f32 {
+    if !config.lod_downscale || original.triangle_count() == 0 {
+        return 1.0;
+    }
+    let ratio = simplified.triangle_count() as f32 / original.triangle_count() as f32;
+    ratio.clamp(0.0, 1.0).sqrt()
+}
+
 /// Write a tile's GLB using atlas repacking when textures are enabled,
 /// then eagerly flush to disk and free the data.
 ///
+/// `texel_scale` scales the atlas resolution (1.0 for full-detail tiles,
+/// smaller for coarse LOD content).
+///
 /// Applies vertex cache optimization before writing to improve GPU
 /// rendering performance and meshopt compression ratios.
 fn write_tile_glb_to_disk(
     mesh: &IndexedMesh,
-    materials: &MaterialLibrary,
-    texture_config: &TextureConfig,
-    out_dir: &Path,
+    ctx: &BuildContext,
     address: &str,
+    texel_scale: f32,
 ) -> TileContent {
-    // Vertex cache optimization: improves GPU rendering perf and compression ratios
+    write_instanced_tile_glb_to_disk(mesh, ctx, address, texel_scale, &[])
+}
+
+/// Like [`write_tile_glb_to_disk`], but draw the mesh once per matrix in
+/// `instances` through EXT_mesh_gpu_instancing (once when empty).
+fn write_instanced_tile_glb_to_disk(
+    mesh: &IndexedMesh,
+    ctx: &BuildContext,
+    address: &str,
+    texel_scale: f32,
+    instances: &[[f64; 16]],
+) -> TileContent {
+    let materials = ctx.materials;
+    let texture_config = ctx.texture_config;
+
+    // Vertex cache optimization: improves GPU rendering perf and compression ratios.
+    // Triangles are only reordered within their material group.
     let mesh = if !mesh.is_empty() {
-        let optimized_indices = meshopt::optimize_vertex_cache(&mesh.indices, mesh.vertex_count());
+        let mut optimized_indices = mesh.indices.clone();
+        for group in mesh.material_ranges() {
+            let optimized = meshopt::optimize_vertex_cache(
+                &mesh.indices[group.indices.clone()],
+                mesh.vertex_count(),
+            );
+            optimized_indices[group.indices].copy_from_slice(&optimized);
+        }
         &IndexedMesh {
             positions: mesh.positions.clone(),
             normals: mesh.normals.clone(),
             uvs: mesh.uvs.clone(),
+            uvs1: mesh.uvs1.clone(),
             colors: mesh.colors.clone(),
+            feature_ids: mesh.feature_ids.clone(),
             indices: optimized_indices,
             material_index: mesh.material_index,
+            name: mesh.name.clone(),
+            material_groups: mesh.material_groups.clone(),
         }
     } else {
         mesh
     };
 
-    let glb_data = if texture_config.enabled && mesh.has_uvs() {
-        if let Some(result) = atlas_repacker::repack_atlas(mesh, materials, texture_config) {
-            write_glb_compressed(&result.mesh, materials, Some(&result.atlas_texture))
+    let uri = content_uri(address, ctx.tiling);
+    let content = TileContent {
+        uri: uri.clone(),
+        bounds: Some(instanced_bounds(
+            &compute_bounding_box(std::slice::from_ref(mesh)),
+            instances,
+        )),
+        obb: (ctx.tiling.oriented_boxes && instances.is_empty())
+            .then(|| OrientedBox::from_points(&mesh.positions))
+            .flatten(),
+        triangles: mesh.triangle_count(),
+    };
+
+    let tile = if texture_config.enabled && mesh.has_uvs() {
+        if let Some(texture) =
+            atlas_repacker::repeating_source_texture(mesh, materials, texture_config, texel_scale)
+        {
+            // Embedded even with shared atlases, whose images are sampled clamped
+            let source_map = |map: fn(&PBRMaterial) -> Option<usize>| {
+                atlas_repacker::source_map_texture(
+                    mesh,
+                    materials,
+                    texture_config,
+                    texel_scale,
+                    map,
+                )
+            };
+            let metallic_roughness = source_map(|m| m.metallic_roughness_texture);
+            let normal = source_map(|m| m.normal_texture);
+            let images = TileImages {
+                base_color: Some(ImageSource::Embedded(&texture)),
+                metallic_roughness: metallic_roughness.as_ref().map(ImageSource::Embedded),
+                normal: normal.as_ref().map(ImageSource::Embedded),
+                ..Default::default()
+            };
+            encode_tile_glb(mesh, ctx, images)
+        } else if texture_config.max_memory.is_some() {
+            // Written by `write_budgeted_tiles` once the tree is built
+            match ctx.spilled_meshes.store(mesh) {
+                Ok(mesh) => {
+                    let tile = BudgetedTile {
+                        address: address.to_string(),
+                        mesh,
+                        texel_scale,
+                        instances: instances.to_vec(),
+                    };
+                    ctx.budgeted_tiles
+                        .lock()
+                        .expect("budgeted tiles poisoned")
+                        .push(tile);
+                }
+                Err(e) => ctx.record_write_error(&uri, e),
+            }
+            return content;
+        } else if let Some(result) = atlas_repacker::repack_atlas_scaled(
+            mesh,
+            materials,
+            texture_config,
+            texel_scale,
+            &ctx.decoded_sources,
+        ) {
+            encode_atlas_tile_glb(&result, ctx, &uri, address)
         } else {
-            write_glb_compressed(mesh, materials, None)
+            encode_tile_glb(mesh, ctx, TileImages::default())
         }
     } else {
-        write_glb_compressed(mesh, materials, None)
+        encode_tile_glb(mesh, ctx, TileImages::default())
     };
+    finish_tile(tile, mesh, instances, &uri, address, ctx);
 
-    let uri = address_to_uri(address);
-    let glb_path = out_dir.join(&uri);
+    // The GLB bytes are dropped here; the tree only keeps the URI
+    content
+}
 
-    // Write to disk immediately
-    if let Some(parent) = glb_path.parent() {
-        let _ = fs::create_dir_all(parent);
+/// Encode a tile drawing `result`'s mesh with its packed atlas, embedded or
+/// shared.
+fn encode_atlas_tile_glb(
+    result: &atlas_repacker::AtlasResult,
+    ctx: &BuildContext,
+    uri: &str,
+    address: &str,
+) -> EncodedTile {
+    ctx.record_packing(result, address);
+    if ctx.texture_config.share_atlases {
+        return write_glb_with_shared_atlas(result, ctx, uri);
     }
-    if let Err(e) = fs::write(&glb_path, &glb_data) {
-        tracing::error!("Failed to write {}: {e}", glb_path.display());
+    let textures: Vec<_> = result.page_textures().collect();
+    for texture in textures.iter().flat_map(|(_, t)| t).flatten() {
+        ctx.register_atlas(texture);
     }
+    let (images, pages) = paged_images(&textures, |t| ImageSource::Embedded(t));
+    let images = TileImages {
+        pages: &pages,
+        ..images
+    };
+    encode_tile_glb(&result.mesh, ctx, images)
+}
 
-    // Return content with empty data (already on disk)
-    TileContent {
-        glb_data: vec![],
-        uri,
+/// Add instancing and the b3dm wrapper to a tile's GLB as configured, then
+/// write it to the sink.
+fn finish_tile(
+    tile: EncodedTile,
+    mesh: &IndexedMesh,
+    instances: &[[f64; 16]],
+    uri: &str,
+    address: &str,
+    ctx: &BuildContext,
+) {
+    let glb_data = if instances.is_empty() {
+        tile.glb
+    } else {
+        add_gpu_instancing(&tile.glb, instances)
+    };
+    let glb_data = match tile.batches {
+        Some(batches) => {
+            let table =
+                (!batches.feature_ids.is_empty()).then(|| batches.batch_table(ctx.materials));
+            write_b3dm(&glb_data, batches.feature_ids.len(), table.as_ref())
+        }
+        None => glb_data,
+    };
+
+    // Write to the sink immediately
+    match write_file(ctx.sink, uri, &glb_data) {
+        Ok(()) => ctx.stats.lock().expect("tile stats poisoned").record_tile(
+            address_depth(address),
+            mesh.triangle_count(),
+            glb_data.len() as u64,
+        ),
+        Err(e) => ctx.record_write_error(uri, e),
+    }
+}
+
+/// A textured tile waiting for its share of `TextureConfig::max_memory`.
+struct BudgetedTile {
+    address: String,
+    /// Where `BuildContext::spilled_meshes` holds the tile's mesh, vertex
+    /// cache optimized.
+    mesh: Range<u64>,
+    texel_scale: f32,
+    instances: Vec<[f64; 16]>,
+}
+
+/// Write the tiles set aside under a texture memory budget.
+///
+/// Tiles claim the budget coarsest level first and in address order within
+/// a level, so the same input always shrinks the same atlases however the
+/// build was scheduled. Only one level's meshes are read back from disk at
+/// a time. A level's atlases are repacked in parallel at the size left when
+/// it starts; only those that must shrink further are repacked again, in
+/// order.
+fn write_budgeted_tiles(ctx: &BuildContext) {
+    let Some(budget) = ctx.texture_config.max_memory else {
+        return;
+    };
+    let mut tiles = mem::take(&mut *ctx.budgeted_tiles.lock().expect("budgeted tiles poisoned"));
+    let depth = |tile: &BudgetedTile| address_depth(&tile.address);
+    tiles.sort_by(|a, b| (depth(a), &a.address).cmp(&(depth(b), &b.address)));
+
+    for level in tiles.chunk_by(|a, b| depth(a) == depth(b)) {
+        let level: Vec<_> = level
+            .par_iter()
+            .filter_map(|tile| match ctx.spilled_meshes.load(&tile.mesh) {
+                Ok(mesh) => Some((tile, mesh)),
+                Err(e) => {
+                    ctx.record_write_error(&content_uri(&tile.address, ctx.tiling), e);
+                    None
+                }
+            })
+            .collect();
+        let max_size = ctx.budget_max_size(budget);
+        let packed: Vec<_> = level
+            .par_iter()
+            .map(|(tile, mesh)| repack_atlas_at(tile, mesh, ctx, max_size))
+            .collect();
+        let fitted: Vec<_> = level
+            .iter()
+            .zip(packed)
+            .map(|((tile, mesh), result)| {
+                fit_atlas_to_budget(tile, mesh, ctx, result?, max_size, budget)
+            })
+            .collect();
+        level
+            .par_iter()
+            .zip(fitted)
+            .for_each(|((tile, mesh), result)| {
+                let address = &tile.address;
+                let uri = content_uri(address, ctx.tiling);
+                let encoded = match result {
+                    Some(result) => encode_atlas_tile_glb(&result, ctx, &uri, address),
+                    None => encode_tile_glb(mesh, ctx, TileImages::default()),
+                };
+                finish_tile(encoded, mesh, &tile.instances, &uri, address, ctx);
+            });
+    }
+}
+
+/// Repack the atlas of `tile`, drawing `mesh`, with its size capped at
+/// `max_size`.
+fn repack_atlas_at(
+    tile: &BudgetedTile,
+    mesh: &IndexedMesh,
+    ctx: &BuildContext,
+    max_size: u32,
+) -> Option<atlas_repacker::AtlasResult> {
+    let config = TextureConfig {
+        max_size,
+        ..ctx.texture_config.clone()
+    };
+    atlas_repacker::repack_atlas_scaled(
+        mesh,
+        ctx.materials,
+        &config,
+        tile.texel_scale,
+        &ctx.decoded_sources,
+    )
+}
+
+/// Reserve `result`, packed at `max_size`, against what is left of the
+/// texture memory `budget`. The atlas is first shrunk to the size the
+/// remaining budget allows, then halved until its encoded bytes fit;
+/// `None` when even the smallest one does not fit.
+fn fit_atlas_to_budget(
+    tile: &BudgetedTile,
+    mesh: &IndexedMesh,
+    ctx: &BuildContext,
+    mut result: atlas_repacker::AtlasResult,
+    mut max_size: u32,
+    budget: u64,
+) -> Option<atlas_repacker::AtlasResult> {
+    let allowed = ctx.budget_max_size(budget);
+    if allowed < max_size {
+        max_size = allowed;
+        result = repack_atlas_at(tile, mesh, ctx, max_size)?;
+    }
+    loop {
+        let bytes = result
+            .page_textures()
+            .flat_map(|(_, textures)| textures.into_iter().flatten())
+            .map(|texture| texture.data.len() as u64)
+            .sum();
+        if ctx.reserve_texture_bytes(bytes, budget) {
+            return Some(result);
+        }
+        if max_size <= MIN_BUDGET_ATLAS_SIZE {
+            debug!(
+                tile = tile.address,
+                "Out of texture memory, writing untextured"
+            );
+            let mut registry = ctx.atlases.lock().expect("atlas registry poisoned");
+            registry.stats.over_budget += 1;
+            return None;
+        }
+        max_size /= 2;
+        result = repack_atlas_at(tile, mesh, ctx, max_size)?;
+    }
+}
+
+/// Bounds of a mesh with local `bounds` drawn at every matrix in
+/// `instances` (`bounds` itself when there are none).
+fn instanced_bounds(bounds: &BoundingBox, instances: &[[f64; 16]]) -> BoundingBox {
+    instances
+        .iter()
+        .map(|matrix| bounds.transformed(matrix))
+        .reduce(|a, b| a.merge(&b))
+        .unwrap_or(*bounds)
+}
+
+/// A leaf tile drawing `mesh` at every instance.
+///
+/// Instanced meshes are never split, since every piece would need its own
+/// copy of the instance list.
+fn build_instanced_tile(
+    mesh: &IndexedMesh,
+    bounds: &BoundingBox,
+    instances: &[[f64; 16]],
+    address: &str,
+    ctx: &BuildContext,
+) -> TileNode {
+    TileNode {
+        address: address.into(),
+        level: 1,
+        bounds: instanced_bounds(bounds, instances),
+        geometric_error: 0.0,
+        content: (!mesh.is_empty())
+            .then(|| write_instanced_tile_glb_to_disk(mesh, ctx, address, 1.0, instances)),
+        children: vec![],
+        transform: None,
     }
 }
 
@@ -118,71 +972,285 @@ pub fn build_tileset(
     texture_config: &TextureConfig,
     out_dir: &Path,
 ) -> TilesetOutput {
-    // Merge all LOD-0 (finest) meshes into a single mesh
+    build_tileset_with_transforms(
+        lod_chains,
+        &[],
+        bounds,
+        config,
+        materials,
+        texture_config,
+        out_dir,
+    )
+}
+
+/// Like [`build_tileset`], but chains whose entry in `transforms` is `Some`
+/// are in a local frame placed by that matrix (missing entries are `None`).
+///
+/// Each such chain becomes its own subtree carrying the matrix as its tile
+/// `transform`. They sit next to the tree of the merged remaining chains
+/// under an empty root covering `bounds`.
+pub fn build_tileset_with_transforms(
+    lod_chains: Vec<LodChain>,
+    transforms: &[Option<[f64; 16]>],
+    bounds: &BoundingBox,
+    config: &TilingConfig,
+    materials: &MaterialLibrary,
+    texture_config: &TextureConfig,
+    out_dir: &Path,
+) -> TilesetOutput {
+    build_tileset_to_sink(
+        lod_chains,
+        transforms,
+        bounds,
+        config,
+        materials,
+        texture_config,
+        &FsSink::new(out_dir),
+    )
+}
+
+/// Like [`build_tileset_with_transforms`], but write tiles and shared
+/// atlases through `sink` instead of into a directory.
+pub fn build_tileset_to_sink(
+    lod_chains: Vec<LodChain>,
+    transforms: &[Option<[f64; 16]>],
+    bounds: &BoundingBox,
+    config: &TilingConfig,
+    materials: &MaterialLibrary,
+    texture_config: &TextureConfig,
+    sink: &dyn TileSink,
+) -> TilesetOutput {
+    // Merge all LOD-0 (finest) meshes into a single mesh, setting aside
+    // those in a local frame and, with `separate_objects`, all others
     let mut merged = IndexedMesh::default();
-    for chain in &lod_chains {
-        if let Some(level) = chain.levels.iter().find(|l| l.level == 0) {
-            merged = merge_meshes(merged, &level.mesh);
+    let mut local = Vec::new();
+    let mut objects = Vec::new();
+    for (i, chain) in lod_chains.into_iter().enumerate() {
+        let chain_bounds = chain.bounds;
+        let Some(level) = chain.levels.into_iter().find(|l| l.level == 0) else {
+            continue;
+        };
+        match transforms.get(i).copied().flatten() {
+            Some(transform) => local.push((level.mesh, chain_bounds, transform, chain.instances)),
+            None if config.separate_objects => {
+                // Meshes sharing a name (one object split by material) stay
+                // together, as for feature IDs
+                let name = level.mesh.name.clone().filter(|n| !n.is_empty());
+                let same = objects.iter().position(|(n, _)| *n == name);
+                match same.filter(|_| name.is_some()) {
+                    Some(i) => {
+                        let object = mem::take(&mut objects[i].1);
+                        objects[i].1 = merge_meshes(object, &level.mesh);
+                    }
+                    None => objects.push((name, level.mesh)),
+                }
+            }
+            None => merged = merge_meshes(merged, &level.mesh),
         }
     }
 
-    drop(lod_chains);
-
     let identity = [
         1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
     ];
 
-    let root = build_tile_recursive(
-        merged,
-        bounds,
-        0,
-        config.max_depth,
-        config.max_triangles_per_tile,
-        "root",
+    let dims = match config.scheme {
+        Scheme::Octree => 3,
+        Scheme::Quadtree => 2,
+    };
+    let auto_config;
+    let config = if config.auto_depth {
+        let object_triangles: usize = objects.iter().map(|(_, m)| m.triangle_count()).sum();
+        let local_triangles: usize = local.iter().map(|(m, ..)| m.triangle_count()).sum();
+        let triangles = merged.triangle_count() + object_triangles + local_triangles;
+        let max_depth = auto_max_depth(triangles, config.max_triangles_per_tile, 1 << dims);
+        info!("Auto max depth: {max_depth} for {triangles} triangles");
+        auto_config = TilingConfig {
+            max_depth,
+            ..config.clone()
+        };
+        &auto_config
+    } else {
+        config
+    };
+    // Cubed cells only drive the subdivision; the tiles' boxes are fitted
+    // back to their content once the tree is built
+    let bounds = &if config.cube_root {
+        bounds.cubed(dims)
+    } else {
+        *bounds
+    };
+
+    let ctx = BuildContext {
+        tiling: config,
         materials,
         texture_config,
-        out_dir,
-    );
+        sink,
+        atlases: Mutex::new(AtlasRegistry::default()),
+        stats: Mutex::new(TileStats::default()),
+        write_errors: Mutex::new(Vec::new()),
+        budgeted_tiles: Mutex::new(Vec::new()),
+        spilled_meshes: MeshSpill::default(),
+        decoded_sources: Default::default(),
+    };
+    let mut root = build_tile_recursive(merged, bounds, 0, "root", &ctx);
+    if !local.is_empty() || !objects.is_empty() {
+        // A tile transform applies to the whole subtree, so local meshes
+        // can't share tiles with the rest. Separate objects get an octree
+        // over their own bounds.
+        let mut children = vec![root];
+        for (i, (_, mesh)) in objects.into_iter().enumerate() {
+            let object_bounds = ensure_min_extent(
+                compute_bounding_box(std::slice::from_ref(&mesh)),
+                MIN_HALF_EXTENT,
+            );
+            let object_bounds = if config.cube_root {
+                object_bounds.cubed(dims)
+            } else {
+                object_bounds
+            };
+            let address = format!("o{i}");
+            let node = build_tile_recursive(mesh, &object_bounds, 1, &address, &ctx);
+            children.push(node);
+        }
+        for (i, (mesh, local_bounds, transform, instances)) in local.into_iter().enumerate() {
+            let address = format!("n{i}");
+            let mut node = if instances.is_empty() {
+                build_tile_recursive(mesh, &local_bounds, 1, &address, &ctx)
+            } else {
+                build_instanced_tile(&mesh, &local_bounds, &instances, &address, &ctx)
+            };
+            node.transform = Some(transform);
+            children.push(node);
+        }
+        root = TileNode {
+            address: "root".into(),
+            level: 0,
+            bounds: *bounds,
+            geometric_error: children
+                .iter()
+                .map(|c| c.geometric_error)
+                .fold(bounds.diagonal(), f64::max),
+            content: None,
+            children,
+            transform: None,
+        };
+    }
+    write_budgeted_tiles(&ctx);
+    prune_empty_tiles(&mut root);
+    if config.cube_root {
+        fit_bounds_to_content(&mut root);
+    }
+    if config.geometric_error_scale != 1.0 {
+        scale_geometric_error(&mut root, config.geometric_error_scale);
+    }
+    if let Some(max) = config.max_geometric_error {
+        clamp_geometric_error(&mut root, max);
+    }
+    if config.tile_order == TileOrder::Hilbert {
+        order_children_hilbert(&mut root, bounds, dims);
+    }
+
+    let atlas_stats = ctx
+        .atlases
+        .into_inner()
+        .expect("atlas registry poisoned")
+        .stats;
+    let tile_stats = ctx.stats.into_inner().expect("tile stats poisoned");
+    let write_errors = ctx
+        .write_errors
+        .into_inner()
+        .expect("write errors poisoned");
 
     TilesetOutput {
         root,
         root_transform: identity,
+        atlas_stats,
+        tile_stats,
+        tiles_version: config.tiles_version,
+        refine: config.refine,
+        // b3dm tiles describe their objects in batch tables instead
+        schema: (!materials.features.is_empty() && !config.b3dm).then(feature_schema),
+        b3dm: config.b3dm,
+        uri_base: config.uri_base.clone(),
+        external_tileset_depth: config.external_tileset_depth,
+        tile_extras: config.tile_extras,
+        ecef_origin: None,
+        write_errors,
     }
 }
 
-/// Recursively build a unified spatial-LOD tile hierarchy.
+/// Drop descendants that have no content anywhere in their subtree.
+///
+/// Empty octants would otherwise still be serialized with a bounding volume
+/// and geometric error. The node passed in (the root) is always kept.
+/// Returns whether `node` has content itself or in a descendant.
+fn prune_empty_tiles(node: &mut TileNode) -> bool {
+    node.children.retain_mut(prune_empty_tiles);
+    node.content.is_some() || !node.children.is_empty()
+}
+
+/// Multiply the geometric error of `node` and its descendants by `scale`.
+/// A positive factor keeps every child at or below its parent.
+fn scale_geometric_error(node: &mut TileNode, scale: f64) {
+    node.geometric_error *= scale;
+    for child in &mut node.children {
+        scale_geometric_error(child, scale);
+    }
+}
+
+/// Cap the geometric error of `node` and its descendants at `max`. Errors
+/// only decrease, so no child ends up above its parent.
+fn clamp_geometric_error(node: &mut TileNode, max: f64) {
+    node.geometric_error = node.geometric_error.min(max);
+    for child in &mut node.children {
+        clamp_geometric_error(child, max);
+    }
+}
+
+/// Sort every node's children by the Hilbert index of their centers within
+/// `frame`, so siblings next to each other in tileset.json are neighbours in
+/// space. Tiles start in octant (Morton) order, which jumps diagonally
+/// between some siblings.
+///
+/// A tile with its own `transform` starts a new frame; siblings in
+/// different frames are left in place.
+fn order_children_hilbert(node: &mut TileNode, frame: &BoundingBox, dims: usize) {
+    for child in &mut node.children {
+        let frame = if child.transform.is_some() {
+            child.bounds
+        } else {
+            *frame
+        };
+        order_children_hilbert(child, &frame, dims);
+    }
+    if node.children.iter().all(|c| c.transform.is_none()) {
+        node.children
+            .sort_by_cached_key(|c| hilbert_index(&c.bounds.center(), frame, dims));
+    }
+}
+
+/// Recursively build a unified spatial-LOD tile hierarchy.
 ///
 /// Each node gets a simplified version of its mesh as display content, while
 /// the original (unsimplified) mesh is spatially subdivided into octant children.
 /// This ensures every internal node has renderable content and the tree combines
 /// both spatial subdivision and LOD at every level.
 ///
-/// Leaf condition: `triangle_count <= max_tris` OR `depth >= max_depth`.
+/// Leaf condition: `triangle_count <= max_triangles_per_tile` (and, when set,
+/// estimated size `<= max_tile_bytes`) OR `depth >= max_depth`.
 fn build_tile_recursive(
     mesh: IndexedMesh,
     bounds: &BoundingBox,
     depth: u32,
-    max_depth: u32,
-    max_tris: usize,
     address: &str,
-    materials: &MaterialLibrary,
-    texture_config: &TextureConfig,
-    out_dir: &Path,
+    ctx: &BuildContext,
 ) -> TileNode {
-    let is_leaf = mesh.triangle_count() <= max_tris || depth >= max_depth;
-
-    let geometric_error = if is_leaf {
-        0.0
-    } else {
-        bounds.diagonal() * 0.5_f64.powi(depth as i32)
-    };
+    let is_leaf = ctx.leaf_budget(&mesh).fits(&mesh) || depth >= ctx.tiling.max_depth;
 
     if is_leaf {
         // Leaf: write the full-detail mesh as content, no children
         let content = if !mesh.is_empty() {
-            Some(write_tile_glb_to_disk(
-                &mesh, materials, texture_config, out_dir, address,
-            ))
+            Some(write_tile_glb_to_disk(&mesh, ctx, address, 1.0))
         } else {
             None
         };
@@ -191,41 +1259,107 @@ fn build_tile_recursive(
             address: address.into(),
             level: depth,
             bounds: *bounds,
-            geometric_error,
+            geometric_error: 0.0,
             content,
             children: vec![],
+            transform: None,
         };
     }
 
     // Internal node: simplify the mesh for this node's display content,
     // then spatially split the ORIGINAL mesh for children.
     // Deeper levels use relaxed simplification (less aggressive, faster).
-    let content_mesh = if mesh.triangle_count() < 64 {
-        // Too few triangles to simplify meaningfully -- use as-is
-        mesh.clone()
+    // With additive refinement the content is a subset of the original
+    // triangles instead, and only the rest is passed on to the children.
+    let full_triangles = mesh.triangle_count();
+    let (ratio, lock_border) = if depth >= 3 {
+        (0.5, false) // Faster, less aggressive for deep/coarse nodes
     } else {
-        let (ratio, lock_border) = if depth >= 3 {
-            (0.5, false) // Faster, less aggressive for deep/coarse nodes
-        } else {
-            (0.25, true) // More aggressive for top-level nodes
-        };
-        simplify_mesh(&mesh, ratio, lock_border).mesh
+        (0.25, true) // More aggressive for top-level nodes
+    };
+    let (content_mesh, mesh, texel_scale) = match ctx.tiling.refine {
+        Refine::Replace => {
+            let content_mesh = if full_triangles < 64 {
+                // Too few triangles to simplify meaningfully -- use as-is
+                mesh.clone()
+            } else {
+                simplify_mesh(&mesh, ratio, lock_border, ctx.tiling).mesh
+            };
+            let texel_scale = lod_texel_scale(&content_mesh, &mesh, ctx.texture_config);
+            (content_mesh, mesh, texel_scale)
+        }
+        Refine::Add => {
+            let keep = if full_triangles < 64 { 1.0 } else { ratio };
+            let (content_mesh, rest) = take_largest_triangles(&mesh, keep);
+            (content_mesh, rest, 1.0)
+        }
     };
 
-    let content = if !content_mesh.is_empty() {
-        Some(write_tile_glb_to_disk(
-            &content_mesh, materials, texture_config, out_dir, address,
-        ))
-    } else {
-        None
+    let content_error = reduction_error(
+        bounds.diagonal(),
+        full_triangles,
+        content_mesh.triangle_count(),
+    );
+
+    // The content's atlas repacking and encoding run alongside the children,
+    // so a node no longer holds up its whole subtree
+    let write_content = move || {
+        (!content_mesh.is_empty())
+            .then(|| write_tile_glb_to_disk(&content_mesh, ctx, address, texel_scale))
+    };
+    let (content, children) = rayon::join(write_content, || {
+        build_children(mesh, bounds, depth, address, ctx)
+    });
+
+    // With a target SSE, the error follows this node's actual reduction;
+    // it is raised to the largest child error so parents never refine later
+    // than their children.
+    let geometric_error = match ctx.tiling.target_sse {
+        Some(target_sse) => children.iter().map(|c| c.geometric_error).fold(
+            calibrated_geometric_error(content_error, target_sse),
+            f64::max,
+        ),
+        // Output-space units, like the LOD chain errors
+        None => bounds.diagonal() * 0.5_f64.powi(depth as i32),
     };
-    drop(content_mesh);
 
-    // Split the ORIGINAL mesh spatially into 8 octants
-    let sub_meshes = split_mesh(&mesh, bounds);
+    TileNode {
+        address: address.into(),
+        level: depth,
+        bounds: *bounds,
+        geometric_error,
+        content,
+        children,
+        transform: None,
+    }
+}
+
+/// Split `mesh` (a node's full-detail mesh, or for ADD what its content
+/// left over) into the node's cells and build a child tile for each
+/// non-empty one.
+fn build_children(
+    mesh: IndexedMesh,
+    bounds: &BoundingBox,
+    depth: u32,
+    address: &str,
+    ctx: &BuildContext,
+) -> Vec<TileNode> {
+    // Split the ORIGINAL mesh (or, for ADD, what the content left over)
+    // spatially into 8 octants, or 4 quadrants that keep the full Z extent
+    let weld = ctx.tiling.clip_weld;
+    let sub_meshes: Vec<IndexedMesh> = match (ctx.tiling.scheme, ctx.tiling.split_mode) {
+        (Scheme::Octree, Split::Clip) if !weld => split_mesh_unwelded::<8>(&mesh, bounds).into(),
+        (Scheme::Quadtree, Split::Clip) if !weld => split_mesh_unwelded::<4>(&mesh, bounds).into(),
+        (Scheme::Octree, Split::Clip) => split_mesh(&mesh, bounds).into(),
+        (Scheme::Quadtree, Split::Clip) => split_mesh_quadtree(&mesh, bounds).into(),
+        (Scheme::Octree, Split::Centroid) => split_mesh_centroid::<8>(&mesh, bounds).into(),
+        (Scheme::Quadtree, Split::Centroid) => split_mesh_centroid::<4>(&mesh, bounds).into(),
+    };
     drop(mesh);
 
-    // Recurse into non-empty octants in parallel
+    // Recurse into non-empty octants in parallel. Collecting an indexed
+    // parallel iterator keeps octant order, so children come out identically
+    // regardless of which thread finishes first.
     let child_tasks: Vec<_> = sub_meshes
         .into_iter()
         .enumerate()
@@ -238,62 +1372,117 @@ fn build_tile_recursive(
             } else {
                 format!("{address}_{i}")
             };
-            let cb = child_bounds(bounds, i);
+            let mut cb = match ctx.tiling.scheme {
+                Scheme::Octree => child_bounds(bounds, i),
+                Scheme::Quadtree => quadrant_bounds(bounds, i),
+            };
+            if ctx.tiling.split_mode == Split::Centroid {
+                // Whole triangles can reach past the cell they were assigned to
+                cb = cb.merge(&compute_bounding_box(std::slice::from_ref(&sub)));
+            }
             Some((child_addr, sub, cb))
         })
         .collect();
+    let child_tasks = if ctx.tiling.merge_leaves {
+        merge_sibling_leaves(child_tasks, ctx)
+    } else {
+        child_tasks
+    };
 
-    let children: Vec<TileNode> = child_tasks
+    child_tasks
         .into_par_iter()
-        .map(|(child_addr, sub, cb)| {
-            build_tile_recursive(
-                sub,
-                &cb,
-                depth + 1,
-                max_depth,
-                max_tris,
-                &child_addr,
-                materials,
-                texture_config,
-                out_dir,
-            )
-        })
-        .collect();
+        .map(|(child_addr, sub, cb)| build_tile_recursive(sub, &cb, depth + 1, &child_addr, ctx))
+        .collect()
+}
 
-    TileNode {
-        address: address.into(),
-        level: depth,
-        bounds: *bounds,
-        geometric_error,
-        content,
-        children,
+/// Combine sibling cells that will become leaves into as few tiles as the
+/// leaf budget allows, so sparse regions don't produce swarms of tiny tiles.
+///
+/// Cells are merged greedily in octant order. A merged tile keeps the first
+/// member's address and covers the union of the members' bounds; cells that
+/// are still over budget pass through unchanged.
+fn merge_sibling_leaves(
+    tasks: Vec<(String, IndexedMesh, BoundingBox)>,
+    ctx: &BuildContext,
+) -> Vec<(String, IndexedMesh, BoundingBox)> {
+    let mut merged: Vec<(String, IndexedMesh, BoundingBox)> = Vec::with_capacity(tasks.len());
+    // Index in `merged` of the leaf group still accepting cells
+    let mut open_group: Option<usize> = None;
+
+    for (addr, sub, cb) in tasks {
+        if !ctx.leaf_budget(&sub).fits(&sub) {
+            merged.push((addr, sub, cb));
+            continue;
+        }
+        if let Some(i) = open_group {
+            let (_, group_mesh, group_bounds) = &mut merged[i];
+            let combined = merge_meshes(group_mesh.clone(), &sub);
+            if ctx.leaf_budget(&combined).fits(&combined) {
+                *group_mesh = combined;
+                *group_bounds = group_bounds.merge(&cb);
+                continue;
+            }
+        }
+        open_group = Some(merged.len());
+        merged.push((addr, sub, cb));
     }
+
+    merged
 }
 
 /// Write the tileset.json to disk.
 ///
 /// GLB files have already been written eagerly during `build_tileset`.
-/// Returns the total number of tiles (content nodes).
+/// `tileset.json` is written last and only if every tile was written, so an
+/// interrupted or failed run never leaves a loadable but incomplete tileset.
+/// Returns the total number of tiles (content nodes); the JSON files' sizes
+/// are added to `output.tile_stats.bytes_written`.
 pub fn write_tileset(
-    output: &TilesetOutput,
+    output: &mut TilesetOutput,
     transform: &[f64; 16],
     out_dir: &Path,
 ) -> Result<usize> {
+    write_tileset_to_sink(output, transform, &FsSink::new(out_dir))
+}
+
+/// Like [`write_tileset`], but write tileset.json and any external tilesets
+/// through `sink`.
+pub fn write_tileset_to_sink(
+    output: &mut TilesetOutput,
+    transform: &[f64; 16],
+    sink: &dyn TileSink,
+) -> Result<usize> {
+    if let Some(first) = output.write_errors.first() {
+        return Err(PhotoTilerError::Output(format!(
+            "{} tile file(s) failed to write, not writing tileset.json (first: {first})",
+            output.write_errors.len()
+        )));
+    }
+
     let tile_count = count_content_nodes(&output.root);
 
-    // Build tileset.json
-    let tileset_json = build_tileset_json(&output.root, transform);
+    // Build tileset.json and any external tilesets it points to
+    let (tileset_json, externals) = build_tileset_json(output, transform);
+
+    // External tilesets first, so the root never references a missing one
+    for (uri, external) in &externals {
+        let json_string = serde_json::to_string_pretty(external)
+            .map_err(|e| PhotoTilerError::Output(format!("Failed to serialize {uri}: {e}")))?;
+        write_file(sink, uri, json_string.as_bytes())
+            .map_err(|e| PhotoTilerError::Output(format!("Failed to write {uri}: {e}")))?;
+        output.tile_stats.bytes_written += json_string.len() as u64;
+    }
 
-    let tileset_path = out_dir.join("tileset.json");
     let json_string = serde_json::to_string_pretty(&tileset_json)
         .map_err(|e| PhotoTilerError::Output(format!("Failed to serialize tileset.json: {e}")))?;
 
-    fs::write(&tileset_path, &json_string)
+    write_file(sink, "tileset.json", json_string.as_bytes())
         .map_err(|e| PhotoTilerError::Output(format!("Failed to write tileset.json: {e}")))?;
+    output.tile_stats.bytes_written += json_string.len() as u64;
 
     info!(
         tiles = tile_count,
-        path = %tileset_path.display(),
+        external_tilesets = externals.len(),
         "Wrote tileset.json"
     );
 
@@ -306,22 +1495,94 @@ fn count_content_nodes(node: &TileNode) -> usize {
     self_count + node.children.iter().map(count_content_nodes).sum::<usize>()
 }
 
-/// Build the tileset.json as a serde_json::Value.
-fn build_tileset_json(root: &TileNode, transform: &[f64; 16]) -> serde_json::Value {
-    let root_tile = tile_node_to_json(root, Some(transform));
+/// Build the tileset.json as a serde_json::Value, along with the external
+/// tilesets it references as `(path relative to out_dir, json)` pairs.
+fn build_tileset_json(
+    output: &TilesetOutput,
+    transform: &[f64; 16],
+) -> (serde_json::Value, Vec<(String, serde_json::Value)>) {
+    let mut ctx = TilesetJsonContext {
+        output,
+        externals: Vec::new(),
+    };
+    let root_tile = tile_node_to_json(&output.root, Some(transform), "", &mut ctx);
+    let mut tileset = tileset_document(output, output.root.geometric_error, root_tile);
+    if let Some(origin) = output.ecef_origin {
+        tileset["extras"] = json!({ "frame": "localEnu", "ecefOrigin": origin });
+    }
+    (tileset, ctx.externals)
+}
 
-    json!({
+/// Wrap a root tile into a tileset document with the asset, extensions and
+/// schema every tileset of this output shares.
+fn tileset_document(
+    output: &TilesetOutput,
+    geometric_error: f64,
+    root_tile: serde_json::Value,
+) -> serde_json::Value {
+    let version = output.tiles_version;
+    let mut tileset = json!({
         "asset": {
-            "version": "1.1",
+            "version": version.as_str(),
             "generator": "photo-tiler"
         },
-        "geometricError": root.geometric_error,
+        "geometricError": geometric_error,
         "root": root_tile
-    })
+    });
+
+    // 1.0 only knows b3dm/i3dm/pnts/cmpt; GLB content needs the extension
+    if version == TilesVersion::V1_0 && !output.b3dm {
+        tileset["extensionsUsed"] = json!(["3DTILES_content_gltf"]);
+        tileset["extensionsRequired"] = json!(["3DTILES_content_gltf"]);
+        tileset["extensions"] = json!({ "3DTILES_content_gltf": {} });
+    }
+
+    // 1.0 has no top-level schema; its tiles still embed theirs
+    if let (Some(schema), TilesVersion::V1_1) = (&output.schema, version) {
+        tileset["schema"] = schema.clone();
+    }
+
+    tileset
+}
+
+/// State for serializing one tile tree into a root tileset and its
+/// external tilesets.
+struct TilesetJsonContext<'a> {
+    output: &'a TilesetOutput,
+    /// External tilesets collected so far, as `(path, json)`.
+    externals: Vec<(String, serde_json::Value)>,
+}
+
+impl TilesetJsonContext<'_> {
+    /// URI of the file at `path` (relative to out_dir) as seen from the
+    /// tileset in directory `dir`. Only the root tileset (`dir` empty)
+    /// applies the URI base; external ones are resolved against their own
+    /// location.
+    fn uri(&self, dir: &str, path: &str) -> String {
+        if dir.is_empty() {
+            with_uri_base(self.output.uri_base.as_deref(), path)
+        } else {
+            path.strip_prefix(dir).unwrap_or(path).to_string()
+        }
+    }
+
+    /// Whether `node` starts a tileset of its own.
+    fn is_external(&self, node: &TileNode) -> bool {
+        let depth = address_depth(&node.address);
+        self.output
+            .external_tileset_depth
+            .is_some_and(|d| d > 0 && depth > 0 && depth % d as usize == 0)
+    }
 }
 
-/// Convert a TileNode to its tileset.json representation.
-fn tile_node_to_json(node: &TileNode, transform: Option<&[f64; 16]>) -> serde_json::Value {
+/// Convert a TileNode to its tileset.json representation, for the tileset
+/// stored in directory `dir`.
+fn tile_node_to_json(
+    node: &TileNode,
+    transform: Option<&[f64; 16]>,
+    dir: &str,
+    ctx: &mut TilesetJsonContext,
+) -> serde_json::Value {
     let bv = bounding_volume_box(&node.bounds);
 
     let mut tile = json!({
@@ -329,24 +1590,57 @@ fn tile_node_to_json(node: &TileNode, transform: Option<&[f64; 16]>) -> serde_js
             "box": bv
         },
         "geometricError": node.geometric_error,
-        "refine": "REPLACE"
+        "refine": ctx.output.refine.as_str()
     });
 
     if let Some(t) = transform {
         tile["transform"] = json!(t);
     }
 
+    if ctx.output.tile_extras {
+        tile["extras"] = json!({
+            "level": node.level,
+            "triangleCount": node.content.as_ref().map_or(0, |c| c.triangles)
+        });
+    }
+
     if let Some(content) = &node.content {
         tile["content"] = json!({
-            "uri": content.uri
+            "uri": ctx.uri(dir, &content.uri)
         });
+        let tight = content.bounds.filter(|b| is_much_tighter(b, &node.bounds));
+        // An oriented box only pays off when it beats the axis-aligned one
+        let aabb_volume = tight.unwrap_or(node.bounds).volume();
+        if let Some(obb) = content.obb.filter(|o| o.volume() < aabb_volume * 0.9) {
+            // A leaf's volume only has to enclose its content, so it can take
+            // the oriented box too. Inner tiles keep their cell box: the box
+            // is fitted to this level's vertices alone, not the children's.
+            let target = if node.children.is_empty() {
+                &mut tile
+            } else {
+                &mut tile["content"]
+            };
+            target["boundingVolume"] = json!({
+                "box": oriented_volume_box(&obb)
+            });
+        } else if let Some(bounds) = tight {
+            tile["content"]["boundingVolume"] = json!({
+                "box": bounding_volume_box(&bounds)
+            });
+        }
     }
 
     if !node.children.is_empty() {
         let children: Vec<serde_json::Value> = node
             .children
             .iter()
-            .map(|c| tile_node_to_json(c, None))
+            .map(|c| {
+                if ctx.is_external(c) {
+                    external_tile_to_json(c, dir, ctx)
+                } else {
+                    tile_node_to_json(c, c.transform.as_ref(), dir, ctx)
+                }
+            })
             .collect();
         tile["children"] = json!(children);
     }
@@ -354,6 +1648,56 @@ fn tile_node_to_json(node: &TileNode, transform: Option<&[f64; 16]>) -> serde_js
     tile
 }
 
+/// Serialize `node`'s subtree as an external tileset next to its tiles and
+/// return the tile pointing to it. The pointer keeps the node's transform,
+/// which applies to the external tileset's root.
+fn external_tile_to_json(
+    node: &TileNode,
+    dir: &str,
+    ctx: &mut TilesetJsonContext,
+) -> serde_json::Value {
+    let glb = address_to_uri(&node.address);
+    let external_dir = &glb[..glb.rfind('/').map_or(0, |i| i + 1)];
+    let path = format!("{external_dir}tileset.json");
+
+    let root_tile = tile_node_to_json(node, None, external_dir, ctx);
+    let tileset = tileset_document(ctx.output, node.geometric_error, root_tile);
+    ctx.externals.push((path.clone(), tileset));
+
+    let mut tile = json!({
+        "boundingVolume": {
+            "box": bounding_volume_box(&node.bounds)
+        },
+        "geometricError": node.geometric_error,
+        "refine": ctx.output.refine.as_str(),
+        "content": {
+            "uri": ctx.uri(dir, &path)
+        }
+    });
+    if let Some(t) = &node.transform {
+        tile["transform"] = json!(t);
+    }
+    tile
+}
+
+/// Prefix a content URI with `base`, with exactly one `/` between them
+/// whether or not the base ends in one. An empty base adds nothing.
+pub fn with_uri_base(base: Option<&str>, uri: &str) -> String {
+    match base.map(|b| b.trim_end_matches('/')) {
+        Some(base) if !base.is_empty() => format!("{base}/{uri}"),
+        _ => uri.to_string(),
+    }
+}
+
+/// Whether a content box is worth emitting next to its tile box: it must
+/// shrink at least one axis to under 90% of the tile's extent. Smaller gains
+/// aren't worth the extra bytes in tileset.json.
+fn is_much_tighter(content: &BoundingBox, tile: &BoundingBox) -> bool {
+    let c = content.half_extents();
+    let t = tile.half_extents();
+    (0..3).any(|axis| c[axis] < t[axis] * 0.9)
+}
+
 /// Convert a BoundingBox to the 12-float `boundingVolume.box` format.
 ///
 /// Format: `[cx, cy, cz, hx, 0, 0, 0, hy, 0, 0, 0, hz]`
@@ -369,6 +1713,13 @@ fn bounding_volume_box(bounds: &BoundingBox) -> [f64; 12] {
     ]
 }
 
+/// Convert an OrientedBox to the 12-float `boundingVolume.box` format:
+/// the center followed by the three half-axis column vectors.
+fn oriented_volume_box(obb: &OrientedBox) -> [f64; 12] {
+    let (c, [x, y, z]) = (obb.center, obb.half_axes);
+    [c[0], c[1], c[2], x[0], x[1], x[2], y[0], y[1], y[2], z[0], z[1], z[2]]
+}
+
 /// Merge two IndexedMeshes by extending `a` with `b`'s data and offsetting indices.
 /// Takes ownership of `a` to avoid cloning it.
 fn merge_meshes(mut a: IndexedMesh, b: &IndexedMesh) -> IndexedMesh {
@@ -381,6 +1732,17 @@ fn merge_meshes(mut a: IndexedMesh, b: &IndexedMesh) -> IndexedMesh {
 
     let a_vertex_count = a.vertex_count() as u32;
 
+    // Keep material groups covering the whole index buffer
+    if !a.material_groups.is_empty() || !b.material_groups.is_empty() {
+        let offset = a.indices.len();
+        let mut groups = a.material_ranges();
+        groups.extend(b.material_ranges().into_iter().map(|group| MaterialGroup {
+            indices: group.indices.start + offset..group.indices.end + offset,
+            ..group
+        }));
+        a.material_groups = groups;
+    }
+
     a.positions.extend_from_slice(&b.positions);
 
     if a.has_normals() && b.has_normals() {
@@ -395,12 +1757,24 @@ fn merge_meshes(mut a: IndexedMesh, b: &IndexedMesh) -> IndexedMesh {
         a.uvs.clear();
     }
 
+    if a.has_uvs1() && b.has_uvs1() {
+        a.uvs1.extend_from_slice(&b.uvs1);
+    } else {
+        a.uvs1.clear();
+    }
+
     if a.has_colors() && b.has_colors() {
         a.colors.extend_from_slice(&b.colors);
     } else {
         a.colors.clear();
     }
 
+    if a.has_feature_ids() && b.has_feature_ids() {
+        a.feature_ids.extend_from_slice(&b.feature_ids);
+    } else {
+        a.feature_ids.clear();
+    }
+
     a.indices.extend(b.indices.iter().map(|&i| i + a_vertex_count));
 
     if a.material_index.is_none() {
@@ -412,8 +1786,14 @@ fn merge_meshes(mut a: IndexedMesh, b: &IndexedMesh) -> IndexedMesh {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fs;
+
     use super::*;
+    use crate::config::TextureFormat;
     use crate::tiling::lod::{LodChain, LodLevel};
+    use crate::tiling::test_textures::{gradient_texture, png_texture};
+    use crate::types::WrapMode;
 
     fn unit_bounds() -> BoundingBox {
         BoundingBox {
@@ -451,6 +1831,15 @@ mod tests {
         }
     }
 
+    fn collect_uris(node: &TileNode, uris: &mut Vec<String>) {
+        if let Some(content) = &node.content {
+            uris.push(content.uri.clone());
+        }
+        for child in &node.children {
+            collect_uris(child, uris);
+        }
+    }
+
     fn identity() -> [f64; 16] {
         [
             1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
@@ -464,6 +1853,85 @@ mod tests {
         }
     }
 
+    /// Tile single-level chains of `meshes` over `bounds` with `config`,
+    /// untextured, into a directory that is removed again.
+    fn build_meshes_tileset(
+        meshes: &[IndexedMesh],
+        bounds: &BoundingBox,
+        config: &TilingConfig,
+    ) -> TilesetOutput {
+        let chains = meshes
+            .iter()
+            .map(|mesh| LodChain {
+                levels: vec![LodLevel {
+                    level: 0,
+                    mesh: mesh.clone(),
+                    geometric_error: 0.0,
+                }],
+                bounds: *bounds,
+                instances: Vec::new(),
+            })
+            .collect();
+        let tmp = tempfile::tempdir().unwrap();
+        let materials = MaterialLibrary::default();
+        let textures = tex_config_disabled();
+        build_tileset(chains, bounds, config, &materials, &textures, tmp.path())
+    }
+
+    /// [`build_meshes_tileset`] of a 10x10 grid over the unit cube.
+    fn build_grid_tileset(config: &TilingConfig) -> TilesetOutput {
+        build_meshes_tileset(&[make_grid_mesh(10)], &unit_bounds(), config)
+    }
+
+    /// A textured quad referencing a 16x16 PNG checkerboard.
+    fn make_textured_quad() -> (IndexedMesh, MaterialLibrary) {
+        let mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.5, 1.0, 0.0, 0.5, 1.0, 1.0, 0.5, 0.0, 1.0, 0.5],
+            uvs: vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0],
+            indices: vec![0, 1, 2, 0, 2, 3],
+            material_index: Some(0),
+            ..Default::default()
+        };
+
+        let img = image::RgbaImage::from_fn(16, 16, |x, y| {
+            if (x / 4 + y / 4) % 2 == 0 {
+                image::Rgba([255, 255, 255, 255])
+            } else {
+                image::Rgba([0, 0, 0, 255])
+            }
+        });
+
+        let mut materials = MaterialLibrary::default();
+        materials.textures.push(png_texture(&img));
+        materials.materials.push(crate::types::PBRMaterial {
+            name: "textured".into(),
+            base_color_texture: Some(0),
+            ..Default::default()
+        });
+
+        (mesh, materials)
+    }
+
+    fn make_context<'a>(
+        tiling: &'a TilingConfig,
+        materials: &'a MaterialLibrary,
+        texture_config: &'a TextureConfig,
+        sink: &'a FsSink,
+    ) -> BuildContext<'a> {
+        BuildContext {
+            tiling,
+            materials,
+            texture_config,
+            sink,
+            atlases: Mutex::new(AtlasRegistry::default()),
+            stats: Mutex::new(TileStats::default()),
+            write_errors: Mutex::new(Vec::new()),
+            budgeted_tiles: Mutex::new(Vec::new()),
+            spilled_meshes: MeshSpill::default(),
+            decoded_sources: Default::default(),
+        }
+    }
+
     #[test]
     fn build_tileset_single_level() {
         let mesh = make_grid_mesh(4); // 32 triangles
@@ -474,11 +1942,13 @@ mod tests {
                 geometric_error: 0.0,
             }],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
 
         let config = TilingConfig {
             max_triangles_per_tile: 100,
             max_depth: 4,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
@@ -495,6 +1965,44 @@ mod tests {
         assert_eq!(output.root.level, 0);
     }
 
+    #[test]
+    fn separate_objects_get_sibling_subtrees() {
+        // Two unit grids 10 m apart, the far one in two meshes of one name
+        let near = make_grid_mesh(4);
+        let mut far = make_grid_mesh(4);
+        for p in far.positions.chunks_exact_mut(3) {
+            p[0] += 10.0;
+        }
+        far.name = Some("far".into());
+        let mut far_top = far.clone();
+        for p in far_top.positions.chunks_exact_mut(3) {
+            p[2] += 0.5;
+        }
+        let meshes = [near.clone(), far.clone(), far_top.clone()];
+        let objects = [near, merge_meshes(far, &far_top)];
+        let bounds = compute_bounding_box(&objects);
+        let config = TilingConfig {
+            max_triangles_per_tile: 8,
+            separate_objects: true,
+            ..Default::default()
+        };
+
+        let output = build_meshes_tileset(&meshes, &bounds, &config);
+        let root = &output.root;
+        assert!(root.content.is_none());
+        assert_eq!(root.children.len(), 2);
+        for (child, object) in root.children.iter().zip(&objects) {
+            let object_bounds = compute_bounding_box(std::slice::from_ref(object));
+            for axis in 0..3 {
+                assert!(child.bounds.min[axis] <= object_bounds.min[axis]);
+                assert!(child.bounds.max[axis] >= object_bounds.max[axis]);
+            }
+            // Each subtree covers its own object only
+            assert!(child.bounds.max[0] - child.bounds.min[0] < 2.0);
+            assert!(!child.children.is_empty());
+        }
+    }
+
     #[test]
     fn build_tileset_multi_level() {
         let mesh = make_grid_mesh(10); // 200 triangles
@@ -508,12 +2016,14 @@ mod tests {
                 },
             ],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
 
         // Use low max_triangles to force subdivision
         let config = TilingConfig {
             max_triangles_per_tile: 50,
             max_depth: 4,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
@@ -543,74 +2053,495 @@ mod tests {
     }
 
     #[test]
-    fn build_tileset_four_lods() {
-        // With the new unified approach, we only use LOD-0 meshes.
-        // Pass a large mesh and force subdivision via low max_triangles.
-        let lod0 = make_grid_mesh(16); // 512 tris
+    fn sparse_sibling_leaves_merge_into_one_tile() {
+        // A dense patch fills the low octant; every other octant holds a
+        // single two-triangle quad.
+        let patch = |n: usize, size: f32, origin: [f32; 3]| {
+            let mut mesh = make_grid_mesh(n);
+            for p in mesh.positions.chunks_exact_mut(3) {
+                p[0] = origin[0] + p[0] * size;
+                p[1] = origin[1] + p[1] * size;
+                p[2] = origin[2];
+            }
+            mesh
+        };
+        let mut mesh = patch(20, 0.4, [0.05, 0.05, 0.25]);
+        for octant in 1..8 {
+            let at = |bit: usize| if octant & bit != 0 { 0.7 } else { 0.2 };
+            mesh = merge_meshes(mesh, &patch(1, 0.1, [at(1), at(2), at(4)]));
+        }
 
-        let chain = LodChain {
-            levels: vec![
-                LodLevel {
+        let build = |merge_leaves: bool| {
+            let chain = LodChain {
+                levels: vec![LodLevel {
                     level: 0,
-                    mesh: lod0,
+                    mesh: mesh.clone(),
                     geometric_error: 0.0,
-                },
-            ],
-            bounds: unit_bounds(),
+                }],
+                bounds: unit_bounds(),
+                instances: Vec::new(),
+            };
+            let config = TilingConfig {
+                max_triangles_per_tile: 300,
+                max_depth: 4,
+                merge_leaves,
+                ..Default::default()
+            };
+            let tmp = tempfile::tempdir().unwrap();
+            build_tileset(
+                vec![chain],
+                &unit_bounds(),
+                &config,
+                &MaterialLibrary::default(),
+                &tex_config_disabled(),
+                tmp.path(),
+            )
+            .root
         };
 
+        assert_eq!(build(false).children.len(), 8);
+
+        let root = build(true);
+        assert_eq!(root.children.len(), 2);
+        let merged = root
+            .children
+            .iter()
+            .find(|c| c.children.is_empty())
+            .expect("the sparse octants should form one leaf");
+        // The union of the seven sparse cells spans the whole root cell
+        assert_eq!(merged.bounds.min, [0.0, 0.0, 0.0]);
+        assert_eq!(merged.bounds.max, [1.0, 1.0, 1.0]);
+        let content = merged.content.as_ref().unwrap().bounds.unwrap();
+        // Quads are flat, so content tops out at their Z
+        for (axis, max) in [0.8, 0.8, 0.7].into_iter().enumerate() {
+            assert!((content.min[axis] - 0.2).abs() < 1e-5);
+            assert!((content.max[axis] - max).abs() < 1e-5);
+        }
+    }
+
+    /// Build a subdivided rolling-terrain tileset without leaf merging.
+    fn build_terrain(scheme: Scheme, tile_order: TileOrder) -> TileNode {
+        let mut mesh = make_grid_mesh(24);
+        for p in mesh.positions.chunks_exact_mut(3) {
+            p[2] = 0.5 + 0.05 * (p[0] * 12.0).sin();
+        }
+        let chain = LodChain {
+            levels: vec![LodLevel {
+                level: 0,
+                mesh,
+                geometric_error: 0.0,
+            }],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
         let config = TilingConfig {
-            max_triangles_per_tile: 50,
-            max_depth: 4,
+            max_triangles_per_tile: 100,
+            max_depth: 3,
+            scheme,
+            tile_order,
+            ..Default::default()
         };
-        let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
-
-        let output = build_tileset(
+        build_tileset(
             vec![chain],
             &unit_bounds(),
             &config,
-            &materials,
+            &MaterialLibrary::default(),
             &tex_config_disabled(),
             tmp.path(),
-        );
+        )
+        .root
+    }
 
-        assert_eq!(output.root.address, "root");
-        assert!(output.root.content.is_some());
+    /// Octant (or quadrant) index of each child, from its address.
+    fn child_octants(node: &TileNode) -> Vec<usize> {
+        node.children
+            .iter()
+            .map(|c| c.address.rsplit('_').next().unwrap().parse().unwrap())
+            .collect()
+    }
 
-        // Verify hierarchy depth >= 2 (root + at least one level of children)
-        fn max_depth(node: &TileNode) -> usize {
-            if node.children.is_empty() {
-                1
-            } else {
-                1 + node.children.iter().map(max_depth).max().unwrap_or(0)
-            }
+    #[test]
+    fn siblings_are_emitted_in_morton_order() {
+        fn check(node: &TileNode, checked: &mut usize) {
+            let octants = child_octants(node);
+            assert!(octants.is_sorted(), "{}: {octants:?}", node.address);
+            *checked += usize::from(octants.len() > 1);
+            node.children.iter().for_each(|c| check(c, checked));
         }
-        let depth = max_depth(&output.root);
-        assert!(
-            depth >= 2,
-            "subdivided hierarchy should have depth >= 2, got {depth}"
-        );
+        let root = build_terrain(Scheme::Octree, TileOrder::Morton);
+        let mut checked = 0;
+        check(&root, &mut checked);
+        assert!(checked > 1);
     }
 
     #[test]
-    fn geometric_error_decreasing() {
-        let lod0 = make_grid_mesh(16); // 512 tris
+    fn hilbert_order_keeps_consecutive_siblings_adjacent() {
+        fn check(node: &TileNode, checked: &mut usize) {
+            let quadrants = child_octants(node);
+            if quadrants.len() == 4 {
+                // Adjacent quadrants differ in exactly one axis bit
+                for pair in quadrants.windows(2) {
+                    let diff = pair[0] ^ pair[1];
+                    assert!(diff.is_power_of_two(), "{}: {quadrants:?}", node.address);
+                }
+                *checked += 1;
+            }
+            node.children.iter().for_each(|c| check(c, checked));
+        }
+        let root = build_terrain(Scheme::Quadtree, TileOrder::Hilbert);
+        let mut checked = 0;
+        check(&root, &mut checked);
+        assert!(checked > 1);
+
+        // Morton order jumps diagonally from quadrant 1 to 2
+        let morton = build_terrain(Scheme::Quadtree, TileOrder::Morton);
+        assert_eq!(child_octants(&morton), vec![0, 1, 2, 3]);
+    }
 
-        let chain = LodChain {
-            levels: vec![
-                LodLevel {
+    #[test]
+    fn cube_root_subdivides_cubes_but_fits_tile_volumes() {
+        // A 10:1:1 scene
+        let mut mesh = make_grid_mesh(24);
+        for p in mesh.positions.chunks_exact_mut(3) {
+            p[2] = 0.5 + 0.5 * (p[0] * 12.0).sin();
+            p[0] *= 10.0;
+        }
+        let scene = compute_bounding_box(std::slice::from_ref(&mesh));
+        let build = |cube_root| {
+            let chain = LodChain {
+                levels: vec![LodLevel {
                     level: 0,
-                    mesh: lod0,
+                    mesh: mesh.clone(),
                     geometric_error: 0.0,
-                },
-            ],
+                }],
+                bounds: scene,
+                instances: Vec::new(),
+            };
+            let config = TilingConfig {
+                max_triangles_per_tile: 100,
+                max_depth: 3,
+                cube_root,
+                ..Default::default()
+            };
+            let tmp = tempfile::tempdir().unwrap();
+            let materials = MaterialLibrary::default();
+            let (textures, out) = (tex_config_disabled(), tmp.path());
+            build_tileset(vec![chain], &scene, &config, &materials, &textures, out).root
+        };
+        fn contains(outer: &BoundingBox, inner: &BoundingBox) -> bool {
+            (0..3).all(|axis| {
+                inner.min[axis] >= outer.min[axis] - 1e-9
+                    && inner.max[axis] <= outer.max[axis] + 1e-9
+            })
+        }
+        // Every tile's box is its content and children's, never a bare cell
+        fn check(node: &TileNode, checked: &mut usize) {
+            let mut union = node.content.as_ref().and_then(|c| c.bounds);
+            for child in &node.children {
+                assert!(contains(&node.bounds, &child.bounds), "{}", child.address);
+                union = Some(union.map_or(child.bounds, |u| u.merge(&child.bounds)));
+                check(child, checked);
+            }
+            assert_eq!(union, Some(node.bounds), "{}", node.address);
+            *checked += 1;
+        }
+        fn addresses(node: &TileNode, out: &mut Vec<String>) {
+            out.push(node.address.clone());
+            node.children.iter().for_each(|c| addresses(c, out));
+        }
+
+        let tight = build(false);
+        let root = build(true);
+        let mut checked = 0;
+        check(&root, &mut checked);
+        assert!(checked > 1);
+
+        // The root keeps the scene's 10:1:1 box, not the 10:10:10 cube
+        let he = root.bounds.half_extents();
+        assert!((he[0] - 5.0).abs() < 1e-4, "{he:?}");
+        assert!(he[1] <= 0.5 + 1e-4 && he[2] <= 0.5 + 1e-4, "{he:?}");
+
+        // ... while the cube still decided where the tree split
+        let (mut cubed, mut fitted) = (Vec::new(), Vec::new());
+        addresses(&root, &mut cubed);
+        addresses(&tight, &mut fitted);
+        assert_ne!(cubed, fitted);
+    }
+
+    #[test]
+    fn quadtree_splits_four_ways_with_fewer_tiles() {
+        // Gently rolling terrain straddling the Z mid-plane, so the octree
+        // also splits vertically while the quadtree keeps cells full height.
+        let mut mesh = make_grid_mesh(24);
+        for p in mesh.positions.chunks_exact_mut(3) {
+            p[2] = 0.5 + 0.05 * (p[0] * 12.0).sin();
+        }
+
+        fn count_nodes(node: &TileNode, max_children: &mut usize) -> usize {
+            *max_children = (*max_children).max(node.children.len());
+            1 + node
+                .children
+                .iter()
+                .map(|c| count_nodes(c, max_children))
+                .sum::<usize>()
+        }
+
+        let mut tile_counts = Vec::new();
+        for scheme in [Scheme::Octree, Scheme::Quadtree] {
+            let chain = LodChain {
+                levels: vec![LodLevel {
+                    level: 0,
+                    mesh: mesh.clone(),
+                    geometric_error: 0.0,
+                }],
+                bounds: unit_bounds(),
+                instances: Vec::new(),
+            };
+            let config = TilingConfig {
+                max_triangles_per_tile: 100,
+                max_depth: 4,
+                scheme,
+                ..Default::default()
+            };
+            let tmp = tempfile::tempdir().unwrap();
+            let output = build_tileset(
+                vec![chain],
+                &unit_bounds(),
+                &config,
+                &MaterialLibrary::default(),
+                &tex_config_disabled(),
+                tmp.path(),
+            );
+
+            let mut max_children = 0;
+            tile_counts.push(count_nodes(&output.root, &mut max_children));
+            if scheme == Scheme::Quadtree {
+                assert_eq!(max_children, 4);
+                for child in &output.root.children {
+                    assert_eq!(child.bounds.min[2], 0.0);
+                    assert_eq!(child.bounds.max[2], 1.0);
+                }
+            }
+        }
+
+        assert!(
+            tile_counts[1] < tile_counts[0],
+            "quadtree {} tiles vs octree {}",
+            tile_counts[1],
+            tile_counts[0]
+        );
+    }
+
+    #[test]
+    fn leaves_omit_children_and_errors_respect_the_clamp() {
+        let build = |max_geometric_error| {
+            let output = build_grid_tileset(&TilingConfig {
+                max_triangles_per_tile: 50,
+                max_depth: 4,
+                max_geometric_error,
+                ..Default::default()
+            });
+            build_tileset_json(&output, &identity()).0
+        };
+
+        fn check(tile: &serde_json::Value, max: f64, leaves: &mut usize) {
+            let error = tile["geometricError"].as_f64().unwrap();
+            assert!(error <= max, "{error} > {max}");
+            match tile.get("children") {
+                Some(children) => {
+                    let children = children.as_array().unwrap();
+                    assert!(!children.is_empty(), "empty children array");
+                    children.iter().for_each(|c| check(c, error, leaves));
+                }
+                None => {
+                    assert_eq!(error, 0.0, "leaf refines further");
+                    *leaves += 1;
+                }
+            }
+        }
+
+        let unclamped = build(None);
+        let root_error = unclamped["geometricError"].as_f64().unwrap();
+        let max = root_error / 4.0;
+        let clamped = build(Some(max));
+        assert_eq!(clamped["geometricError"].as_f64(), Some(max));
+        assert_eq!(clamped["root"]["geometricError"].as_f64(), Some(max));
+
+        let mut leaves = 0;
+        check(&clamped["root"], max, &mut leaves);
+        assert!(leaves > 1);
+    }
+
+    #[test]
+    fn geometric_error_scale_multiplies_every_error() {
+        let build = |geometric_error_scale| {
+            let output = build_grid_tileset(&TilingConfig {
+                max_triangles_per_tile: 50,
+                max_depth: 4,
+                geometric_error_scale,
+                ..Default::default()
+            });
+            build_tileset_json(&output, &identity()).0
+        };
+
+        fn check(base: &serde_json::Value, scaled: &serde_json::Value, parent: f64) {
+            let error = scaled["geometricError"].as_f64().unwrap();
+            assert_eq!(error, base["geometricError"].as_f64().unwrap() * 2.0);
+            assert!(error <= parent, "{error} > {parent}");
+            let children = |tile: &serde_json::Value| tile["children"].as_array().cloned();
+            let (base, scaled) = (children(base), children(scaled));
+            assert_eq!(base.is_some(), scaled.is_some());
+            for (b, s) in base.iter().flatten().zip(scaled.iter().flatten()) {
+                check(b, s, error);
+            }
+        }
+
+        let base = build(1.0);
+        let scaled = build(2.0);
+        let root_error = base["geometricError"].as_f64().unwrap();
+        assert!(root_error > 0.0);
+        assert_eq!(scaled["geometricError"].as_f64(), Some(root_error * 2.0));
+        check(&base["root"], &scaled["root"], f64::INFINITY);
+    }
+
+    #[test]
+    fn tile_extras_record_level_and_triangle_count() {
+        let output = build_grid_tileset(&TilingConfig {
+            max_triangles_per_tile: 50,
+            max_depth: 4,
+            tile_extras: true,
+            ..Default::default()
+        });
+        let tileset = build_tileset_json(&output, &identity()).0;
+
+        fn check(node: &TileNode, tile: &serde_json::Value, tiles: &mut usize) {
+            let extras = &tile["extras"];
+            assert_eq!(extras["level"].as_u64(), Some(u64::from(node.level)));
+            let triangles = node.content.as_ref().map_or(0, |c| c.triangles);
+            assert_eq!(extras["triangleCount"].as_u64(), Some(triangles as u64));
+            let children = tile["children"].as_array().map_or(&[][..], Vec::as_slice);
+            assert_eq!(children.len(), node.children.len());
+            for (child, child_tile) in node.children.iter().zip(children) {
+                check(child, child_tile, tiles);
+            }
+            *tiles += 1;
+        }
+        let mut tiles = 0;
+        check(&output.root, &tileset["root"], &mut tiles);
+        assert!(tiles > 2);
+        assert!(output.root.children.iter().all(|c| c.level == 1));
+
+        // Off by default
+        let output = TilesetOutput {
+            tile_extras: false,
+            ..output
+        };
+        let tileset = build_tileset_json(&output, &identity()).0;
+        assert!(tileset["root"].get("extras").is_none());
+        assert!(tileset.get("extras").is_none());
+
+        // The tileset itself records a local ENU frame's origin
+        let output = TilesetOutput {
+            ecef_origin: Some([1.0, 2.0, 3.0]),
+            ..output
+        };
+        let tileset = build_tileset_json(&output, &identity()).0;
+        assert_eq!(tileset["extras"]["ecefOrigin"], json!([1.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn build_tileset_four_lods() {
+        // With the new unified approach, we only use LOD-0 meshes.
+        // Pass a large mesh and force subdivision via low max_triangles.
+        let lod0 = make_grid_mesh(16); // 512 tris
+
+        let chain = LodChain {
+            levels: vec![
+                LodLevel {
+                    level: 0,
+                    mesh: lod0,
+                    geometric_error: 0.0,
+                },
+            ],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
+
+        let config = TilingConfig {
+            max_triangles_per_tile: 50,
+            max_depth: 4,
+            ..Default::default()
+        };
+        let materials = MaterialLibrary::default();
+        let tmp = tempfile::tempdir().unwrap();
+
+        let output = build_tileset(
+            vec![chain],
+            &unit_bounds(),
+            &config,
+            &materials,
+            &tex_config_disabled(),
+            tmp.path(),
+        );
+
+        assert_eq!(output.root.address, "root");
+        assert!(output.root.content.is_some());
+
+        // Verify hierarchy depth >= 2 (root + at least one level of children)
+        fn max_depth(node: &TileNode) -> usize {
+            if node.children.is_empty() {
+                1
+            } else {
+                1 + node.children.iter().map(max_depth).max().unwrap_or(0)
+            }
+        }
+        let depth = max_depth(&output.root);
+        assert!(
+            depth >= 2,
+            "subdivided hierarchy should have depth >= 2, got {depth}"
+        );
+    }
+
+    #[test]
+    fn auto_depth_overrides_max_depth() {
+        // max_depth 0 alone would keep a single tile
+        let config = TilingConfig {
+            max_triangles_per_tile: 50,
+            max_depth: 0,
+            auto_depth: true,
+            ..Default::default()
+        };
+        let mesh = make_grid_mesh(16); // 512 tris
+        let output = build_meshes_tileset(&[mesh], &unit_bounds(), &config);
+
+        fn deepest(node: &TileNode) -> u32 {
+            node.children.iter().map(deepest).fold(node.level, u32::max)
+        }
+        // 512 / 50 triangles need two octree levels
+        assert_eq!(deepest(&output.root), 2);
+    }
+
+    #[test]
+    fn geometric_error_decreasing() {
+        let lod0 = make_grid_mesh(16); // 512 tris
+
+        let chain = LodChain {
+            levels: vec![
+                LodLevel {
+                    level: 0,
+                    mesh: lod0,
+                    geometric_error: 0.0,
+                },
+            ],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
 
         let config = TilingConfig {
             max_triangles_per_tile: 50,
             max_depth: 4,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
@@ -659,6 +2590,57 @@ mod tests {
         check_leaf_zero(&output.root);
     }
 
+    #[test]
+    fn target_sse_calibrates_monotonic_errors() {
+        fn check_monotonic(node: &TileNode) {
+            for child in &node.children {
+                assert!(child.geometric_error <= node.geometric_error);
+                check_monotonic(child);
+            }
+        }
+
+        let mut root_errors = Vec::new();
+        for target_sse in [2.0, 8.0] {
+            let chain = LodChain {
+                levels: vec![LodLevel {
+                    level: 0,
+                    mesh: make_grid_mesh(40),
+                    geometric_error: 0.0,
+                }],
+                bounds: unit_bounds(),
+                instances: Vec::new(),
+            };
+            let config = TilingConfig {
+                max_triangles_per_tile: 200,
+                max_depth: 4,
+                target_sse: Some(target_sse),
+                ..Default::default()
+            };
+            let tmp = tempfile::tempdir().unwrap();
+            let output = build_tileset(
+                vec![chain],
+                &unit_bounds(),
+                &config,
+                &MaterialLibrary::default(),
+                &tex_config_disabled(),
+                tmp.path(),
+            );
+            assert!(output.root.geometric_error > 0.0);
+            check_monotonic(&output.root);
+            root_errors.push(output.root.geometric_error);
+        }
+
+        // A 4x larger pixel budget swaps 4x closer.
+        assert!((root_errors[0] / root_errors[1] - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn address_depth_counts_segments() {
+        assert_eq!(address_depth("root"), 0);
+        assert_eq!(address_depth("5"), 1);
+        assert_eq!(address_depth("0_3_1"), 3);
+    }
+
     #[test]
     fn address_to_uri_mapping() {
         assert_eq!(address_to_uri("root"), "tiles/root.glb");
@@ -668,217 +2650,1304 @@ mod tests {
     }
 
     #[test]
-    fn write_tileset_creates_files() {
-        let mesh = make_grid_mesh(4);
-        let chain = LodChain {
+    fn uri_base_joins_with_one_slash() {
+        let uri = "tiles/0/tile.glb";
+        let expected = "https://cdn.example.com/a/tiles/0/tile.glb";
+        assert_eq!(
+            with_uri_base(Some("https://cdn.example.com/a"), uri),
+            expected
+        );
+        assert_eq!(
+            with_uri_base(Some("https://cdn.example.com/a/"), uri),
+            expected
+        );
+        assert_eq!(with_uri_base(Some(""), uri), uri);
+        assert_eq!(with_uri_base(None, uri), uri);
+    }
+
+    #[test]
+    fn repeating_texture_bypasses_atlas_with_source_sampler() {
+        use gltf::texture::WrappingMode;
+
+        let (mut mesh, mut materials) = make_textured_quad();
+        // The checkerboard repeats three times across the quad
+        mesh.uvs = vec![0.0, 0.0, 3.0, 0.0, 3.0, 3.0, 0.0, 3.0];
+        // Plain buffers so the GLB can be imported
+        let tiling = TilingConfig {
+            meshopt: false,
+            ..Default::default()
+        };
+        let texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+
+        for (wrap, expected) in [
+            (WrapMode::Repeat, WrappingMode::Repeat),
+            (WrapMode::ClampToEdge, WrappingMode::ClampToEdge),
+        ] {
+            materials.textures[0].wrap = [wrap; 2];
+            let sink = FsSink::new(tmp.path());
+            let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+            write_tile_glb_to_disk(&mesh, &ctx, "0", 1.0);
+
+            let glb = fs::read(tmp.path().join(address_to_uri("0"))).unwrap();
+            let (gltf, _, images) = gltf::import_slice(&glb).unwrap();
+            let texture = gltf.textures().next().expect("tile should be textured");
+            let sampler = texture.sampler();
+            assert_eq!(sampler.wrap_s(), expected);
+            assert_eq!(sampler.wrap_t(), expected);
+
+            // Only the bypassed tile keeps the source image and UVs
+            let bypassed = wrap == WrapMode::Repeat;
+            assert_eq!(images[0].width == 16 && images[0].height == 16, bypassed);
+            let stats = ctx.atlases.into_inner().unwrap().stats;
+            assert_eq!(stats.atlases, usize::from(!bypassed));
+        }
+    }
+
+    #[test]
+    fn original_format_keeps_png_sources() {
+        let (mut mesh, materials) = make_textured_quad();
+        let tiling = TilingConfig {
+            meshopt: false,
+            ..Default::default()
+        };
+        let texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let embedded_image = |mesh: &IndexedMesh| {
+            let sink = FsSink::new(tmp.path());
+            let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+            write_tile_glb_to_disk(mesh, &ctx, "0", 1.0);
+            let glb = fs::read(tmp.path().join(address_to_uri("0"))).unwrap();
+            let gltf = gltf::Gltf::from_slice(&glb).unwrap();
+            let blob = gltf.blob.as_deref().unwrap();
+            let image = gltf.images().next().expect("tile should be textured");
+            match image.source() {
+                gltf::image::Source::View { view, mime_type } => {
+                    let bytes = &blob[view.offset()..view.offset() + view.length()];
+                    (mime_type.to_string(), bytes.to_vec())
+                }
+                gltf::image::Source::Uri { .. } => panic!("image should be embedded"),
+            }
+        };
+
+        // A recomposited atlas is re-encoded, as PNG rather than WebP
+        let (mime_type, _) = embedded_image(&mesh);
+        assert_eq!(mime_type, "image/png");
+
+        // A repeating source texture is written exactly as it was read
+        mesh.uvs = vec![0.0, 0.0, 3.0, 0.0, 3.0, 3.0, 0.0, 3.0];
+        let (mime_type, bytes) = embedded_image(&mesh);
+        assert_eq!(mime_type, "image/png");
+        assert_eq!(bytes, materials.textures[0].data);
+    }
+
+    #[test]
+    fn parallel_repack_matches_sequential_tiles() {
+        // Two textured halves, so tiles decode and composite two sources
+        let (_, mut materials) = make_textured_quad();
+        materials.textures[0] = gradient_texture(256);
+        materials.textures.push(materials.textures[0].clone());
+        materials.materials.push(crate::types::PBRMaterial {
+            name: "second".into(),
+            base_color_texture: Some(1),
+            ..Default::default()
+        });
+        let mut mesh = make_grid_mesh(24);
+        mesh.uvs = mesh
+            .positions
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1]])
+            .collect();
+        let face_materials: Vec<Option<usize>> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|tri| Some(usize::from(mesh.positions[tri[0] as usize * 3] >= 0.5)))
+            .collect();
+        mesh.set_triangle_materials(&face_materials);
+
+        let tiling = TilingConfig {
+            max_triangles_per_tile: 200,
+            max_depth: 3,
+            merge_leaves: true,
+            ..Default::default()
+        };
+        let textures = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let build = |threads, textures: &TextureConfig| {
+            let tmp = tempfile::tempdir().unwrap();
+            let chain = LodChain {
+                levels: vec![LodLevel {
+                    level: 0,
+                    mesh: mesh.clone(),
+                    geometric_error: 0.0,
+                }],
+                bounds: unit_bounds(),
+                instances: Vec::new(),
+            };
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let output = pool.install(|| {
+                let (bounds, out) = (unit_bounds(), tmp.path());
+                build_tileset(vec![chain], &bounds, &tiling, &materials, textures, out)
+            });
+
+            let mut tiles = Vec::new();
+            let mut stack = vec![&output.root];
+            while let Some(node) = stack.pop() {
+                if let Some(content) = &node.content {
+                    let bytes = fs::read(tmp.path().join(&content.uri)).unwrap();
+                    tiles.push((content.uri.clone(), bytes));
+                }
+                stack.extend(&node.children);
+            }
+            tiles.sort();
+            (tiles, output.atlas_stats)
+        };
+
+        let (sequential, sequential_stats) = build(1, &textures);
+        let (parallel, parallel_stats) = build(4, &textures);
+        assert!(sequential.len() > 2, "{} tiles", sequential.len());
+        assert_eq!(sequential_stats.atlases, sequential.len());
+        assert_eq!(parallel_stats, sequential_stats);
+        for ((uri, a), (_, b)) in sequential.iter().zip(&parallel) {
+            assert!(a == b, "{uri} differs between 1 and 4 threads");
+        }
+        assert_eq!(parallel.len(), sequential.len());
+
+        // A texture memory budget shrinks the same atlases whatever the schedule
+        let total: u64 = sequential.iter().map(|(_, bytes)| bytes.len() as u64).sum();
+        let budgeted = TextureConfig {
+            max_memory: Some(total / 4),
+            ..textures
+        };
+        let (sequential_budgeted, sequential_stats) = build(1, &budgeted);
+        assert!(sequential_budgeted != sequential, "budget should shrink");
+        assert!(sequential_stats.budget_bytes > 0);
+        for _ in 0..2 {
+            let (parallel_budgeted, parallel_stats) = build(4, &budgeted);
+            assert_eq!(parallel_stats, sequential_stats);
+            assert!(parallel_budgeted == sequential_budgeted);
+        }
+    }
+
+    #[test]
+    fn shared_atlas_uri_is_relative_to_glb() {
+        assert_eq!(shared_atlas_uri("tiles/root.glb", "a.png"), "shared/a.png");
+        assert_eq!(shared_atlas_uri("tiles/0/tile.glb", "a.png"), "../shared/a.png");
+        assert_eq!(
+            shared_atlas_uri("tiles/0/0_3/tile.glb", "a.png"),
+            "../../shared/a.png"
+        );
+    }
+
+    #[test]
+    fn shared_atlases_with_colliding_hashes_keep_their_own_files() {
+        let tiling = TilingConfig::default();
+        let materials = MaterialLibrary::default();
+        let texture_config = TextureConfig {
+            share_atlases: true,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let sink = FsSink::new(tmp.path());
+        let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+        let atlas = TextureData {
+            data: vec![1, 2, 3, 4],
+            mime_type: "image/png".into(),
+            width: 1,
+            height: 1,
+            wrap: [WrapMode::Repeat; 2],
+        };
+        let hash = xxh64(&atlas.data);
+
+        // Other bytes of the same length already took this hash
+        let other = SeenAtlas {
+            len: 4,
+            data: Some(vec![4, 3, 2, 1]),
+        };
+        ctx.atlases.lock().unwrap().seen.insert(hash, vec![other]);
+        let (name, is_new) = ctx.register_atlas(&atlas);
+        assert!(is_new);
+        assert_eq!(name, format!("{hash:016x}-1"));
+        assert_eq!(ctx.register_atlas(&atlas), (name, false));
+    }
+
+    #[test]
+    fn identical_atlases_written_once_when_shared() {
+        let (mesh, materials) = make_textured_quad();
+        let tiling = TilingConfig::default();
+        let texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            share_atlases: true,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let sink = FsSink::new(tmp.path());
+        let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+
+        write_tile_glb_to_disk(&mesh, &ctx, "0", 1.0);
+        write_tile_glb_to_disk(&mesh, &ctx, "1", 1.0);
+
+        let shared: Vec<_> = fs::read_dir(tmp.path().join("tiles/shared"))
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .collect();
+        assert_eq!(shared.len(), 1, "only one atlas image should be written");
+        let atlas_len = shared[0].metadata().unwrap().len();
+
+        let stats = ctx.atlases.into_inner().unwrap().stats;
+        assert_eq!(stats.atlases, 2);
+        assert_eq!(stats.unique_atlases, 1);
+        assert_eq!(stats.duplicate_bytes, atlas_len);
+
+        // Both GLBs reference the shared file rather than embedding it
+        for addr in ["0", "1"] {
+            let glb = fs::read(tmp.path().join(address_to_uri(addr))).unwrap();
+            let gltf = gltf::Gltf::from_slice_without_validation(&glb).unwrap();
+            let image = gltf.document.images().next().expect("tile should have an image");
+            match image.source() {
+                gltf::image::Source::Uri { uri, .. } => {
+                    let resolved = tmp.path().join("tiles").join(addr).join(uri);
+                    assert!(resolved.exists(), "{uri} should resolve from tile {addr}");
+                }
+                gltf::image::Source::View { .. } => panic!("atlas should not be embedded"),
+            }
+        }
+    }
+
+    #[test]
+    fn duplicate_atlas_bytes_reported_when_embedded() {
+        let (mesh, materials) = make_textured_quad();
+        let tiling = TilingConfig::default();
+        let texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let sink = FsSink::new(tmp.path());
+        let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+
+        write_tile_glb_to_disk(&mesh, &ctx, "0", 1.0);
+        write_tile_glb_to_disk(&mesh, &ctx, "1", 1.0);
+
+        assert!(!tmp.path().join("tiles/shared").exists());
+        let stats = ctx.atlases.into_inner().unwrap().stats;
+        assert_eq!(stats.atlases, 2);
+        assert_eq!(stats.unique_atlases, 1);
+        assert!(stats.duplicate_bytes > 0);
+    }
+
+    #[test]
+    fn atlas_over_hard_max_size_splits_into_primitives() {
+        // A 4x4 grid of separate quads, each sampling its own 32px cell of a
+        // 128px texture, so every quad is a UV island
+        let (_, mut materials) = make_textured_quad();
+        materials.textures[0] = gradient_texture(128);
+        let mut mesh = IndexedMesh {
+            material_index: Some(0),
+            ..Default::default()
+        };
+        for cell in 0..16u32 {
+            let (cx, cy) = ((cell % 4) as f32, (cell / 4) as f32);
+            let base = mesh.vertex_count() as u32;
+            for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+                mesh.positions.extend([cx * 2.0 + dx, cy * 2.0 + dy, 0.0]);
+                mesh.uvs.extend([(cx + dx) / 4.0, (cy + dy) / 4.0]);
+            }
+            mesh.indices
+                .extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+
+        let tiling = TilingConfig::default();
+        let texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            max_size: 1024,
+            hard_max_size: 64,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let sink = FsSink::new(tmp.path());
+        let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+        write_tile_glb_to_disk(&mesh, &ctx, "0", 1.0);
+
+        let glb = fs::read(tmp.path().join(address_to_uri("0"))).unwrap();
+        let gltf = gltf::Gltf::from_slice_without_validation(&glb).unwrap();
+        let buffers = gltf::import_buffers(&gltf.document, None, gltf.blob.clone()).unwrap();
+        let images = gltf::import_images(&gltf.document, None, &buffers).unwrap();
+        let gltf_mesh = gltf.document.meshes().next().unwrap();
+        let primitives: Vec<_> = gltf_mesh.primitives().collect();
+        assert!(
+            primitives.len() > 1,
+            "atlas should be split over primitives"
+        );
+        let mut triangles = 0;
+        for primitive in &primitives {
+            triangles += primitive.indices().unwrap().count() / 3;
+            let pbr = primitive.material().pbr_metallic_roughness();
+            let texture = pbr
+                .base_color_texture()
+                .expect("primitive should be textured");
+            let image = &images[texture.texture().source().index()];
+            assert!(
+                image.width <= 64 && image.height <= 64,
+                "{}x{}",
+                image.width,
+                image.height
+            );
+        }
+        assert_eq!(triangles, 32);
+    }
+
+    #[test]
+    fn texture_memory_budget_shrinks_later_atlases() {
+        let (mesh, mut materials) = make_textured_quad();
+        materials.textures[0] = gradient_texture(256);
+        let tiling = TilingConfig::default();
+        let mut texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let sink = FsSink::new(tmp.path());
+        let atlas_bytes = |addr: &str| {
+            let glb = fs::read(tmp.path().join(address_to_uri(addr))).unwrap();
+            let gltf = gltf::Gltf::from_slice_without_validation(&glb).unwrap();
+            let buffers = gltf::import_buffers(&gltf.document, None, gltf.blob.clone()).unwrap();
+            let images = gltf::import_images(&gltf.document, None, &buffers).unwrap();
+            let Some(image) = gltf.document.images().next() else {
+                return (0, 0);
+            };
+            let gltf::image::Source::View { view, .. } = image.source() else {
+                panic!("atlas should be embedded");
+            };
+            (images[0].width, view.length() as u64)
+        };
+
+        // Without a budget, every tile gets the full atlas
+        let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+        write_tile_glb_to_disk(&mesh, &ctx, "0", 1.0);
+        let (full_width, full_bytes) = atlas_bytes("0");
+
+        let budget = full_bytes * 5 / 2;
+        texture_config.max_memory = Some(budget);
+        let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+        for i in 0..8 {
+            write_tile_glb_to_disk(&mesh, &ctx, &i.to_string(), 1.0);
+        }
+        // Budgeted tiles are written once the tree is built
+        assert!(!tmp.path().join(address_to_uri("7")).exists());
+        write_budgeted_tiles(&ctx);
+        let tiles: Vec<_> = (0..8).map(|i| atlas_bytes(&i.to_string())).collect();
+        assert_eq!(tiles[0], (full_width, full_bytes));
+        assert!(tiles.windows(2).all(|w| w[1].0 <= w[0].0), "{tiles:?}");
+        assert!(tiles[5].0 < full_width, "{tiles:?}");
+
+        let total: u64 = tiles.iter().map(|&(_, bytes)| bytes).sum();
+        assert!(total <= budget, "{total} > {budget}");
+        let stats = ctx.atlases.into_inner().unwrap().stats;
+        assert_eq!(stats.budget_bytes, total);
+    }
+
+    #[test]
+    fn spilled_meshes_read_back_unchanged() {
+        let spill = MeshSpill::default();
+        let (quad, _) = make_textured_quad();
+        let grid = make_grid_mesh(4);
+        let quad_range = spill.store(&quad).unwrap();
+        let grid_range = spill.store(&grid).unwrap();
+        assert_eq!(quad_range.end, grid_range.start);
+
+        for (mesh, range) in [(&grid, &grid_range), (&quad, &quad_range)] {
+            let loaded = spill.load(range).unwrap();
+            assert_eq!(loaded.positions, mesh.positions);
+            assert_eq!(loaded.uvs, mesh.uvs);
+            assert_eq!(loaded.indices, mesh.indices);
+            assert_eq!(loaded.material_index, mesh.material_index);
+        }
+    }
+
+    #[test]
+    fn coarse_lod_tile_gets_smaller_atlas() {
+        let (mesh, materials) = make_textured_quad();
+        let tiling = TilingConfig::default();
+        let texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let sink = FsSink::new(tmp.path());
+        let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+
+        // Same geometry: once as full-detail leaf, once as a 25% LOD
+        write_tile_glb_to_disk(&mesh, &ctx, "0", 1.0);
+        write_tile_glb_to_disk(&mesh, &ctx, "1", 0.5);
+
+        let atlas_width = |addr: &str| {
+            let glb = fs::read(tmp.path().join(address_to_uri(addr))).unwrap();
+            let gltf = gltf::Gltf::from_slice_without_validation(&glb).unwrap();
+            let buffers =
+                gltf::import_buffers(&gltf.document, None, gltf.blob.clone()).unwrap();
+            let images = gltf::import_images(&gltf.document, None, &buffers).unwrap();
+            images[0].width
+        };
+        let fine = atlas_width("0");
+        let coarse = atlas_width("1");
+        assert!(
+            coarse < fine,
+            "coarse LOD atlas ({coarse}px) should be smaller than fine ({fine}px)"
+        );
+    }
+
+    #[test]
+    fn lod_texel_scale_follows_simplification_ratio() {
+        let original = make_grid_mesh(8); // 128 tris
+        let simplified = make_grid_mesh(4); // 32 tris
+        let config = TextureConfig::default();
+        assert!((lod_texel_scale(&simplified, &original, &config) - 0.5).abs() < 1e-6);
+        assert_eq!(lod_texel_scale(&original, &original, &config), 1.0);
+
+        let disabled = TextureConfig {
+            lod_downscale: false,
+            ..Default::default()
+        };
+        assert_eq!(lod_texel_scale(&simplified, &original, &disabled), 1.0);
+    }
+
+    #[test]
+    fn write_tileset_creates_files() {
+        let mesh = make_grid_mesh(4);
+        let chain = LodChain {
+            levels: vec![LodLevel {
+                level: 0,
+                mesh: mesh.clone(),
+                geometric_error: 0.0,
+            }],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
+
+        let config = TilingConfig {
+            max_triangles_per_tile: 100_000,
+            max_depth: 4,
+            ..Default::default()
+        };
+        let materials = MaterialLibrary::default();
+        let tmp = tempfile::tempdir().unwrap();
+
+        let mut output = build_tileset(
+            vec![chain],
+            &unit_bounds(),
+            &config,
+            &materials,
+            &tex_config_disabled(),
+            tmp.path(),
+        );
+
+        let transform = identity();
+        let tile_count = write_tileset(&mut output, &transform, tmp.path()).unwrap();
+
+        // Should have tileset.json
+        assert!(tmp.path().join("tileset.json").exists());
+
+        // Should have tiles directory (GLBs written eagerly)
+        assert!(tmp.path().join("tiles").exists());
+
+        // Should have at least 1 tile
+        assert!(tile_count >= 1);
+    }
+
+    #[test]
+    fn tileset_json_is_valid() {
+        let mesh = make_grid_mesh(4);
+        let chain = LodChain {
+            levels: vec![LodLevel {
+                level: 0,
+                mesh: mesh.clone(),
+                geometric_error: 0.0,
+            }],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
+
+        let config = TilingConfig {
+            max_triangles_per_tile: 100_000,
+            max_depth: 4,
+            ..Default::default()
+        };
+        let materials = MaterialLibrary::default();
+        let tmp = tempfile::tempdir().unwrap();
+
+        let mut output = build_tileset(
+            vec![chain],
+            &unit_bounds(),
+            &config,
+            &materials,
+            &tex_config_disabled(),
+            tmp.path(),
+        );
+
+        let transform = identity();
+        write_tileset(&mut output, &transform, tmp.path()).unwrap();
+
+        // Parse tileset.json
+        let json_str = fs::read_to_string(tmp.path().join("tileset.json")).unwrap();
+        let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+
+        // Check required fields
+        assert_eq!(tileset["asset"]["version"], "1.1");
+        assert_eq!(tileset["asset"]["generator"], "photo-tiler");
+        assert!(tileset["root"].is_object());
+        assert!(tileset["root"]["boundingVolume"]["box"].is_array());
+        assert_eq!(tileset["root"]["refine"], "REPLACE");
+    }
+
+    #[test]
+    fn add_refinement_writes_incremental_content() {
+        let chain = LodChain {
+            levels: vec![LodLevel {
+                level: 0,
+                mesh: make_grid_mesh(40), // 3200 tris
+                geometric_error: 0.0,
+            }],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
+        let config = TilingConfig {
+            max_triangles_per_tile: 200,
+            max_depth: 4,
+            refine: Refine::Add,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let mut output = build_tileset(
+            vec![chain],
+            &unit_bounds(),
+            &config,
+            &MaterialLibrary::default(),
+            &tex_config_disabled(),
+            tmp.path(),
+        );
+        write_tileset(&mut output, &identity(), tmp.path()).unwrap();
+
+        fn check_refine(tile: &serde_json::Value) {
+            assert_eq!(tile["refine"], "ADD");
+            for child in tile["children"].as_array().into_iter().flatten() {
+                check_refine(child);
+            }
+        }
+        let json_str = fs::read_to_string(tmp.path().join("tileset.json")).unwrap();
+        let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+        check_refine(&tileset["root"]);
+
+        fn check_smaller(node: &TileNode, dir: &Path) {
+            let size = |n: &TileNode| {
+                let uri = &n.content.as_ref().unwrap().uri;
+                fs::metadata(dir.join(uri)).unwrap().len()
+            };
+            for child in &node.children {
+                assert!(
+                    size(child) < size(node),
+                    "{} is not smaller than {}",
+                    child.address,
+                    node.address
+                );
+                check_smaller(child, dir);
+            }
+        }
+        assert!(!output.root.children.is_empty());
+        check_smaller(&output.root, tmp.path());
+    }
+
+    #[test]
+    fn tileset_json_has_transform() {
+        let mesh = make_grid_mesh(4);
+        let chain = LodChain {
+            levels: vec![LodLevel {
+                level: 0,
+                mesh: mesh.clone(),
+                geometric_error: 0.0,
+            }],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
+
+        let config = TilingConfig {
+            max_triangles_per_tile: 100_000,
+            max_depth: 4,
+            ..Default::default()
+        };
+        let _materials = MaterialLibrary::default();
+
+        let transform = [
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 100.0, 200.0, 300.0,
+            1.0,
+        ];
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut output = build_tileset(
+            vec![chain],
+            &unit_bounds(),
+            &config,
+            &MaterialLibrary::default(),
+            &tex_config_disabled(),
+            tmp.path(),
+        );
+        write_tileset(&mut output, &transform, tmp.path()).unwrap();
+
+        let json_str = fs::read_to_string(tmp.path().join("tileset.json")).unwrap();
+        let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+
+        let t = tileset["root"]["transform"].as_array().unwrap();
+        assert_eq!(t.len(), 16);
+        // Check translation column
+        assert_eq!(t[12].as_f64().unwrap(), 100.0);
+        assert_eq!(t[13].as_f64().unwrap(), 200.0);
+        assert_eq!(t[14].as_f64().unwrap(), 300.0);
+    }
+
+    #[test]
+    fn bounding_volume_box_format() {
+        let bounds = BoundingBox {
+            min: [0.0, 0.0, 0.0],
+            max: [2.0, 4.0, 6.0],
+        };
+        let bv = bounding_volume_box(&bounds);
+        // center = (1, 2, 3), half-extents = (1, 2, 3)
+        assert_eq!(bv[0], 1.0); // cx
+        assert_eq!(bv[1], 2.0); // cy
+        assert_eq!(bv[2], 3.0); // cz
+        assert_eq!(bv[3], 1.0); // hx
+        assert_eq!(bv[4], 0.0);
+        assert_eq!(bv[5], 0.0);
+        assert_eq!(bv[6], 0.0);
+        assert_eq!(bv[7], 2.0); // hy
+        assert_eq!(bv[8], 0.0);
+        assert_eq!(bv[9], 0.0);
+        assert_eq!(bv[10], 0.0);
+        assert_eq!(bv[11], 3.0); // hz
+    }
+
+    #[test]
+    fn merge_meshes_concatenates() {
+        let a = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        };
+        let b = IndexedMesh {
+            positions: vec![2.0, 0.0, 0.0, 3.0, 0.0, 0.0, 2.0, 1.0, 0.0],
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        };
+
+        let merged = merge_meshes(a, &b);
+        assert_eq!(merged.vertex_count(), 6);
+        assert_eq!(merged.triangle_count(), 2);
+        // Second triangle's indices should be offset by 3
+        assert_eq!(merged.indices[3], 3);
+        assert_eq!(merged.indices[4], 4);
+        assert_eq!(merged.indices[5], 5);
+    }
+
+    #[test]
+    fn second_uv_set_survives_tiling_roundtrip() {
+        // Both UV sets are linear in position, so clipping must keep them exact.
+        let mut mesh = make_grid_mesh(6);
+        mesh.uvs = mesh.positions.chunks_exact(3).flat_map(|p| [p[0], p[1]]).collect();
+        mesh.uvs1 = mesh
+            .positions
+            .chunks_exact(3)
+            .flat_map(|p| [0.5 * p[0] + 0.25, 0.5 * p[1]])
+            .collect();
+
+        // Split at an off-centre box so triangles straddle the planes.
+        let bounds = BoundingBox {
+            min: [-0.1, -0.1, 0.0],
+            max: [1.0, 1.0, 1.0],
+        };
+        let children = split_mesh(&mesh, &bounds);
+        let merged = children.iter().fold(IndexedMesh::default(), merge_meshes);
+        assert!(merged.has_uvs1());
+
+        for tile in children.iter().filter(|c| !c.is_empty()).chain([&merged]) {
+            let bytes =
+                crate::tiling::glb_writer::write_glb(tile, &MaterialLibrary::default(), None);
+            let (doc, buffers, _) = gltf::import_slice(&bytes).unwrap();
+            let primitive = doc.meshes().next().unwrap().primitives().next().unwrap();
+            let reader = primitive.reader(|b| Some(&buffers[b.index()]));
+
+            let positions: Vec<[f32; 3]> = reader.read_positions().unwrap().collect();
+            let uv0: Vec<[f32; 2]> = reader.read_tex_coords(0).unwrap().into_f32().collect();
+            let uv1: Vec<[f32; 2]> = reader.read_tex_coords(1).unwrap().into_f32().collect();
+            assert_eq!(uv1.len(), positions.len());
+
+            for ((p, a), b) in positions.iter().zip(&uv0).zip(&uv1) {
+                assert!((a[0] - p[0]).abs() < 1e-5 && (a[1] - p[1]).abs() < 1e-5);
+                assert!((b[0] - (0.5 * p[0] + 0.25)).abs() < 1e-5);
+                assert!((b[1] - 0.5 * p[1]).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_meshes_empty() {
+        let empty = IndexedMesh::default();
+        let mesh = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0],
+            indices: vec![],
+            ..Default::default()
+        };
+
+        let result = merge_meshes(empty, &mesh);
+        assert_eq!(result.positions.len(), mesh.positions.len());
+
+        let result2 = merge_meshes(mesh.clone(), &IndexedMesh::default());
+        assert_eq!(result2.positions.len(), mesh.positions.len());
+    }
+
+    #[test]
+    fn hierarchical_dirs_created() {
+        let lod0 = make_grid_mesh(10);
+
+        let chain = LodChain {
+            levels: vec![
+                LodLevel {
+                    level: 0,
+                    mesh: lod0,
+                    geometric_error: 0.0,
+                },
+            ],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
+
+        let config = TilingConfig {
+            max_triangles_per_tile: 50,
+            max_depth: 4,
+            ..Default::default()
+        };
+        let materials = MaterialLibrary::default();
+        let tmp = tempfile::tempdir().unwrap();
+
+        let mut output = build_tileset(
+            vec![chain],
+            &unit_bounds(),
+            &config,
+            &materials,
+            &tex_config_disabled(),
+            tmp.path(),
+        );
+
+        write_tileset(&mut output, &identity(), tmp.path()).unwrap();
+
+        // tiles/ directory should exist
+        assert!(tmp.path().join("tiles").exists());
+        // tileset.json should exist
+        assert!(tmp.path().join("tileset.json").exists());
+    }
+
+    /// Textured checkerboard of `cells` x `cells` squares where only the dark
+    /// squares carry faces. Each square is a 2x2-quad UV island touching its
+    /// diagonal neighbours at single corner vertices, so the repacker must
+    /// duplicate those corners in whatever order its flood fill visits faces.
+    fn make_checker_mesh(cells: u32) -> (IndexedMesh, MaterialLibrary) {
+        let (_, materials) = make_textured_quad();
+        let side = cells * 2 + 1;
+        let mut positions = Vec::new();
+        let mut uvs = Vec::new();
+        for y in 0..side {
+            for x in 0..side {
+                let (u, v) = (x as f32 / (side - 1) as f32, y as f32 / (side - 1) as f32);
+                positions.extend_from_slice(&[u, v, 0.5]);
+                uvs.extend_from_slice(&[u, v]);
+            }
+        }
+        let mut indices = Vec::new();
+        for cy in 0..cells {
+            for cx in (0..cells).filter(|cx| (cx + cy) % 2 == 0) {
+                for y in cy * 2..cy * 2 + 2 {
+                    for x in cx * 2..cx * 2 + 2 {
+                        let i = y * side + x;
+                        indices.extend_from_slice(&[i, i + 1, i + side]);
+                        indices.extend_from_slice(&[i + 1, i + side + 1, i + side]);
+                    }
+                }
+            }
+        }
+        let mesh = IndexedMesh {
+            positions,
+            uvs,
+            indices,
+            material_index: Some(0),
+            ..Default::default()
+        };
+        (mesh, materials)
+    }
+
+    #[test]
+    fn repeated_builds_produce_identical_tiles() {
+        let (mesh, materials) = make_checker_mesh(6);
+        let config = TilingConfig {
+            max_triangles_per_tile: 40,
+            max_depth: 2,
+            ..Default::default()
+        };
+        let texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+
+        let runs: Vec<_> = (0..2)
+            .map(|_| {
+                let tmp = tempfile::tempdir().unwrap();
+                let chain = LodChain {
+                    levels: vec![LodLevel {
+                        level: 0,
+                        mesh: mesh.clone(),
+                        geometric_error: 0.0,
+                    }],
+                    bounds: unit_bounds(),
+                    instances: Vec::new(),
+                };
+                let output = build_tileset(
+                    vec![chain],
+                    &unit_bounds(),
+                    &config,
+                    &materials,
+                    &texture_config,
+                    tmp.path(),
+                );
+                let mut uris = Vec::new();
+                collect_uris(&output.root, &mut uris);
+                (tmp, uris)
+            })
+            .collect();
+
+        let (first_dir, first_uris) = &runs[0];
+        let (second_dir, second_uris) = &runs[1];
+        assert!(first_uris.len() > 1, "mesh should split into several tiles");
+        assert_eq!(first_uris, second_uris);
+        // Tile content is flushed to disk eagerly, so compare the written GLBs
+        for uri in first_uris {
+            let a = fs::read(first_dir.path().join(uri)).unwrap();
+            let b = fs::read(second_dir.path().join(uri)).unwrap();
+            assert!(a == b, "{uri} differs between runs");
+        }
+    }
+
+    #[test]
+    fn tile_stats_match_written_tiles() {
+        let (mesh, materials) = make_checker_mesh(6);
+        let chain = LodChain {
+            levels: vec![LodLevel {
+                level: 0,
+                mesh: mesh.clone(),
+                geometric_error: 0.0,
+            }],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
+        let config = TilingConfig {
+            max_triangles_per_tile: 40,
+            max_depth: 2,
+            ..Default::default()
+        };
+        let texture_config = TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+
+        let mut output = build_tileset(
+            vec![chain],
+            &unit_bounds(),
+            &config,
+            &materials,
+            &texture_config,
+            tmp.path(),
+        );
+        let tile_count = write_tileset(&mut output, &identity(), tmp.path()).unwrap();
+
+        let stats = &output.tile_stats;
+        assert_eq!(stats.tiles_per_level.iter().sum::<usize>(), tile_count);
+        assert_eq!(stats.tiles_per_level[0], 1, "one root tile");
+        assert!(stats.tiles_per_level.len() > 1);
+        // Leaves hold every source triangle; the root adds its own content
+        assert!(stats.triangles >= mesh.triangle_count());
+        assert!(stats.bytes_written > 0);
+        assert!(output.atlas_stats.texels > 0);
+        let efficiency = output.atlas_stats.packing_efficiency().unwrap();
+        assert!(efficiency > 0.0 && efficiency <= 1.0);
+    }
+
+    /// Keeps every written file in memory.
+    #[derive(Default)]
+    struct MemorySink {
+        files: Mutex<BTreeMap<String, Vec<u8>>>,
+        dirs: Mutex<BTreeSet<String>>,
+    }
+
+    impl TileSink for MemorySink {
+        fn write(&self, path: &str, bytes: &[u8]) -> std::io::Result<()> {
+            let mut files = self.files.lock().unwrap();
+            files.insert(path.to_string(), bytes.to_vec());
+            Ok(())
+        }
+
+        fn create_dir(&self, path: &str) -> std::io::Result<()> {
+            self.dirs.lock().unwrap().insert(path.to_string());
+            Ok(())
+        }
+    }
+
+    /// Files under `dir`, keyed by their `/`-separated relative path.
+    fn read_tree(dir: &Path) -> BTreeMap<String, Vec<u8>> {
+        let mut files = BTreeMap::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(current).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else {
+                    let relative = path.strip_prefix(dir).unwrap().to_str().unwrap();
+                    files.insert(relative.replace('\\', "/"), fs::read(&path).unwrap());
+                }
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn memory_sink_receives_the_same_files_as_disk() {
+        let (_, materials) = make_textured_quad();
+        let mut mesh = make_grid_mesh(8);
+        mesh.uvs = mesh.positions.chunks_exact(3).flat_map(|p| [p[0], p[1]]).collect();
+        mesh.material_index = Some(0);
+        let chain = || LodChain {
             levels: vec![LodLevel {
                 level: 0,
                 mesh: mesh.clone(),
                 geometric_error: 0.0,
             }],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
-
         let config = TilingConfig {
-            max_triangles_per_tile: 100_000,
-            max_depth: 4,
+            max_triangles_per_tile: 32,
+            external_tileset_depth: Some(1),
+            ..Default::default()
+        };
+        let textures = TextureConfig {
+            format: TextureFormat::Original,
+            share_atlases: true,
+            ..Default::default()
         };
-        let materials = MaterialLibrary::default();
-        let tmp = tempfile::tempdir().unwrap();
 
-        let output = build_tileset(
-            vec![chain],
-            &unit_bounds(),
+        let tmp = tempfile::tempdir().unwrap();
+        let (bounds, out) = (unit_bounds(), tmp.path());
+        let mut output = build_tileset(vec![chain()], &bounds, &config, &materials, &textures, out);
+        let on_disk_tiles = write_tileset(&mut output, &identity(), tmp.path()).unwrap();
+
+        let sink = MemorySink::default();
+        let mut output = build_tileset_to_sink(
+            vec![chain()],
+            &[],
+            &bounds,
             &config,
             &materials,
-            &tex_config_disabled(),
-            tmp.path(),
+            &textures,
+            &sink,
         );
-
-        let transform = identity();
-        let tile_count = write_tileset(&output, &transform, tmp.path()).unwrap();
-
-        // Should have tileset.json
-        assert!(tmp.path().join("tileset.json").exists());
-
-        // Should have tiles directory (GLBs written eagerly)
-        assert!(tmp.path().join("tiles").exists());
-
-        // Should have at least 1 tile
-        assert!(tile_count >= 1);
+        let tiles = write_tileset_to_sink(&mut output, &identity(), &sink).unwrap();
+
+        assert_eq!(tiles, on_disk_tiles);
+        let files = sink.files.into_inner().unwrap();
+        assert_eq!(files, read_tree(tmp.path()));
+        assert!(files.contains_key("tileset.json"));
+        assert!(files.contains_key("tiles/0/tileset.json"));
+        assert!(files.keys().any(|path| path.starts_with("tiles/shared/")));
+        // Every file counts towards the bytes written, JSON included
+        let total: usize = files.values().map(Vec::len).sum();
+        assert_eq!(output.tile_stats.bytes_written, total as u64);
+        // Every file's directory was created first
+        let dirs = sink.dirs.into_inner().unwrap();
+        for path in files.keys() {
+            if let Some((dir, _)) = path.rsplit_once('/') {
+                assert!(dirs.contains(dir), "{dir} not created");
+            }
+        }
     }
 
     #[test]
-    fn tileset_json_is_valid() {
-        let mesh = make_grid_mesh(4);
+    fn failed_tile_write_skips_tileset_json() {
         let chain = LodChain {
             levels: vec![LodLevel {
                 level: 0,
-                mesh: mesh.clone(),
+                mesh: make_grid_mesh(4),
                 geometric_error: 0.0,
             }],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
-
-        let config = TilingConfig {
-            max_triangles_per_tile: 100_000,
-            max_depth: 4,
-        };
-        let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
+        // A non-empty directory where the root GLB belongs makes the rename fail
+        let blocker = tmp.path().join("tiles").join("root.glb");
+        fs::create_dir_all(blocker.join("occupied")).unwrap();
 
-        let output = build_tileset(
+        let mut output = build_tileset(
             vec![chain],
             &unit_bounds(),
-            &config,
-            &materials,
-            &tex_config_disabled(),
+            &TilingConfig::default(),
+            &MaterialLibrary::default(),
+            &TextureConfig::default(),
             tmp.path(),
         );
+        assert_eq!(output.write_errors.len(), 1);
 
-        let transform = identity();
-        write_tileset(&output, &transform, tmp.path()).unwrap();
-
-        // Parse tileset.json
-        let json_str = fs::read_to_string(tmp.path().join("tileset.json")).unwrap();
-        let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
-
-        // Check required fields
-        assert_eq!(tileset["asset"]["version"], "1.1");
-        assert_eq!(tileset["asset"]["generator"], "photo-tiler");
-        assert!(tileset["root"].is_object());
-        assert!(tileset["root"]["boundingVolume"]["box"].is_array());
-        assert_eq!(tileset["root"]["refine"], "REPLACE");
+        let result = write_tileset(&mut output, &identity(), tmp.path());
+        assert!(matches!(result, Err(PhotoTilerError::Output(_))));
+        assert!(!tmp.path().join("tileset.json").exists());
+        assert!(!tmp.path().join("tiles").join("root.glb.tmp").exists());
     }
 
     #[test]
-    fn tileset_json_has_transform() {
-        let mesh = make_grid_mesh(4);
+    fn sparse_octant_gets_tight_content_box() {
+        // Two small patches in opposite corners, so each octant tile holds
+        // geometry filling only a fraction of its cell
+        let patch = |offset: f32, z: f32| {
+            let mut mesh = make_grid_mesh(4);
+            for p in mesh.positions.chunks_exact_mut(3) {
+                p[0] = offset + p[0] * 0.1;
+                p[1] = offset + p[1] * 0.1;
+                p[2] = z;
+            }
+            mesh
+        };
+        let mesh = merge_meshes(patch(0.05, 0.1), &patch(0.85, 0.9));
         let chain = LodChain {
             levels: vec![LodLevel {
                 level: 0,
-                mesh: mesh.clone(),
+                mesh,
                 geometric_error: 0.0,
             }],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
-
         let config = TilingConfig {
-            max_triangles_per_tile: 100_000,
-            max_depth: 4,
+            max_triangles_per_tile: 40,
+            max_depth: 2,
+            ..Default::default()
         };
-        let _materials = MaterialLibrary::default();
-
-        let transform = [
-            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 100.0, 200.0, 300.0,
-            1.0,
-        ];
-
+        let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
-        let output = build_tileset(
+
+        let mut output = build_tileset(
             vec![chain],
             &unit_bounds(),
             &config,
-            &MaterialLibrary::default(),
+            &materials,
             &tex_config_disabled(),
             tmp.path(),
         );
-        write_tileset(&output, &transform, tmp.path()).unwrap();
+        write_tileset(&mut output, &identity(), tmp.path()).unwrap();
 
         let json_str = fs::read_to_string(tmp.path().join("tileset.json")).unwrap();
         let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
-
-        let t = tileset["root"]["transform"].as_array().unwrap();
-        assert_eq!(t.len(), 16);
-        // Check translation column
-        assert_eq!(t[12].as_f64().unwrap(), 100.0);
-        assert_eq!(t[13].as_f64().unwrap(), 200.0);
-        assert_eq!(t[14].as_f64().unwrap(), 300.0);
+        let children = tileset["root"]["children"].as_array().unwrap();
+        let octant = children
+            .iter()
+            .find(|c| c["content"]["uri"] == "tiles/0/tile.glb")
+            .expect("octant 0 should have content");
+
+        let tile_box = octant["boundingVolume"]["box"].as_array().unwrap();
+        let content_box = octant["content"]["boundingVolume"]["box"]
+            .as_array()
+            .expect("sparse octant should carry a content bounding volume");
+        for axis in [3, 7] {
+            let tile_half = tile_box[axis].as_f64().unwrap();
+            let content_half = content_box[axis].as_f64().unwrap();
+            assert!(
+                content_half < tile_half * 0.5,
+                "content half-extent {content_half} should be well inside {tile_half}"
+            );
+        }
+        // Content center sits on the patch, not the octant center
+        assert!((content_box[0].as_f64().unwrap() - 0.1).abs() < 1e-6);
     }
 
     #[test]
-    fn bounding_volume_box_format() {
-        let bounds = BoundingBox {
-            min: [0.0, 0.0, 0.0],
-            max: [2.0, 4.0, 6.0],
+    fn content_box_omitted_when_not_tighter() {
+        let tile = unit_bounds();
+        let nearly_full = BoundingBox {
+            min: [0.02, 0.02, 0.02],
+            max: [0.98, 0.98, 0.98],
         };
-        let bv = bounding_volume_box(&bounds);
-        // center = (1, 2, 3), half-extents = (1, 2, 3)
-        assert_eq!(bv[0], 1.0); // cx
-        assert_eq!(bv[1], 2.0); // cy
-        assert_eq!(bv[2], 3.0); // cz
-        assert_eq!(bv[3], 1.0); // hx
-        assert_eq!(bv[4], 0.0);
-        assert_eq!(bv[5], 0.0);
-        assert_eq!(bv[6], 0.0);
-        assert_eq!(bv[7], 2.0); // hy
-        assert_eq!(bv[8], 0.0);
-        assert_eq!(bv[9], 0.0);
-        assert_eq!(bv[10], 0.0);
-        assert_eq!(bv[11], 3.0); // hz
+        assert!(!is_much_tighter(&nearly_full, &tile));
+        let flat = BoundingBox {
+            min: [0.0, 0.0, 0.5],
+            max: [1.0, 1.0, 0.5],
+        };
+        assert!(is_much_tighter(&flat, &tile));
     }
 
     #[test]
-    fn merge_meshes_concatenates() {
-        let a = IndexedMesh {
-            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
-            indices: vec![0, 1, 2],
+    fn oriented_boxes_tighten_rotated_content() {
+        // A 10 x 1 x 0.2 slab turned 45° about Z
+        let (c, s) = (0.5f32.sqrt(), 0.5f32.sqrt());
+        let mut positions = Vec::new();
+        for corner in 0..8 {
+            let x = if corner & 1 == 0 { -5.0 } else { 5.0 };
+            let y = if corner & 2 == 0 { -0.5 } else { 0.5 };
+            let z = if corner & 4 == 0 { 0.0 } else { 0.2 };
+            positions.extend([c * x - s * y, s * x + c * y, z]);
+        }
+        let mesh = IndexedMesh {
+            positions,
+            indices: vec![
+                0, 1, 3, 0, 3, 2, 4, 7, 5, 4, 6, 7, 0, 5, 1, 0, 4, 5, 2, 3, 7, 2, 7, 6, 0, 2, 6,
+                0, 6, 4, 1, 5, 7, 1, 7, 3,
+            ],
             ..Default::default()
         };
-        let b = IndexedMesh {
-            positions: vec![2.0, 0.0, 0.0, 3.0, 0.0, 0.0, 2.0, 1.0, 0.0],
-            indices: vec![0, 1, 2],
+        let bounds = compute_bounding_box(std::slice::from_ref(&mesh));
+        let chain = LodChain {
+            levels: vec![LodLevel {
+                level: 0,
+                mesh,
+                geometric_error: 0.0,
+            }],
+            bounds,
+            instances: Vec::new(),
+        };
+        let config = TilingConfig {
+            oriented_boxes: true,
             ..Default::default()
         };
+        let tmp = tempfile::tempdir().unwrap();
+        let output = build_tileset(
+            vec![chain.clone()],
+            &bounds,
+            &config,
+            &MaterialLibrary::default(),
+            &tex_config_disabled(),
+            tmp.path(),
+        );
 
-        let merged = merge_meshes(a, &b);
-        assert_eq!(merged.vertex_count(), 6);
-        assert_eq!(merged.triangle_count(), 2);
-        // Second triangle's indices should be offset by 3
-        assert_eq!(merged.indices[3], 3);
-        assert_eq!(merged.indices[4], 4);
-        assert_eq!(merged.indices[5], 5);
+        let tileset = build_tileset_json(&output, &identity()).0;
+        // The root is a leaf, so the oriented box is its own volume as well
+        let root = &tileset["root"];
+        assert!(root["children"].is_null());
+        assert!(root["content"]["boundingVolume"].is_null());
+        let bv: Vec<f64> = serde_json::from_value(root["boundingVolume"]["box"].clone()).unwrap();
+        // One half-axis runs diagonally along the slab's length
+        let diagonal = |axis: &[f64]| axis[0].abs() > 1.0 && axis[1].abs() > 1.0;
+        assert!(bv[3..].chunks(3).any(diagonal), "{bv:?}");
+        let length = |v: &[f64]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        let volume = 8.0 * length(&bv[3..6]) * length(&bv[6..9]) * length(&bv[9..12]);
+        assert!(volume < bounds.volume() / 5.0, "{volume} vs {}", bounds.volume());
+
+        // With children, the root keeps its axis-aligned box
+        let config = TilingConfig {
+            max_triangles_per_tile: 4,
+            max_depth: 1,
+            ..config
+        };
+        let output = build_tileset(
+            vec![chain],
+            &bounds,
+            &config,
+            &MaterialLibrary::default(),
+            &tex_config_disabled(),
+            tmp.path(),
+        );
+        let tileset = build_tileset_json(&output, &identity()).0;
+        let root = &tileset["root"];
+        assert!(root["children"].is_array());
+        assert_eq!(
+            root["boundingVolume"]["box"],
+            json!(bounding_volume_box(&bounds))
+        );
     }
 
     #[test]
-    fn merge_meshes_empty() {
-        let empty = IndexedMesh::default();
-        let mesh = IndexedMesh {
-            positions: vec![0.0, 0.0, 0.0],
-            indices: vec![],
-            ..Default::default()
+    fn prune_removes_empty_subtrees() {
+        let node = |address: &str, content: bool, children: Vec<TileNode>| TileNode {
+            address: address.into(),
+            level: address.split('_').count() as u32,
+            bounds: unit_bounds(),
+            geometric_error: 0.0,
+            content: content.then(|| TileContent {
+                uri: address_to_uri(address),
+                bounds: None,
+                obb: None,
+                triangles: 0,
+            }),
+            children,
+            transform: None,
         };
+        let mut root = node(
+            "root",
+            false,
+            vec![
+                node(
+                    "0",
+                    false,
+                    vec![node("0_1", false, vec![]), node("0_2", true, vec![])],
+                ),
+                node("1", false, vec![node("1_0", false, vec![])]),
+                node("2", false, vec![]),
+            ],
+        );
 
-        let result = merge_meshes(empty, &mesh);
-        assert_eq!(result.positions.len(), mesh.positions.len());
+        assert!(prune_empty_tiles(&mut root));
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].address, "0");
+        assert_eq!(root.children[0].children.len(), 1);
+        assert_eq!(root.children[0].children[0].address, "0_2");
 
-        let result2 = merge_meshes(mesh.clone(), &IndexedMesh::default());
-        assert_eq!(result2.positions.len(), mesh.positions.len());
+        // An empty root is still kept as the tileset root
+        let mut empty = node("root", false, vec![node("0", false, vec![])]);
+        assert!(!prune_empty_tiles(&mut empty));
+        assert!(empty.children.is_empty());
     }
 
     #[test]
-    fn hierarchical_dirs_created() {
-        let lod0 = make_grid_mesh(10);
-
+    fn single_octant_mesh_has_no_empty_siblings() {
+        let mut mesh = make_grid_mesh(8);
+        for p in mesh.positions.chunks_exact_mut(3) {
+            p[0] *= 0.4;
+            p[1] *= 0.4;
+            p[2] = 0.2;
+        }
         let chain = LodChain {
-            levels: vec![
-                LodLevel {
-                    level: 0,
-                    mesh: lod0,
-                    geometric_error: 0.0,
-                },
-            ],
+            levels: vec![LodLevel {
+                level: 0,
+                mesh,
+                geometric_error: 0.0,
+            }],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
-
         let config = TilingConfig {
-            max_triangles_per_tile: 50,
-            max_depth: 4,
+            max_triangles_per_tile: 40,
+            max_depth: 1,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
 
-        let output = build_tileset(
+        let mut output = build_tileset(
             vec![chain],
             &unit_bounds(),
             &config,
@@ -886,13 +3955,20 @@ mod tests {
             &tex_config_disabled(),
             tmp.path(),
         );
+        write_tileset(&mut output, &identity(), tmp.path()).unwrap();
 
-        write_tileset(&output, &identity(), tmp.path()).unwrap();
+        assert_eq!(
+            output.root.children.len(),
+            1,
+            "7 empty octants should be absent"
+        );
+        assert_eq!(output.root.children[0].address, "0");
 
-        // tiles/ directory should exist
-        assert!(tmp.path().join("tiles").exists());
-        // tileset.json should exist
-        assert!(tmp.path().join("tileset.json").exists());
+        let json_str = fs::read_to_string(tmp.path().join("tileset.json")).unwrap();
+        let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+        let children = tileset["root"]["children"].as_array().unwrap();
+        assert_eq!(children.len(), 1);
+        assert!(children[0]["content"].is_object());
     }
 
     #[test]
@@ -908,16 +3984,18 @@ mod tests {
                 },
             ],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
 
         let config = TilingConfig {
             max_triangles_per_tile: 50,
             max_depth: 4,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
 
-        let output = build_tileset(
+        let mut output = build_tileset(
             vec![chain],
             &unit_bounds(),
             &config,
@@ -926,17 +4004,7 @@ mod tests {
             tmp.path(),
         );
 
-        write_tileset(&output, &identity(), tmp.path()).unwrap();
-
-        // Collect all URIs from the tileset
-        fn collect_uris(node: &TileNode, uris: &mut Vec<String>) {
-            if let Some(content) = &node.content {
-                uris.push(content.uri.clone());
-            }
-            for child in &node.children {
-                collect_uris(child, uris);
-            }
-        }
+        write_tileset(&mut output, &identity(), tmp.path()).unwrap();
 
         let mut uris = Vec::new();
         collect_uris(&output.root, &mut uris);
@@ -952,6 +4020,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn tiles_are_on_disk_before_write_tileset() {
+        let chain = LodChain {
+            levels: vec![LodLevel {
+                level: 0,
+                mesh: make_grid_mesh(10),
+                geometric_error: 0.0,
+            }],
+            bounds: unit_bounds(),
+            instances: Vec::new(),
+        };
+        let config = TilingConfig {
+            max_triangles_per_tile: 50,
+            max_depth: 4,
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+
+        let output = build_tileset(
+            vec![chain],
+            &unit_bounds(),
+            &config,
+            &MaterialLibrary::default(),
+            &tex_config_disabled(),
+            tmp.path(),
+        );
+
+        // The tree holds only URIs; every byte counted is already a file
+        let mut uris = Vec::new();
+        collect_uris(&output.root, &mut uris);
+        assert!(uris.len() > 1);
+        let on_disk: u64 = uris
+            .iter()
+            .map(|uri| fs::metadata(tmp.path().join(uri)).unwrap().len())
+            .sum();
+        assert_eq!(on_disk, output.tile_stats.bytes_written);
+        assert!(!tmp.path().join("tileset.json").exists());
+    }
+
     #[test]
     fn glb_files_exist_on_disk() {
         let mesh = make_grid_mesh(10); // 200 triangles
@@ -965,16 +4072,18 @@ mod tests {
                 },
             ],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
 
         let config = TilingConfig {
             max_triangles_per_tile: 50,
             max_depth: 4,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
 
-        let output = build_tileset(
+        let mut output = build_tileset(
             vec![chain],
             &unit_bounds(),
             &config,
@@ -983,7 +4092,7 @@ mod tests {
             tmp.path(),
         );
 
-        let tile_count = write_tileset(&output, &identity(), tmp.path()).unwrap();
+        let tile_count = write_tileset(&mut output, &identity(), tmp.path()).unwrap();
 
         assert!(tile_count >= 1, "should have written at least 1 tile");
 
@@ -1021,11 +4130,13 @@ mod tests {
                 geometric_error: 0.0,
             }],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
 
         let config = TilingConfig {
             max_triangles_per_tile: 50,
             max_depth: 4,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
@@ -1066,11 +4177,13 @@ mod tests {
                 geometric_error: 0.0,
             }],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
 
         let config = TilingConfig {
             max_triangles_per_tile: 50,
             max_depth: 4,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
@@ -1111,11 +4224,13 @@ mod tests {
                 geometric_error: 0.0,
             }],
             bounds: unit_bounds(),
+            instances: Vec::new(),
         };
 
         let config = TilingConfig {
             max_triangles_per_tile: 50,
             max_depth: 4,
+            ..Default::default()
         };
         let materials = MaterialLibrary::default();
         let tmp = tempfile::tempdir().unwrap();
@@ -1153,4 +4268,44 @@ mod tests {
         }
         check_containment(&output.root);
     }
+
+    #[test]
+    fn b3dm_batch_table_describes_the_encoded_mesh() {
+        // Two separate triangles of objects 5 and 9
+        let positions = vec![
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, //
+            5.0, 0.0, 0.0, 6.0, 0.0, 0.0, 5.0, 1.0, 0.0,
+        ];
+        let source = IndexedMesh {
+            positions,
+            feature_ids: vec![5, 5, 5, 9, 9, 9],
+            indices: vec![0, 1, 2, 3, 4, 5],
+            ..Default::default()
+        };
+        // What an encoder may draw instead, here object 9 alone
+        let encoded = compact_mesh_with_materials(vec![3, 4, 5], &[None], &source);
+
+        let tiling = TilingConfig {
+            meshopt: false,
+            b3dm: true,
+            ..Default::default()
+        };
+        let materials = MaterialLibrary {
+            features: (0..10).map(|i| format!("object{i}")).collect(),
+            ..Default::default()
+        };
+        let tmp = tempfile::tempdir().unwrap();
+        let sink = FsSink::new(tmp.path());
+        let texture_config = tex_config_disabled();
+        let ctx = make_context(&tiling, &materials, &texture_config, &sink);
+        let tile = encode_tile_glb(&encoded, &ctx, TileImages::default());
+        finish_tile(tile, &source, &[], "0.b3dm", "0", &ctx);
+
+        let bytes = fs::read(tmp.path().join("0.b3dm")).unwrap();
+        let b3dm = crate::tiling::glb_writer::read_b3dm(&bytes).unwrap();
+        assert_eq!(b3dm.feature_table["BATCH_LENGTH"], 1);
+        let table = b3dm.batch_table.unwrap();
+        assert_eq!(table["name"], json!(["object9"]));
+        assert_eq!(table["triangleCount"], json!([1]));
+    }
 }
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
-use crate::tiling::octree::{child_bounds, octant_index};
+use rayon::prelude::*;
+
+use crate::tiling::octree::{child_bounds, octant_index, quadrant_bounds};
 use crate::types::{BoundingBox, IndexedMesh};
 
 /// Working vertex for clipping (f64 precision for math, cast to f32 at output).
@@ -9,17 +12,93 @@ struct ClipVertex {
     pos: [f64; 3],
     normal: [f64; 3],
     uv: [f64; 2],
+    uv1: [f64; 2],
     color: [f64; 4],
+    feature_id: u32,
 }
 
 /// Axis-aligned clipping half-plane.
 struct ClipPlane {
-    axis: usize,  // 0=X, 1=Y, 2=Z
+    axis: usize,  // 0=X, 1=Y, 2=Z, 3=U, 4=V
     value: f64,
-    positive: bool, // true = keep where pos[axis] >= value
+    positive: bool, // true = keep where coord(axis) >= value
+}
+
+impl ClipPlane {
+    /// The vertex coordinate this plane tests: position, or UV for axes 3-4.
+    fn coord(&self, v: &ClipVertex) -> f64 {
+        if self.axis < 3 {
+            v.pos[self.axis]
+        } else {
+            v.uv[self.axis - 3]
+        }
+    }
 }
 
-/// Quantized vertex key for deduplication at boundaries (position + UV + normal).
+/// Grid that clipped vertex positions are welded on, sized to the region
+/// being split.
+///
+/// A fixed step would over-weld models smaller than it, and its keys
+/// saturate far from the origin, welding every vertex there together; a
+/// step of the bounding-box diagonal / 1e6, measured from the box corner,
+/// is equally fine at any scale and distance.
+#[derive(Debug, Clone, Copy)]
+struct WeldGrid {
+    origin: [f64; 3],
+    /// Grid steps per unit length.
+    scale: f64,
+}
+
+impl WeldGrid {
+    /// Grid steps along the bounding-box diagonal.
+    const STEPS_PER_DIAGONAL: f64 = 1e6;
+
+    fn new(bounds: &BoundingBox) -> Self {
+        let diagonal = bounds.diagonal();
+        Self {
+            origin: bounds.min,
+            // A box around a single point keeps the 1 µm default
+            scale: if diagonal > 0.0 && diagonal.is_finite() {
+                Self::STEPS_PER_DIAGONAL / diagonal
+            } else {
+                Self::default().scale
+            },
+        }
+    }
+
+    /// Grid over the bounding box of flat xyz `positions`.
+    fn for_positions(positions: &[f32]) -> Self {
+        if positions.is_empty() {
+            return Self::default();
+        }
+        let mut bounds = BoundingBox {
+            min: [f64::INFINITY; 3],
+            max: [f64::NEG_INFINITY; 3],
+        };
+        for p in positions.chunks_exact(3) {
+            for (axis, &c) in p.iter().enumerate() {
+                bounds.min[axis] = bounds.min[axis].min(c as f64);
+                bounds.max[axis] = bounds.max[axis].max(c as f64);
+            }
+        }
+        Self::new(&bounds)
+    }
+
+    fn key(&self, pos: [f64; 3]) -> [i64; 3] {
+        std::array::from_fn(|i| ((pos[i] - self.origin[i]) * self.scale).round() as i64)
+    }
+}
+
+impl Default for WeldGrid {
+    fn default() -> Self {
+        Self {
+            origin: [0.0; 3],
+            scale: 1e6,
+        }
+    }
+}
+
+/// Quantized vertex key for deduplication at boundaries (position + UVs + normal).
 ///
 /// Hashing only position would merge vertices at UV seams (same position,
 /// different UVs), corrupting texture coordinates after the first octree split.
@@ -27,26 +106,29 @@ struct ClipPlane {
 struct DedupKey {
     pos: [i64; 3],
     uv: [i64; 2],
+    uv1: [i64; 2],
     normal: [i64; 3],
+    feature_id: u32,
 }
 
 impl DedupKey {
-    fn new(v: &ClipVertex) -> Self {
+    fn new(v: &ClipVertex, grid: &WeldGrid) -> Self {
         Self {
-            pos: [
-                (v.pos[0] * 1e6).round() as i64,
-                (v.pos[1] * 1e6).round() as i64,
-                (v.pos[2] * 1e6).round() as i64,
-            ],
+            pos: grid.key(v.pos),
             uv: [
                 (v.uv[0] * 1e6).round() as i64,
                 (v.uv[1] * 1e6).round() as i64,
             ],
+            uv1: [
+                (v.uv1[0] * 1e6).round() as i64,
+                (v.uv1[1] * 1e6).round() as i64,
+            ],
             normal: [
                 (v.normal[0] * 1e4).round() as i64,
                 (v.normal[1] * 1e4).round() as i64,
                 (v.normal[2] * 1e4).round() as i64,
             ],
+            feature_id: v.feature_id,
         }
     }
 }
@@ -78,6 +160,15 @@ fn extract_clip_vertex(mesh: &IndexedMesh, vertex_index: usize) -> ClipVertex {
         [0.0; 2]
     };
 
+    let uv1 = if mesh.has_uvs1() {
+        [
+            mesh.uvs1[vertex_index * 2] as f64,
+            mesh.uvs1[vertex_index * 2 + 1] as f64,
+        ]
+    } else {
+        [0.0; 2]
+    };
+
     let color = if mesh.has_colors() {
         [
             mesh.colors[vertex_index * 4] as f64,
@@ -89,13 +180,22 @@ fn extract_clip_vertex(mesh: &IndexedMesh, vertex_index: usize) -> ClipVertex {
         [0.0; 4]
     };
 
-    ClipVertex { pos, normal, uv, color }
+    let feature_id = mesh.feature_ids.get(vertex_index).copied().unwrap_or(0);
+
+    ClipVertex {
+        pos,
+        normal,
+        uv,
+        uv1,
+        color,
+        feature_id,
+    }
 }
 
 /// Compute parametric intersection of edge (a→b) with a clipping plane, lerp ALL attributes.
 fn intersect_edge(a: &ClipVertex, b: &ClipVertex, plane: &ClipPlane) -> ClipVertex {
-    let da = a.pos[plane.axis] - plane.value;
-    let db = b.pos[plane.axis] - plane.value;
+    let da = plane.coord(a) - plane.value;
+    let db = plane.coord(b) - plane.value;
     let denom = da - db;
     let t = if denom.abs() < 1e-15 { 0.5 } else { da / denom };
 
@@ -127,6 +227,11 @@ fn intersect_edge(a: &ClipVertex, b: &ClipVertex, plane: &ClipPlane) -> ClipVert
         lerp(a.uv[1], b.uv[1]),
     ];
 
+    let uv1 = [
+        lerp(a.uv1[0], b.uv1[0]),
+        lerp(a.uv1[1], b.uv1[1]),
+    ];
+
     let color = [
         lerp(a.color[0], b.color[0]),
         lerp(a.color[1], b.color[1]),
@@ -134,7 +239,15 @@ fn intersect_edge(a: &ClipVertex, b: &ClipVertex, plane: &ClipPlane) -> ClipVert
         lerp(a.color[3], b.color[3]),
     ];
 
-    ClipVertex { pos, normal, uv, color }
+    // Both ends belong to the same source triangle, hence the same feature
+    ClipVertex {
+        pos,
+        normal,
+        uv,
+        uv1,
+        color,
+        feature_id: a.feature_id,
+    }
 }
 
 /// Sutherland-Hodgman: clip a polygon by a single half-plane.
@@ -145,9 +258,9 @@ fn clip_polygon_by_plane(polygon: &[ClipVertex], plane: &ClipPlane) -> Vec<ClipV
 
     let is_inside = |v: &ClipVertex| {
         if plane.positive {
-            v.pos[plane.axis] >= plane.value - 1e-10
+            plane.coord(v) >= plane.value - 1e-10
         } else {
-            v.pos[plane.axis] <= plane.value + 1e-10
+            plane.coord(v) <= plane.value + 1e-10
         }
     };
 
@@ -183,21 +296,24 @@ fn clip_polygon_by_plane(polygon: &[ClipVertex], plane: &ClipPlane) -> Vec<ClipV
     output
 }
 
-/// Clip a triangle against the 6 AABB planes of one octant.
-fn clip_triangle_to_octant(tri: [ClipVertex; 3], octant_bounds: &BoundingBox) -> Vec<ClipVertex> {
-    let planes = [
-        ClipPlane { axis: 0, value: octant_bounds.min[0], positive: true },
-        ClipPlane { axis: 0, value: octant_bounds.max[0], positive: false },
-        ClipPlane { axis: 1, value: octant_bounds.min[1], positive: true },
-        ClipPlane { axis: 1, value: octant_bounds.max[1], positive: false },
-        ClipPlane { axis: 2, value: octant_bounds.min[2], positive: true },
-        ClipPlane { axis: 2, value: octant_bounds.max[2], positive: false },
-    ];
+/// Clip a triangle against the AABB planes of one cell on the first `axes`
+/// axes: all 6 planes for an octant, only the 4 X/Y planes for a quadrant.
+fn clip_triangle_to_octant(
+    tri: [ClipVertex; 3],
+    octant_bounds: &BoundingBox,
+    axes: usize,
+) -> Vec<ClipVertex> {
+    let planes = (0..axes).flat_map(|axis| {
+        [
+            ClipPlane { axis, value: octant_bounds.min[axis], positive: true },
+            ClipPlane { axis, value: octant_bounds.max[axis], positive: false },
+        ]
+    });
 
     let mut polygon: Vec<ClipVertex> = tri.into();
 
-    for plane in &planes {
-        polygon = clip_polygon_by_plane(&polygon, plane);
+    for plane in planes {
+        polygon = clip_polygon_by_plane(&polygon, &plane);
         if polygon.is_empty() {
             return polygon;
         }
@@ -225,39 +341,78 @@ fn fan_triangulate(polygon: &[ClipVertex]) -> Vec<[ClipVertex; 3]> {
 
 /// Accumulator for building an IndexedMesh per octant with vertex deduplication.
 struct OctantMeshBuilder {
+    /// Share vertices through `dedup`; off, only source vertices are shared
+    /// (through `sources`) and every vertex a clip creates is new.
+    weld: bool,
+    grid: WeldGrid,
     positions: Vec<f32>,
     normals: Vec<f32>,
     uvs: Vec<f32>,
+    uvs1: Vec<f32>,
     colors: Vec<f32>,
+    feature_ids: Vec<u32>,
     indices: Vec<u32>,
     dedup: HashMap<DedupKey, u32>,
+    /// Source mesh vertex -> output vertex, for unwelded building.
+    sources: HashMap<u32, u32>,
     has_normals: bool,
     has_uvs: bool,
+    has_uvs1: bool,
     has_colors: bool,
+    has_feature_ids: bool,
 }
 
 impl OctantMeshBuilder {
-    fn new(has_normals: bool, has_uvs: bool, has_colors: bool) -> Self {
+    fn new(
+        grid: WeldGrid,
+        has_normals: bool,
+        has_uvs: bool,
+        has_uvs1: bool,
+        has_colors: bool,
+        has_feature_ids: bool,
+    ) -> Self {
         Self {
+            weld: true,
+            grid,
             positions: Vec::new(),
             normals: Vec::new(),
             uvs: Vec::new(),
+            uvs1: Vec::new(),
             colors: Vec::new(),
+            feature_ids: Vec::new(),
             indices: Vec::new(),
             dedup: HashMap::new(),
+            sources: HashMap::new(),
             has_normals,
             has_uvs,
+            has_uvs1,
             has_colors,
+            has_feature_ids,
         }
     }
 
-    /// Add a vertex (dedup by quantized position + UV + normal), return its index.
+    /// Append vertices without deduplication, except that source vertices
+    /// stay shared (see [`Self::add_source_triangle`]).
+    fn unwelded(self) -> Self {
+        Self {
+            weld: false,
+            ..self
+        }
+    }
+
+    /// Add a vertex (dedup by quantized position + UVs + normal), return its index.
     fn add_vertex(&mut self, v: &ClipVertex) -> u32 {
-        let key = DedupKey::new(v);
+        let key = DedupKey::new(v, &self.grid);
         if let Some(&idx) = self.dedup.get(&key) {
             return idx;
         }
+        let idx = self.push_vertex(v);
+        self.dedup.insert(key, idx);
+        idx
+    }
 
+    /// Append a vertex and return its index.
+    fn push_vertex(&mut self, v: &ClipVertex) -> u32 {
         let idx = (self.positions.len() / 3) as u32;
         self.positions.extend_from_slice(&[v.pos[0] as f32, v.pos[1] as f32, v.pos[2] as f32]);
 
@@ -267,16 +422,50 @@ impl OctantMeshBuilder {
         if self.has_uvs {
             self.uvs.extend_from_slice(&[v.uv[0] as f32, v.uv[1] as f32]);
         }
+        if self.has_uvs1 {
+            self.uvs1.extend_from_slice(&[v.uv1[0] as f32, v.uv1[1] as f32]);
+        }
         if self.has_colors {
             self.colors.extend_from_slice(&[v.color[0] as f32, v.color[1] as f32, v.color[2] as f32, v.color[3] as f32]);
         }
-
-        self.dedup.insert(key, idx);
+        if self.has_feature_ids {
+            self.feature_ids.push(v.feature_id);
+        }
         idx
     }
 
     /// Add a triangle from 3 ClipVertices. Skips degenerate (collapsed indices).
     fn add_triangle(&mut self, a: &ClipVertex, b: &ClipVertex, c: &ClipVertex) {
+        self.add_source_triangle([a, b, c], [None; 3]);
+    }
+
+    /// Like [`Self::add_triangle`], where each corner is either source mesh
+    /// vertex `sources[k]` or (`None`) a vertex a clip created. Unwelded,
+    /// a source vertex is still shared by every triangle using it, so only
+    /// clipped edges lose their shared vertices.
+    fn add_source_triangle(&mut self, corners: [&ClipVertex; 3], sources: [Option<u32>; 3]) {
+        let [a, b, c] = corners;
+        if !self.weld {
+            // Skip the triangles welding would collapse
+            let [ka, kb, kc] = corners.map(|v| DedupKey::new(v, &self.grid));
+            if ka != kb && kb != kc && ka != kc {
+                for (v, source) in corners.into_iter().zip(sources) {
+                    let idx = match source {
+                        Some(source) => match self.sources.get(&source) {
+                            Some(&idx) => idx,
+                            None => {
+                                let idx = self.push_vertex(v);
+                                self.sources.insert(source, idx);
+                                idx
+                            }
+                        },
+                        None => self.push_vertex(v),
+                    };
+                    self.indices.push(idx);
+                }
+            }
+            return;
+        }
         let ia = self.add_vertex(a);
         let ib = self.add_vertex(b);
         let ic = self.add_vertex(c);
@@ -287,14 +476,18 @@ impl OctantMeshBuilder {
     }
 
     /// Build the final IndexedMesh.
-    fn build(self, material_index: Option<usize>) -> IndexedMesh {
+    fn build(self, material_index: Option<usize>, name: Option<String>) -> IndexedMesh {
         IndexedMesh {
             positions: self.positions,
             normals: self.normals,
             uvs: self.uvs,
+            uvs1: self.uvs1,
             colors: self.colors,
+            feature_ids: self.feature_ids,
             indices: self.indices,
             material_index,
+            name,
+            material_groups: Vec::new(),
         }
     }
 }
@@ -306,49 +499,114 @@ impl OctantMeshBuilder {
 /// triangles (all 3 vertices in the same octant) take a fast path that skips
 /// clipping entirely.
 pub fn split_mesh_clipping(mesh: &IndexedMesh, bounds: &BoundingBox) -> [IndexedMesh; 8] {
-    let center = bounds.center();
-    let child_boxes: [BoundingBox; 8] = std::array::from_fn(|i| child_bounds(bounds, i));
+    split_into_cells(mesh, bounds, true, |i| child_bounds(bounds, i))
+}
+
+/// Split a mesh into 4 quadrant sub-meshes, clipping only against the X/Y
+/// mid-planes. Every quadrant keeps the parent's full Z extent.
+pub fn split_mesh_clipping_xy(mesh: &IndexedMesh, bounds: &BoundingBox) -> [IndexedMesh; 4] {
+    split_into_cells(mesh, bounds, true, |i| quadrant_bounds(bounds, i))
+}
+
+/// Like [`split_mesh_clipping`] (`N = 8`) or [`split_mesh_clipping_xy`]
+/// (`N = 4`), but without welding: source vertices keep their sharing
+/// through a plain index map, and every vertex a clip creates is new,
+/// skipping the per-vertex hash lookup.
+///
+/// The geometry is the same, but clipped edges are no longer shared between
+/// triangles, so later simplification can open cracks along them.
+pub fn split_mesh_clipping_unwelded<const N: usize>(
+    mesh: &IndexedMesh,
+    bounds: &BoundingBox,
+) -> [IndexedMesh; N] {
+    split_into_cells(mesh, bounds, false, |i| match N {
+        8 => child_bounds(bounds, i),
+        _ => quadrant_bounds(bounds, i),
+    })
+}
+
+/// Cell marker for triangles whose vertices fall in different cells.
+const STRADDLING: u8 = u8::MAX;
 
-    let mut builders: [OctantMeshBuilder; 8] = std::array::from_fn(|_| {
-        OctantMeshBuilder::new(mesh.has_normals(), mesh.has_uvs(), mesh.has_colors())
+/// Classify every triangle by the cell (octant index, masked to `N` cells)
+/// holding all three of its vertices, or `STRADDLING`.
+///
+/// This is a single read-only scan over positions, so it runs in parallel
+/// and leaves only the straddling subset for the clipping pass.
+fn classify_triangles<const N: usize>(mesh: &IndexedMesh, center: [f64; 3]) -> Vec<u8> {
+    let cell_of = |vertex: u32| {
+        let i = vertex as usize * 3;
+        let p = [
+            mesh.positions[i] as f64,
+            mesh.positions[i + 1] as f64,
+            mesh.positions[i + 2] as f64,
+        ];
+        octant_index(center, p) & (N - 1)
+    };
+
+    mesh.indices
+        .par_chunks_exact(3)
+        .map(|tri| {
+            let cell = cell_of(tri[0]);
+            if cell == cell_of(tri[1]) && cell == cell_of(tri[2]) {
+                cell as u8
+            } else {
+                STRADDLING
+            }
+        })
+        .collect()
+}
+
+/// Shared body of the octant (`N = 8`) and quadrant (`N = 4`) splits. Cell
+/// indices are octant indices with the Z bit masked off for quadrants.
+/// `weld` shares vertices between each cell's triangles.
+fn split_into_cells<const N: usize>(
+    mesh: &IndexedMesh,
+    bounds: &BoundingBox,
+    weld: bool,
+    cell_bounds: impl Fn(usize) -> BoundingBox,
+) -> [IndexedMesh; N] {
+    let center = bounds.center();
+    let child_boxes: [BoundingBox; N] = std::array::from_fn(cell_bounds);
+    let axes = if N == 8 { 3 } else { 2 };
+
+    let grid = WeldGrid::new(bounds);
+    let mut builders: [OctantMeshBuilder; N] = std::array::from_fn(|_| {
+        let builder = OctantMeshBuilder::new(
+            grid,
+            mesh.has_normals(),
+            mesh.has_uvs(),
+            mesh.has_uvs1(),
+            mesh.has_colors(),
+            mesh.has_feature_ids(),
+        );
+        if weld { builder } else { builder.unwelded() }
     });
 
-    for tri in mesh.indices.chunks_exact(3) {
+    let cells = classify_triangles::<N>(mesh, center);
+
+    // Per-triangle materials of each cell, tracked only for multi-material meshes
+    let tri_materials = (!mesh.material_groups.is_empty()).then(|| mesh.triangle_materials());
+    let mut cell_materials: [Vec<Option<usize>>; N] = std::array::from_fn(|_| Vec::new());
+
+    for (t, (tri, &cell)) in mesh.indices.chunks_exact(3).zip(&cells).enumerate() {
+        let material = tri_materials.as_ref().and_then(|m| m[t]);
         let i0 = tri[0] as usize;
         let i1 = tri[1] as usize;
         let i2 = tri[2] as usize;
 
-        let p0 = [
-            mesh.positions[i0 * 3] as f64,
-            mesh.positions[i0 * 3 + 1] as f64,
-            mesh.positions[i0 * 3 + 2] as f64,
-        ];
-        let p1 = [
-            mesh.positions[i1 * 3] as f64,
-            mesh.positions[i1 * 3 + 1] as f64,
-            mesh.positions[i1 * 3 + 2] as f64,
-        ];
-        let p2 = [
-            mesh.positions[i2 * 3] as f64,
-            mesh.positions[i2 * 3 + 1] as f64,
-            mesh.positions[i2 * 3 + 2] as f64,
-        ];
-
-        let oct0 = octant_index(center, p0);
-        let oct1 = octant_index(center, p1);
-        let oct2 = octant_index(center, p2);
+        let v0 = extract_clip_vertex(mesh, i0);
+        let v1 = extract_clip_vertex(mesh, i1);
+        let v2 = extract_clip_vertex(mesh, i2);
 
-        if oct0 == oct1 && oct1 == oct2 {
+        if cell != STRADDLING {
             // Fast path: all vertices in same octant — no clipping needed
-            let v0 = extract_clip_vertex(mesh, i0);
-            let v1 = extract_clip_vertex(mesh, i1);
-            let v2 = extract_clip_vertex(mesh, i2);
-            builders[oct0].add_triangle(&v0, &v1, &v2);
+            let cell = cell as usize;
+            builders[cell].add_source_triangle([&v0, &v1, &v2], [tri[0], tri[1], tri[2]].map(Some));
+            cell_materials[cell].resize(builders[cell].indices.len() / 3, material);
         } else {
             // Slow path: triangle straddles boundary — clip against candidate octants
-            let v0 = extract_clip_vertex(mesh, i0);
-            let v1 = extract_clip_vertex(mesh, i1);
-            let v2 = extract_clip_vertex(mesh, i2);
+            let (p0, p1, p2) = (v0.pos, v1.pos, v2.pos);
 
             // AABB pre-filter: compute triangle bounding box, skip non-overlapping octants
             let tri_min = [
@@ -373,25 +631,97 @@ pub fn split_mesh_clipping(mesh: &IndexedMesh, bounds: &BoundingBox) -> [Indexed
                 let clipped = clip_triangle_to_octant(
                     [v0.clone(), v1.clone(), v2.clone()],
                     cb,
+                    axes,
                 );
                 let sub_tris = fan_triangulate(&clipped);
                 for sub_tri in &sub_tris {
-                    builders[oct_idx].add_triangle(&sub_tri[0], &sub_tri[1], &sub_tri[2]);
+                    // Corners the clip kept are copies of the source vertices
+                    let sources = [&sub_tri[0], &sub_tri[1], &sub_tri[2]].map(|v| {
+                        [&v0, &v1, &v2]
+                            .iter()
+                            .position(|source| source.pos == v.pos)
+                            .map(|k| tri[k])
+                    });
+                    builders[oct_idx]
+                        .add_source_triangle([&sub_tri[0], &sub_tri[1], &sub_tri[2]], sources);
                 }
+                cell_materials[oct_idx].resize(builders[oct_idx].indices.len() / 3, material);
             }
         }
     }
 
     let material_index = mesh.material_index;
     std::array::from_fn(|i| {
-        std::mem::replace(
+        let mut child = std::mem::replace(
             &mut builders[i],
-            OctantMeshBuilder::new(false, false, false),
+            OctantMeshBuilder::new(grid, false, false, false, false, false),
         )
-        .build(material_index)
+        .build(material_index, mesh.name.clone());
+        if tri_materials.is_some() {
+            child.set_triangle_materials(&cell_materials[i]);
+        }
+        child
     })
 }
 
+/// Rebuild a mesh group by group, clipping each group's faces in UV space
+/// to its `(uv_min, uv_max)` rectangle (`None` keeps the faces as they are).
+///
+/// Returns the new mesh and the range of face indices each group occupies
+/// in it. Faces not listed in any group are dropped.
+pub fn clip_faces_to_uv_rects(
+    mesh: &IndexedMesh,
+    groups: &[(&[usize], Option<([f32; 2], [f32; 2])>)],
+) -> (IndexedMesh, Vec<Range<usize>>) {
+    let mut builder = OctantMeshBuilder::new(
+        WeldGrid::for_positions(&mesh.positions),
+        mesh.has_normals(),
+        mesh.has_uvs(),
+        mesh.has_uvs1(),
+        mesh.has_colors(),
+        mesh.has_feature_ids(),
+    );
+    let mut ranges = Vec::with_capacity(groups.len());
+
+    for (faces, rect) in groups {
+        let start = builder.indices.len() / 3;
+        for &face in faces.iter() {
+            let tri = std::array::from_fn(|k| {
+                extract_clip_vertex(mesh, mesh.indices[face * 3 + k] as usize)
+            });
+            let polygon = match rect {
+                Some((uv_min, uv_max)) => clip_triangle_to_uv_rect(tri, *uv_min, *uv_max),
+                None => tri.into(),
+            };
+            for sub_tri in fan_triangulate(&polygon) {
+                builder.add_triangle(&sub_tri[0], &sub_tri[1], &sub_tri[2]);
+            }
+        }
+        ranges.push(start..builder.indices.len() / 3);
+    }
+
+    (builder.build(mesh.material_index, mesh.name.clone()), ranges)
+}
+
+/// Clip a triangle against the 4 edges of a UV-space rectangle.
+fn clip_triangle_to_uv_rect(
+    tri: [ClipVertex; 3],
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+) -> Vec<ClipVertex> {
+    let mut polygon: Vec<ClipVertex> = tri.into();
+    for axis in 0..2 {
+        for (value, positive) in [(uv_min[axis], true), (uv_max[axis], false)] {
+            let plane = ClipPlane { axis: axis + 3, value: value as f64, positive };
+            polygon = clip_polygon_by_plane(&polygon, &plane);
+            if polygon.is_empty() {
+                return polygon;
+            }
+        }
+    }
+    polygon
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -399,9 +729,30 @@ mod tests {
     #[test]
     fn clip_polygon_fully_inside() {
         let polygon = vec![
-            ClipVertex { pos: [0.2, 0.2, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
-            ClipVertex { pos: [0.4, 0.2, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
-            ClipVertex { pos: [0.3, 0.4, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
+            ClipVertex {
+                pos: [0.2, 0.2, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
+            ClipVertex {
+                pos: [0.4, 0.2, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
+            ClipVertex {
+                pos: [0.3, 0.4, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
         ];
         let plane = ClipPlane { axis: 0, value: 0.0, positive: true };
         let result = clip_polygon_by_plane(&polygon, &plane);
@@ -411,9 +762,30 @@ mod tests {
     #[test]
     fn clip_polygon_fully_outside() {
         let polygon = vec![
-            ClipVertex { pos: [-0.5, 0.2, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
-            ClipVertex { pos: [-0.3, 0.2, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
-            ClipVertex { pos: [-0.4, 0.4, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
+            ClipVertex {
+                pos: [-0.5, 0.2, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
+            ClipVertex {
+                pos: [-0.3, 0.2, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
+            ClipVertex {
+                pos: [-0.4, 0.4, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
         ];
         let plane = ClipPlane { axis: 0, value: 0.0, positive: true };
         let result = clip_polygon_by_plane(&polygon, &plane);
@@ -424,9 +796,30 @@ mod tests {
     fn clip_polygon_one_vertex_out() {
         // Triangle with 2 verts inside (x >= 0) and 1 outside
         let polygon = vec![
-            ClipVertex { pos: [0.5, 0.0, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
-            ClipVertex { pos: [0.5, 1.0, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
-            ClipVertex { pos: [-0.5, 0.5, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
+            ClipVertex {
+                pos: [0.5, 0.0, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
+            ClipVertex {
+                pos: [0.5, 1.0, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
+            ClipVertex {
+                pos: [-0.5, 0.5, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
         ];
         let plane = ClipPlane { axis: 0, value: 0.0, positive: true };
         let result = clip_polygon_by_plane(&polygon, &plane);
@@ -437,9 +830,30 @@ mod tests {
     fn clip_polygon_two_vertices_out() {
         // Triangle with 1 vert inside (x >= 0.5) and 2 outside
         let polygon = vec![
-            ClipVertex { pos: [1.0, 0.5, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
-            ClipVertex { pos: [0.0, 0.0, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
-            ClipVertex { pos: [0.0, 1.0, 0.0], normal: [0.0; 3], uv: [0.0; 2], color: [0.0; 4] },
+            ClipVertex {
+                pos: [1.0, 0.5, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
+            ClipVertex {
+                pos: [0.0, 0.0, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
+            ClipVertex {
+                pos: [0.0, 1.0, 0.0],
+                normal: [0.0; 3],
+                uv: [0.0; 2],
+                uv1: [0.0; 2],
+                color: [0.0; 4],
+                feature_id: 0,
+            },
         ];
         let plane = ClipPlane { axis: 0, value: 0.5, positive: true };
         let result = clip_polygon_by_plane(&polygon, &plane);
@@ -452,13 +866,17 @@ mod tests {
             pos: [0.0, 0.0, 0.0],
             normal: [0.0, 0.0, 1.0],
             uv: [0.0, 0.0],
+            uv1: [0.0, 1.0],
             color: [1.0, 0.0, 0.0, 1.0],
+            feature_id: 0,
         };
         let b = ClipVertex {
             pos: [1.0, 1.0, 1.0],
             normal: [1.0, 0.0, 0.0],
             uv: [1.0, 1.0],
+            uv1: [1.0, 0.0],
             color: [0.0, 1.0, 0.0, 1.0],
+            feature_id: 0,
         };
         let plane = ClipPlane { axis: 0, value: 0.5, positive: true };
         let v = intersect_edge(&a, &b, &plane);
@@ -471,6 +889,8 @@ mod tests {
         // UV at midpoint
         assert!((v.uv[0] - 0.5).abs() < 1e-10);
         assert!((v.uv[1] - 0.5).abs() < 1e-10);
+        assert!((v.uv1[0] - 0.5).abs() < 1e-10);
+        assert!((v.uv1[1] - 0.5).abs() < 1e-10);
 
         // Color at midpoint
         assert!((v.color[0] - 0.5).abs() < 1e-10);
@@ -490,7 +910,9 @@ mod tests {
                     pos: [angle.cos(), angle.sin(), 0.0],
                     normal: [0.0; 3],
                     uv: [0.0; 2],
+                    uv1: [0.0; 2],
                     color: [0.0; 4],
+                    feature_id: 0,
                 }
             })
             .collect();
@@ -545,15 +967,46 @@ mod tests {
         assert!(total_tris >= 1);
     }
 
+    #[test]
+    fn split_mesh_keeps_feature_ids_apart() {
+        // Two objects sharing an edge, each straddling the center plane
+        let mesh = IndexedMesh {
+            positions: vec![
+                0.25, 0.25, 0.25, 0.75, 0.25, 0.25, 0.25, 0.75, 0.25, // object 0
+                0.25, 0.25, 0.25, 0.75, 0.25, 0.25, 0.75, 0.10, 0.25, // object 1
+            ],
+            feature_ids: vec![0, 0, 0, 1, 1, 1],
+            indices: vec![0, 1, 2, 3, 4, 5],
+            ..Default::default()
+        };
+        let bounds = BoundingBox {
+            min: [0.0, 0.0, 0.0],
+            max: [1.0, 1.0, 1.0],
+        };
+
+        for child in split_mesh_clipping(&mesh, &bounds) {
+            assert_eq!(child.feature_ids.len(), child.vertex_count());
+            // Shared corners stay separate vertices, one per feature
+            for tri in child.indices.chunks_exact(3) {
+                let id = child.feature_ids[tri[0] as usize];
+                assert!(tri.iter().all(|&i| child.feature_ids[i as usize] == id));
+            }
+        }
+    }
+
     #[test]
     fn split_mesh_preserves_attributes() {
         let mesh = IndexedMesh {
             positions: vec![0.1, 0.1, 0.1, 0.2, 0.1, 0.1, 0.1, 0.2, 0.1],
             normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
             uvs: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            uvs1: Vec::new(),
             colors: vec![1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2],
             material_index: Some(2),
+            name: None,
+            material_groups: Vec::new(),
         };
         let bounds = BoundingBox {
             min: [0.0, 0.0, 0.0],
@@ -602,6 +1055,228 @@ mod tests {
         assert!(rel_error < 1e-4, "area should be conserved within ε, got relative error {rel_error}");
     }
 
+    #[test]
+    fn classified_split_matches_clipping_every_triangle() {
+        // 3D grid: tilted 11x11 layers stacked through all octants, with
+        // grid lines off the mid-planes so triangles cross them
+        let n = 11;
+        let mut mesh = IndexedMesh::default();
+        for layer in 0..5 {
+            let base = (mesh.positions.len() / 3) as u32;
+            for y in 0..=n {
+                for x in 0..=n {
+                    let fx = x as f32 / n as f32;
+                    let fy = y as f32 / n as f32;
+                    let fz = 0.05 + 0.2 * layer as f32 + 0.15 * fx;
+                    mesh.positions.extend_from_slice(&[fx, fy, fz.min(1.0)]);
+                }
+            }
+            for y in 0..n {
+                for x in 0..n {
+                    let tl = base + (y * (n + 1) + x);
+                    let bl = tl + n + 1;
+                    mesh.indices.extend_from_slice(&[tl, bl, tl + 1, tl + 1, bl, bl + 1]);
+                }
+            }
+        }
+        let bounds = BoundingBox {
+            min: [0.0, 0.0, 0.0],
+            max: [1.0, 1.0, 1.0],
+        };
+
+        // Reference: no classification, every triangle clipped against every octant
+        let grid = WeldGrid::new(&bounds);
+        let mut reference: Vec<OctantMeshBuilder> = (0..8)
+            .map(|_| OctantMeshBuilder::new(grid, false, false, false, false, false))
+            .collect();
+        for tri in mesh.indices.chunks_exact(3) {
+            let verts: [ClipVertex; 3] =
+                std::array::from_fn(|k| extract_clip_vertex(&mesh, tri[k] as usize));
+            for (i, builder) in reference.iter_mut().enumerate() {
+                let clipped = clip_triangle_to_octant(verts.clone(), &child_bounds(&bounds, i), 3);
+                for sub in fan_triangulate(&clipped) {
+                    builder.add_triangle(&sub[0], &sub[1], &sub[2]);
+                }
+            }
+        }
+
+        let area = |m: &IndexedMesh| -> f64 {
+            m.indices
+                .chunks_exact(3)
+                .map(|t| {
+                    let [a, b, c] = [t[0], t[1], t[2]].map(|i| i as usize);
+                    triangle_area_f32(&m.positions, a, b, c)
+                })
+                .sum()
+        };
+
+        let children = split_mesh_clipping(&mesh, &bounds);
+        let mut total = 0;
+        for (child, builder) in children.iter().zip(reference) {
+            let expected = builder.build(None, None);
+            assert_eq!(child.triangle_count(), expected.triangle_count());
+            assert!((area(child) - area(&expected)).abs() < 1e-9);
+            total += child.triangle_count();
+        }
+        // The grid must exercise both paths
+        assert!(total > mesh.triangle_count());
+        let cells = classify_triangles::<8>(&mesh, bounds.center());
+        assert!(cells.contains(&STRADDLING));
+        assert!(cells.iter().any(|&c| c != STRADDLING));
+    }
+
+    #[test]
+    fn unwelded_split_keeps_geometry_with_more_vertices() {
+        // Tilted grid crossing every mid-plane
+        let n = 9;
+        let mut mesh = IndexedMesh::default();
+        for y in 0..=n {
+            for x in 0..=n {
+                let (fx, fy) = (x as f32 / n as f32, y as f32 / n as f32);
+                mesh.positions.extend_from_slice(&[fx, fy, 0.1 + 0.8 * fx]);
+            }
+        }
+        for y in 0..n {
+            for x in 0..n {
+                let (tl, bl) = (y * (n + 1) + x, (y + 1) * (n + 1) + x);
+                mesh.indices.extend([tl, bl, tl + 1, tl + 1, bl, bl + 1]);
+            }
+        }
+        let bounds = BoundingBox {
+            min: [0.0, 0.0, 0.0],
+            max: [1.0, 1.0, 1.0],
+        };
+
+        // Corner positions of every triangle, in a canonical order
+        let triangles = |m: &IndexedMesh| {
+            let mut tris: Vec<[[i64; 3]; 3]> = m
+                .indices
+                .chunks_exact(3)
+                .map(|t| {
+                    let mut tri = [t[0], t[1], t[2]].map(|i| {
+                        let p = &m.positions[i as usize * 3..i as usize * 3 + 3];
+                        [0, 1, 2].map(|k| (p[k] * 1e5).round() as i64)
+                    });
+                    tri.sort();
+                    tri
+                })
+                .collect();
+            tris.sort();
+            tris
+        };
+        // Welded cells can hold unreferenced vertices of collapsed triangles
+        let used_vertices = |m: &IndexedMesh| {
+            let mut used = m.indices.clone();
+            used.sort_unstable();
+            used.dedup();
+            used.len()
+        };
+        let check = |welded: &[IndexedMesh], unwelded: &[IndexedMesh]| {
+            let (mut welded_total, mut unwelded_total, mut corners) = (0, 0, 0);
+            for (w, u) in welded.iter().zip(unwelded) {
+                assert!(u.vertex_count() >= used_vertices(w));
+                assert_eq!(triangles(u), triangles(w));
+                welded_total += used_vertices(w);
+                unwelded_total += u.vertex_count();
+                corners += u.indices.len();
+            }
+            assert!(unwelded_total > welded_total);
+            // Source vertices stay shared; only clipped ones are duplicated
+            assert!(
+                unwelded_total < corners / 2,
+                "{unwelded_total} of {corners}"
+            );
+        };
+        check(
+            &split_mesh_clipping(&mesh, &bounds),
+            &split_mesh_clipping_unwelded::<8>(&mesh, &bounds),
+        );
+        check(
+            &split_mesh_clipping_xy(&mesh, &bounds),
+            &split_mesh_clipping_unwelded::<4>(&mesh, &bounds),
+        );
+    }
+
+    #[test]
+    fn unwelded_split_shares_unclipped_vertices() {
+        // A grid inside one octant is never clipped
+        let n = 4;
+        let mut mesh = IndexedMesh::default();
+        for y in 0..=n {
+            for x in 0..=n {
+                let (fx, fy) = (x as f32 / n as f32, y as f32 / n as f32);
+                let (px, py) = (0.1 + 0.3 * fx, 0.1 + 0.3 * fy);
+                mesh.positions.extend_from_slice(&[px, py, 0.2]);
+            }
+        }
+        for y in 0..n {
+            for x in 0..n {
+                let (tl, bl) = (y * (n + 1) + x, (y + 1) * (n + 1) + x);
+                mesh.indices.extend([tl, bl, tl + 1, tl + 1, bl, bl + 1]);
+            }
+        }
+        let bounds = BoundingBox {
+            min: [0.0, 0.0, 0.0],
+            max: [1.0, 1.0, 1.0],
+        };
+
+        let welded = split_mesh_clipping(&mesh, &bounds);
+        let unwelded = split_mesh_clipping_unwelded::<8>(&mesh, &bounds);
+
+        assert_eq!(unwelded[0].vertex_count(), mesh.vertex_count());
+        assert_eq!(unwelded[0].indices, welded[0].indices);
+    }
+
+    /// Two triangles in the upper octant around `center`. Vertex 1 of the
+    /// first and vertex 0 of the second lie on the X clipping plane, `gap`
+    /// apart.
+    fn close_vertices_mesh(center: [f32; 3], edge: f32, gap: f32) -> IndexedMesh {
+        let [x, y, z] = center;
+        let positions = [
+            [x, y, z],
+            [x, y + edge, z],
+            [x + edge, y, z],
+            [x, y + edge + gap, z],
+            [x, y + 2.0 * edge, z],
+            [x + edge, y + 2.0 * edge, z],
+        ];
+        assert_ne!(positions[1], positions[3], "gap below f32 precision");
+        IndexedMesh {
+            positions: positions.into_iter().flatten().collect(),
+            indices: vec![0, 2, 1, 3, 5, 4],
+            ..Default::default()
+        }
+    }
+
+    fn cube(center: [f32; 3], half: f32) -> BoundingBox {
+        BoundingBox {
+            min: center.map(|c| c as f64 - half as f64),
+            max: center.map(|c| c as f64 + half as f64),
+        }
+    }
+
+    #[test]
+    fn weld_keeps_close_vertices_of_large_coordinate_mesh() {
+        // Beyond i64::MAX µm (about 9.2e12) a fixed 1 µm grid saturates
+        // and welds every vertex onto the same key
+        let center = [1.0e13, 1.0e13, 0.0];
+        let mesh = close_vertices_mesh(center, 6.4e7, 4.0e6);
+        let children = split_mesh_clipping(&mesh, &cube(center, 4.0e9));
+
+        assert_eq!(children[7].triangle_count(), 2);
+        assert_eq!(children[7].vertex_count(), 6);
+    }
+
+    #[test]
+    fn weld_grid_scales_down_for_micro_models() {
+        // A fixed 1 µm grid would merge the two vertices 0.1 µm apart
+        let mesh = close_vertices_mesh([0.0; 3], 1e-5, 1e-7);
+        let children = split_mesh_clipping(&mesh, &cube([0.0; 3], 1e-4));
+
+        assert_eq!(children[7].triangle_count(), 2);
+        assert_eq!(children[7].vertex_count(), 6);
+    }
+
     #[test]
     fn split_mesh_boundary_vertex_shared() {
         // Triangle straddling the X midpoint
@@ -664,9 +1339,13 @@ mod tests {
                 1.0, 1.0,  // v4
                 0.25, 0.5, // v5
             ],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2, 3, 4, 5],
             material_index: None,
+            name: None,
+            material_groups: Vec::new(),
         };
 
         let bounds = BoundingBox {
@@ -1,3 +1,5 @@
+use glam::{DMat3, DMat4, DVec3};
+
 use crate::config::Units;
 use crate::types::{BoundingBox, IndexedMesh};
 
@@ -12,89 +14,182 @@ pub fn unit_scale_factor(units: Units) -> f64 {
     }
 }
 
-/// Scale all vertex positions in-place (f64 math, write back f32).
-pub fn apply_unit_scaling(meshes: &mut [IndexedMesh], factor: f64) {
+/// A mesh whose positions are held in f64 during the transform stage.
+///
+/// Georeferenced scenes can sit hundreds of kilometres from the origin,
+/// where f32 resolves only a few centimetres. Positions stay in f64 through
+/// scaling, axis swap and rotation, and are narrowed back to f32 only after
+/// centering has brought them close to the origin.
+#[derive(Debug, Clone)]
+pub struct WideMesh {
+    /// `[x, y, z, ...]` in f64; `mesh.positions` is empty until narrowed.
+    pub positions: Vec<f64>,
+    pub mesh: IndexedMesh,
+}
+
+impl From<IndexedMesh> for WideMesh {
+    fn from(mut mesh: IndexedMesh) -> Self {
+        let positions = mesh.positions.iter().map(|&p| p as f64).collect();
+        mesh.positions = Vec::new();
+        Self { positions, mesh }
+    }
+}
+
+impl WideMesh {
+    /// Write the f64 positions back into the mesh as f32.
+    pub fn into_mesh(self) -> IndexedMesh {
+        let mut mesh = self.mesh;
+        mesh.positions = self.positions.iter().map(|&p| p as f32).collect();
+        mesh
+    }
+}
+
+/// Place a mesh held in its glTF node's local space into the world with the
+/// node's `world` matrix, like the glTF loader's own baking but in f64.
+pub fn apply_node_transform(mesh: &mut WideMesh, world: DMat4) {
+    for p in mesh.positions.chunks_exact_mut(3) {
+        let v = world.transform_point3(DVec3::new(p[0], p[1], p[2]));
+        p.copy_from_slice(&v.to_array());
+    }
+
+    let linear = DMat3::from_mat4(world);
+    let normal_matrix = linear.inverse().transpose();
+    for n in mesh.mesh.normals.chunks_exact_mut(3) {
+        let v = normal_matrix * DVec3::new(n[0] as f64, n[1] as f64, n[2] as f64);
+        n.copy_from_slice(&v.normalize_or_zero().as_vec3().to_array());
+    }
+
+    if linear.determinant() < 0.0 {
+        for tri in mesh.mesh.indices.chunks_exact_mut(3) {
+            tri.swap(1, 2);
+        }
+    }
+}
+
+/// Scale all vertex positions in-place by a per-axis factor.
+///
+/// A non-uniform factor also changes surface orientation, so normals are
+/// scaled by the inverse factor (the inverse-transpose of a diagonal
+/// matrix) and renormalized.
+pub fn apply_unit_scaling(meshes: &mut [WideMesh], factor: [f64; 3]) {
+    let uniform = factor[0] == factor[1] && factor[1] == factor[2];
+
     for mesh in meshes.iter_mut() {
-        for pos in mesh.positions.iter_mut() {
-            *pos = ((*pos as f64) * factor) as f32;
+        for pos in mesh.positions.chunks_exact_mut(3) {
+            for axis in 0..3 {
+                pos[axis] *= factor[axis];
+            }
+        }
+        if uniform {
+            continue;
+        }
+        for n in mesh.mesh.normals.chunks_exact_mut(3) {
+            let scaled = [
+                n[0] as f64 / factor[0],
+                n[1] as f64 / factor[1],
+                n[2] as f64 / factor[2],
+            ];
+            let len =
+                (scaled[0] * scaled[0] + scaled[1] * scaled[1] + scaled[2] * scaled[2]).sqrt();
+            if len > 0.0 {
+                for axis in 0..3 {
+                    n[axis] = (scaled[axis] / len) as f32;
+                }
+            }
         }
     }
 }
 
 /// Convert from right-handed Y-up (OBJ/glTF) to right-handed Z-up (3D Tiles).
 ///
-/// Transform: `(x, y, z)` → `(x, z, -y)`
-pub fn swap_y_up_to_z_up(meshes: &mut [IndexedMesh]) {
+/// Transform: `(x, y, z)` → `(x, -z, y)`, a +90° rotation about X. The
+/// input's up (+Y) becomes +Z and its forward (-Z) becomes +Y, so a
+/// georeferenced Y-up model keeps east, north and up as X, Y and Z.
+pub fn swap_y_up_to_z_up(meshes: &mut [WideMesh]) {
     for mesh in meshes.iter_mut() {
         for tri in mesh.positions.chunks_exact_mut(3) {
             let y = tri[1];
             let z = tri[2];
-            tri[1] = z;
-            tri[2] = -y;
+            tri[1] = -z;
+            tri[2] = y;
         }
         // Normals follow the same rotation
-        for tri in mesh.normals.chunks_exact_mut(3) {
+        for tri in mesh.mesh.normals.chunks_exact_mut(3) {
             let y = tri[1];
             let z = tri[2];
-            tri[1] = z;
-            tri[2] = -y;
+            tri[1] = -z;
+            tri[2] = y;
         }
     }
 }
 
-/// Rotate all vertex positions about the Z axis by the given angle in degrees.
-pub fn apply_true_north_rotation(meshes: &mut [IndexedMesh], degrees: f64) {
+/// Rotate all vertex positions by the given angle in degrees about the
+/// vertical axis through the scene centroid.
+///
+/// Pivoting on the centroid rather than the world origin keeps an off-origin
+/// scene in place, so the centroid later added to the georeferenced offset is
+/// the same point before and after the rotation. Returns the pivot.
+pub fn apply_true_north_rotation(meshes: &mut [WideMesh], degrees: f64) -> [f64; 3] {
     let radians = degrees.to_radians();
     let cos_a = radians.cos();
     let sin_a = radians.sin();
+    let pivot = centroid(meshes).unwrap_or([0.0; 3]);
+    let [cx, cy, _] = pivot;
 
     for mesh in meshes.iter_mut() {
         for tri in mesh.positions.chunks_exact_mut(3) {
-            let x = tri[0] as f64;
-            let y = tri[1] as f64;
-            tri[0] = (x * cos_a - y * sin_a) as f32;
-            tri[1] = (x * sin_a + y * cos_a) as f32;
+            let x = tri[0] - cx;
+            let y = tri[1] - cy;
+            tri[0] = cx + x * cos_a - y * sin_a;
+            tri[1] = cy + x * sin_a + y * cos_a;
         }
-        for tri in mesh.normals.chunks_exact_mut(3) {
+        for tri in mesh.mesh.normals.chunks_exact_mut(3) {
             let x = tri[0] as f64;
             let y = tri[1] as f64;
             tri[0] = (x * cos_a - y * sin_a) as f32;
             tri[1] = (x * sin_a + y * cos_a) as f32;
         }
     }
+    pivot
 }
 
-/// Compute the centroid of all vertices, subtract it from every position,
-/// and return the centroid offset `[cx, cy, cz]`.
-pub fn center_meshes(meshes: &mut [IndexedMesh]) -> [f64; 3] {
-    // Accumulate in f64
+/// Mean of all vertex positions, or `None` when there are no vertices.
+fn centroid(meshes: &[WideMesh]) -> Option<[f64; 3]> {
     let mut sum = [0.0_f64; 3];
     let mut count: usize = 0;
 
     for mesh in meshes.iter() {
         for tri in mesh.positions.chunks_exact(3) {
-            sum[0] += tri[0] as f64;
-            sum[1] += tri[1] as f64;
-            sum[2] += tri[2] as f64;
+            sum[0] += tri[0];
+            sum[1] += tri[1];
+            sum[2] += tri[2];
             count += 1;
         }
     }
 
     if count == 0 {
-        return [0.0; 3];
+        return None;
     }
 
-    let centroid = [
+    Some([
         sum[0] / count as f64,
         sum[1] / count as f64,
         sum[2] / count as f64,
-    ];
+    ])
+}
+
+/// Compute the centroid of all vertices, subtract it from every position,
+/// and return the centroid offset `[cx, cy, cz]`.
+pub fn center_meshes(meshes: &mut [WideMesh]) -> [f64; 3] {
+    let Some(centroid) = centroid(meshes) else {
+        return [0.0; 3];
+    };
 
     for mesh in meshes.iter_mut() {
         for tri in mesh.positions.chunks_exact_mut(3) {
-            tri[0] = ((tri[0] as f64) - centroid[0]) as f32;
-            tri[1] = ((tri[1] as f64) - centroid[1]) as f32;
-            tri[2] = ((tri[2] as f64) - centroid[2]) as f32;
+            tri[0] -= centroid[0];
+            tri[1] -= centroid[1];
+            tri[2] -= centroid[2];
         }
     }
 
@@ -143,6 +238,25 @@ pub fn compute_bounding_box(meshes: &[IndexedMesh]) -> BoundingBox {
     BoundingBox { min, max }
 }
 
+/// Smallest half-extent, in metres, the scene bounding box may have on any axis.
+pub const MIN_HALF_EXTENT: f64 = 0.005;
+
+/// Grow every axis thinner than `2 * min_half_extent` symmetrically about
+/// its centre, so octree splits and diagonal-based geometric errors stay
+/// well-defined for coincident or flat inputs.
+pub fn ensure_min_extent(bounds: BoundingBox, min_half_extent: f64) -> BoundingBox {
+    let center = bounds.center();
+    let half = bounds.half_extents();
+    let mut out = bounds;
+    for axis in 0..3 {
+        if half[axis] < min_half_extent {
+            out.min[axis] = center[axis] - min_half_extent;
+            out.max[axis] = center[axis] + min_half_extent;
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,9 +266,13 @@ mod tests {
             positions: vec![x0, y0, z0, x1, y1, z1, x2, y2, z2],
             normals: vec![0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0],
             uvs: vec![],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2],
             material_index: None,
+            name: None,
+            material_groups: Vec::new(),
         }
     }
 
@@ -169,57 +287,108 @@ mod tests {
 
     #[test]
     fn apply_unit_scaling_doubles_positions() {
-        let mut meshes = vec![IndexedMesh {
+        let mut meshes = vec![WideMesh::from(IndexedMesh {
             positions: vec![1.0, 2.0, 3.0],
             ..Default::default()
-        }];
-        apply_unit_scaling(&mut meshes, 2.0);
+        })];
+        apply_unit_scaling(&mut meshes, [2.0; 3]);
         assert!((meshes[0].positions[0] - 2.0).abs() < 1e-5);
         assert!((meshes[0].positions[1] - 4.0).abs() < 1e-5);
         assert!((meshes[0].positions[2] - 6.0).abs() < 1e-5);
     }
 
+    #[test]
+    fn per_axis_scaling_renormalizes_normals() {
+        let mut meshes = vec![WideMesh::from(IndexedMesh {
+            positions: vec![1.0, 2.0, 3.0, -1.0, 0.5, 0.0],
+            normals: vec![0.6, 0.0, 0.8, 0.0, 0.0, 1.0],
+            ..Default::default()
+        })];
+        apply_unit_scaling(&mut meshes, [1.0, 1.0, 2.0]);
+
+        assert_eq!(meshes[0].positions, vec![1.0, 2.0, 6.0, -1.0, 0.5, 0.0]);
+        for n in meshes[0].mesh.normals.chunks_exact(3) {
+            let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            assert!((len - 1.0).abs() < 1e-6);
+        }
+        // A stretched slope gets steeper, so its normal tips toward horizontal
+        assert!(meshes[0].mesh.normals[0] > 0.6);
+        assert_eq!(&meshes[0].mesh.normals[3..], &[0.0, 0.0, 1.0]);
+    }
+
     #[test]
     fn swap_y_up_to_z_up_known_triangle() {
-        // Y-up: vertex at (1, 2, 3) → Z-up: (1, 3, -2)
-        let mut meshes = vec![make_triangle(1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0)];
+        // Y-up: vertex at (1, 2, 3) → Z-up: (1, -3, 2)
+        let mut meshes = vec![WideMesh::from(make_triangle(
+            1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+        ))];
         swap_y_up_to_z_up(&mut meshes);
         let p = &meshes[0].positions;
         assert!((p[0] - 1.0).abs() < 1e-6);  // x unchanged
-        assert!((p[1] - 3.0).abs() < 1e-6);  // new y = old z
-        assert!((p[2] - (-2.0)).abs() < 1e-6); // new z = -old y
+        assert!((p[1] - (-3.0)).abs() < 1e-6); // new y = -old z
+        assert!((p[2] - 2.0).abs() < 1e-6); // new z = old y
 
-        // Normal (0,1,0) → (0,0,-1)
-        let n = &meshes[0].normals;
+        // Normal (0,1,0) → (0,0,1): up stays up
+        let n = &meshes[0].mesh.normals;
         assert!((n[0] - 0.0).abs() < 1e-6);
         assert!((n[1] - 0.0).abs() < 1e-6);
-        assert!((n[2] - (-1.0)).abs() < 1e-6);
+        assert!((n[2] - 1.0).abs() < 1e-6);
     }
 
     #[test]
     fn true_north_rotation_90_degrees() {
-        // Point (1, 0, 0) rotated 90° about Z → (0, 1, 0)
-        let mut meshes = vec![IndexedMesh {
-            positions: vec![1.0, 0.0, 5.0],
-            normals: vec![],
+        // Segment (1, 0)-(3, 0) rotated 90° about its centroid (2, 0) → (2, -1)-(2, 1)
+        let mut meshes = vec![WideMesh::from(IndexedMesh {
+            positions: vec![1.0, 0.0, 5.0, 3.0, 0.0, 5.0],
+            normals: vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
             ..Default::default()
-        }];
+        })];
         apply_true_north_rotation(&mut meshes, 90.0);
         let p = &meshes[0].positions;
-        assert!((p[0] - 0.0).abs() < 1e-5);
-        assert!((p[1] - 1.0).abs() < 1e-5);
-        assert!((p[2] - 5.0).abs() < 1e-5); // z unchanged
+        assert!((p[0] - 2.0).abs() < 1e-9);
+        assert!((p[1] - (-1.0)).abs() < 1e-9);
+        assert!((p[2] - 5.0).abs() < 1e-9); // z unchanged
+        assert!((p[3] - 2.0).abs() < 1e-9);
+        assert!((p[4] - 1.0).abs() < 1e-9);
+
+        // Normals are directions and rotate about the origin
+        let n = &meshes[0].mesh.normals;
+        assert!(n[0].abs() < 1e-6);
+        assert!((n[1] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn true_north_rotation_keeps_off_origin_centroid() {
+        let mut meshes = vec![WideMesh::from(IndexedMesh {
+            positions: vec![
+                1000.0, 2000.0, 10.0, 1010.0, 2000.0, 10.0, 1000.0, 2020.0, 12.0,
+            ],
+            ..Default::default()
+        })];
+        let before = centroid(&meshes).unwrap();
+
+        apply_true_north_rotation(&mut meshes, 30.0);
+
+        let after = centroid(&meshes).unwrap();
+        for axis in 0..3 {
+            assert!((after[axis] - before[axis]).abs() < 1e-9);
+        }
+        // Orientation changed: the first edge no longer runs along +X
+        let p = &meshes[0].positions;
+        let (dx, dy) = (p[3] - p[0], p[4] - p[1]);
+        assert!((dy.atan2(dx).to_degrees() - 30.0).abs() < 1e-9);
+        assert!(((dx * dx + dy * dy).sqrt() - 10.0).abs() < 1e-9);
     }
 
     #[test]
     fn centering_returns_correct_offset() {
-        let mut meshes = vec![IndexedMesh {
+        let mut meshes = vec![WideMesh::from(IndexedMesh {
             positions: vec![
                 10.0, 20.0, 30.0,
                 20.0, 40.0, 60.0,
             ],
             ..Default::default()
-        }];
+        })];
         let offset = center_meshes(&mut meshes);
         // Centroid = (15, 30, 45)
         assert!((offset[0] - 15.0).abs() < 1e-6);
@@ -238,7 +407,7 @@ mod tests {
 
     #[test]
     fn centering_empty_meshes() {
-        let mut meshes: Vec<IndexedMesh> = vec![];
+        let mut meshes: Vec<WideMesh> = vec![];
         let offset = center_meshes(&mut meshes);
         assert_eq!(offset, [0.0, 0.0, 0.0]);
     }
@@ -271,4 +440,15 @@ mod tests {
         assert_eq!(bb.min, [0.0; 3]);
         assert_eq!(bb.max, [0.0; 3]);
     }
+
+    #[test]
+    fn min_extent_inflates_only_thin_axes() {
+        let flat = BoundingBox {
+            min: [-1.0, -2.0, 3.0],
+            max: [1.0, 2.0, 3.0],
+        };
+        let bb = ensure_min_extent(flat, 0.5);
+        assert_eq!(bb.min, [-1.0, -2.0, 2.5]);
+        assert_eq!(bb.max, [1.0, 2.0, 3.5]);
+    }
 }
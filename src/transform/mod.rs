@@ -2,16 +2,20 @@ pub mod coordinates;
 pub mod ecef;
 pub mod projection;
 
+use glam::{DMat4, DVec3, DVec4};
 use tracing::info;
 
-use crate::config::PipelineConfig;
-use crate::error::Result;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{CartographicOrigin, Frame, MatrixOrder, PipelineConfig, Units, UpAxis};
+use crate::error::{PhotoTilerError, Result};
 use crate::ingestion::IngestionResult;
 use crate::types::{BoundingBox, IndexedMesh, MaterialLibrary};
 
 use coordinates::{
-    apply_true_north_rotation, apply_unit_scaling, center_meshes, compute_bounding_box,
-    swap_y_up_to_z_up, unit_scale_factor,
+    MIN_HALF_EXTENT, WideMesh, apply_node_transform, apply_true_north_rotation, apply_unit_scaling,
+    center_meshes, compute_bounding_box, ensure_min_extent, swap_y_up_to_z_up, unit_scale_factor,
 };
 use ecef::{build_root_transform, enu_rotation_matrix, geodetic_to_ecef, identity_transform};
 
@@ -21,68 +25,234 @@ pub struct TransformResult {
     pub meshes: Vec<IndexedMesh>,
     pub materials: MaterialLibrary,
     pub root_transform: [f64; 16],
+    /// ECEF position of the output frame's origin when
+    /// [`PipelineConfig::frame`] is [`Frame::LocalEnu`] and the model is
+    /// placed on the globe; `root_transform` is then identity.
+    pub ecef_origin: Option<[f64; 3]>,
     pub bounds: BoundingBox,
+    /// Per-mesh tile transform for meshes kept in their glTF node's local
+    /// space (see [`PipelineConfig::keep_node_transforms`]); `None` for
+    /// meshes in the centered output frame. `bounds` covers every mesh as
+    /// placed in that frame.
+    pub tile_transforms: Vec<Option<[f64; 16]>>,
+    /// Instance matrices per mesh, from [`IngestionResult::instances`].
+    /// Instanced meshes stay in local space; their tile transform maps the
+    /// instances' frame into the centered output frame.
+    pub instances: Vec<Vec<[f64; 16]>>,
 }
 
 /// Run the full transform stage.
 pub fn transform(config: &PipelineConfig, ingestion: &IngestionResult) -> Result<TransformResult> {
-    // 1. Clone meshes (we modify in-place)
-    let mut meshes = ingestion.meshes.clone();
+    // 1. Clone meshes, widening positions to f64 until they are centered.
+    // Meshes kept in node-local space are placed like the others for now.
+    let mut meshes: Vec<WideMesh> = ingestion
+        .meshes
+        .iter()
+        .cloned()
+        .map(WideMesh::from)
+        .collect();
+    for (mesh, world) in meshes.iter_mut().zip(&ingestion.node_transforms) {
+        if let Some(world) = world {
+            apply_node_transform(mesh, DMat4::from_cols_array(world));
+        }
+    }
+    // Instanced meshes only contribute their bounds at every instance
+    for (i, instances) in ingestion.instances.iter().enumerate() {
+        if !instances.is_empty() {
+            let local = compute_bounding_box(std::slice::from_ref(&ingestion.meshes[i]));
+            meshes[i] = WideMesh {
+                positions: instances
+                    .iter()
+                    .map(|m| local.transformed(m))
+                    .flat_map(|b| b.min.into_iter().chain(b.max))
+                    .collect(),
+                mesh: IndexedMesh::default(),
+            };
+        }
+    }
     let materials = ingestion.materials.clone();
 
-    // 2. Unit scaling
-    if let Some(units) = config.units {
+    // 2. Unit scaling, from `--units` or else units detected in metadata
+    let units = config.units.or(ingestion.units);
+    if let Some(units) = units {
         let factor = unit_scale_factor(units);
         if (factor - 1.0).abs() > f64::EPSILON {
             info!(units = %units, factor, "Applying unit scaling");
-            apply_unit_scaling(&mut meshes, factor);
+            apply_unit_scaling(&mut meshes, [factor; 3]);
+        }
+    }
+
+    // 3. Y-up → Z-up axis swap (skipped for inputs that are already Z-up)
+    match config.input_up {
+        UpAxis::YUp => {
+            info!("Swapping Y-up to Z-up");
+            swap_y_up_to_z_up(&mut meshes);
         }
+        UpAxis::ZUp => info!("Input is already Z-up -- no axis swap"),
     }
 
-    // 3. Y-up → Z-up axis swap
-    info!("Swapping Y-up to Z-up");
-    swap_y_up_to_z_up(&mut meshes);
+    // 4. Per-axis scale, in the Z-up frame so [1, 1, k] exaggerates height
+    if let Some(scale) = config.scale {
+        info!(
+            x = scale[0],
+            y = scale[1],
+            z = scale[2],
+            "Applying per-axis scale"
+        );
+        apply_unit_scaling(&mut meshes, scale);
+    }
 
-    // 4. True-north rotation
+    // 5. True-north rotation
     let true_north = ingestion
         .georeference
         .as_ref()
         .map(|g| g.true_north)
         .unwrap_or(0.0);
-    if true_north.abs() > f64::EPSILON {
+    let pivot = if true_north.abs() > f64::EPSILON {
         info!(degrees = true_north, "Applying true-north rotation");
-        apply_true_north_rotation(&mut meshes, true_north);
-    }
-
-    // 5. Center meshes (subtract centroid)
-    let centroid = center_meshes(&mut meshes);
-    info!(
-        cx = centroid[0],
-        cy = centroid[1],
-        cz = centroid[2],
-        "Centered meshes"
-    );
+        apply_true_north_rotation(&mut meshes, true_north)
+    } else {
+        [0.0; 3]
+    };
 
-    // 6. Compute bounding box
-    let bounds = compute_bounding_box(&meshes);
+    // 6. Center meshes (subtract centroid), unless the input coordinates
+    // are kept; the root transform then has no centroid to add back
+    let centroid = if config.recenter {
+        let centroid = center_meshes(&mut meshes);
+        info!(
+            cx = centroid[0],
+            cy = centroid[1],
+            cz = centroid[2],
+            "Centered meshes"
+        );
+        centroid
+    } else {
+        info!("Keeping original coordinates -- no centering");
+        [0.0; 3]
+    };
+    let mut meshes: Vec<IndexedMesh> = meshes.into_iter().map(WideMesh::into_mesh).collect();
+
+    // 7. Compute bounding box, padded so a point-like scene still has volume
+    let bounds = ensure_min_extent(compute_bounding_box(&meshes), MIN_HALF_EXTENT);
+
+    // 8. Return kept meshes to node-local space; their node matrix followed
+    // by this stage's own mapping becomes their tile transform
+    let mut tile_transforms = vec![None; meshes.len()];
+    let mut instances = ingestion.instances.clone();
+    instances.resize(meshes.len(), Vec::new());
+    let instanced = instances.iter().any(|m| !m.is_empty());
+    if ingestion.node_transforms.iter().any(Option::is_some) || instanced {
+        let stage = stage_matrix(config, units, true_north, pivot, centroid);
+        for (i, world) in ingestion.node_transforms.iter().enumerate() {
+            if let Some(world) = world {
+                meshes[i] = ingestion.meshes[i].clone();
+                tile_transforms[i] = Some((stage * DMat4::from_cols_array(world)).to_cols_array());
+            }
+        }
+        // Instances carry their own placement; the tile only adds the stage
+        for (i, _) in instances.iter().enumerate().filter(|(_, m)| !m.is_empty()) {
+            meshes[i] = ingestion.meshes[i].clone();
+            tile_transforms[i] = Some(stage.to_cols_array());
+        }
+        info!(
+            meshes = tile_transforms.iter().flatten().count(),
+            "Keeping node transforms as tile transforms"
+        );
+    }
 
-    // 7. Compute root transform
-    let root_transform = compute_root_transform(config, ingestion, centroid)?;
+    // 9. Compute root transform; a local ENU frame only records its origin
+    let placement = compute_placement(config, ingestion, centroid)?;
+    let (root_transform, ecef_origin) = match config.frame {
+        Frame::Ecef => (placement.root_transform(), None),
+        Frame::LocalEnu => (identity_transform(), local_enu_origin(config, &placement)?),
+    };
 
     Ok(TransformResult {
         meshes,
         materials,
         root_transform,
+        ecef_origin,
         bounds,
+        tile_transforms,
+        instances,
     })
 }
 
-/// Determine the 4×4 root transform based on georeferencing info.
-fn compute_root_transform(
+/// The affine map steps 2-6 of [`transform`] apply to every position, from
+/// input coordinates to the centered Z-up frame. The true-north rotation
+/// turns about `pivot`, the scene centroid it was applied around.
+fn stage_matrix(
+    config: &PipelineConfig,
+    units: Option<Units>,
+    true_north: f64,
+    pivot: [f64; 3],
+    centroid: [f64; 3],
+) -> DMat4 {
+    let units = units.map_or(1.0, unit_scale_factor);
+    let axes = match config.input_up {
+        // (x, y, z) -> (x, -z, y)
+        UpAxis::YUp => DMat4::from_cols(DVec4::X, DVec4::Z, DVec4::NEG_Y, DVec4::W),
+        UpAxis::ZUp => DMat4::IDENTITY,
+    };
+    let scale = DVec3::from_array(config.scale.unwrap_or([1.0; 3]));
+
+    let pivot = DVec3::from_array(pivot);
+    DMat4::from_translation(-DVec3::from_array(centroid))
+        * DMat4::from_translation(pivot)
+        * DMat4::from_rotation_z(true_north.to_radians())
+        * DMat4::from_translation(-pivot)
+        * DMat4::from_scale(scale)
+        * axes
+        * DMat4::from_scale(DVec3::splat(units))
+}
+
+/// Where the centered output frame sits on the globe.
+#[derive(Debug)]
+enum Placement {
+    /// No georeference: the model keeps its local coordinates.
+    Unplaced,
+    /// An east-north-up frame at `lon`/`lat`, with its origin at the ECEF
+    /// position `origin`.
+    Enu { lon: f64, lat: f64, origin: [f64; 3] },
+    /// A user-supplied root transform from `--transform-file`.
+    Matrix([f64; 16]),
+}
+
+impl Placement {
+    /// The 4×4 root transform mapping the output frame to ECEF.
+    fn root_transform(&self) -> [f64; 16] {
+        match *self {
+            Placement::Unplaced => identity_transform(),
+            Placement::Enu { lon, lat, origin } => {
+                build_root_transform(origin, enu_rotation_matrix(lon, lat))
+            }
+            Placement::Matrix(matrix) => matrix,
+        }
+    }
+}
+
+/// Determine the model's placement based on georeferencing info.
+fn compute_placement(
     config: &PipelineConfig,
     ingestion: &IngestionResult,
     centroid: [f64; 3],
-) -> Result<[f64; 16]> {
+) -> Result<Placement> {
+    if let Some(path) = &config.transform_file {
+        info!(path = %path.display(), "Using root transform from file");
+        let matrix = load_transform_file(path, config.transform_order)?;
+        return Ok(Placement::Matrix(restore_centroid(matrix, centroid)));
+    }
+
+    if let Some(origin) = config.origin {
+        info!(
+            lon = origin.lon,
+            lat = origin.lat,
+            height = origin.height,
+            "Placing model at cartographic origin"
+        );
+        return Ok(cartographic_placement(origin, centroid));
+    }
+
     // Merge georeference from ingestion detection and CLI config
     let georef = ingestion
         .georeference
@@ -91,15 +261,16 @@ fn compute_root_transform(
 
     let Some(geo) = georef else {
         info!("No georeference -- using identity transform");
-        return Ok(identity_transform());
+        return Ok(Placement::Unplaced);
     };
 
     if geo.epsg == 0 {
         info!("Georeference without EPSG -- using identity transform (local coordinates)");
-        return Ok(identity_transform());
+        return Ok(Placement::Unplaced);
     }
 
-    // Project the georeferenced offset (+ centroid) to WGS84
+    // Project the georeferenced offset (+ centroid) to WGS84. The centroid
+    // is in the Z-up frame, where X, Y and Z are east, north and up
     let origin_easting = geo.easting + centroid[0];
     let origin_northing = geo.northing + centroid[1];
     let origin_elevation = geo.elevation + centroid[2];
@@ -116,13 +287,118 @@ fn compute_root_transform(
 
     info!(lon, lat, "Projected to WGS84");
 
-    let ecef = geodetic_to_ecef(lon, lat, origin_elevation);
-    let enu = enu_rotation_matrix(lon, lat);
-    let rt = build_root_transform(ecef, enu);
+    let origin = geodetic_to_ecef(lon, lat, origin_elevation);
 
     info!("Computed ECEF root transform");
 
-    Ok(rt)
+    Ok(Placement::Enu { lon, lat, origin })
+}
+
+/// ECEF origin of the local east-north-up frame the model stays in with
+/// [`Frame::LocalEnu`], or `None` when it isn't placed on the globe.
+///
+/// A `--transform-file` matrix only qualifies if it places an unscaled ENU
+/// frame; anything else would be lost with the identity root transform.
+fn local_enu_origin(config: &PipelineConfig, placement: &Placement) -> Result<Option<[f64; 3]>> {
+    let origin = match placement {
+        Placement::Unplaced => return Ok(None),
+        Placement::Enu { origin, .. } => *origin,
+        Placement::Matrix(matrix) => enu_frame_origin(matrix).ok_or_else(|| {
+            let path = config.transform_file.as_deref().unwrap_or(Path::new(""));
+            PhotoTilerError::Transform(format!(
+                "{}: --frame local-enu needs a transform whose axes are east, north and up; \
+                 this one also rotates or scales the model",
+                path.display()
+            ))
+        })?,
+    };
+    info!(x = origin[0], y = origin[1], z = origin[2], "Writing a local ENU frame");
+    Ok(Some(origin))
+}
+
+/// The translation of `matrix` if its axes are the unscaled ENU axes at
+/// some longitude and latitude, as [`enu_rotation_matrix`] builds them.
+fn enu_frame_origin(matrix: &[f64; 16]) -> Option<[f64; 3]> {
+    // East is (-sin lon, cos lon, 0) and up's Z is sin lat
+    let lon = (-matrix[0]).atan2(matrix[1]).to_degrees();
+    let lat = matrix[10].clamp(-1.0, 1.0).asin().to_degrees();
+    let enu = enu_rotation_matrix(lon, lat);
+    let is_enu = (0..12).all(|i| (matrix[i] - enu[i]).abs() < 1e-6);
+    is_enu.then(|| [matrix[12], matrix[13], matrix[14]])
+}
+
+/// Read a 4x4 root transform from `path` as a column-major matrix.
+///
+/// The file holds exactly 16 finite numbers separated by whitespace or
+/// commas, in `order`. The matrix must be affine (bottom row `0 0 0 1`), as
+/// 3D Tiles requires; a mismatch usually means the wrong order was given.
+pub fn load_transform_file(path: &Path, order: MatrixOrder) -> Result<[f64; 16]> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        PhotoTilerError::Transform(format!("Failed to read {}: {e}", path.display()))
+    })?;
+
+    let values = content
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            token
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| {
+                    PhotoTilerError::Transform(format!(
+                        "{}: expected a finite number, got {token:?}",
+                        path.display()
+                    ))
+                })
+        })
+        .collect::<Result<Vec<f64>>>()?;
+
+    let Ok(values) = <[f64; 16]>::try_from(values.as_slice()) else {
+        return Err(PhotoTilerError::Transform(format!(
+            "{}: expected 16 numbers, found {}",
+            path.display(),
+            values.len()
+        )));
+    };
+
+    let matrix = match order {
+        MatrixOrder::Column => values,
+        MatrixOrder::Row => std::array::from_fn(|i| values[(i % 4) * 4 + i / 4]),
+    };
+    if [matrix[3], matrix[7], matrix[11], matrix[15]] != [0.0, 0.0, 0.0, 1.0] {
+        return Err(PhotoTilerError::Transform(format!(
+            "{}: bottom row must be 0 0 0 1 for a {order}-major matrix",
+            path.display()
+        )));
+    }
+
+    Ok(matrix)
+}
+
+/// Placement of the model's (pre-centering) origin at `origin`, with its
+/// axes aligned to the local ENU frame.
+fn cartographic_placement(origin: CartographicOrigin, centroid: [f64; 3]) -> Placement {
+    let ecef = geodetic_to_ecef(origin.lon, origin.lat, origin.height);
+    let enu = enu_rotation_matrix(origin.lon, origin.lat);
+    let placed = restore_centroid(build_root_transform(ecef, enu), centroid);
+    Placement::Enu {
+        lon: origin.lon,
+        lat: origin.lat,
+        origin: [placed[12], placed[13], placed[14]],
+    }
+}
+
+/// Compose a column-major transform meant for the model's pre-centering
+/// coordinates with a translation by the removed `centroid`, so it can be
+/// applied to the centered meshes.
+fn restore_centroid(mut matrix: [f64; 16], centroid: [f64; 3]) -> [f64; 16] {
+    for row in 0..3 {
+        matrix[12 + row] += (0..3)
+            .map(|axis| matrix[axis * 4 + row] * centroid[axis])
+            .sum::<f64>();
+    }
+    matrix
 }
 
 #[cfg(test)]
@@ -134,8 +410,11 @@ mod tests {
     fn mock_ingestion(meshes: Vec<IndexedMesh>, georef: Option<Georeference>) -> IngestionResult {
         IngestionResult {
             meshes,
+            node_transforms: Vec::new(),
+            instances: Vec::new(),
             materials: MaterialLibrary::default(),
             georeference: georef,
+            units: None,
             stats: IngestionStats {
                 total_vertices: 0,
                 total_triangles: 0,
@@ -146,7 +425,11 @@ mod tests {
                 texture_count: 0,
                 material_count: 0,
                 input_format: "test".into(),
+                dropped_triangles: 0,
+                missing_textures: 0,
+                topology: None,
             },
+            source_files: Vec::new(),
         }
     }
 
@@ -183,14 +466,14 @@ mod tests {
         let result = transform(&config, &ingestion).unwrap();
         // 1000mm = 1m, then axis swap, then centering (single vertex → stays at 0)
         // After scaling: (1.0, 0.0, 0.0)
-        // After Y-up→Z-up: (1.0, 0.0, 0.0) → (1.0, 0.0, -0.0)
+        // After Y-up→Z-up: (1.0, 0.0, 0.0) → (1.0, -0.0, 0.0)
         // After centering single vertex: all zero
         assert!(result.meshes[0].positions[0].abs() < 1e-3);
     }
 
     #[test]
     fn transform_axis_swap_applied() {
-        // Y-up triangle: vertex at (1, 2, 3) should become (1, 3, -2) in Z-up
+        // Y-up triangle: vertex at (1, 2, 3) should become (1, -3, 2) in Z-up
         let meshes = vec![IndexedMesh {
             positions: vec![1.0, 2.0, 3.0, 1.0, 2.0, 3.0, 1.0, 2.0, 3.0],
             indices: vec![0, 1, 2],
@@ -200,8 +483,8 @@ mod tests {
         let config = simple_config();
         let result = transform(&config, &ingestion).unwrap();
 
-        // After axis swap: all vertices are (1, 3, -2)
-        // After centering: centroid = (1, 3, -2), so all become (0, 0, 0)
+        // After axis swap: all vertices are (1, -3, 2)
+        // After centering: centroid = (1, -3, 2), so all become (0, 0, 0)
         for p in result.meshes[0].positions.chunks_exact(3) {
             assert!(p[0].abs() < 1e-5);
             assert!(p[1].abs() < 1e-5);
@@ -209,6 +492,194 @@ mod tests {
         }
     }
 
+    #[test]
+    fn single_vertex_scene_has_volume_and_tiles() {
+        use crate::tiling::{lod, tileset_writer};
+
+        let meshes = vec![IndexedMesh {
+            positions: vec![4.0, 5.0, 6.0],
+            indices: vec![0, 0, 0],
+            ..Default::default()
+        }];
+        let ingestion = mock_ingestion(meshes, None);
+        let config = simple_config();
+        let result = transform(&config, &ingestion).unwrap();
+
+        for half in result.bounds.half_extents() {
+            assert!(half >= MIN_HALF_EXTENT);
+        }
+        assert!(result.bounds.diagonal() > 0.0);
+
+        let tmp = tempfile::tempdir().unwrap();
+        let chains = result
+            .meshes
+            .into_iter()
+            .map(|mesh| lod::generate_lod_chain(mesh, &result.bounds, 1, &config.tiling))
+            .collect();
+        let mut output = tileset_writer::build_tileset(
+            chains,
+            &result.bounds,
+            &config.tiling,
+            &result.materials,
+            &config.texture,
+            tmp.path(),
+        );
+        tileset_writer::write_tileset(&mut output, &result.root_transform, tmp.path()).unwrap();
+        assert!(output.root.geometric_error.is_finite());
+    }
+
+    #[test]
+    fn cartographic_origin_places_model_in_ecef() {
+        // Two Z-up vertices whose centroid sits 10 m east of the model origin
+        let meshes = vec![IndexedMesh {
+            positions: vec![9.0, 0.0, 0.0, 11.0, 0.0, 0.0],
+            ..Default::default()
+        }];
+        let ingestion = mock_ingestion(meshes, None);
+        let config = PipelineConfig {
+            input_up: UpAxis::ZUp,
+            origin: Some(CartographicOrigin {
+                lon: 90.0,
+                lat: 0.0,
+                height: 100.0,
+            }),
+            ..simple_config()
+        };
+        let rt = transform(&config, &ingestion).unwrap().root_transform;
+
+        // On the equator at 90° E, up is +Y and east is -X in ECEF
+        let expected = [-10.0, 6_378_137.0 + 100.0, 0.0];
+        for (axis, value) in expected.into_iter().enumerate() {
+            assert!((rt[12 + axis] - value).abs() < 1e-6, "{rt:?}");
+        }
+        assert_eq!(&rt[..12], &enu_rotation_matrix(90.0, 0.0)[..12]);
+    }
+
+    #[test]
+    fn transform_file_accepts_either_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transform.txt");
+
+        fs::write(&path, "1 0 0 10\n0 1 0 20\n0 0 1 30\n0 0 0 1\n").unwrap();
+        let m = load_transform_file(&path, MatrixOrder::Row).unwrap();
+        assert_eq!(&m[12..], &[10.0, 20.0, 30.0, 1.0]);
+
+        fs::write(&path, "1,0,0,0, 0,1,0,0, 0,0,1,0, 10,20,30,1").unwrap();
+        assert_eq!(load_transform_file(&path, MatrixOrder::Column).unwrap(), m);
+        // Read in the wrong order, the translation lands in the bottom row
+        assert!(load_transform_file(&path, MatrixOrder::Row).is_err());
+    }
+
+    #[test]
+    fn transform_file_rejects_malformed_matrices() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transform.txt");
+        let load = |content: &str| {
+            fs::write(&path, content).unwrap();
+            load_transform_file(&path, MatrixOrder::Column)
+                .unwrap_err()
+                .to_string()
+        };
+
+        let identity = "1 0 0 0 0 1 0 0 0 0 1 0 0 0 0 1";
+        assert!(load(&identity[..identity.len() - 2]).contains("found 15"));
+        assert!(load(&format!("{identity} 1")).contains("found 17"));
+        assert!(load(&identity.replacen('1', "NaN", 1)).contains("finite"));
+        assert!(load(&identity.replacen('0', "x", 1)).contains("\"x\""));
+        assert!(load_transform_file(&dir.path().join("missing"), MatrixOrder::Column).is_err());
+    }
+
+    #[test]
+    fn input_up_axis_controls_swap() {
+        // Two vertices so centering leaves the axis step visible
+        let meshes = vec![IndexedMesh {
+            positions: vec![1.0, 2.0, 3.0, -1.0, -2.0, -3.0],
+            normals: vec![0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+            ..Default::default()
+        }];
+        let ingestion = mock_ingestion(meshes, None);
+
+        let y_up = transform(&simple_config(), &ingestion).unwrap();
+        assert_eq!(&y_up.meshes[0].positions[..3], &[1.0, -3.0, 2.0]);
+        assert_eq!(&y_up.meshes[0].normals[..3], &[0.0, 0.0, 1.0]);
+
+        let config = PipelineConfig {
+            input_up: UpAxis::ZUp,
+            ..simple_config()
+        };
+        let z_up = transform(&config, &ingestion).unwrap();
+        assert_eq!(z_up.meshes[0].positions, ingestion.meshes[0].positions);
+        assert_eq!(z_up.meshes[0].normals, ingestion.meshes[0].normals);
+        assert!((z_up.bounds.max[2] - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn true_north_applies_after_either_axis_convention() {
+        let meshes = vec![IndexedMesh {
+            positions: vec![1.0, 0.0, 0.0, -1.0, 0.0, 0.0],
+            ..Default::default()
+        }];
+        let georef = Georeference {
+            true_north: 90.0,
+            ..Default::default()
+        };
+        let ingestion = mock_ingestion(meshes, Some(georef));
+
+        for input_up in [UpAxis::YUp, UpAxis::ZUp] {
+            let config = PipelineConfig {
+                input_up,
+                ..simple_config()
+            };
+            let result = transform(&config, &ingestion).unwrap();
+            // +X rotates onto +Y about the vertical axis in both modes
+            let p = &result.meshes[0].positions;
+            assert!(p[0].abs() < 1e-5, "{input_up}: {p:?}");
+            assert!((p[1] - 1.0).abs() < 1e-5, "{input_up}: {p:?}");
+        }
+    }
+
+    #[test]
+    fn utm_scale_positions_keep_sub_millimetre_precision() {
+        // A small patch of a UTM scene: eastings near 500 km, northings near 2800 km
+        let positions: Vec<f32> = (0..16)
+            .flat_map(|i| {
+                let (dx, dy) = ((i % 4) as f32 * 3.7, (i / 4) as f32 * 2.9);
+                [500_000.0 + dx, 41.0 + 0.1 * dx, -2_800_000.0 - dy]
+            })
+            .collect();
+        let meshes = vec![IndexedMesh {
+            positions: positions.clone(),
+            ..Default::default()
+        }];
+        let georef = Georeference {
+            true_north: 1.5,
+            ..Default::default()
+        };
+        let ingestion = mock_ingestion(meshes, Some(georef));
+        let result = transform(&simple_config(), &ingestion).unwrap();
+
+        // f64 reference: axis swap, rotation, then centering
+        let (sin_a, cos_a) = 1.5_f64.to_radians().sin_cos();
+        let reference: Vec<[f64; 3]> = positions
+            .chunks_exact(3)
+            .map(|p| {
+                let [x, y, z] = [p[0] as f64, -(p[2] as f64), p[1] as f64];
+                [x * cos_a - y * sin_a, x * sin_a + y * cos_a, z]
+            })
+            .collect();
+        let n = reference.len() as f64;
+        let centroid: Vec<f64> = (0..3)
+            .map(|axis| reference.iter().map(|p| p[axis]).sum::<f64>() / n)
+            .collect();
+
+        for (out, expected) in result.meshes[0].positions.chunks_exact(3).zip(&reference) {
+            for axis in 0..3 {
+                let error = (out[axis] as f64 - (expected[axis] - centroid[axis])).abs();
+                assert!(error < 1e-4, "axis {axis} off by {error} m");
+            }
+        }
+    }
+
     #[test]
     fn transform_georef_without_epsg_identity() {
         let meshes = vec![IndexedMesh {
@@ -261,6 +732,218 @@ mod tests {
         assert!(tz.abs() < 10_000.0);
     }
 
+    #[test]
+    fn local_enu_frame_records_the_ecef_origin() {
+        let meshes = vec![IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 4.0, 2.0, 0.0],
+            ..Default::default()
+        }];
+        let georef = Georeference {
+            epsg: 32636,
+            easting: 500_000.0,
+            northing: 0.0,
+            elevation: 0.0,
+            true_north: 0.0,
+        };
+        let ingestion = mock_ingestion(meshes, Some(georef));
+        let ecef = transform(&simple_config(), &ingestion).unwrap();
+        let config = PipelineConfig {
+            frame: Frame::LocalEnu,
+            ..simple_config()
+        };
+        let local = transform(&config, &ingestion).unwrap();
+
+        // ECEF bakes the translation; local ENU keeps it out of the transform
+        assert!(ecef.ecef_origin.is_none());
+        assert!(ecef.root_transform[12] > 5_000_000.0);
+        assert_eq!(local.root_transform, ecef::identity_transform());
+        assert_eq!(local.ecef_origin, Some([12, 13, 14].map(|i| ecef.root_transform[i])));
+        assert_eq!(local.meshes[0].positions, ecef.meshes[0].positions);
+        assert!(local.meshes[0].positions.iter().all(|c| c.abs() < 10.0));
+
+        // Without a placement there is no origin to record
+        let ingestion = mock_ingestion(local.meshes, None);
+        assert!(transform(&config, &ingestion).unwrap().ecef_origin.is_none());
+    }
+
+    #[test]
+    fn local_enu_frame_follows_origin_and_transform_file() {
+        // Centered on the model origin, so no centroid offset is restored
+        let meshes = vec![IndexedMesh {
+            positions: vec![-1.0, -2.0, 0.0, 1.0, 2.0, 0.0],
+            ..Default::default()
+        }];
+        let ingestion = mock_ingestion(meshes, None);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transform.txt");
+        let config = PipelineConfig {
+            input_up: UpAxis::ZUp,
+            frame: Frame::LocalEnu,
+            origin: Some(CartographicOrigin {
+                lon: 30.0,
+                lat: 45.0,
+                height: 10.0,
+            }),
+            ..simple_config()
+        };
+        let ecef = geodetic_to_ecef(30.0, 45.0, 10.0);
+        let local = transform(&config, &ingestion).unwrap();
+        assert_eq!(local.root_transform, identity_transform());
+        assert_eq!(local.ecef_origin, Some(ecef));
+
+        // A transform file placing the same ENU frame records the same origin
+        let placed = build_root_transform(ecef, enu_rotation_matrix(30.0, 45.0));
+        let write = |matrix: [f64; 16]| {
+            let numbers: Vec<String> = matrix.iter().map(f64::to_string).collect();
+            fs::write(&path, numbers.join(" ")).unwrap();
+        };
+        write(placed);
+        let config = PipelineConfig {
+            origin: None,
+            transform_file: Some(path.clone()),
+            ..config
+        };
+        let local = transform(&config, &ingestion).unwrap();
+        assert_eq!(local.ecef_origin, Some(ecef));
+
+        // One that also scales the model can't be reduced to an origin
+        let mut scaled = placed;
+        scaled[..3].iter_mut().for_each(|v| *v *= 2.0);
+        write(scaled);
+        let err = transform(&config, &ingestion).unwrap_err().to_string();
+        assert!(err.contains("--frame local-enu"), "{err}");
+        assert!(transform(&PipelineConfig { frame: Frame::Ecef, ..config }, &ingestion).is_ok());
+    }
+
+    #[test]
+    fn up_offset_raises_the_georeferenced_height() {
+        let georef = Georeference {
+            epsg: 32636,
+            easting: 500_000.0,
+            northing: 3_650_000.0,
+            elevation: 10.0,
+            true_north: 0.0,
+        };
+        let root_transform = |offset: [f32; 3]| {
+            let meshes = vec![IndexedMesh {
+                positions: vec![offset[0], offset[1], offset[2]],
+                ..Default::default()
+            }];
+            let ingestion = mock_ingestion(meshes, Some(georef.clone()));
+            transform(&simple_config(), &ingestion).unwrap().root_transform
+        };
+
+        // Shift of the root origin along the base frame's east, north and up
+        let base = root_transform([0.0; 3]);
+        let enu_shift = |m: [f64; 16]| -> [f64; 3] {
+            let delta: [f64; 3] = std::array::from_fn(|i| m[12 + i] - base[12 + i]);
+            std::array::from_fn(|axis| (0..3).map(|i| base[axis * 4 + i] * delta[i]).sum())
+        };
+
+        // +Y is up in a Y-up input: only the ellipsoidal height grows
+        let [east, north, up] = enu_shift(root_transform([0.0, 100.0, 0.0]));
+        assert!(east.abs() < 0.01 && north.abs() < 0.01, "{east}, {north}");
+        assert!((up - 100.0).abs() < 0.01, "{up}");
+
+        // -Z is forward, which maps to north (100 m of grid distance, give
+        // or take the UTM scale factor)
+        let [east, north, up] = enu_shift(root_transform([0.0, 0.0, -100.0]));
+        assert!(east.abs() < 0.01 && up.abs() < 0.01, "{east}, {up}");
+        assert!((north - 100.0).abs() < 0.1, "{north}");
+    }
+
+    #[test]
+    fn recenter_off_keeps_input_coordinates() {
+        let meshes = vec![IndexedMesh {
+            positions: vec![100.0, 20.0, -300.0, 102.0, 24.0, -306.0],
+            ..Default::default()
+        }];
+        let georef = Georeference {
+            epsg: 32636,
+            easting: 500_000.0,
+            northing: 3_650_000.0,
+            elevation: 10.0,
+            true_north: 0.0,
+        };
+        let ingestion = mock_ingestion(meshes, Some(georef));
+        let centered = transform(&simple_config(), &ingestion).unwrap();
+        let config = PipelineConfig {
+            recenter: false,
+            ..simple_config()
+        };
+        let kept = transform(&config, &ingestion).unwrap();
+
+        // Only the axis swap applies: (x, y, z) -> (x, -z, y)
+        assert_eq!(
+            kept.meshes[0].positions,
+            [100.0, 300.0, 20.0, 102.0, 306.0, 24.0]
+        );
+        assert_eq!(kept.bounds.min, [100.0, 300.0, 20.0]);
+        assert_eq!(kept.bounds.max, [102.0, 306.0, 24.0]);
+
+        // Both place the model at about the same spot: the centroid moves
+        // from the vertices into the root transform, whose tangent frame
+        // now sits at the georeferenced origin instead (off by the UTM
+        // scale factor over the 300 m between them)
+        let origin = |m: &[f64; 16], p: &[f32]| -> [f64; 3] {
+            std::array::from_fn(|r| {
+                (0..3).map(|c| m[c * 4 + r] * p[c] as f64).sum::<f64>() + m[12 + r]
+            })
+        };
+        let a = origin(&centered.root_transform, &centered.meshes[0].positions[..3]);
+        let b = origin(&kept.root_transform, &kept.meshes[0].positions[..3]);
+        for axis in 0..3 {
+            assert!((a[axis] - b[axis]).abs() < 0.5, "{a:?} vs {b:?}");
+        }
+    }
+
+    #[test]
+    fn node_transforms_match_baked_geometry_under_true_north() {
+        let local = IndexedMesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0, 1.0],
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        };
+        let other = IndexedMesh {
+            positions: vec![50.0, 3.0, -7.0, 52.0, 3.0, -7.0, 50.0, 4.0, -9.0],
+            indices: vec![0, 1, 2],
+            ..Default::default()
+        };
+        let world = DMat4::from_translation(DVec3::new(20.0, 1.0, -4.0));
+        let georef = Georeference {
+            true_north: 30.0,
+            ..Default::default()
+        };
+
+        // The same scene with the node matrix kept, and baked in
+        let mut kept = mock_ingestion(vec![other.clone(), local.clone()], Some(georef.clone()));
+        kept.node_transforms = vec![None, Some(world.to_cols_array())];
+        let mut baked_mesh = local.clone();
+        for p in baked_mesh.positions.chunks_exact_mut(3) {
+            let w = world.transform_point3(DVec3::new(p[0] as f64, p[1] as f64, p[2] as f64));
+            p.copy_from_slice(&w.as_vec3().to_array());
+        }
+        let baked = mock_ingestion(vec![other, baked_mesh], Some(georef));
+
+        for recenter in [true, false] {
+            let config = PipelineConfig {
+                recenter,
+                ..simple_config()
+            };
+            let kept = transform(&config, &kept).unwrap();
+            let baked = transform(&config, &baked).unwrap();
+
+            let tile = DMat4::from_cols_array(&kept.tile_transforms[1].unwrap());
+            let placed = kept.meshes[1].positions.chunks_exact(3).map(|p| {
+                tile.transform_point3(DVec3::new(p[0] as f64, p[1] as f64, p[2] as f64))
+            });
+            for (p, q) in placed.zip(baked.meshes[1].positions.chunks_exact(3)) {
+                let q = DVec3::new(q[0] as f64, q[1] as f64, q[2] as f64);
+                assert!(p.distance(q) < 1e-4, "recenter {recenter}: {p} vs {q}");
+            }
+        }
+    }
+
     #[test]
     fn transform_bounding_box_computed() {
         let meshes = vec![IndexedMesh {
@@ -1,23 +1,44 @@
 use crate::error::{PhotoTilerError, Result};
 
+/// The CRS codes [`project_to_wgs84`] handles, for error messages.
+const SUPPORTED_EPSG: &str = "2D projected or geographic CRS codes known to PROJ \
+     (UTM 326xx/327xx, national grids, state plane, 3857, 4326, ...)";
+
 /// Project an (easting, northing) pair from the given EPSG CRS to WGS84.
 ///
-/// Returns `(longitude, latitude)` in degrees.
+/// Any CRS in the PROJ database works, including southern-hemisphere UTM
+/// (false northing 10,000,000 m), state plane and Web Mercator; coordinates
+/// are in the CRS's own linear unit. Returns `(longitude, latitude)` in
+/// degrees.
 pub fn project_to_wgs84(epsg: u32, easting: f64, northing: f64) -> Result<(f64, f64)> {
-    let from = format!("EPSG:{epsg}");
-    let proj = proj::Proj::new_known_crs(&from, "EPSG:4326", None).map_err(|e| {
-        PhotoTilerError::Transform(format!(
-            "Failed to create projection from {from} to WGS84: {e}"
-        ))
-    })?;
+    let proj = projection_from(epsg)?;
 
     let (lon, lat) = proj
         .convert((easting, northing))
         .map_err(|e| PhotoTilerError::Transform(format!("Projection failed: {e}")))?;
 
+    // Coordinates outside the CRS's domain come back as inf/NaN or off the
+    // globe rather than as an error; never place the tileset there
+    if !lon.is_finite() || !lat.is_finite() || lat.abs() > 90.0 {
+        return Err(PhotoTilerError::Georeference(format!(
+            "EPSG {epsg} coordinates ({easting}, {northing}) do not map to a WGS84 position; \
+             check the EPSG code and the offset"
+        )));
+    }
+
     Ok((lon, lat))
 }
 
+/// Build the projection from `epsg` to WGS84, rejecting codes PROJ cannot
+/// project from before any coordinates are converted.
+fn projection_from(epsg: u32) -> Result<proj::Proj> {
+    proj::Proj::new_known_crs(&format!("EPSG:{epsg}"), "EPSG:4326", None).map_err(|e| {
+        PhotoTilerError::Georeference(format!(
+            "EPSG {epsg} not supported; supported: {SUPPORTED_EPSG} ({e})"
+        ))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -45,9 +66,45 @@ mod tests {
         assert!(lat > 30.0 && lat < 35.0, "latitude {lat} out of range");
     }
 
+    #[test]
+    fn utm_zone_18s_control_point() {
+        // EPSG:32718 = UTM zone 18S (central meridian 75°W). Lima, Peru,
+        // forward-projected with the Krüger series
+        let (lon, lat) = project_to_wgs84(32718, 277_617.453, 8_667_487.897).unwrap();
+        assert!((lon - -77.0428).abs() < 1e-6, "longitude {lon}");
+        assert!((lat - -12.0464).abs() < 1e-6, "latitude {lat}");
+
+        // The equator on the central meridian sits at the false northing
+        let (lon, lat) = project_to_wgs84(32718, 500_000.0, 10_000_000.0).unwrap();
+        assert!((lon - -75.0).abs() < 1e-9);
+        assert!(lat.abs() < 1e-9);
+    }
+
+    #[test]
+    fn web_mercator_control_point() {
+        // EPSG:3857, Sydney, Australia
+        let (lon, lat) = project_to_wgs84(3857, 16_832_542.279, -4_011_198.647).unwrap();
+        assert!((lon - 151.2093).abs() < 1e-6, "longitude {lon}");
+        assert!((lat - -33.8688).abs() < 1e-6, "latitude {lat}");
+    }
+
     #[test]
     fn invalid_epsg_returns_error() {
         let result = project_to_wgs84(99999, 0.0, 0.0);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn unsupported_epsg_error_lists_supported_codes() {
+        let err = project_to_wgs84(99999, 0.0, 0.0).unwrap_err();
+        assert!(matches!(err, PhotoTilerError::Georeference(_)));
+        let message = err.to_string();
+        assert!(message.contains("EPSG 99999 not supported"), "{message}");
+        assert!(message.contains("327xx"), "{message}");
+    }
+
+    #[test]
+    fn non_finite_result_is_rejected() {
+        assert!(project_to_wgs84(32636, f64::NAN, 0.0).is_err());
+    }
 }
@@ -1,3 +1,17 @@
+use std::path::PathBuf;
+
+/// How texture coordinates outside [0, 1] are resolved (glTF sampler wrap).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WrapMode {
+    /// Tile the texture (glTF and OBJ default).
+    #[default]
+    Repeat,
+    /// Tile the texture, mirroring every other repeat.
+    MirroredRepeat,
+    /// Stretch the edge texels.
+    ClampToEdge,
+}
+
 /// Raw texture image data.
 #[derive(Debug, Clone)]
 pub struct TextureData {
@@ -5,6 +19,27 @@ pub struct TextureData {
     pub mime_type: String,
     pub width: u32,
     pub height: u32,
+    /// Wrap modes `[s, t]` the texture is sampled with.
+    pub wrap: [WrapMode; 2],
+}
+
+impl TextureData {
+    /// Whether either axis tiles the texture rather than clamping.
+    pub fn repeats(&self) -> bool {
+        self.wrap.iter().any(|&w| w != WrapMode::ClampToEdge)
+    }
+}
+
+/// How a material's alpha channel is interpreted (glTF `alphaMode`).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AlphaMode {
+    /// Alpha is ignored.
+    #[default]
+    Opaque,
+    /// Fully opaque at or above `cutoff`, fully transparent below.
+    Mask { cutoff: f32 },
+    /// Alpha blends with what is behind the surface.
+    Blend,
 }
 
 /// PBR metallic-roughness material.
@@ -17,6 +52,17 @@ pub struct PBRMaterial {
     pub roughness: f32,
     /// Index into `MaterialLibrary::textures`.
     pub base_color_texture: Option<usize>,
+    /// Index into `MaterialLibrary::textures` of the glTF metallic-roughness
+    /// map (roughness in G, metalness in B).
+    pub metallic_roughness_texture: Option<usize>,
+    /// Index into `MaterialLibrary::textures` of a tangent-space normal map.
+    pub normal_texture: Option<usize>,
+    /// Scale applied to the normal map's X and Y components.
+    pub normal_scale: f32,
+    pub alpha_mode: AlphaMode,
+    /// Linear emitted radiance: the glTF emissive factor multiplied by
+    /// `KHR_materials_emissive_strength`, so components may exceed 1.
+    pub emissive: [f32; 3],
 }
 
 impl Default for PBRMaterial {
@@ -27,6 +73,11 @@ impl Default for PBRMaterial {
             metallic: 0.0,
             roughness: 1.0,
             base_color_texture: None,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            alpha_mode: AlphaMode::Opaque,
+            emissive: [0.0; 3],
         }
     }
 }
@@ -36,6 +87,15 @@ impl Default for PBRMaterial {
 pub struct MaterialLibrary {
     pub materials: Vec<PBRMaterial>,
     pub textures: Vec<TextureData>,
+    /// Source object names that `IndexedMesh::feature_ids` index into;
+    /// empty unless feature IDs are written
+    pub features: Vec<String>,
+    /// Texture files referenced by the input that could not be found or
+    /// read; the materials using them are untextured
+    pub missing_textures: Vec<String>,
+    /// Files the loader read besides the input itself (material libraries,
+    /// textures, external buffers), or looked for and did not find
+    pub source_files: Vec<PathBuf>,
 }
 
 #[cfg(test)]
@@ -50,6 +110,11 @@ mod tests {
         assert_eq!(mat.metallic, 0.0);
         assert_eq!(mat.roughness, 1.0);
         assert_eq!(mat.base_color_texture, None);
+        assert_eq!(mat.metallic_roughness_texture, None);
+        assert_eq!(mat.normal_texture, None);
+        assert_eq!(mat.normal_scale, 1.0);
+        assert_eq!(mat.alpha_mode, AlphaMode::Opaque);
+        assert_eq!(mat.emissive, [0.0; 3]);
     }
 
     #[test]
@@ -63,6 +128,7 @@ mod tests {
             mime_type: "image/png".into(),
             width: 1,
             height: 1,
+            wrap: [WrapMode::Repeat; 2],
         });
 
         lib.materials.push(PBRMaterial {
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 /// The fundamental geometry container.
 ///
 /// All buffers are contiguous `Vec<f32>` / `Vec<u32>` for zero-copy interop
@@ -10,12 +12,31 @@ pub struct IndexedMesh {
     pub normals: Vec<f32>,
     /// Interleaved UVs: [u, v, u, v, ...] or empty
     pub uvs: Vec<f32>,
+    /// Second UV set (TEXCOORD_1, e.g. lightmaps): [u, v, ...] or empty
+    pub uvs1: Vec<f32>,
     /// Interleaved vertex colors: [r, g, b, a, ...] or empty
     pub colors: Vec<f32>,
+    /// Per-vertex feature ID (`_FEATURE_ID_0`, indexing
+    /// `MaterialLibrary::features`) or empty
+    pub feature_ids: Vec<u32>,
     /// Triangle indices into the vertex buffers
     pub indices: Vec<u32>,
     /// Index into the associated `MaterialLibrary`
     pub material_index: Option<usize>,
+    /// Source object/group name (e.g. OBJ `o`/`g`), if any
+    pub name: Option<String>,
+    /// Index ranges drawn with their own material; empty when every
+    /// triangle uses `material_index`
+    pub material_groups: Vec<MaterialGroup>,
+}
+
+/// A contiguous run of triangles that share one material.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaterialGroup {
+    /// Range into `IndexedMesh::indices`, aligned to whole triangles
+    pub indices: Range<usize>,
+    /// Index into the associated `MaterialLibrary`
+    pub material_index: Option<usize>,
 }
 
 impl IndexedMesh {
@@ -39,15 +60,73 @@ impl IndexedMesh {
         !self.uvs.is_empty()
     }
 
+    /// Whether a second UV set is present.
+    pub fn has_uvs1(&self) -> bool {
+        !self.uvs1.is_empty()
+    }
+
     /// Whether vertex colors are present.
     pub fn has_colors(&self) -> bool {
         !self.colors.is_empty()
     }
 
+    /// Whether per-vertex feature IDs are present.
+    pub fn has_feature_ids(&self) -> bool {
+        !self.feature_ids.is_empty()
+    }
+
     /// Whether the mesh contains no geometry.
     pub fn is_empty(&self) -> bool {
         self.positions.is_empty()
     }
+
+    /// Material groups covering the whole index buffer.
+    ///
+    /// Meshes without explicit groups yield a single group spanning all
+    /// indices with `material_index`.
+    pub fn material_ranges(&self) -> Vec<MaterialGroup> {
+        if self.material_groups.is_empty() {
+            return vec![MaterialGroup {
+                indices: 0..self.indices.len(),
+                material_index: self.material_index,
+            }];
+        }
+        self.material_groups.clone()
+    }
+
+    /// Material of each triangle, following `material_ranges`.
+    pub fn triangle_materials(&self) -> Vec<Option<usize>> {
+        let mut materials = vec![self.material_index; self.triangle_count()];
+        for group in &self.material_groups {
+            materials[group.indices.start / 3..group.indices.end / 3].fill(group.material_index);
+        }
+        materials
+    }
+
+    /// Set `material_index` and `material_groups` from the material of each
+    /// triangle. Runs of triangles with the same material share a group; a
+    /// single run leaves `material_groups` empty.
+    pub fn set_triangle_materials(&mut self, materials: &[Option<usize>]) {
+        let mut groups: Vec<MaterialGroup> = Vec::new();
+        for (tri, &material_index) in materials.iter().enumerate() {
+            match groups.last_mut() {
+                Some(group) if group.material_index == material_index => {
+                    group.indices.end = (tri + 1) * 3;
+                }
+                _ => groups.push(MaterialGroup {
+                    indices: tri * 3..(tri + 1) * 3,
+                    material_index,
+                }),
+            }
+        }
+        if let Some(first) = groups.first() {
+            self.material_index = first.material_index;
+        }
+        if groups.len() <= 1 {
+            groups.clear();
+        }
+        self.material_groups = groups;
+    }
 }
 
 #[cfg(test)]
@@ -64,6 +143,39 @@ mod tests {
         assert!(!mesh.has_uvs());
         assert!(!mesh.has_colors());
         assert_eq!(mesh.material_index, None);
+        assert_eq!(mesh.name, None);
+    }
+
+    #[test]
+    fn triangle_materials_roundtrip_through_groups() {
+        let mut mesh = IndexedMesh {
+            positions: vec![0.0; 9],
+            indices: vec![0, 1, 2, 0, 1, 2, 0, 1, 2, 0, 1, 2],
+            material_index: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(mesh.triangle_materials(), vec![Some(7); 4]);
+
+        let materials = [Some(1), Some(1), None, Some(2)];
+        mesh.set_triangle_materials(&materials);
+        assert_eq!(mesh.material_index, Some(1));
+        let group = |indices, material_index| MaterialGroup {
+            indices,
+            material_index,
+        };
+        assert_eq!(
+            mesh.material_groups,
+            vec![
+                group(0..6, Some(1)),
+                group(6..9, None),
+                group(9..12, Some(2)),
+            ]
+        );
+        assert_eq!(mesh.triangle_materials(), materials);
+
+        mesh.set_triangle_materials(&[Some(3); 4]);
+        assert_eq!(mesh.material_index, Some(3));
+        assert!(mesh.material_groups.is_empty());
     }
 
     #[test]
@@ -72,9 +184,13 @@ mod tests {
             positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
             normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
             uvs: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            uvs1: Vec::new(),
             colors: vec![],
+            feature_ids: Vec::new(),
             indices: vec![0, 1, 2],
             material_index: Some(0),
+            name: None,
+            material_groups: Vec::new(),
         };
 
         assert!(!mesh.is_empty());
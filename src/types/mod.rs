@@ -2,6 +2,6 @@ pub mod material;
 pub mod mesh;
 pub mod tile;
 
-pub use material::{MaterialLibrary, PBRMaterial, TextureData};
-pub use mesh::IndexedMesh;
-pub use tile::{BoundingBox, TileContent, TileNode};
+pub use material::{AlphaMode, MaterialLibrary, PBRMaterial, TextureData, WrapMode};
+pub use mesh::{IndexedMesh, MaterialGroup};
+pub use tile::{BoundingBox, OrientedBox, TileContent, TileNode};
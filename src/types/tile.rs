@@ -1,5 +1,5 @@
 /// Axis-aligned bounding box in 3-D.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
 pub struct BoundingBox {
     pub min: [f64; 3],
     pub max: [f64; 3],
@@ -24,6 +24,12 @@ impl BoundingBox {
         ]
     }
 
+    /// Enclosed volume.
+    pub fn volume(&self) -> f64 {
+        let he = self.half_extents();
+        8.0 * he[0] * he[1] * he[2]
+    }
+
     /// Length of the space diagonal.
     pub fn diagonal(&self) -> f64 {
         let dx = self.max[0] - self.min[0];
@@ -57,13 +63,166 @@ impl BoundingBox {
             ],
         }
     }
+
+    /// Expand the first `axes` axes to the longest of them around the same
+    /// centre: a cube for 3, a square footprint keeping Z for 2.
+    pub fn cubed(&self, axes: usize) -> BoundingBox {
+        let he = self.half_extents();
+        let half = he[..axes].iter().copied().fold(0.0, f64::max);
+        let mut cube = *self;
+        for (axis, center) in self.center().into_iter().enumerate().take(axes) {
+            cube.min[axis] = center - half;
+            cube.max[axis] = center + half;
+        }
+        cube
+    }
+
+    /// Return the smallest box that contains this box's corners after the
+    /// column-major affine `matrix`.
+    pub fn transformed(&self, matrix: &[f64; 16]) -> BoundingBox {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for corner in 0..8 {
+            let p: [f64; 3] = std::array::from_fn(|axis| {
+                if corner & (1 << axis) == 0 {
+                    self.min[axis]
+                } else {
+                    self.max[axis]
+                }
+            });
+            for axis in 0..3 {
+                let v = matrix[axis] * p[0]
+                    + matrix[4 + axis] * p[1]
+                    + matrix[8 + axis] * p[2]
+                    + matrix[12 + axis];
+                min[axis] = min[axis].min(v);
+                max[axis] = max[axis].max(v);
+            }
+        }
+        BoundingBox { min, max }
+    }
+}
+
+/// Box along arbitrary orthogonal axes, as the 3D Tiles `box` volume
+/// stores it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientedBox {
+    pub center: [f64; 3],
+    /// Half-axis vectors; their lengths are the half-extents.
+    pub half_axes: [[f64; 3]; 3],
+}
+
+impl OrientedBox {
+    /// Fit a box to flat xyz `positions` along their principal axes (PCA of
+    /// the vertex covariance). `None` without positions.
+    pub fn from_points(positions: &[f32]) -> Option<OrientedBox> {
+        let n = positions.len() / 3;
+        if n == 0 {
+            return None;
+        }
+        let points = || {
+            positions
+                .chunks_exact(3)
+                .map(|p| [f64::from(p[0]), f64::from(p[1]), f64::from(p[2])])
+        };
+
+        let mut mean = [0.0; 3];
+        for p in points() {
+            for axis in 0..3 {
+                mean[axis] += p[axis] / n as f64;
+            }
+        }
+        let mut covariance = [[0.0; 3]; 3];
+        for p in points() {
+            let d: [f64; 3] = std::array::from_fn(|axis| p[axis] - mean[axis]);
+            for i in 0..3 {
+                for j in 0..3 {
+                    covariance[i][j] += d[i] * d[j];
+                }
+            }
+        }
+
+        // Extent of the points along each principal axis
+        let axes = symmetric_eigenvectors(covariance);
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for p in points() {
+            for (k, axis) in axes.iter().enumerate() {
+                let t = (0..3).map(|i| (p[i] - mean[i]) * axis[i]).sum::<f64>();
+                min[k] = min[k].min(t);
+                max[k] = max[k].max(t);
+            }
+        }
+
+        let mut center = mean;
+        for (k, axis) in axes.iter().enumerate() {
+            let mid = (min[k] + max[k]) * 0.5;
+            for i in 0..3 {
+                center[i] += axis[i] * mid;
+            }
+        }
+        let half_axes = std::array::from_fn(|k| axes[k].map(|v| v * (max[k] - min[k]) * 0.5));
+        Some(OrientedBox { center, half_axes })
+    }
+
+    /// Enclosed volume.
+    pub fn volume(&self) -> f64 {
+        let length = |v: &[f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        8.0 * self.half_axes.iter().map(length).product::<f64>()
+    }
+
+    /// Whether a point lies inside (or within `tolerance` of) the box.
+    pub fn contains_point(&self, p: [f64; 3], tolerance: f64) -> bool {
+        let d: [f64; 3] = std::array::from_fn(|i| p[i] - self.center[i]);
+        self.half_axes.iter().all(|axis| {
+            let len2 = axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2];
+            let t = d[0] * axis[0] + d[1] * axis[1] + d[2] * axis[2];
+            t.abs() <= len2 + tolerance * len2.sqrt()
+        })
+    }
+}
+
+/// Unit eigenvectors of a symmetric 3x3 matrix by cyclic Jacobi rotations.
+fn symmetric_eigenvectors(mut a: [[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    let scale = a[0][0].abs() + a[1][1].abs() + a[2][2].abs();
+    for _ in 0..50 {
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q].abs() <= 1e-15 * scale {
+                continue;
+            }
+            // Rotation in the (p, q) plane that zeroes a[p][q]
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+            for row in a.iter_mut().chain(v.iter_mut()) {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = std::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = std::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+        }
+    }
+    // Eigenvectors are the columns of the accumulated rotation
+    std::array::from_fn(|k| [v[0][k], v[1][k], v[2][k]])
 }
 
-/// Binary GLB payload for a single tile.
+/// A tile's GLB content. The bytes are written to disk as soon as the tile
+/// is built, so only the URI relative to the output directory is kept.
 #[derive(Debug, Clone)]
 pub struct TileContent {
-    pub glb_data: Vec<u8>,
     pub uri: String,
+    /// Tight bounds of the content's vertices, which can be much smaller than
+    /// the tile's octree cell after clipping.
+    pub bounds: Option<BoundingBox>,
+    /// Oriented box fitted to the content's vertices, computed with
+    /// `TilingConfig::oriented_boxes`.
+    pub obb: Option<OrientedBox>,
+    /// Triangles in the content's mesh, counted once for instanced meshes.
+    pub triangles: usize,
 }
 
 /// Octree hierarchy node.
@@ -76,6 +235,8 @@ pub struct TileNode {
     pub geometric_error: f64,
     pub content: Option<TileContent>,
     pub children: Vec<TileNode>,
+    /// Column-major transform from this tile's frame to its parent's.
+    pub transform: Option<[f64; 16]>,
 }
 
 #[cfg(test)]
@@ -136,6 +297,59 @@ mod tests {
         assert_eq!(merged.max, [1.0, 1.0, 1.0]);
     }
 
+    #[test]
+    fn bounding_box_cubed() {
+        let slab = BoundingBox {
+            min: [0.0, 0.0, 0.0],
+            max: [10.0, 1.0, 2.0],
+        };
+        let cube = slab.cubed(3);
+        assert_eq!(cube.min, [0.0, -4.5, -4.0]);
+        assert_eq!(cube.max, [10.0, 5.5, 6.0]);
+        let square = slab.cubed(2);
+        assert_eq!(square.min, [0.0, -4.5, 0.0]);
+        assert_eq!(square.max, [10.0, 5.5, 2.0]);
+    }
+
+    #[test]
+    fn bounding_box_transformed() {
+        // 90° about Z, then translate by (10, 0, 0)
+        let matrix = [
+            0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 10.0, 0.0, 0.0, 1.0,
+        ];
+        let moved = unit_box().transformed(&matrix);
+        assert_eq!(moved.min, [9.0, 0.0, 0.0]);
+        assert_eq!(moved.max, [10.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn oriented_box_fits_rotated_slab() {
+        // A 10 x 1 x 0.2 slab turned 45° about Z
+        let (c, s) = (0.5f32.sqrt(), 0.5f32.sqrt());
+        let mut positions = Vec::new();
+        for corner in 0..8 {
+            let x = if corner & 1 == 0 { -5.0 } else { 5.0 };
+            let y = if corner & 2 == 0 { -0.5 } else { 0.5 };
+            let z = if corner & 4 == 0 { 0.0 } else { 0.2 };
+            positions.extend([c * x - s * y + 3.0, s * x + c * y, z]);
+        }
+
+        let obb = OrientedBox::from_points(&positions).unwrap();
+        let mut aabb = BoundingBox {
+            min: [f64::INFINITY; 3],
+            max: [f64::NEG_INFINITY; 3],
+        };
+        for p in positions.chunks_exact(3) {
+            let p = [f64::from(p[0]), f64::from(p[1]), f64::from(p[2])];
+            assert!(obb.contains_point(p, 1e-5), "{p:?} outside");
+            aabb = aabb.merge(&BoundingBox { min: p, max: p });
+        }
+
+        assert!((obb.volume() - 2.0).abs() < 1e-4, "volume {}", obb.volume());
+        assert!(obb.volume() < aabb.volume() / 5.0);
+        assert!((obb.center[0] - 3.0).abs() < 1e-5);
+    }
+
     #[test]
     fn tile_node_construction() {
         let node = TileNode {
@@ -153,11 +367,15 @@ mod tests {
                 },
                 geometric_error: 50.0,
                 content: Some(TileContent {
-                    glb_data: vec![0x67, 0x6C, 0x54, 0x46],
                     uri: "tiles/0/tile.glb".into(),
+                    bounds: None,
+                    obb: None,
+                    triangles: 0,
                 }),
                 children: vec![],
+                transform: None,
             }],
+            transform: None,
         };
 
         assert_eq!(node.address, "root");
@@ -6,7 +6,10 @@
 use std::fs;
 use std::path::Path;
 
-use photo_tiler::config::{PipelineConfig, TextureConfig, TextureFormat, TilingConfig};
+use photo_tiler::config::{
+    MatrixOrder, PipelineConfig, TextureConfig, TextureFormat, TilesVersion, TilingConfig, Units,
+    UpAxis,
+};
 use photo_tiler::Pipeline;
 
 /// Write a minimal OBJ + MTL + PNG texture to `dir`.
@@ -71,13 +74,18 @@ map_Kd texture.png
 
 /// Write a minimal OBJ without textures or materials.
 fn write_plain_obj(dir: &Path) {
+    write_plain_obj_at(dir, 0.0);
+}
+
+/// Write the plain OBJ grid shifted by `offset_x` along X.
+fn write_plain_obj_at(dir: &Path, offset_x: f32) {
     let mut obj = String::new();
     let n = 4usize;
     let verts = n + 1;
 
     for y in 0..verts {
         for x in 0..verts {
-            let fx = x as f32 / n as f32;
+            let fx = x as f32 / n as f32 + offset_x;
             let fy = y as f32 / n as f32;
             obj.push_str(&format!("v {} {} 0\n", fx, fy));
         }
@@ -113,10 +121,12 @@ fn full_pipeline_textured_obj() {
             quality: 100,
             max_size: 512,
             enabled: true,
+            ..Default::default()
         },
         tiling: TilingConfig {
             max_triangles_per_tile: 100_000,
             max_depth: 4,
+            ..Default::default()
         },
         validate: true,
         ..Default::default()
@@ -213,6 +223,7 @@ fn full_pipeline_plain_obj_no_textures() {
         tiling: TilingConfig {
             max_triangles_per_tile: 100_000,
             max_depth: 4,
+            ..Default::default()
         },
         validate: true,
         ..Default::default()
@@ -229,6 +240,524 @@ fn full_pipeline_plain_obj_no_textures() {
     assert_eq!(tileset["asset"]["version"], "1.1");
 }
 
+#[test]
+fn meshopt_tiles_are_smaller_and_import() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    fs::create_dir_all(&input_dir).unwrap();
+    write_synthetic_obj(&input_dir);
+
+    let run = |meshopt: bool| {
+        let output_dir = tmp.path().join(format!("output_{meshopt}"));
+        let config = PipelineConfig {
+            input: input_dir.join("model.obj"),
+            output: output_dir.clone(),
+            texture: TextureConfig {
+                enabled: false,
+                ..Default::default()
+            },
+            tiling: TilingConfig {
+                max_triangles_per_tile: 50,
+                max_depth: 3,
+                meshopt,
+                ..Default::default()
+            },
+            validate: true,
+            ..Default::default()
+        };
+        Pipeline::run(&config).expect("pipeline should succeed");
+
+        let mut glbs = Vec::new();
+        let mut stack = vec![output_dir.join("tiles")];
+        while let Some(dir) = stack.pop() {
+            for entry in fs::read_dir(&dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    stack.push(path);
+                } else if path.extension().is_some_and(|e| e == "glb") {
+                    glbs.push(fs::read(&path).unwrap());
+                }
+            }
+        }
+        glbs
+    };
+
+    let plain = run(false);
+    let compressed = run(true);
+    assert!(!compressed.is_empty());
+    assert_eq!(plain.len(), compressed.len());
+
+    let total = |glbs: &[Vec<u8>]| glbs.iter().map(Vec::len).sum::<usize>();
+    assert!(
+        total(&compressed) < total(&plain),
+        "meshopt tiles ({} bytes) should be smaller than plain ones ({} bytes)",
+        total(&compressed),
+        total(&plain)
+    );
+
+    for bytes in &compressed {
+        let gltf = gltf::Gltf::from_slice_without_validation(bytes).unwrap();
+        assert!(
+            gltf.extensions_required()
+                .any(|ext| ext == "EXT_meshopt_compression")
+        );
+    }
+    for bytes in &plain {
+        let gltf = gltf::Gltf::from_slice_without_validation(bytes).unwrap();
+        assert_eq!(gltf.extensions_used().count(), 0);
+    }
+}
+
+#[test]
+fn transform_file_becomes_root_transform() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    // Square centered on the origin, so centering leaves the matrix unchanged
+    fs::write(
+        input_dir.join("model.obj"),
+        "v -1 -1 0\nv 1 -1 0\nv 1 1 0\nv -1 1 0\nf 1 2 3\nf 1 3 4\n",
+    )
+    .unwrap();
+
+    // Row-major ENU frame at 90° E on the equator, 100 m above the ellipsoid
+    let transform_path = input_dir.join("placement.txt");
+    fs::write(
+        &transform_path,
+        "-1 0 0 0\n\
+         0 0 1 6378237\n\
+         0 1 0 0\n\
+         0 0 0 1\n",
+    )
+    .unwrap();
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        transform_file: Some(transform_path),
+        transform_order: MatrixOrder::Row,
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        validate: true,
+        ..Default::default()
+    };
+    Pipeline::run(&config).expect("pipeline should succeed");
+
+    let json_str = fs::read_to_string(output_dir.join("tileset.json")).unwrap();
+    let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+    assert_eq!(
+        tileset["root"]["transform"],
+        serde_json::json!([
+            -1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 6378237.0, 0.0, 1.0
+        ])
+    );
+}
+
+/// Write a glTF with one triangle (external .bin) instanced by `nodes`.
+fn write_triangle_gltf(path: &Path, nodes: &str) {
+    let mut bin = Vec::new();
+    for v in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend_from_slice(&v.to_le_bytes());
+    }
+    for i in [0u16, 1, 2, 0] {
+        bin.extend_from_slice(&i.to_le_bytes());
+    }
+    fs::write(path.with_extension("bin"), &bin).unwrap();
+
+    let bin_name = path.with_extension("bin");
+    let json = format!(
+        r#"{{
+  "asset": {{"version": "2.0"}},
+  "buffers": [{{"uri": "{uri}", "byteLength": {len}}}],
+  "bufferViews": [
+    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+    {{"buffer": 0, "byteOffset": 36, "byteLength": 6}}
+  ],
+  "accessors": [
+    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+      "min": [0, 0, 0], "max": [1, 1, 0]}},
+    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+  ],
+  "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1}}]}}],
+  {nodes}
+}}"#,
+        uri = bin_name.file_name().unwrap().to_str().unwrap(),
+        len = bin.len(),
+    );
+    fs::write(path, json).unwrap();
+}
+
+#[test]
+fn kept_node_transform_becomes_tile_transform() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    // The triangle once in place and once under a translated node
+    write_triangle_gltf(
+        &input_dir.join("model.gltf"),
+        r#""nodes": [{"mesh": 0}, {"mesh": 0, "translation": [100, 50, 0]}],
+  "scenes": [{"nodes": [0, 1]}]"#,
+    );
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.gltf"),
+        output: output_dir.clone(),
+        input_up: UpAxis::ZUp,
+        keep_node_transforms: true,
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        validate: true,
+        ..Default::default()
+    };
+    Pipeline::run(&config).expect("pipeline should succeed");
+
+    let json_str = fs::read_to_string(output_dir.join("tileset.json")).unwrap();
+    let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+    let children = tileset["root"]["children"].as_array().unwrap();
+    assert_eq!(children.len(), 2);
+    assert!(children[0].get("transform").is_none());
+
+    // The node translation, less the centroid the scene was centered on
+    let transform = children[1]["transform"].as_array().unwrap();
+    let centroid = [50.0 + 1.0 / 3.0, 25.0 + 1.0 / 3.0, 0.0];
+    let expected = [100.0 - centroid[0], 50.0 - centroid[1], 0.0 - centroid[2]];
+    for axis in 0..3 {
+        let actual = transform[12 + axis].as_f64().unwrap();
+        assert!(
+            (actual - expected[axis]).abs() < 1e-4,
+            "axis {axis}: {actual} != {}",
+            expected[axis]
+        );
+    }
+}
+
+#[test]
+fn repeated_mesh_is_written_once_with_gpu_instancing() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    // Three nodes placing the same triangle
+    write_triangle_gltf(
+        &input_dir.join("model.gltf"),
+        r#""nodes": [{"mesh": 0}, {"mesh": 0, "translation": [5, 0, 0]},
+    {"mesh": 0, "translation": [10, 0, 0]}],
+  "scenes": [{"nodes": [0, 1, 2]}]"#,
+    );
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.gltf"),
+        output: output_dir.clone(),
+        input_up: UpAxis::ZUp,
+        gpu_instancing: true,
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        validate: true,
+        ..Default::default()
+    };
+    Pipeline::run(&config).expect("pipeline should succeed");
+
+    let tileset: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output_dir.join("tileset.json")).unwrap())
+            .unwrap();
+    let mut pending = vec![&tileset["root"]];
+    let mut uris = Vec::new();
+    while let Some(tile) = pending.pop() {
+        if let Some(uri) = tile["content"]["uri"].as_str() {
+            uris.push(uri.to_string());
+        }
+        pending.extend(tile["children"].as_array().into_iter().flatten());
+    }
+    assert_eq!(uris.len(), 1, "one tile for the instanced mesh: {uris:?}");
+
+    let bytes = fs::read(output_dir.join(&uris[0])).unwrap();
+    let glb = gltf::binary::Glb::from_slice(&bytes).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&glb.json).unwrap();
+    assert_eq!(json["meshes"].as_array().unwrap().len(), 1);
+    let instancing = &json["nodes"][0]["extensions"]["EXT_mesh_gpu_instancing"];
+    for attribute in ["TRANSLATION", "ROTATION", "SCALE"] {
+        let accessor = instancing["attributes"][attribute].as_u64().unwrap() as usize;
+        assert_eq!(json["accessors"][accessor]["count"], 3, "{attribute}");
+    }
+}
+
+#[test]
+fn gltf_unit_hint_scales_without_units_flag() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("model.gltf");
+    write_triangle_gltf(
+        &path,
+        r#""nodes": [{"mesh": 0, "scale": [1000, 1000, 1000]}],
+  "scenes": [{"nodes": [0]}]"#,
+    );
+    let json = fs::read_to_string(&path).unwrap().replace(
+        r#""asset": {"version": "2.0"}"#,
+        r#""asset": {"version": "2.0", "extras": {"units": "millimeters"}}"#,
+    );
+    fs::write(&path, json).unwrap();
+
+    let x_extent = |config: &PipelineConfig| {
+        let ingested = Pipeline::ingest(config).unwrap();
+        let transformed = Pipeline::transform(config, &ingested).unwrap();
+        let bounds = transformed.bounds;
+        (ingested.units, bounds.max[0] - bounds.min[0])
+    };
+
+    // The 1000 mm triangle leg becomes 1 m
+    let mut config = PipelineConfig {
+        input: path,
+        input_up: UpAxis::ZUp,
+        ..Default::default()
+    };
+    let (detected, extent) = x_extent(&config);
+    assert_eq!(detected, Some(Units::Millimeters));
+    assert!((extent - 1.0).abs() < 1e-6, "extent {extent}");
+
+    // --units overrides the hint
+    config.units = Some(Units::Meters);
+    let (detected, extent) = x_extent(&config);
+    assert_eq!(detected, None);
+    assert!((extent - 1000.0).abs() < 1e-3, "extent {extent}");
+}
+
+#[test]
+fn feature_ids_name_each_source_object() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    // Two unit squares, left at x = 0 and right at x = 5
+    let mut obj = String::new();
+    for (i, (name, x)) in [("left", 0.0), ("right", 5.0)].into_iter().enumerate() {
+        obj.push_str(&format!("o {name}\n"));
+        for (dx, dy) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            obj.push_str(&format!("v {} {dy} 0\n", x + dx));
+        }
+        let base = i * 4;
+        obj.push_str(&format!("f {} {} {}\n", base + 1, base + 2, base + 3));
+        obj.push_str(&format!("f {} {} {}\n", base + 1, base + 3, base + 4));
+    }
+    fs::write(input_dir.join("model.obj"), obj).unwrap();
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        tiling: TilingConfig {
+            meshopt: false,
+            feature_ids: true,
+            ..Default::default()
+        },
+        validate: true,
+        ..Default::default()
+    };
+    Pipeline::run(&config).expect("pipeline should succeed");
+
+    let tileset: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output_dir.join("tileset.json")).unwrap())
+            .unwrap();
+    let class = &tileset["schema"]["classes"]["object"];
+    assert_eq!(class["properties"]["name"]["type"], "STRING");
+
+    let uri = tileset["root"]["content"]["uri"].as_str().unwrap();
+    let bytes = fs::read(output_dir.join(uri)).unwrap();
+    let glb = gltf::binary::Glb::from_slice(&bytes).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&glb.json).unwrap();
+    let bin = glb.bin.unwrap();
+    let view_bytes = |view: &serde_json::Value| {
+        let view = &json["bufferViews"][view.as_u64().unwrap() as usize];
+        let offset = view["byteOffset"].as_u64().unwrap_or(0) as usize;
+        &bin[offset..offset + view["byteLength"].as_u64().unwrap() as usize]
+    };
+    let floats = |accessor: &serde_json::Value| -> Vec<f32> {
+        let accessor = &json["accessors"][accessor.as_u64().unwrap() as usize];
+        view_bytes(&accessor["bufferView"])
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    };
+
+    // Object names from the property table
+    let table = &json["extensions"]["EXT_structural_metadata"]["propertyTables"][0];
+    assert_eq!(table["count"], 2);
+    let values = view_bytes(&table["properties"]["name"]["values"]);
+    let offsets: Vec<usize> = view_bytes(&table["properties"]["name"]["stringOffsets"])
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+        .collect();
+    let names: Vec<&str> = offsets
+        .windows(2)
+        .map(|w| std::str::from_utf8(&values[w[0]..w[1]]).unwrap())
+        .collect();
+
+    // Every vertex's feature ID names the object it came from
+    let primitive = &json["meshes"][0]["primitives"][0];
+    let feature_ids = &primitive["extensions"]["EXT_mesh_features"]["featureIds"][0];
+    assert_eq!(feature_ids["featureCount"], 2);
+    let ids = floats(&primitive["attributes"]["_FEATURE_ID_0"]);
+    let positions = floats(&primitive["attributes"]["POSITION"]);
+    let x_range = positions.chunks_exact(3).map(|p| p[0]);
+    let (min_x, max_x) = x_range.fold((f32::MAX, f32::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+    let mid_x = (min_x + max_x) / 2.0;
+
+    let mut seen = std::collections::HashSet::new();
+    for (id, p) in ids.iter().zip(positions.chunks_exact(3)) {
+        let expected = if p[0] < mid_x { "left" } else { "right" };
+        assert_eq!(names[*id as usize], expected);
+        seen.insert(*id as u32);
+    }
+    assert_eq!(seen.len(), 2, "both objects keep distinct feature IDs");
+}
+
+#[test]
+fn b3dm_batch_ids_index_the_batch_table() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    // Three objects of one, two and three triangles
+    let mut obj = String::new();
+    let mut base = 1;
+    for (i, name) in ["one", "two", "three"].into_iter().enumerate() {
+        obj.push_str(&format!("o {name}\n"));
+        let x = i as f32 * 5.0;
+        for t in 0..=i {
+            let y = t as f32;
+            let (x1, y1) = (x + 1.0, y + 1.0);
+            obj.push_str(&format!("v {x} {y} 0\nv {x1} {y} 0\nv {x} {y1} 0\n"));
+            obj.push_str(&format!("f {base} {} {}\n", base + 1, base + 2));
+            base += 3;
+        }
+    }
+    fs::write(input_dir.join("model.obj"), obj).unwrap();
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        tiling: TilingConfig {
+            meshopt: false,
+            b3dm: true,
+            tiles_version: TilesVersion::V1_0,
+            ..Default::default()
+        },
+        validate: true,
+        ..Default::default()
+    };
+    Pipeline::run(&config).expect("pipeline should succeed");
+
+    let tileset: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output_dir.join("tileset.json")).unwrap())
+            .unwrap();
+    assert!(tileset.get("extensionsRequired").is_none());
+    let uri = tileset["root"]["content"]["uri"].as_str().unwrap();
+    assert!(uri.ends_with(".b3dm"), "{uri}");
+
+    let bytes = fs::read(output_dir.join(uri)).unwrap();
+    let b3dm = photo_tiler::tiling::glb_writer::read_b3dm(&bytes).expect("b3dm parses");
+    let batch_length = b3dm.feature_table["BATCH_LENGTH"].as_u64().unwrap() as usize;
+    let table = b3dm.batch_table.expect("batch table");
+    let names = table["name"].as_array().unwrap();
+    let counts = table["triangleCount"].as_array().unwrap();
+    assert_eq!(names.len(), batch_length);
+    assert_eq!(counts.len(), batch_length);
+
+    let glb = gltf::binary::Glb::from_slice(b3dm.glb).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&glb.json).unwrap();
+    let bin = glb.bin.unwrap();
+    let primitive = &json["meshes"][0]["primitives"][0];
+    assert!(primitive["attributes"].get("_FEATURE_ID_0").is_none());
+    let accessor = primitive["attributes"]["_BATCHID"].as_u64().unwrap() as usize;
+    let accessor = &json["accessors"][accessor];
+    let view = &json["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+    let offset = view["byteOffset"].as_u64().unwrap_or(0) as usize;
+    let batch_ids: std::collections::BTreeSet<u32> = bin
+        [offset..offset + view["byteLength"].as_u64().unwrap() as usize]
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()) as u32)
+        .collect();
+
+    // Every batch is used and every ID is in range
+    assert_eq!(batch_ids.len(), batch_length);
+    assert!(batch_ids.iter().all(|&id| (id as usize) < batch_length));
+    let mut triangles: Vec<(String, u64)> = names
+        .iter()
+        .zip(counts)
+        .map(|(name, count)| (name.as_str().unwrap().to_string(), count.as_u64().unwrap()))
+        .collect();
+    triangles.sort();
+    assert_eq!(
+        triangles,
+        [("one".into(), 1), ("three".into(), 3), ("two".into(), 2)]
+    );
+}
+
+#[test]
+fn tiles_version_1_0_validates() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    write_plain_obj(&input_dir);
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        tiling: TilingConfig {
+            max_triangles_per_tile: 8,
+            max_depth: 4,
+            tiles_version: TilesVersion::V1_0,
+            ..Default::default()
+        },
+        validate: true,
+        ..Default::default()
+    };
+
+    Pipeline::run(&config).expect("1.0 tileset should validate");
+
+    let json_str = fs::read_to_string(output_dir.join("tileset.json")).unwrap();
+    let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+    assert_eq!(tileset["asset"]["version"], "1.0");
+    assert_eq!(
+        tileset["extensionsRequired"],
+        serde_json::json!(["3DTILES_content_gltf"])
+    );
+
+    // Without the extension, GLB content is not valid 1.0
+    let mut stripped = tileset.clone();
+    stripped
+        .as_object_mut()
+        .unwrap()
+        .remove("extensionsRequired");
+    fs::write(output_dir.join("tileset.json"), stripped.to_string()).unwrap();
+    let err = Pipeline::validate_tileset(&output_dir).unwrap_err();
+    assert!(err.to_string().contains("3DTILES_content_gltf"));
+}
+
 #[test]
 fn full_pipeline_with_validation_passes() {
     let tmp = tempfile::tempdir().unwrap();
@@ -246,10 +775,12 @@ fn full_pipeline_with_validation_passes() {
             quality: 100,
             max_size: 256,
             enabled: true,
+            ..Default::default()
         },
         tiling: TilingConfig {
             max_triangles_per_tile: 50,
             max_depth: 3,
+            ..Default::default()
         },
         validate: true,
         ..Default::default()
@@ -260,6 +791,556 @@ fn full_pipeline_with_validation_passes() {
     assert!(result.tile_count >= 1);
 }
 
+#[test]
+fn uri_base_makes_content_uris_absolute() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+    write_plain_obj(&input_dir);
+
+    let base = "https://cdn.example.com/assets/";
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        tiling: TilingConfig {
+            max_triangles_per_tile: 50,
+            uri_base: Some(base.into()),
+            ..Default::default()
+        },
+        // Resolves the prefixed URIs back to the output dir
+        validate: true,
+        ..Default::default()
+    };
+    let result = Pipeline::run(&config).expect("pipeline should succeed");
+
+    let tileset: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output_dir.join("tileset.json")).unwrap())
+            .unwrap();
+    let mut pending = vec![&tileset["root"]];
+    let mut uris = Vec::new();
+    while let Some(tile) = pending.pop() {
+        if let Some(uri) = tile["content"]["uri"].as_str() {
+            uris.push(uri.to_string());
+        }
+        pending.extend(tile["children"].as_array().into_iter().flatten());
+    }
+    assert_eq!(uris.len(), result.tile_count);
+    for uri in &uris {
+        let path = uri
+            .strip_prefix(base)
+            .expect("URI should start with the base");
+        assert!(path.starts_with("tiles/"), "{uri}");
+        assert!(output_dir.join(path).is_file(), "{path} not written");
+    }
+}
+
+#[test]
+fn external_tilesets_split_at_configured_depth() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+    write_synthetic_obj(&input_dir);
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        tiling: TilingConfig {
+            max_triangles_per_tile: 50,
+            max_depth: 3,
+            external_tileset_depth: Some(1),
+            ..Default::default()
+        },
+        validate: true,
+        ..Default::default()
+    };
+    Pipeline::run(&config).expect("pipeline should succeed");
+
+    let read_json = |path: &Path| -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    };
+    let tileset = read_json(&output_dir.join("tileset.json"));
+
+    // Every depth-1 tile points to a tileset.json in its own directory
+    let children = tileset["root"]["children"].as_array().unwrap();
+    assert!(!children.is_empty());
+    for child in children {
+        let uri = child["content"]["uri"].as_str().unwrap();
+        assert!(
+            uri.starts_with("tiles/") && uri.ends_with("/tileset.json"),
+            "{uri}"
+        );
+        assert!(child.get("children").is_none());
+
+        let child_path = output_dir.join(uri);
+        let child_tileset = read_json(&child_path);
+        assert_eq!(child_tileset["asset"]["version"], "1.1");
+        assert_eq!(child_tileset["geometricError"], child["geometricError"]);
+        let root_uri = child_tileset["root"]["content"]["uri"].as_str().unwrap();
+        assert_eq!(
+            root_uri, "tile.glb",
+            "URIs are relative to the child tileset"
+        );
+
+        Pipeline::validate_tileset(&child_path).expect("child tileset should validate");
+    }
+}
+
+#[test]
+fn second_run_reuses_cached_geometry() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+    write_synthetic_obj(&input_dir);
+
+    let mut config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        texture: TextureConfig {
+            format: TextureFormat::Original,
+            quality: 90,
+            ..Default::default()
+        },
+        cache_dir: Some(tmp.path().join(".photo-tiler-cache")),
+        force: true,
+        ..Default::default()
+    };
+    let first = Pipeline::run(&config).expect("first run should succeed");
+    assert_eq!(first.report_json()["geometryCached"], false);
+
+    // Only a texture setting changes
+    config.texture.quality = 50;
+    let second = Pipeline::run(&config).expect("second run should succeed");
+    assert_eq!(second.report_json()["geometryCached"], true);
+    assert_eq!(second.tile_count, first.tile_count);
+    assert_eq!(second.triangles_in, first.triangles_in);
+    assert_eq!(second.triangles_out, first.triangles_out);
+
+    // A setting transform reads invalidates the entry
+    config.scale = Some([1.0, 1.0, 2.0]);
+    let third = Pipeline::run(&config).expect("third run should succeed");
+    assert!(!third.geometry_cached);
+}
+
+#[test]
+fn cached_geometry_misses_after_a_texture_in_a_subfolder_changes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    fs::create_dir_all(input_dir.join("maps")).unwrap();
+    write_synthetic_obj(&input_dir);
+    fs::rename(
+        input_dir.join("texture.png"),
+        input_dir.join("maps/texture.png"),
+    )
+    .unwrap();
+    let mtl = fs::read_to_string(input_dir.join("material.mtl")).unwrap();
+    let mtl = mtl.replace("map_Kd texture.png", "map_Kd maps/texture.png");
+    fs::write(input_dir.join("material.mtl"), mtl).unwrap();
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: tmp.path().join("output"),
+        cache_dir: Some(tmp.path().join(".photo-tiler-cache")),
+        force: true,
+        ..Default::default()
+    };
+    assert!(!Pipeline::run(&config).unwrap().geometry_cached);
+    assert!(Pipeline::run(&config).unwrap().geometry_cached);
+
+    // Same size and name, other texels
+    let img = image::RgbaImage::from_pixel(16, 16, image::Rgba([10, 200, 10, 255]));
+    img.save(input_dir.join("maps/texture.png")).unwrap();
+    assert!(!Pipeline::run(&config).unwrap().geometry_cached);
+}
+
+#[test]
+fn report_matches_written_tileset() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    let report_path = tmp.path().join("report.json");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    write_synthetic_obj(&input_dir);
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        texture: TextureConfig {
+            format: TextureFormat::Original,
+            ..Default::default()
+        },
+        tiling: TilingConfig {
+            max_triangles_per_tile: 50,
+            max_depth: 3,
+            ..Default::default()
+        },
+        validate: true,
+        report: Some(report_path.clone()),
+        ..Default::default()
+    };
+
+    let result = Pipeline::run(&config).expect("pipeline should succeed");
+
+    let report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&report_path).unwrap()).unwrap();
+
+    // tile_count is what write_tileset returned
+    assert_eq!(report["tileCount"], result.tile_count);
+    let per_level: u64 = report["tilesPerLevel"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n.as_u64().unwrap())
+        .sum();
+    assert_eq!(per_level as usize, result.tile_count);
+
+    assert_eq!(report["triangles"]["input"], 200);
+    assert!(report["triangles"]["output"].as_u64().unwrap() >= 200);
+    assert!(report["atlases"]["texels"].as_u64().unwrap() > 0);
+    let efficiency = report["atlases"]["packingEfficiency"].as_f64().unwrap();
+    assert!(efficiency > 0.0 && efficiency <= 1.0, "{efficiency}");
+    assert!(report["bytesWritten"].as_u64().unwrap() > 0);
+
+    for stage in ["total", "ingestion", "transform", "tiling", "validation"] {
+        let secs = report["durationSeconds"][stage].as_f64().unwrap();
+        assert!(secs > 0.0, "{stage} duration should be non-zero");
+    }
+}
+
+#[test]
+fn dry_run_json_describes_input() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    write_synthetic_obj(&input_dir);
+
+    let config = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        units: Some(Units::Meters),
+        dry_run: true,
+        json: true,
+        ..Default::default()
+    };
+
+    let info = Pipeline::dry_run_json(&config).expect("dry run should succeed");
+    let stats = &info["stats"];
+    assert_eq!(stats["inputFormat"], "OBJ");
+    assert_eq!(stats["totalMeshes"], 1);
+    assert_eq!(stats["totalVertices"], 121);
+    assert_eq!(stats["totalTriangles"], 200);
+    assert_eq!(stats["hasUvs"], true);
+    assert_eq!(stats["textureCount"], 1);
+    assert!(info["georeference"].is_null());
+
+    // The 1 x 0.5 grid, centered
+    let min = info["bounds"]["min"].as_array().unwrap();
+    let max = info["bounds"]["max"].as_array().unwrap();
+    let extents: Vec<f64> = (0..3)
+        .map(|i| max[i].as_f64().unwrap() - min[i].as_f64().unwrap())
+        .collect();
+    assert!((extents[0] - 1.0).abs() < 1e-6, "{extents:?}");
+    assert_eq!(info["rootTransform"].as_array().unwrap().len(), 16);
+
+    // Nothing is written
+    assert!(!output_dir.exists());
+}
+
+#[test]
+fn concurrent_pipelines_on_custom_pools() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    fs::create_dir_all(&input_dir).unwrap();
+    write_plain_obj(&input_dir);
+
+    let handles: Vec<_> = (0..2)
+        .map(|i| {
+            let config = PipelineConfig {
+                input: input_dir.join("model.obj"),
+                output: tmp.path().join(format!("output-{i}")),
+                texture: TextureConfig {
+                    enabled: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            std::thread::spawn(move || {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(2)
+                    .build()
+                    .unwrap();
+                Pipeline::run_in(&config, &pool)
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        let result = handle.join().unwrap().expect("pipeline should succeed");
+        assert!(result.tile_count >= 1);
+    }
+    assert!(tmp.path().join("output-0/tileset.json").exists());
+    assert!(tmp.path().join("output-1/tileset.json").exists());
+}
+
+#[test]
+fn ingest_once_tile_twice() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    write_plain_obj(&input_dir);
+
+    let base = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let ingestion = Pipeline::ingest(&base).expect("ingestion should succeed");
+
+    let mut tile_counts = Vec::new();
+    for (name, max_triangles) in [("coarse", 100_000), ("fine", 8)] {
+        let config = PipelineConfig {
+            output: tmp.path().join(name),
+            tiling: TilingConfig {
+                max_triangles_per_tile: max_triangles,
+                max_depth: 4,
+                ..Default::default()
+            },
+            ..base.clone()
+        };
+        let transformed = Pipeline::transform(&config, &ingestion).unwrap();
+        let result = Pipeline::tile_only(transformed, &config).unwrap();
+        assert!(config.output.join("tileset.json").exists());
+        assert_eq!(result.triangles_in, ingestion.stats.total_triangles);
+        tile_counts.push(result.tile_count);
+    }
+
+    assert_eq!(tile_counts[0], 1, "a single leaf holds the whole mesh");
+    assert!(tile_counts[1] > tile_counts[0], "smaller tiles should split the mesh");
+}
+
+#[test]
+fn multiple_inputs_merge_into_one_tileset() {
+    let tmp = tempfile::tempdir().unwrap();
+    let west_dir = tmp.path().join("west");
+    let east_dir = tmp.path().join("east");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&west_dir).unwrap();
+    fs::create_dir_all(&east_dir).unwrap();
+
+    // Two 1x1 grids, ten units apart along X
+    write_plain_obj_at(&west_dir, 0.0);
+    write_plain_obj_at(&east_dir, 10.0);
+
+    let config = PipelineConfig {
+        input: west_dir.join("model.obj"),
+        extra_inputs: vec![east_dir.join("model.obj")],
+        output: output_dir.clone(),
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        tiling: TilingConfig {
+            max_triangles_per_tile: 16,
+            max_depth: 4,
+            ..Default::default()
+        },
+        validate: true,
+        ..Default::default()
+    };
+
+    let ingestion = Pipeline::ingest(&config).unwrap();
+    assert_eq!(ingestion.meshes.len(), 2);
+    assert_eq!(ingestion.stats.total_triangles, 64);
+
+    let transformed = Pipeline::transform(&config, &ingestion).unwrap();
+    let width = transformed.bounds.max[0] - transformed.bounds.min[0];
+    assert!(
+        (width - 11.0).abs() < 1e-4,
+        "bounds should span both inputs, got {width}"
+    );
+
+    let result = Pipeline::run(&config).expect("pipeline should succeed");
+    assert_eq!(result.triangles_in, 64);
+
+    let json_str = fs::read_to_string(output_dir.join("tileset.json")).unwrap();
+    let tileset: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+    let root_box = tileset["root"]["boundingVolume"]["box"].as_array().unwrap();
+    let half_x = root_box[3].as_f64().unwrap();
+    assert!(
+        half_x * 2.0 >= 11.0 - 1e-4,
+        "root box should enclose both inputs"
+    );
+
+    // Content tiles exist on both sides of the merged model's center
+    fn content_centers_x(tile: &serde_json::Value, out: &mut Vec<f64>) {
+        if tile.get("content").is_some() {
+            out.push(tile["boundingVolume"]["box"][0].as_f64().unwrap());
+        }
+        for child in tile["children"].as_array().into_iter().flatten() {
+            content_centers_x(child, out);
+        }
+    }
+    let mut centers = Vec::new();
+    content_centers_x(&tileset["root"], &mut centers);
+    assert!(
+        centers.iter().any(|&x| x < -1.0),
+        "west input should have tiles"
+    );
+    assert!(
+        centers.iter().any(|&x| x > 1.0),
+        "east input should have tiles"
+    );
+}
+
+#[test]
+fn populated_output_requires_force() {
+    let tmp = tempfile::tempdir().unwrap();
+    let input_dir = tmp.path().join("input");
+    let output_dir = tmp.path().join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    write_plain_obj(&input_dir);
+
+    let fine = PipelineConfig {
+        input: input_dir.join("model.obj"),
+        output: output_dir.clone(),
+        texture: TextureConfig {
+            enabled: false,
+            ..Default::default()
+        },
+        tiling: TilingConfig {
+            max_triangles_per_tile: 8,
+            max_depth: 4,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let first = Pipeline::run(&fine).expect("first run should succeed");
+    assert!(first.tile_count > 1);
+
+    // A second run into the populated directory must not touch it
+    let coarse = PipelineConfig {
+        tiling: TilingConfig {
+            max_triangles_per_tile: 100_000,
+            ..fine.tiling.clone()
+        },
+        ..fine.clone()
+    };
+    assert!(Pipeline::run(&coarse).is_err());
+    assert!(output_dir.join("tileset.json").exists());
+
+    // The directory is checked before the input is even read
+    let missing_input = PipelineConfig {
+        input: input_dir.join("missing.obj"),
+        ..coarse.clone()
+    };
+    let err = Pipeline::run(&missing_input).unwrap_err().to_string();
+    assert!(err.contains("is not empty"), "{err}");
+
+    // With --force the stale tiles are cleared: only the new root GLB remains
+    let forced = PipelineConfig {
+        force: true,
+        ..coarse
+    };
+    let second = Pipeline::run(&forced).expect("forced run should succeed");
+    assert_eq!(second.tile_count, 1);
+    let glbs: Vec<_> = fs::read_dir(output_dir.join("tiles"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(glbs, vec!["root.glb"]);
+}
+
+/// Write a minimal valid GLB (JSON chunk only) to `path`.
+fn write_minimal_glb(path: &Path) {
+    let mut json = br#"{"asset":{"version":"2.0"}}"#.to_vec();
+    while json.len() % 4 != 0 {
+        json.push(b' ');
+    }
+    let total_len = 12 + 8 + json.len() as u32;
+
+    let mut glb = Vec::new();
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&total_len.to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, glb).unwrap();
+}
+
+/// Write a hand-made two-level tileset referencing `tiles/a.glb` and `tiles/b.glb`.
+fn write_foreign_tileset(dir: &Path) {
+    let tileset = serde_json::json!({
+        "asset": { "version": "1.1", "generator": "some other tool" },
+        "geometricError": 100.0,
+        "root": {
+            "boundingVolume": { "box": [0, 0, 0, 10, 0, 0, 0, 10, 0, 0, 0, 10] },
+            "geometricError": 50.0,
+            "refine": "REPLACE",
+            "content": { "uri": "tiles/a.glb" },
+            "children": [{
+                "boundingVolume": { "box": [5, 5, 5, 5, 0, 0, 0, 5, 0, 0, 0, 5] },
+                "geometricError": 0.0,
+                "content": { "uri": "tiles/b.glb" }
+            }]
+        }
+    });
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("tileset.json"), tileset.to_string()).unwrap();
+}
+
+#[test]
+fn validate_tileset_accepts_foreign_tileset() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("foreign");
+    write_foreign_tileset(&dir);
+    write_minimal_glb(&dir.join("tiles/a.glb"));
+    write_minimal_glb(&dir.join("tiles/b.glb"));
+
+    let tile_count = Pipeline::validate_tileset(&dir.join("tileset.json"))
+        .expect("hand-written tileset should validate");
+    assert_eq!(tile_count, 2);
+
+    // The CLI mode accepts the directory as well
+    let config = PipelineConfig {
+        validate_only: Some(dir.clone()),
+        ..Default::default()
+    };
+    let result = Pipeline::run(&config).expect("--validate-only should pass");
+    assert_eq!(result.tile_count, 2);
+}
+
+#[test]
+fn validate_tileset_reports_missing_glb() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("foreign");
+    write_foreign_tileset(&dir);
+    write_minimal_glb(&dir.join("tiles/a.glb"));
+
+    let err = Pipeline::validate_tileset(&dir).unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.contains("GLB not found: tiles/b.glb"),
+        "unexpected message: {message}"
+    );
+}
+
 #[test]
 fn pipeline_missing_input_returns_error() {
     let tmp = tempfile::tempdir().unwrap();